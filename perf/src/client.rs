@@ -19,7 +19,7 @@ use crate::{
     CommonOpt, PERF_CIPHER_SUITES,
     noprotection::NoProtectionClientConfig,
     parse_byte_size,
-    stats::{OpenStreamStats, Stats},
+    stats::{HandshakeStats, OpenStreamStats, Stats},
 };
 
 /// Connects to a QUIC perf server and maintains a specified pattern of requests until interrupted
@@ -59,6 +59,15 @@ pub struct Opt {
     /// The interval in seconds at which stats are reported
     #[clap(long, default_value = "1")]
     interval: u64,
+    /// Measure the connection setup rate instead of throughput
+    ///
+    /// Repeatedly connects to the server and immediately closes each connection, reporting
+    /// handshakes per second and handshake latency.
+    #[clap(long)]
+    handshakes_per_second: bool,
+    /// Resume previous sessions using 0-RTT when measuring the connection setup rate
+    #[clap(long, requires = "handshakes_per_second")]
+    resume: bool,
     /// File path to output JSON statistics to. If the file is '-', stdout will be used
     #[cfg(feature = "json-output")]
     #[clap(long)]
@@ -117,6 +126,14 @@ pub async fn run(opt: Opt) -> Result<()> {
         .with_custom_certificate_verifier(SkipServerVerification::new(provider))
         .with_no_client_auth();
     crypto.alpn_protocols = vec![b"perf".to_vec()];
+    if opt.handshakes_per_second {
+        if opt.resume {
+            crypto.enable_early_data = true;
+        } else {
+            // Ensure every connection performs a full handshake
+            crypto.resumption = rustls::client::Resumption::disabled();
+        }
+    }
 
     if opt.common.keylog {
         crypto.key_log = Arc::new(rustls::KeyLogFile::new());
@@ -134,6 +151,10 @@ pub async fn run(opt: Opt) -> Result<()> {
     });
     config.transport_config(Arc::new(transport));
 
+    if opt.handshakes_per_second {
+        return run_handshakes(&opt, &endpoint, config, addr, host_name).await;
+    }
+
     let stream_stats = OpenStreamStats::default();
 
     let connection = endpoint
@@ -212,6 +233,97 @@ pub async fn run(opt: Opt) -> Result<()> {
     Ok(())
 }
 
+/// Repeatedly establishes and closes connections until interrupted, reporting the handshake rate
+async fn run_handshakes(
+    opt: &Opt,
+    endpoint: &quinn::Endpoint,
+    config: quinn::ClientConfig,
+    addr: SocketAddr,
+    host_name: &str,
+) -> Result<()> {
+    let handshake_stats = HandshakeStats::default();
+
+    let mut stats = Stats::default();
+
+    let stats_fut = async {
+        let interval_duration = Duration::from_secs(opt.interval);
+
+        #[cfg(feature = "json-output")]
+        let allow_table_output = opt.json.clone().is_none_or(|path| path != Path::new("-"));
+        #[cfg(not(feature = "json-output"))]
+        let allow_table_output = true;
+
+        loop {
+            let start = Instant::now();
+            tokio::time::sleep(interval_duration).await;
+            stats.on_handshake_interval(start, &handshake_stats);
+
+            if allow_table_output {
+                stats.print_handshakes();
+            }
+        }
+    };
+
+    let drive_fut = drive_handshakes(
+        endpoint,
+        config,
+        addr,
+        host_name,
+        opt.resume,
+        handshake_stats.clone(),
+    );
+
+    tokio::select! {
+        result = drive_fut => result?,
+        _ = stats_fut => {}
+        _ = tokio::signal::ctrl_c() => {
+            info!("shutting down");
+        }
+        // Add a small duration so the final interval can be reported
+        _ = tokio::time::sleep(Duration::from_secs(opt.duration) + Duration::from_millis(200)) => {
+            info!("shutting down");
+        }
+    }
+
+    endpoint.wait_idle().await;
+
+    #[cfg(feature = "json-output")]
+    if let Some(path) = &opt.json {
+        stats.print_json(path.as_path())?;
+    }
+
+    Ok(())
+}
+
+async fn drive_handshakes(
+    endpoint: &quinn::Endpoint,
+    config: quinn::ClientConfig,
+    addr: SocketAddr,
+    host_name: &str,
+    resume: bool,
+    handshake_stats: HandshakeStats,
+) -> Result<()> {
+    loop {
+        let start = Instant::now();
+        let connecting = endpoint.connect_with(config.clone(), addr, host_name)?;
+        let (connection, resumed) = match connecting.into_0rtt() {
+            Ok(connection) => {
+                let resumed = connection.zero_rtt_accepted().await?;
+                (connection, resumed)
+            }
+            Err(connecting) => (connecting.await.context("connecting")?, false),
+        };
+        handshake_stats.on_handshake(start.elapsed(), resumed);
+
+        if resume {
+            // The server sends session tickets along with HANDSHAKE_DONE, so wait for them to
+            // arrive before closing to make them available to the next connection
+            connection.handshake_confirmed().await?;
+        }
+        connection.close(0u32.into(), b"done");
+    }
+}
+
 async fn drain_stream(
     mut stream: quinn::RecvStream,
     download: u64,
@@ -201,6 +201,7 @@ pub fn parse_byte_size(s: &str) -> Result<u64, ParseIntError> {
 pub enum CongestionAlgorithm {
     Cubic,
     Bbr,
+    BbrV2,
     NewReno,
 }
 
@@ -209,6 +210,7 @@ impl CongestionAlgorithm {
         match self {
             Self::Cubic => Arc::new(congestion::CubicConfig::default()),
             Self::Bbr => Arc::new(congestion::BbrConfig::default()),
+            Self::BbrV2 => Arc::new(congestion::BbrV2Config::default()),
             Self::NewReno => Arc::new(congestion::NewRenoConfig::default()),
         }
     }
@@ -57,6 +57,8 @@ pub async fn run(opt: Opt) -> Result<()> {
         .with_single_cert(cert, key)
         .unwrap();
     crypto.alpn_protocols = vec![b"perf".to_vec()];
+    // Allow clients measuring the handshake rate with resumption to use 0-RTT
+    crypto.max_early_data_size = u32::MAX;
 
     if opt.common.keylog {
         crypto.key_log = Arc::new(rustls::KeyLogFile::new());
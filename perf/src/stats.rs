@@ -23,6 +23,12 @@ pub struct Stats {
     download_throughput: Histogram<u64>,
     /// The total amount of requests executed
     requests: usize,
+    /// Time from initiating a connection until its handshake completed
+    handshake_duration: Histogram<u64>,
+    /// The total amount of handshakes completed
+    handshakes: usize,
+    /// The amount of completed handshakes which resumed a previous session using 0-RTT
+    resumed_handshakes: usize,
     /// Stats accumulated over each interval
     intervals: Vec<Interval>,
 }
@@ -38,6 +44,9 @@ impl Default for Stats {
             upload_throughput: Histogram::new(3).unwrap(),
             download_throughput: Histogram::new(3).unwrap(),
             requests: 0,
+            handshake_duration: Histogram::new(3).unwrap(),
+            handshakes: 0,
+            resumed_handshakes: 0,
             intervals: vec![],
         }
     }
@@ -58,6 +67,26 @@ impl Stats {
         self.intervals.push(interval);
     }
 
+    pub fn on_handshake_interval(&mut self, start: Instant, handshake_stats: &HandshakeStats) {
+        let mut interval = Interval::new(start - self.start_instant, self.start_instant.elapsed());
+        let mut interval_handshakes = HandshakeIntervalStats::default();
+
+        for handshake in handshake_stats.0.lock().unwrap().drain(..) {
+            self.handshake_duration
+                .record(handshake.duration.as_micros() as u64)
+                .unwrap();
+            self.handshakes += 1;
+            interval_handshakes.handshakes += 1;
+            if handshake.resumed {
+                self.resumed_handshakes += 1;
+                interval_handshakes.resumed += 1;
+            }
+        }
+
+        interval.handshakes = Some(interval_handshakes);
+        self.intervals.push(interval);
+    }
+
     fn record(&mut self, stream_stats: Arc<StreamStats>) {
         if stream_stats.finished.load(Ordering::SeqCst) {
             let duration = stream_stats.duration.load(Ordering::SeqCst);
@@ -118,6 +147,39 @@ impl Stats {
         println!();
     }
 
+    pub fn print_handshakes(&self) {
+        let dt = self.start_instant.elapsed();
+        let hps = self.handshakes as f64 / dt.as_secs_f64();
+
+        println!("Overall stats:");
+        println!(
+            "HPS: {:.2} ({} handshakes, {} resumed, in {:4.2?})",
+            hps, self.handshakes, self.resumed_handshakes, dt,
+        );
+        println!();
+
+        println!("Handshake metrics:\n");
+
+        println!("      │ Handshake Duration");
+        println!("──────┼───────────────────");
+
+        let print_metric = |label: &'static str, get_metric: fn(&Histogram<u64>) -> u64| {
+            println!(
+                " {} │ {:>18.2?}",
+                label,
+                Duration::from_micros(get_metric(&self.handshake_duration)),
+            );
+        };
+
+        print_metric("AVG ", |hist| hist.mean() as u64);
+        print_metric("P0  ", |hist| hist.value_at_quantile(0.00));
+        print_metric("P10 ", |hist| hist.value_at_quantile(0.10));
+        print_metric("P50 ", |hist| hist.value_at_quantile(0.50));
+        print_metric("P90 ", |hist| hist.value_at_quantile(0.90));
+        print_metric("P100", |hist| hist.value_at_quantile(1.00));
+        println!();
+    }
+
     #[cfg(feature = "json-output")]
     pub fn print_json(&self, path: &Path) -> io::Result<()> {
         if path == Path::new("-") {
@@ -170,6 +232,24 @@ impl OpenStreamStats {
     }
 }
 
+/// Handshakes completed since statistics were last collected
+#[derive(Clone, Default)]
+pub struct HandshakeStats(Arc<Mutex<Vec<HandshakeRecord>>>);
+
+impl HandshakeStats {
+    pub fn on_handshake(&self, duration: Duration, resumed: bool) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(HandshakeRecord { duration, resumed });
+    }
+}
+
+struct HandshakeRecord {
+    duration: Duration,
+    resumed: bool,
+}
+
 pub struct StreamStats {
     id: StreamId,
     request_size: u64,
@@ -199,6 +279,8 @@ impl StreamStats {
 
 struct Interval {
     streams: Vec<StreamIntervalStats>,
+    /// Handshakes completed during the interval, when measuring the handshake rate
+    handshakes: Option<HandshakeIntervalStats>,
     period: IntervalPeriod,
 }
 
@@ -212,6 +294,7 @@ impl Interval {
 
         Self {
             streams: vec![],
+            handshakes: None,
             period,
         }
     }
@@ -238,6 +321,12 @@ struct StreamIntervalStats {
     sender: bool,
 }
 
+#[derive(Default)]
+struct HandshakeIntervalStats {
+    handshakes: usize,
+    resumed: usize,
+}
+
 fn throughput_bytes_per_second(duration_in_micros: u64, size: u64) -> f64 {
     (size as f64) / (duration_in_micros as f64 / 1000000.0)
 }
@@ -245,7 +334,8 @@ fn throughput_bytes_per_second(duration_in_micros: u64, size: u64) -> f64 {
 #[cfg(feature = "json-output")]
 mod json {
     use crate::stats;
-    use crate::stats::{Stats, StreamIntervalStats};
+    use crate::stats::{HandshakeIntervalStats, Stats, StreamIntervalStats};
+    use hdrhistogram::Histogram;
     use quinn::StreamId;
     use serde::{self, Serialize, Serializer, ser::SerializeStruct};
     use std::io::Write;
@@ -261,6 +351,7 @@ mod json {
                 .iter()
                 .map(Interval::from_stats_interval)
                 .collect(),
+            handshakes: (stats.handshakes > 0).then(|| Handshakes::from_stats(stats)),
         };
 
         serde_json::to_writer(out, &report).unwrap();
@@ -270,9 +361,58 @@ mod json {
     struct Report<'a> {
         start: Start,
         intervals: &'a Vec<Interval>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        handshakes: Option<Handshakes>,
         // TODO: add end stats
     }
 
+    /// Summary of the handshakes completed over the whole test
+    #[derive(Serialize)]
+    struct Handshakes {
+        handshakes: usize,
+        resumed: usize,
+        handshakes_per_second: f64,
+        latency_micros: Latency,
+    }
+
+    impl Handshakes {
+        fn from_stats(stats: &Stats) -> Self {
+            let seconds = stats
+                .intervals
+                .last()
+                .map_or(0.0, |interval| interval.period.end);
+            Self {
+                handshakes: stats.handshakes,
+                resumed: stats.resumed_handshakes,
+                handshakes_per_second: stats.handshakes as f64 / seconds,
+                latency_micros: Latency::from_histogram(&stats.handshake_duration),
+            }
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Latency {
+        mean: f64,
+        p0: u64,
+        p10: u64,
+        p50: u64,
+        p90: u64,
+        p100: u64,
+    }
+
+    impl Latency {
+        fn from_histogram(hist: &Histogram<u64>) -> Self {
+            Self {
+                mean: hist.mean(),
+                p0: hist.value_at_quantile(0.00),
+                p10: hist.value_at_quantile(0.10),
+                p50: hist.value_at_quantile(0.50),
+                p90: hist.value_at_quantile(0.90),
+                p100: hist.value_at_quantile(1.00),
+            }
+        }
+    }
+
     #[derive(Serialize)]
     struct Start {
         #[serde(serialize_with = "serialize_timestamp")]
@@ -296,6 +436,7 @@ mod json {
         streams: Vec<Stream>,
         recv_sum: Sum,
         send_sum: Sum,
+        handshakes: Option<HandshakeSum>,
     }
 
     impl Interval {
@@ -316,6 +457,9 @@ mod json {
                     &interval.period,
                     true,
                 ),
+                handshakes: interval.handshakes.as_ref().map(|stats| {
+                    HandshakeSum::from_handshake_interval_stats(stats, &interval.period)
+                }),
             }
         }
     }
@@ -328,7 +472,8 @@ mod json {
         where
             S: Serializer,
         {
-            let mut state = serializer.serialize_struct("Interval", 2)?;
+            let len = if self.handshakes.is_some() { 3 } else { 2 };
+            let mut state = serializer.serialize_struct("Interval", len)?;
             state.serialize_field("streams", &self.streams)?;
             // iperf3 outputs duplicate "sum" entries when run in bidirectional mode
             // serde does not support duplicate keys, so only output one of the sums
@@ -337,6 +482,9 @@ mod json {
             } else {
                 state.serialize_field("sum", &self.recv_sum)?;
             }
+            if let Some(handshakes) = &self.handshakes {
+                state.serialize_field("handshakes", handshakes)?;
+            }
             state.end()
         }
     }
@@ -409,4 +557,30 @@ mod json {
             }
         }
     }
+
+    #[derive(Serialize)]
+    struct HandshakeSum {
+        start: f64,
+        end: f64,
+        seconds: f64,
+        handshakes: usize,
+        resumed: usize,
+        handshakes_per_second: f64,
+    }
+
+    impl HandshakeSum {
+        fn from_handshake_interval_stats(
+            stats: &HandshakeIntervalStats,
+            period: &stats::IntervalPeriod,
+        ) -> Self {
+            Self {
+                start: period.start,
+                end: period.end,
+                seconds: period.seconds,
+                handshakes: stats.handshakes,
+                resumed: stats.resumed,
+                handshakes_per_second: stats.handshakes as f64 / period.seconds,
+            }
+        }
+    }
 }
@@ -1,6 +1,6 @@
 use std::hash::Hasher;
 
-use rand::{Rng, RngExt};
+use rand::{Rng, RngExt, SeedableRng, rngs::StdRng};
 
 use crate::Duration;
 use crate::MAX_CID_SIZE;
@@ -17,6 +17,19 @@ pub trait ConnectionIdGenerator: Send + Sync {
     /// or cryptographic-grade random data.
     fn generate_cid(&mut self) -> ConnectionId;
 
+    /// Generates a new CID for a connection to which `active_cids` CIDs are currently issued
+    ///
+    /// Used in place of [`generate_cid`](Self::generate_cid) whenever a CID is issued for a
+    /// particular connection, including when the peer retires one and a replacement is needed.
+    /// How often that happens is governed by [`cid_lifetime`](Self::cid_lifetime) and the peer's
+    /// `active_connection_id_limit`. The same requirements on the result apply.
+    ///
+    /// Defaults to calling [`generate_cid`](Self::generate_cid).
+    fn generate_cid_for_connection(&mut self, active_cids: usize) -> ConnectionId {
+        let _ = active_cids;
+        self.generate_cid()
+    }
+
     /// Quickly determine whether `cid` could have been generated by this generator
     ///
     /// False positives are permitted, but increase the cost of handling invalid packets.
@@ -44,6 +57,9 @@ pub struct InvalidCid;
 pub struct RandomConnectionIdGenerator {
     cid_len: usize,
     lifetime: Option<Duration>,
+    rng_seed: Option<[u8; 32]>,
+    /// Number of CIDs generated so far, mixed into `rng_seed`
+    generated: u64,
 }
 
 impl Default for RandomConnectionIdGenerator {
@@ -51,6 +67,8 @@ impl Default for RandomConnectionIdGenerator {
         Self {
             cid_len: 8,
             lifetime: None,
+            rng_seed: None,
+            generated: 0,
         }
     }
 }
@@ -72,12 +90,32 @@ impl RandomConnectionIdGenerator {
         self.lifetime = Some(d);
         self
     }
+
+    /// Derive CIDs deterministically from `seed` rather than the thread-local rng
+    ///
+    /// Generators created with the same seed produce the same sequence of CIDs, which makes tests
+    /// reproducible. See [`EndpointConfig::rng_seed()`](crate::EndpointConfig::rng_seed) for the
+    /// risks of using this outside of tests.
+    pub fn set_rng_seed(&mut self, seed: [u8; 32]) -> &mut Self {
+        self.rng_seed = Some(seed);
+        self.generated = 0;
+        self
+    }
 }
 
 impl ConnectionIdGenerator for RandomConnectionIdGenerator {
     fn generate_cid(&mut self) -> ConnectionId {
         let mut bytes_arr = [0; MAX_CID_SIZE];
-        rand::rng().fill_bytes(&mut bytes_arr[..self.cid_len]);
+        match self.rng_seed {
+            Some(mut seed) => {
+                for (x, y) in seed.iter_mut().zip(self.generated.to_le_bytes()) {
+                    *x ^= y;
+                }
+                self.generated += 1;
+                StdRng::from_seed(seed).fill_bytes(&mut bytes_arr[..self.cid_len]);
+            }
+            None => rand::rng().fill_bytes(&mut bytes_arr[..self.cid_len]),
+        }
 
         ConnectionId::new(&bytes_arr[..self.cid_len])
     }
@@ -164,6 +202,85 @@ impl ConnectionIdGenerator for HashedConnectionIdGenerator {
     }
 }
 
+/// Generates random connection IDs whose first byte identifies one of several endpoints
+///
+/// Intended for servers that spread one address across a group of endpoints (shards), e.g. by
+/// binding each shard's socket with `SO_REUSEPORT`. The kernel picks a shard by hashing the
+/// 4-tuple, which changes whenever a peer migrates or is rebound by a NAT. Because every CID issued
+/// by a shard starts with that shard's index, a packet landing on the wrong shard can still be
+/// steered to the one owning its connection using [`shard_of`](Self::shard_of), by the receiving
+/// shard itself or by a load balancer in front of it.
+///
+/// The shard index is visible to on-path observers, which can use it to tell apart connections
+/// handled by different shards, but not to link CIDs of the same connection to each other.
+#[derive(Debug, Clone, Copy)]
+pub struct ShardedConnectionIdGenerator {
+    shard: u8,
+    cid_len: usize,
+    lifetime: Option<Duration>,
+}
+
+impl ShardedConnectionIdGenerator {
+    /// Create a generator for the shard with index `shard`, producing CIDs of `cid_len` bytes
+    ///
+    /// # Panics
+    /// - when `cid_len` is 0 or greater than 20, the maximum CID length
+    pub fn new(shard: u8, cid_len: usize) -> Self {
+        assert!(
+            (1..=MAX_CID_SIZE).contains(&cid_len),
+            "invalid CID length {cid_len}"
+        );
+        Self {
+            shard,
+            cid_len,
+            lifetime: None,
+        }
+    }
+
+    /// Set the lifetime of CIDs created by this generator
+    pub fn set_lifetime(&mut self, d: Duration) -> &mut Self {
+        self.lifetime = Some(d);
+        self
+    }
+
+    /// Index of the shard whose CIDs this generator produces
+    pub fn shard(&self) -> u8 {
+        self.shard
+    }
+
+    /// Index of the shard that issued `cid`, if it was generated by a `ShardedConnectionIdGenerator`
+    ///
+    /// Returns `None` only if `cid` is empty. CIDs from other sources decode to an arbitrary
+    /// shard, so callers must be prepared for indices that don't match any shard.
+    pub fn shard_of(cid: &[u8]) -> Option<u8> {
+        cid.first().copied()
+    }
+}
+
+impl ConnectionIdGenerator for ShardedConnectionIdGenerator {
+    fn generate_cid(&mut self) -> ConnectionId {
+        let mut bytes_arr = [0; MAX_CID_SIZE];
+        bytes_arr[0] = self.shard;
+        rand::rng().fill_bytes(&mut bytes_arr[1..self.cid_len]);
+        ConnectionId::new(&bytes_arr[..self.cid_len])
+    }
+
+    fn validate(&self, cid: ConnectionId) -> Result<(), InvalidCid> {
+        match Self::shard_of(&cid) == Some(self.shard) {
+            true => Ok(()),
+            false => Err(InvalidCid),
+        }
+    }
+
+    fn cid_len(&self) -> usize {
+        self.cid_len
+    }
+
+    fn cid_lifetime(&self) -> Option<Duration> {
+        self.lifetime
+    }
+}
+
 const NONCE_LEN: usize = 3; // Good for more than 16 million connections
 const SIGNATURE_LEN: usize = 8 - NONCE_LEN; // 8-byte total CID length
 
@@ -177,4 +294,34 @@ mod tests {
         let cid = generator.generate_cid();
         generator.validate(cid).unwrap();
     }
+
+    #[test]
+    fn sharded_cids() {
+        let mut generator = ShardedConnectionIdGenerator::new(5, 8);
+        for _ in 0..4 {
+            let cid = generator.generate_cid();
+            assert_eq!(cid.len(), 8);
+            assert_eq!(ShardedConnectionIdGenerator::shard_of(&cid), Some(5));
+            generator.validate(cid).unwrap();
+        }
+        let other = ShardedConnectionIdGenerator::new(6, 8).generate_cid();
+        assert!(generator.validate(other).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid CID length 0")]
+    fn sharded_cids_require_shard_byte() {
+        ShardedConnectionIdGenerator::new(5, 0);
+    }
+
+    #[test]
+    fn seeded_random_cids() {
+        let mut a = RandomConnectionIdGenerator::new(8);
+        a.set_rng_seed([7; 32]);
+        let mut b = a;
+        let cids = (0..3).map(|_| a.generate_cid()).collect::<Vec<_>>();
+        assert_eq!(cids, (0..3).map(|_| b.generate_cid()).collect::<Vec<_>>());
+        assert_ne!(cids[0], cids[1]);
+        assert_ne!(cids[1], cids[2]);
+    }
 }
@@ -1,6 +1,6 @@
 use std::{
     fmt,
-    net::{SocketAddrV4, SocketAddrV6},
+    net::{SocketAddr, SocketAddrV4, SocketAddrV6},
     num::TryFromIntError,
     sync::Arc,
 };
@@ -16,10 +16,11 @@ use crate::BloomTokenLog;
 #[cfg(not(feature = "bloom"))]
 use crate::NoneTokenLog;
 #[cfg(any(feature = "rustls-aws-lc-rs", feature = "rustls-ring"))]
-use crate::crypto::rustls::{QuicServerConfig, configured_provider};
+use crate::crypto::rustls::{QuicServerConfig, ResolvesServerConfig, configured_provider};
 use crate::{
-    DEFAULT_SUPPORTED_VERSIONS, Duration, MAX_CID_SIZE, RandomConnectionIdGenerator, SystemTime,
-    TokenLog, TokenMemoryCache, TokenStore, VarInt, VarIntBoundsExceeded,
+    DEFAULT_SUPPORTED_VERSIONS, Duration, Instant, MAX_CID_SIZE, RandomConnectionIdGenerator,
+    RttCache, Side, SystemTime, TokenGenerator, TokenLog, TokenMemoryCache, TokenStore,
+    TokenValidator, VarInt, VarIntBoundsExceeded,
     cid_generator::{ConnectionIdGenerator, HashedConnectionIdGenerator},
     crypto::{self, HandshakeTokenKey, HmacKey},
     shared::ConnectionId,
@@ -28,7 +29,10 @@ use crate::{
 mod transport;
 #[cfg(feature = "qlog")]
 pub use transport::QlogConfig;
-pub use transport::{AckFrequencyConfig, IdleTimeout, MtuDiscoveryConfig, TransportConfig};
+pub use transport::{
+    AckFrequencyConfig, EcnPolicy, IdleTimeout, KeepAlivePolicy, MtuDiscoveryConfig,
+    TransportConfig,
+};
 
 /// Global configuration for the endpoint, affecting all connections
 ///
@@ -48,6 +52,11 @@ pub struct EndpointConfig {
     pub(crate) min_reset_interval: Duration,
     /// Optional seed to be used internally for random number generation
     pub(crate) rng_seed: Option<[u8; 32]>,
+    pub(crate) max_receive_segments: Option<usize>,
+    /// Round-trip times remembered across connections
+    pub(crate) rtt_cache: Option<Arc<dyn RttCache>>,
+    pub(crate) disable_gso: bool,
+    pub(crate) max_send_rate: Option<u64>,
 }
 
 impl EndpointConfig {
@@ -63,6 +72,10 @@ impl EndpointConfig {
             grease_quic_bit: true,
             min_reset_interval: Duration::from_millis(20),
             rng_seed: None,
+            max_receive_segments: None,
+            rtt_cache: None,
+            disable_gso: false,
+            max_send_rate: None,
         }
     }
 
@@ -119,6 +132,10 @@ impl EndpointConfig {
     }
 
     /// Override supported QUIC versions
+    ///
+    /// Incoming packets for any other version are answered with a Version Negotiation packet
+    /// listing these versions in the given order. Use [`ClientConfig::version()`] to choose the
+    /// version outgoing connections start with.
     pub fn supported_versions(&mut self, supported_versions: Vec<u32>) -> &mut Self {
         self.supported_versions = supported_versions;
         self
@@ -155,10 +172,85 @@ impl EndpointConfig {
     /// However, you can seed the rng yourself through this method (e.g. if you need to run quinn
     /// deterministically or if you are using quinn in an environment that doesn't have a source of
     /// entropy available).
+    ///
+    /// The endpoint's rng drives retry tokens, stateless reset padding, and the per-connection rngs
+    /// used for e.g. PATH_CHALLENGE data and packet number skipping. Connection IDs are created by
+    /// the [`cid_generator()`](Self::cid_generator) instead; use
+    /// [`RandomConnectionIdGenerator::set_rng_seed()`] to make those deterministic as well. TLS
+    /// randomness comes from the crypto provider and is not affected.
+    ///
+    /// The seed must be secret and unique to be secure: reusing a seed across real connections
+    /// makes their connection IDs, tokens and challenges predictable to anyone who knows it.
     pub fn rng_seed(&mut self, seed: Option<[u8; 32]>) -> &mut Self {
         self.rng_seed = seed;
         self
     }
+
+    /// Maximum number of coalesced datagrams to read from the socket at once
+    ///
+    /// Applies where the socket supports receive offloading, e.g. UDP GRO on Linux. There, only 1,
+    /// which disables offloading and shrinks the endpoint's receive buffer accordingly, or the
+    /// platform's limit (64 segments on current Linux kernels) are accepted, and constructing an
+    /// endpoint with any other value fails. Defaults to `None`, which keeps the socket's own
+    /// default.
+    pub fn max_receive_segments(&mut self, value: Option<usize>) -> &mut Self {
+        self.max_receive_segments = value;
+        self
+    }
+
+    /// Remember round-trip times to peers across connections
+    ///
+    /// Each connection consults the cache for its peer's address when it's created, starting its
+    /// RTT estimate from the cached value instead of [`TransportConfig::initial_rtt()`], and
+    /// stores its latest estimate when it closes. Useful for clients which repeatedly connect to
+    /// the same servers, as a good initial estimate speeds up early loss detection. Defaults to
+    /// `None`; [`RttMemoryCache`](crate::RttMemoryCache) is a suitable implementation.
+    pub fn rtt_cache(&mut self, cache: Option<Arc<dyn RttCache>>) -> &mut Self {
+        self.rtt_cache = cache;
+        self
+    }
+
+    /// Never batch outgoing datagrams with segmentation offload, even where it's supported
+    ///
+    /// Segmentation offload (GSO/`UDP_SEGMENT` on Linux, USO on Windows) is normally used wherever
+    /// probing finds it available. Some environments, notably certain virtualized network devices
+    /// on Linux, pass the probe but then silently drop segmented datagrams; enabling this works
+    /// around them at the cost of one system call per datagram. Defaults to `false`.
+    pub fn disable_gso(&mut self, value: bool) -> &mut Self {
+        self.disable_gso = value;
+        self
+    }
+
+    /// Get the current value of [`disable_gso`](Self::disable_gso)
+    pub fn get_disable_gso(&self) -> bool {
+        self.disable_gso
+    }
+
+    /// Maximum rate, in bytes per second, at which all connections of the endpoint may send
+    ///
+    /// Enforced by the I/O layer, which defers a connection's transmissions while the endpoint as
+    /// a whole has exceeded its budget, in addition to each connection's own congestion control
+    /// and pacing. Useful to keep many connections sharing an uplink from saturating it with
+    /// simultaneous bursts. Short bursts of up to 20ms worth of data may still be sent at once.
+    /// Stateless responses, such as version negotiation, aren't counted. Defaults to `None`,
+    /// which disables the limit.
+    pub fn max_send_rate(&mut self, value: Option<u64>) -> &mut Self {
+        self.max_send_rate = value;
+        self
+    }
+
+    /// Get the current value of [`max_send_rate`](Self::max_send_rate)
+    pub fn get_max_send_rate(&self) -> Option<u64> {
+        self.max_send_rate
+    }
+
+    /// Get the current value of [`max_receive_segments`](Self::max_receive_segments)
+    //
+    // Exposed for the same reason as `get_max_udp_payload_size`: the socket is owned by
+    // higher-level layers.
+    pub fn get_max_receive_segments(&self) -> Option<usize> {
+        self.max_receive_segments
+    }
 }
 
 impl fmt::Debug for EndpointConfig {
@@ -170,6 +262,10 @@ impl fmt::Debug for EndpointConfig {
             .field("supported_versions", &self.supported_versions)
             .field("grease_quic_bit", &self.grease_quic_bit)
             .field("rng_seed", &self.rng_seed)
+            .field("max_receive_segments", &self.max_receive_segments)
+            // rtt_cache not debug
+            .field("disable_gso", &self.disable_gso)
+            .field("max_send_rate", &self.max_send_rate)
             .finish_non_exhaustive()
     }
 }
@@ -212,20 +308,29 @@ pub struct ServerConfig {
     /// Duration after a retry token was issued for which it's considered valid
     pub(crate) retry_token_lifetime: Duration,
 
+    pub(crate) retry_token_generator: Option<Arc<dyn TokenGenerator>>,
+    pub(crate) retry_token_validator: Option<Arc<dyn TokenValidator>>,
+
     /// Whether to allow clients to migrate to new addresses
     ///
     /// Improves behavior for clients that move between different internet connections or suffer NAT
     /// rebinding. Enabled by default.
     pub(crate) migration: bool,
 
+    pub(crate) anti_amplification_factor: u8,
+
     pub(crate) preferred_address_v4: Option<SocketAddrV4>,
     pub(crate) preferred_address_v6: Option<SocketAddrV6>,
 
     pub(crate) max_incoming: usize,
+    pub(crate) max_concurrent_connections: usize,
     pub(crate) incoming_buffer_size: u64,
     pub(crate) incoming_buffer_size_total: u64,
 
     pub(crate) time_source: Arc<dyn TimeSource>,
+
+    pub(crate) connection_rate_limiter: Option<Arc<dyn ConnectionRateLimiter>>,
+    pub(crate) refuse_rate_limited: bool,
 }
 
 impl ServerConfig {
@@ -240,19 +345,27 @@ impl ServerConfig {
 
             token_key,
             retry_token_lifetime: Duration::from_secs(15),
+            retry_token_generator: None,
+            retry_token_validator: None,
 
             migration: true,
 
+            anti_amplification_factor: 3,
+
             validation_token: ValidationTokenConfig::default(),
 
             preferred_address_v4: None,
             preferred_address_v6: None,
 
             max_incoming: 1 << 16,
+            max_concurrent_connections: usize::MAX,
             incoming_buffer_size: 10 << 20,
             incoming_buffer_size_total: 100 << 20,
 
             time_source: Arc::new(StdSystemTime),
+
+            connection_rate_limiter: None,
+            refuse_rate_limited: false,
         }
     }
 
@@ -285,15 +398,63 @@ impl ServerConfig {
         self
     }
 
+    /// Custom generator for the tokens sent in Retry packets
+    ///
+    /// Defaults to `None`, which uses tokens encrypted with [`token_key`](Self::token_key).
+    pub fn retry_token_generator(&mut self, value: Option<Arc<dyn TokenGenerator>>) -> &mut Self {
+        self.retry_token_generator = value;
+        self
+    }
+
+    /// Custom validator for tokens presented following a Retry packet
+    ///
+    /// Tokens the validator doesn't recognize are still checked as built-in tokens. Defaults to
+    /// `None`.
+    pub fn retry_token_validator(&mut self, value: Option<Arc<dyn TokenValidator>>) -> &mut Self {
+        self.retry_token_validator = value;
+        self
+    }
+
     /// Whether to allow clients to migrate to new addresses
     ///
     /// Improves behavior for clients that move between different internet connections or suffer NAT
     /// rebinding. Enabled by default.
+    ///
+    /// When disabled, the `disable_active_migration` transport parameter is sent to clients, and
+    /// packets from addresses other than the connection's current one are ignored. NAT rebinding
+    /// is still tolerated: a client changing only its port is assumed to be subject to rebinding
+    /// and is followed to its new address after path validation.
     pub fn migration(&mut self, value: bool) -> &mut Self {
         self.migration = value;
         self
     }
 
+    /// Maximum ratio of bytes sent to bytes received on a path whose address hasn't been validated
+    ///
+    /// Bounds how much traffic an attacker spoofing a victim's address can cause the server to
+    /// direct at the victim. Lower values may delay handshakes whose first flight exceeds the
+    /// permitted budget. Values of 0 are treated as 1, and values greater than 3, the maximum
+    /// permitted by RFC 9000, are treated as 3; see
+    /// [`insecure_anti_amplification_factor()`](Self::insecure_anti_amplification_factor) to
+    /// exceed it.
+    ///
+    /// Defaults to 3.
+    pub fn anti_amplification_factor(&mut self, value: u8) -> &mut Self {
+        self.anti_amplification_factor = value.clamp(1, 3);
+        self
+    }
+
+    /// Like [`anti_amplification_factor()`](Self::anti_amplification_factor), but permits values
+    /// greater than 3
+    ///
+    /// Larger values let handshakes with large certificate chains complete sooner, but violate RFC
+    /// 9000 and let the server be used to amplify attacks. Only appropriate when every peer that
+    /// could reach the server is trusted. Values of 0 are treated as 1.
+    pub fn insecure_anti_amplification_factor(&mut self, value: u8) -> &mut Self {
+        self.anti_amplification_factor = value.max(1);
+        self
+    }
+
     /// The preferred IPv4 address that will be communicated to clients during handshaking
     ///
     /// If the client is able to reach this address, it will switch to it.
@@ -326,6 +487,20 @@ impl ServerConfig {
         self
     }
 
+    /// Maximum number of connections to allow to exist at a time, including incoming connection
+    /// attempts not yet accepted or refused
+    ///
+    /// While this limit is reached, new incoming connection attempts are refused with a
+    /// `CONNECTION_REFUSED` error before any connection state is allocated for them. Unlike
+    /// [`max_incoming()`](Self::max_incoming), this bounds the total memory used by connections
+    /// rather than the backlog of attempts awaiting a decision from the application.
+    ///
+    /// Unlimited by default.
+    pub fn max_concurrent_connections(&mut self, max_concurrent_connections: usize) -> &mut Self {
+        self.max_concurrent_connections = max_concurrent_connections;
+        self
+    }
+
     /// Maximum number of received bytes to buffer for each [`Incoming`][crate::Incoming]
     ///
     /// An [`Incoming`][crate::Incoming] comes into existence when an incoming connection attempt
@@ -368,6 +543,35 @@ impl ServerConfig {
         self
     }
 
+    /// Admission check run on each incoming connection attempt, e.g. to limit the rate of
+    /// attempts per source address
+    ///
+    /// The limiter is consulted before any token validation or cryptographic work, so rejecting
+    /// an attempt is cheap. Rejected attempts are dropped without a response, unless `refuse` is
+    /// set, in which case the client is sent a `CONNECTION_REFUSED` error at the cost of deriving
+    /// the initial keys needed to do so.
+    ///
+    /// Defaults to `None`, admitting every attempt.
+    pub fn connection_rate_limiter(
+        &mut self,
+        limiter: Option<Arc<dyn ConnectionRateLimiter>>,
+        refuse: bool,
+    ) -> &mut Self {
+        self.connection_rate_limiter = limiter;
+        self.refuse_rate_limited = refuse;
+        self
+    }
+
+    /// Perform the cryptographic provider's one-time initialization ahead of the first connection
+    ///
+    /// Derives and discards a set of initial keys, so that the first incoming connection doesn't
+    /// bear the cost of lazily initializing the crypto library, e.g. detecting CPU features.
+    pub fn prewarm(&self) {
+        let _ = self
+            .crypto
+            .initial_keys(DEFAULT_SUPPORTED_VERSIONS[0], ConnectionId::new(&[0; 8]));
+    }
+
     pub(crate) fn has_preferred_address(&self) -> bool {
         self.preferred_address_v4.is_some() || self.preferred_address_v6.is_some()
     }
@@ -386,6 +590,18 @@ impl ServerConfig {
             cert_chain, key,
         )?)))
     }
+
+    /// Create a server config which chooses the TLS configuration for each connection according
+    /// to the server name the client requests
+    ///
+    /// Each connection's certificate and client authentication requirements, including the trust
+    /// anchors client certificates are verified against, come from the [`rustls::ServerConfig`]
+    /// returned by `resolver`. See [`QuicServerConfig::with_resolver()`].
+    ///
+    /// Uses a randomized handshake token key.
+    pub fn with_cert_resolver(resolver: Arc<dyn ResolvesServerConfig>) -> Self {
+        Self::with_crypto(Arc::new(QuicServerConfig::with_resolver(resolver)))
+    }
 }
 
 #[cfg(any(feature = "aws-lc-rs", feature = "ring"))]
@@ -416,17 +632,26 @@ impl fmt::Debug for ServerConfig {
             // crypto not debug
             // token not debug
             .field("retry_token_lifetime", &self.retry_token_lifetime)
+            // retry_token_generator not debug
+            // retry_token_validator not debug
             .field("validation_token", &self.validation_token)
             .field("migration", &self.migration)
+            .field("anti_amplification_factor", &self.anti_amplification_factor)
             .field("preferred_address_v4", &self.preferred_address_v4)
             .field("preferred_address_v6", &self.preferred_address_v6)
             .field("max_incoming", &self.max_incoming)
+            .field(
+                "max_concurrent_connections",
+                &self.max_concurrent_connections,
+            )
             .field("incoming_buffer_size", &self.incoming_buffer_size)
             .field(
                 "incoming_buffer_size_total",
                 &self.incoming_buffer_size_total,
             )
             // system_time_clock not debug
+            // connection_rate_limiter not debug
+            .field("refuse_rate_limited", &self.refuse_rate_limited)
             .finish_non_exhaustive()
     }
 }
@@ -565,6 +790,9 @@ pub struct ClientConfig {
 
     /// QUIC protocol version to use
     pub(crate) version: u32,
+
+    /// Whether to resend 0-RTT stream data in 1-RTT packets if the server rejects 0-RTT
+    pub(crate) retransmit_rejected_0rtt: bool,
 }
 
 impl ClientConfig {
@@ -578,6 +806,7 @@ impl ClientConfig {
                 RandomConnectionIdGenerator::new(MAX_CID_SIZE).generate_cid()
             }),
             version: 1,
+            retransmit_rejected_0rtt: false,
         }
     }
 
@@ -616,6 +845,42 @@ impl ClientConfig {
         self.version = version;
         self
     }
+
+    /// Whether to resend stream data written in 0-RTT if the server rejects 0-RTT
+    ///
+    /// By default, data sent in rejected 0-RTT packets is discarded, and the affected streams are
+    /// reported by `Connection::zero_rtt_rejected_streams` so the application can decide whether to
+    /// replay them. When enabled, the data is instead queued for retransmission in 1-RTT packets
+    /// on the same streams once the handshake completes, as long as the server's new transport
+    /// parameters are at least as permissive as those the 0-RTT data was sent under.
+    ///
+    /// Only enable this if everything sent in 0-RTT is idempotent. The server may well have acted
+    /// on the data before rejecting it, e.g. if it was received by a different server instance,
+    /// and rejection can also mean the connection negotiated a different application protocol.
+    ///
+    /// Defaults to `false`.
+    pub fn retransmit_rejected_0rtt(&mut self, value: bool) -> &mut Self {
+        self.retransmit_rejected_0rtt = value;
+        self
+    }
+
+    /// Perform the cryptographic provider's one-time initialization ahead of the first connection
+    ///
+    /// Starts and discards a handshake, deriving initial keys and generating a ClientHello, so
+    /// that the first connection attempt doesn't bear the cost of lazily initializing the crypto
+    /// library, e.g. detecting CPU features. Nothing is sent, and the session store is not
+    /// consulted for any real server name.
+    pub fn prewarm(&self) {
+        let Ok(mut session) = self.crypto.clone().start_session(
+            self.version,
+            "prewarm.invalid",
+            &crate::transport_parameters::TransportParameters::default(),
+        ) else {
+            return;
+        };
+        let _ = session.initial_keys(ConnectionId::new(&[0; 8]), Side::Client);
+        let _ = session.write_handshake(&mut Vec::new());
+    }
 }
 
 #[cfg(any(feature = "rustls-aws-lc-rs", feature = "rustls-ring"))]
@@ -645,6 +910,7 @@ impl fmt::Debug for ClientConfig {
             // crypto not debug
             // token_store not debug
             .field("version", &self.version)
+            .field("retransmit_rejected_0rtt", &self.retransmit_rejected_0rtt)
             .finish_non_exhaustive()
     }
 }
@@ -656,6 +922,9 @@ pub enum ConfigError {
     /// Value exceeds supported bounds
     #[error("value exceeds supported bounds")]
     OutOfBounds,
+    /// Transport parameter ID is implemented by quinn, reserved, or was supplied more than once
+    #[error("transport parameter ID is unavailable")]
+    UnavailableTransportParameter,
 }
 
 impl From<TryFromIntError> for ConfigError {
@@ -670,6 +939,16 @@ impl From<VarIntBoundsExceeded> for ConfigError {
     }
 }
 
+/// Decides whether to admit incoming connection attempts
+///
+/// See [`ServerConfig::connection_rate_limiter()`].
+pub trait ConnectionRateLimiter: Send + Sync {
+    /// Whether to proceed with a connection attempt from `remote`, received at `now`
+    ///
+    /// Called once for every connection attempt, including retries following address validation.
+    fn should_accept(&self, remote: SocketAddr, now: Instant) -> bool;
+}
+
 /// Object to get current [`SystemTime`]
 ///
 /// This exists to allow system time to be mocked in tests, or wherever else desired.
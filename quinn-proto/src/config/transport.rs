@@ -8,8 +8,11 @@ use qlog::streamer::QlogStreamer;
 #[cfg(feature = "qlog")]
 use crate::QlogStream;
 use crate::{
-    Duration, INITIAL_MTU, MAX_UDP_PAYLOAD, VarInt, VarIntBoundsExceeded, congestion,
+    ConfigError, Duration, EcnCodepoint, INITIAL_MTU, MAX_ACK_BLOCKS, MAX_UDP_PAYLOAD, VarInt,
+    VarIntBoundsExceeded, congestion,
     connection::qlog::QlogSink,
+    pacing,
+    transport_parameters::{ExtraTransportParameters, TransportParameterId},
 };
 
 /// Parameters governing the core QUIC state machine
@@ -28,6 +31,7 @@ pub struct TransportConfig {
     pub(crate) max_concurrent_bidi_streams: VarInt,
     pub(crate) max_concurrent_uni_streams: VarInt,
     pub(crate) max_idle_timeout: Option<VarInt>,
+    pub(crate) handshake_timeout: Option<Duration>,
     pub(crate) stream_receive_window: VarInt,
     pub(crate) receive_window: VarInt,
     pub(crate) send_window: u64,
@@ -36,26 +40,37 @@ pub struct TransportConfig {
     pub(crate) packet_threshold: u32,
     pub(crate) time_threshold: f32,
     pub(crate) initial_rtt: Duration,
+    pub(crate) rtt_sample_history: usize,
     pub(crate) initial_mtu: u16,
     pub(crate) min_mtu: u16,
     pub(crate) mtu_discovery_config: Option<MtuDiscoveryConfig>,
     pub(crate) pad_to_mtu: bool,
     pub(crate) ack_frequency_config: Option<AckFrequencyConfig>,
+    pub(crate) ack_delay_exponent: u8,
+    pub(crate) max_ack_ranges: usize,
     pub(crate) max_outgoing_bytes_per_second: Option<u64>,
+    pub(crate) min_pacing_rate: Option<u64>,
 
     pub(crate) persistent_congestion_threshold: u32,
     pub(crate) keep_alive_interval: Option<Duration>,
+    pub(crate) keep_alive_policy: KeepAlivePolicy,
     pub(crate) crypto_buffer_size: usize,
     pub(crate) allow_spin: bool,
+    pub(crate) outgoing_ecn: EcnPolicy,
     pub(crate) datagram_receive_buffer_size: Option<usize>,
     pub(crate) datagram_send_buffer_size: usize,
     #[cfg(test)]
     pub(crate) deterministic_packet_numbers: bool,
 
     pub(crate) congestion_controller_factory: Arc<dyn congestion::ControllerFactory + Send + Sync>,
+    pub(crate) initial_window: Option<u64>,
+
+    pub(crate) pacer_factory: Arc<dyn pacing::PacerFactory + Send + Sync>,
 
     pub(crate) enable_segmentation_offload: bool,
 
+    pub(crate) extra_transport_parameters: ExtraTransportParameters,
+
     pub(crate) qlog_sink: QlogSink,
 }
 
@@ -104,6 +119,19 @@ impl TransportConfig {
         self
     }
 
+    /// Maximum duration of the handshake before the connection attempt is abandoned
+    ///
+    /// Unlike the idle timeout, this applies regardless of whether the peer responds, and is
+    /// measured from the creation of the connection until the handshake completes. When it
+    /// elapses, the connection is discarded without notifying the peer and fails with
+    /// [`ConnectionError::HandshakeTimeout`](crate::ConnectionError::HandshakeTimeout).
+    ///
+    /// `None` to disable, which is the default.
+    pub fn handshake_timeout(&mut self, value: Option<Duration>) -> &mut Self {
+        self.handshake_timeout = value;
+        self
+    }
+
     /// Maximum number of bytes the peer may transmit without acknowledgement on any one stream
     /// before becoming blocked.
     ///
@@ -244,6 +272,46 @@ impl TransportConfig {
         self
     }
 
+    /// Exponent used to scale the ACK Delay field of the ACK frames we send
+    ///
+    /// The ACK delay we report is divided by two to the power of this value, and is advertised to
+    /// the peer in the `ack_delay_exponent` transport parameter so that it can scale it back. Lower
+    /// values report delays more precisely at the cost of slightly larger ACK frames. Values
+    /// greater than 20, the maximum permitted by RFC 9000, are treated as 20.
+    ///
+    /// Defaults to 3, i.e. a resolution of 8 microseconds.
+    pub fn ack_delay_exponent(&mut self, value: u8) -> &mut Self {
+        self.ack_delay_exponent = value.min(20);
+        self
+    }
+
+    /// Maximum number of packet number ranges to track and acknowledge per packet number space
+    ///
+    /// Heavy loss or reordering fragments the set of received packets into many ranges, each of
+    /// which enlarges ACK frames. When more ranges than this exist, those with the lowest packet
+    /// numbers are forgotten and no longer acknowledged, trading smaller ACK frames for the chance
+    /// of the peer spuriously retransmitting data it can't tell arrived. Values of 0 are treated
+    /// as 1, and values greater than the default are treated as the default, which ensures all
+    /// ranges fit in a single minimum-size packet.
+    ///
+    /// Defaults to 64.
+    pub fn max_ack_ranges(&mut self, value: usize) -> &mut Self {
+        self.max_ack_ranges = value.clamp(1, MAX_ACK_BLOCKS);
+        self
+    }
+
+    /// Number of raw RTT measurements to retain for [`Connection::rtt_samples()`]
+    ///
+    /// Each retained measurement takes up 16 bytes per path, so none are kept unless requested.
+    ///
+    /// Defaults to 0.
+    ///
+    /// [`Connection::rtt_samples()`]: crate::Connection::rtt_samples
+    pub fn rtt_sample_history(&mut self, value: usize) -> &mut Self {
+        self.rtt_sample_history = value;
+        self
+    }
+
     /// Configures an outbound rate limit (in bytes per second) for each connection.
     ///
     /// Defaults to `None`, which disables rate limiting.
@@ -252,6 +320,20 @@ impl TransportConfig {
         self
     }
 
+    /// Lower bound on each connection's sending rate, in bytes per second
+    ///
+    /// When congestion control would limit the connection to less than this rate, e.g. after
+    /// heavy loss on a high-latency link, the congestion window is raised to sustain it, so that
+    /// transfers keep making progress. Since this overrides congestion control, it risks
+    /// aggravating congestion and taking more than a fair share of a bottleneck; only use it on
+    /// links whose capacity is known to exceed the floor.
+    ///
+    /// Defaults to `None`, leaving the sending rate entirely to congestion control.
+    pub fn min_pacing_rate(&mut self, value: Option<u64>) -> &mut Self {
+        self.min_pacing_rate = value;
+        self
+    }
+
     /// Number of consecutive PTOs after which network is considered to be experiencing persistent congestion.
     pub fn persistent_congestion_threshold(&mut self, value: u32) -> &mut Self {
         self.persistent_congestion_threshold = value;
@@ -270,6 +352,15 @@ impl TransportConfig {
         self
     }
 
+    /// When keep-alive packets are sent, if [`keep_alive_interval`](Self::keep_alive_interval) is
+    /// set
+    ///
+    /// Defaults to [`KeepAlivePolicy::Always`].
+    pub fn keep_alive_policy(&mut self, value: KeepAlivePolicy) -> &mut Self {
+        self.keep_alive_policy = value;
+        self
+    }
+
     /// Maximum quantity of out-of-order crypto layer data to buffer
     pub fn crypto_buffer_size(&mut self, value: usize) -> &mut Self {
         self.crypto_buffer_size = value;
@@ -280,11 +371,26 @@ impl TransportConfig {
     ///
     /// This allows passive observers to easily judge the round trip time of a connection, which can
     /// be useful for network administration but sacrifices a small amount of privacy.
+    ///
+    /// Even when permitted, the spin bit is disabled on a random eighth of connections, as
+    /// recommended by RFC 9000. On connections where it's disabled, it's set to a random value
+    /// that changes only along with the connection ID used to address the peer. Enabled by
+    /// default.
     pub fn allow_spin(&mut self, value: bool) -> &mut Self {
         self.allow_spin = value;
         self
     }
 
+    /// How outgoing packets are marked for Explicit Congestion Notification
+    ///
+    /// Whichever codepoint is used, marking stops on a path if the peer's ECN feedback fails
+    /// validation, e.g. because the network clears the marks, and resumes on the next path.
+    /// Defaults to [`EcnPolicy::Adaptive`].
+    pub fn outgoing_ecn(&mut self, value: EcnPolicy) -> &mut Self {
+        self.outgoing_ecn = value;
+        self
+    }
+
     /// Maximum number of incoming application datagram bytes to buffer, or None to disable
     /// incoming datagrams
     ///
@@ -336,6 +442,43 @@ impl TransportConfig {
         self
     }
 
+    /// Initial congestion window in bytes, overriding that of the congestion controller's own
+    /// configuration
+    ///
+    /// Defaults to the congestion controller's own configured value, e.g. the window recommended
+    /// by [RFC 9002] for [`NewReno`](congestion::NewReno) and [`Cubic`](congestion::Cubic). Also
+    /// applies to the fresh controller constructed when a connection migrates to a new path.
+    ///
+    /// Large values let short transfers complete without waiting for the window to ramp up, and
+    /// may be appropriate on a well-provisioned, controlled network such as a datacenter fabric.
+    /// On the open internet they risk overflowing buffers at the bottleneck, causing heavy loss
+    /// for this and competing connections before congestion control can react.
+    ///
+    /// [RFC 9002]: https://www.rfc-editor.org/rfc/rfc9002.html#section-7.2
+    pub fn initial_window(&mut self, value: u64) -> &mut Self {
+        self.initial_window = Some(value);
+        self
+    }
+
+    /// How to construct new `pacing::Pacer`s
+    ///
+    /// Typically the refcounted configuration of a `pacing::Pacer`, e.g. a
+    /// `pacing::TokenBucketConfig`, which is the default.
+    ///
+    /// # Example
+    /// ```
+    /// # use quinn_proto::*; use std::sync::Arc;
+    /// let mut config = TransportConfig::default();
+    /// config.pacer_factory(Arc::new(pacing::TokenBucketConfig::default()));
+    /// ```
+    pub fn pacer_factory(
+        &mut self,
+        factory: Arc<dyn pacing::PacerFactory + Send + Sync + 'static>,
+    ) -> &mut Self {
+        self.pacer_factory = factory;
+        self
+    }
+
     /// Whether to use "Generic Segmentation Offload" to accelerate transmits, when supported by the
     /// environment
     ///
@@ -351,6 +494,29 @@ impl TransportConfig {
         self
     }
 
+    /// Additional transport parameters to advertise to the peer, as `(id, value)` pairs
+    ///
+    /// An escape hatch for experimenting with extensions quinn doesn't implement natively. The
+    /// parameters are appended verbatim to those quinn sends during the handshake, and can be
+    /// inspected by the peer with [`Connection::peer_extra_transport_params`]. IDs of parameters
+    /// quinn manages itself or reserves for greasing are rejected, as are duplicate IDs.
+    ///
+    /// [`Connection::peer_extra_transport_params`]: crate::Connection::peer_extra_transport_params
+    pub fn extra_transport_parameters(
+        &mut self,
+        params: Vec<(u64, Vec<u8>)>,
+    ) -> Result<&mut Self, ConfigError> {
+        let mut extra = ExtraTransportParameters::default();
+        for (id, value) in params {
+            VarInt::from_u64(id)?;
+            if TransportParameterId::is_managed(id) || !extra.insert(id, value) {
+                return Err(ConfigError::UnavailableTransportParameter);
+            }
+        }
+        self.extra_transport_parameters = extra;
+        Ok(self)
+    }
+
     /// qlog capture configuration to use for a particular connection
     #[cfg(feature = "qlog")]
     pub fn qlog_stream(&mut self, stream: Option<QlogStream>) -> &mut Self {
@@ -372,6 +538,7 @@ impl Default for TransportConfig {
             max_concurrent_uni_streams: 100u32.into(),
             // 30 second default recommended by RFC 9308 § 3.2
             max_idle_timeout: Some(VarInt(30_000)),
+            handshake_timeout: None,
             stream_receive_window: STREAM_RWND.into(),
             receive_window: VarInt::MAX,
             send_window: (8 * STREAM_RWND).into(),
@@ -380,17 +547,23 @@ impl Default for TransportConfig {
             packet_threshold: 3,
             time_threshold: 9.0 / 8.0,
             initial_rtt: Duration::from_millis(333), // per spec, intentionally distinct from EXPECTED_RTT
+            rtt_sample_history: 0,
             initial_mtu: INITIAL_MTU,
             min_mtu: INITIAL_MTU,
             mtu_discovery_config: Some(MtuDiscoveryConfig::default()),
             pad_to_mtu: false,
             ack_frequency_config: None,
+            ack_delay_exponent: 3,
+            max_ack_ranges: MAX_ACK_BLOCKS,
             max_outgoing_bytes_per_second: None,
+            min_pacing_rate: None,
 
             persistent_congestion_threshold: 3,
             keep_alive_interval: None,
+            keep_alive_policy: KeepAlivePolicy::Always,
             crypto_buffer_size: 16 * 1024,
             allow_spin: true,
+            outgoing_ecn: EcnPolicy::Adaptive,
             datagram_receive_buffer_size: Some(STREAM_RWND as usize),
             datagram_send_buffer_size: 1024 * 1024,
             #[cfg(test)]
@@ -398,8 +571,13 @@ impl Default for TransportConfig {
 
             congestion_controller_factory: Arc::new(congestion::CubicConfig::default()),
 
+            initial_window: None,
+            pacer_factory: Arc::new(pacing::TokenBucketConfig::default()),
+
             enable_segmentation_offload: true,
 
+            extra_transport_parameters: ExtraTransportParameters::default(),
+
             qlog_sink: QlogSink::default(),
         }
     }
@@ -411,6 +589,7 @@ impl fmt::Debug for TransportConfig {
             max_concurrent_bidi_streams,
             max_concurrent_uni_streams,
             max_idle_timeout,
+            handshake_timeout,
             stream_receive_window,
             receive_window,
             send_window,
@@ -418,22 +597,31 @@ impl fmt::Debug for TransportConfig {
             packet_threshold,
             time_threshold,
             initial_rtt,
+            rtt_sample_history,
             initial_mtu,
             min_mtu,
             mtu_discovery_config,
             pad_to_mtu,
             ack_frequency_config,
+            ack_delay_exponent,
+            max_ack_ranges,
             max_outgoing_bytes_per_second,
+            min_pacing_rate,
             persistent_congestion_threshold,
             keep_alive_interval,
+            keep_alive_policy,
             crypto_buffer_size,
             allow_spin,
+            outgoing_ecn,
             datagram_receive_buffer_size,
             datagram_send_buffer_size,
             #[cfg(test)]
                 deterministic_packet_numbers: _,
             congestion_controller_factory: _,
+            initial_window,
+            pacer_factory: _,
             enable_segmentation_offload,
+            extra_transport_parameters,
             qlog_sink,
         } = self;
         let mut s = fmt.debug_struct("TransportConfig");
@@ -441,6 +629,7 @@ impl fmt::Debug for TransportConfig {
         s.field("max_concurrent_bidi_streams", max_concurrent_bidi_streams)
             .field("max_concurrent_uni_streams", max_concurrent_uni_streams)
             .field("max_idle_timeout", max_idle_timeout)
+            .field("handshake_timeout", handshake_timeout)
             .field("stream_receive_window", stream_receive_window)
             .field("receive_window", receive_window)
             .field("send_window", send_window)
@@ -448,26 +637,35 @@ impl fmt::Debug for TransportConfig {
             .field("packet_threshold", packet_threshold)
             .field("time_threshold", time_threshold)
             .field("initial_rtt", initial_rtt)
+            .field("rtt_sample_history", rtt_sample_history)
             .field("initial_mtu", initial_mtu)
             .field("min_mtu", min_mtu)
             .field("mtu_discovery_config", mtu_discovery_config)
             .field("pad_to_mtu", pad_to_mtu)
             .field("ack_frequency_config", ack_frequency_config)
+            .field("ack_delay_exponent", ack_delay_exponent)
+            .field("max_ack_ranges", max_ack_ranges)
             .field(
                 "max_outgoing_bytes_per_second",
                 max_outgoing_bytes_per_second,
             )
+            .field("min_pacing_rate", min_pacing_rate)
             .field(
                 "persistent_congestion_threshold",
                 persistent_congestion_threshold,
             )
             .field("keep_alive_interval", keep_alive_interval)
+            .field("keep_alive_policy", keep_alive_policy)
             .field("crypto_buffer_size", crypto_buffer_size)
             .field("allow_spin", allow_spin)
+            .field("outgoing_ecn", outgoing_ecn)
             .field("datagram_receive_buffer_size", datagram_receive_buffer_size)
             .field("datagram_send_buffer_size", datagram_send_buffer_size)
             // congestion_controller_factory not debug
-            .field("enable_segmentation_offload", enable_segmentation_offload);
+            .field("initial_window", initial_window)
+            // pacer_factory not debug
+            .field("enable_segmentation_offload", enable_segmentation_offload)
+            .field("extra_transport_parameters", extra_transport_parameters);
         if cfg!(feature = "qlog") {
             s.field("qlog_stream", &qlog_sink.is_enabled());
         }
@@ -476,6 +674,46 @@ impl fmt::Debug for TransportConfig {
     }
 }
 
+/// ECN marking applied to outgoing packets, see [`TransportConfig::outgoing_ecn`]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum EcnPolicy {
+    /// Don't mark packets as ECN-capable
+    Disabled,
+    /// Mark packets with ECT(0)
+    Ect0,
+    /// Mark packets with ECT(1), as used by L4S congestion controllers
+    Ect1,
+    /// Let quinn choose, currently equivalent to [`EcnPolicy::Ect0`]
+    #[default]
+    Adaptive,
+}
+
+impl EcnPolicy {
+    /// The codepoint to mark packets with while ECN is validated
+    pub(crate) fn codepoint(self) -> Option<EcnCodepoint> {
+        match self {
+            Self::Disabled => None,
+            Self::Ect0 | Self::Adaptive => Some(EcnCodepoint::Ect0),
+            Self::Ect1 => Some(EcnCodepoint::Ect1),
+        }
+    }
+}
+
+/// When to send keep-alive packets, see [`TransportConfig::keep_alive_policy`]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum KeepAlivePolicy {
+    /// Keep every connection alive, even when it has no streams
+    #[default]
+    Always,
+    /// Only keep connections alive while at least one stream is open
+    ///
+    /// A connection without streams is left to time out once idle, e.g. to save power on mobile
+    /// devices. Datagrams don't count as activity for this purpose.
+    WhenStreamsOpen,
+}
+
 /// Parameters for controlling the peer's acknowledgement frequency
 ///
 /// The parameters provided in this config will be sent to the peer at the beginning of the
@@ -487,6 +725,10 @@ impl fmt::Debug for TransportConfig {
 /// The defaults produce behavior slightly different than the behavior without this extension,
 /// because they change the way reordered packets are handled (see
 /// [`AckFrequencyConfig::reordering_threshold`] for details).
+///
+/// The configuration only takes effect if the peer supports the extension, which it signals by
+/// sending the `min_ack_delay` transport parameter. Otherwise the peer acknowledges packets as
+/// described in RFC 9000, using the `max_ack_delay` from its own transport parameters.
 #[derive(Clone, Debug)]
 pub struct AckFrequencyConfig {
     pub(crate) ack_eliciting_threshold: VarInt,
@@ -721,6 +963,13 @@ impl MtuDiscoveryConfig {
     ///
     /// It is safe to use an arbitrarily high upper bound, regardless of the network path's MTU. The
     /// only drawback is that MTU discovery might take more time to finish.
+    ///
+    /// The search never exceeds the peer's `max_udp_payload_size` transport parameter, so for
+    /// e.g. 9000 byte jumbo frames both peers must also raise
+    /// [`EndpointConfig::max_udp_payload_size`](crate::EndpointConfig::max_udp_payload_size).
+    /// Probes are sent with the don't-fragment bit set where the platform supports it, so the
+    /// local interface MTU also caps what can be discovered; probes the socket refuses to send
+    /// are treated as lost.
     pub fn upper_bound(&mut self, value: u16) -> &mut Self {
         self.upper_bound = value.min(MAX_UDP_PAYLOAD);
         self
@@ -738,6 +987,10 @@ impl MtuDiscoveryConfig {
 
     /// Specifies the minimum MTU change to stop the MTU discovery phase.
     /// Defaults to 20.
+    ///
+    /// This is the granularity of the binary search: once the next probe would differ from the
+    /// last by less than this amount, the search ends. Smaller values find an MTU closer to the
+    /// path's true limit at the cost of more probes.
     pub fn minimum_change(&mut self, value: u16) -> &mut Self {
         self.minimum_change = value;
         self
@@ -781,6 +1034,12 @@ impl From<VarInt> for IdleTimeout {
     }
 }
 
+impl From<IdleTimeout> for VarInt {
+    fn from(timeout: IdleTimeout) -> Self {
+        timeout.0
+    }
+}
+
 impl TryFrom<Duration> for IdleTimeout {
     type Error = VarIntBoundsExceeded;
 
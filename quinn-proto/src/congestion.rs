@@ -6,10 +6,12 @@ use std::any::Any;
 use std::sync::Arc;
 
 mod bbr;
+mod bbr2;
 mod cubic;
 mod new_reno;
 
 pub use bbr::{Bbr, BbrConfig};
+pub use bbr2::{BbrV2, BbrV2Config};
 pub use cubic::{Cubic, CubicConfig};
 pub use new_reno::{NewReno, NewRenoConfig};
 
@@ -60,6 +62,16 @@ pub trait Controller: Send + Sync {
         lost_bytes: u64,
     );
 
+    /// The peer reported that packets were marked with ECN Congestion Experienced (CE)
+    ///
+    /// `ce_count` is the number of newly reported CE marks in a single ACK frame, and `sent` is
+    /// when the largest acknowledged packet was sent. Called before the corresponding
+    /// [`on_congestion_event`](Self::on_congestion_event) with `is_ecn` set, allowing controllers
+    /// which scale their response to the fraction of marked packets, as in L4S, to distinguish
+    /// marks from loss.
+    #[allow(unused_variables)]
+    fn on_ecn_ce(&mut self, now: Instant, sent: Instant, ce_count: u64) {}
+
     /// Packets were incorrectly deemed lost
     ///
     /// This function is called when all packets that were deemed lost (for instance because
@@ -87,6 +99,13 @@ pub trait Controller: Send + Sync {
     /// Initial congestion window
     fn initial_window(&self) -> u64;
 
+    /// Short, human-readable name of the algorithm, for logging and metrics
+    ///
+    /// Defaults to the implementing type's name.
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
     /// Returns Self for use in down-casting to extract implementation details
     fn into_any(self: Box<Self>) -> Box<dyn Any>;
 }
@@ -107,6 +126,35 @@ pub struct ControllerMetrics {
 pub trait ControllerFactory {
     /// Construct a fresh `Controller`
     fn build(self: Arc<Self>, now: Instant, current_mtu: u16) -> Box<dyn Controller>;
+
+    /// Construct a fresh `Controller` starting from a congestion window of `initial_window` bytes
+    ///
+    /// Used in place of [`build()`](Self::build) when
+    /// [`TransportConfig::initial_window()`](crate::TransportConfig::initial_window) is set, which
+    /// should take precedence over any initial window configured for the controller itself.
+    ///
+    /// Defaults to calling [`build()`](Self::build), ignoring `initial_window`.
+    fn build_with_initial_window(
+        self: Arc<Self>,
+        now: Instant,
+        current_mtu: u16,
+        initial_window: u64,
+    ) -> Box<dyn Controller> {
+        let _ = initial_window;
+        self.build(now, current_mtu)
+    }
+}
+
+/// Returns `config` with its initial window replaced by `initial_window`, using `set`
+///
+/// `config` is only copied if it is shared.
+fn with_initial_window<C: Clone>(
+    mut config: Arc<C>,
+    initial_window: u64,
+    set: fn(&mut C, u64) -> &mut C,
+) -> Arc<C> {
+    set(Arc::make_mut(&mut config), initial_window);
+    config
 }
 
 const BASE_DATAGRAM_SIZE: u64 = 1200;
@@ -11,9 +11,9 @@ use crate::congestion::bbr::min_max::MinMax;
 use crate::connection::RttEstimator;
 use crate::{Duration, Instant};
 
-use super::{BASE_DATAGRAM_SIZE, Controller, ControllerFactory};
+use super::{BASE_DATAGRAM_SIZE, Controller, ControllerFactory, with_initial_window};
 
-mod bw_estimation;
+pub(super) mod bw_estimation;
 mod min_max;
 
 /// Experimental! Use at your own risk.
@@ -508,6 +508,10 @@ impl Controller for Bbr {
         self.config.initial_window
     }
 
+    fn name(&self) -> &'static str {
+        "bbr"
+    }
+
     fn into_any(self: Box<Self>) -> Box<dyn Any> {
         self
     }
@@ -541,6 +545,15 @@ impl ControllerFactory for BbrConfig {
     fn build(self: Arc<Self>, _now: Instant, current_mtu: u16) -> Box<dyn Controller> {
         Box::new(Bbr::new(self, current_mtu))
     }
+
+    fn build_with_initial_window(
+        self: Arc<Self>,
+        now: Instant,
+        current_mtu: u16,
+        initial_window: u64,
+    ) -> Box<dyn Controller> {
+        with_initial_window(self, initial_window, Self::initial_window).build(now, current_mtu)
+    }
 }
 
 #[derive(Debug, Default, Copy, Clone)]
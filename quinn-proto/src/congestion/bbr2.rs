@@ -0,0 +1,814 @@
+use std::any::Any;
+use std::mem;
+use std::sync::Arc;
+
+use rand::{RngExt, SeedableRng};
+use rand_pcg::Pcg32;
+
+use super::bbr::bw_estimation::BandwidthEstimation;
+use super::{
+    BASE_DATAGRAM_SIZE, Controller, ControllerFactory, ControllerMetrics, with_initial_window,
+};
+use crate::connection::RttEstimator;
+use crate::{Duration, Instant};
+
+/// Experimental! Use at your own risk.
+///
+/// Second version of the BBR model <https://datatracker.ietf.org/meeting/104/materials/slides-104-iccrg-an-update-on-bbr-00>.
+/// In addition to the bandwidth and min-RTT estimates driving [`Bbr`](super::Bbr), it bounds the
+/// amount of data in flight using loss: `inflight_hi` tracks the volume of data in flight beyond
+/// which loss exceeds a tolerable rate, while the short-term `inflight_lo` and `bw_lo` bounds
+/// back off multiplicatively in rounds that saw loss. This makes for much less loss and queueing
+/// than BBRv1 on shallow-buffered paths, and more stable steady-state behavior.
+#[derive(Debug, Clone)]
+pub struct BbrV2 {
+    config: Arc<BbrV2Config>,
+    current_mtu: u64,
+    init_cwnd: u64,
+    min_cwnd: u64,
+    cwnd: u64,
+    mode: Mode,
+    pacing_gain: f32,
+    cwnd_gain: f32,
+    pacing_rate: u64,
+    max_bandwidth: BandwidthEstimation,
+    /// Minimum RTT observed within the last [`MIN_RTT_FILTER_LEN`]
+    min_rtt: Duration,
+    min_rtt_stamp: Option<Instant>,
+    /// Minimum RTT observed since the last `ProbeRtt`, used to schedule the next one
+    probe_rtt_min_delay: Duration,
+    probe_rtt_min_stamp: Option<Instant>,
+    /// Whether `probe_rtt_min_delay` went [`PROBE_RTT_INTERVAL`] without being lowered
+    probe_rtt_expired: bool,
+    probe_rtt_done_stamp: Option<Instant>,
+    probe_rtt_round_done: bool,
+    prior_cwnd: u64,
+    acked_bytes: u64,
+    max_sent_packet_number: u64,
+    max_acked_packet_number: u64,
+    current_round_trip_end_packet_number: u64,
+    round_count: u64,
+    round_start_time: Option<Instant>,
+    is_at_full_bandwidth: bool,
+    full_bw: u64,
+    full_bw_count: u8,
+    /// Volume of data in flight beyond which loss was excessive, or `u64::MAX` if unknown
+    inflight_hi: u64,
+    /// Short-term bound on data in flight, reduced in rounds with loss
+    inflight_lo: u64,
+    /// Short-term bound on the delivery rate, reduced in rounds with loss
+    bw_lo: u64,
+    bytes_delivered_in_round: u64,
+    bytes_lost_in_round: u64,
+    loss_events_in_round: u32,
+    cycle_start: Option<Instant>,
+    bw_probe_wait: Duration,
+    rounds_since_bw_probe: u64,
+    bw_probe_up_rounds: u32,
+    bw_probe_up_acks: u64,
+    bw_probe_up_cnt: u64,
+    random_number_generator: Pcg32,
+}
+
+impl BbrV2 {
+    /// Construct a state using the given `config` and current time `now`
+    pub fn new(config: Arc<BbrV2Config>, current_mtu: u16) -> Self {
+        let current_mtu = current_mtu as u64;
+        let min_cwnd = calculate_min_window(current_mtu);
+        let init_cwnd = config.initial_window.max(min_cwnd);
+        Self {
+            config,
+            current_mtu,
+            init_cwnd,
+            min_cwnd,
+            cwnd: init_cwnd,
+            mode: Mode::Startup,
+            pacing_gain: STARTUP_PACING_GAIN,
+            cwnd_gain: DEFAULT_CWND_GAIN,
+            pacing_rate: 0,
+            max_bandwidth: BandwidthEstimation::default(),
+            min_rtt: Duration::ZERO,
+            min_rtt_stamp: None,
+            probe_rtt_min_delay: Duration::ZERO,
+            probe_rtt_min_stamp: None,
+            probe_rtt_expired: false,
+            probe_rtt_done_stamp: None,
+            probe_rtt_round_done: false,
+            prior_cwnd: 0,
+            acked_bytes: 0,
+            max_sent_packet_number: 0,
+            max_acked_packet_number: 0,
+            current_round_trip_end_packet_number: 0,
+            round_count: 0,
+            round_start_time: None,
+            is_at_full_bandwidth: false,
+            full_bw: 0,
+            full_bw_count: 0,
+            inflight_hi: u64::MAX,
+            inflight_lo: u64::MAX,
+            bw_lo: u64::MAX,
+            bytes_delivered_in_round: 0,
+            bytes_lost_in_round: 0,
+            loss_events_in_round: 0,
+            cycle_start: None,
+            bw_probe_wait: Duration::ZERO,
+            rounds_since_bw_probe: 0,
+            bw_probe_up_rounds: 0,
+            bw_probe_up_acks: 0,
+            bw_probe_up_cnt: u64::MAX,
+            random_number_generator: Pcg32::from_rng(&mut rand::rng()),
+        }
+    }
+
+    /// Bandwidth the model currently believes the path can sustain
+    fn bw(&self) -> u64 {
+        self.max_bandwidth.get_estimate().min(self.bw_lo)
+    }
+
+    /// Estimated bandwidth-delay product scaled by `gain`
+    fn bdp(&self, gain: f32) -> u64 {
+        let bw = self.max_bandwidth.get_estimate();
+        if bw == 0 || self.min_rtt.is_zero() {
+            return self.init_cwnd;
+        }
+        let bdp = (bw as u128 * self.min_rtt.as_micros()) / 1_000_000;
+        ((gain as f64 * bdp as f64) as u64).max(self.min_cwnd)
+    }
+
+    /// `inflight_hi` less some headroom, leaving space for competing flows to grow into
+    fn inflight_with_headroom(&self) -> u64 {
+        if self.inflight_hi == u64::MAX {
+            return u64::MAX;
+        }
+        let headroom = ((HEADROOM * self.inflight_hi as f32) as u64).max(self.current_mtu);
+        self.inflight_hi.saturating_sub(headroom).max(self.min_cwnd)
+    }
+
+    fn is_probing_bw(&self) -> bool {
+        matches!(
+            self.mode,
+            Mode::Startup | Mode::ProbeBw(ProbeBwPhase::Refill | ProbeBwPhase::Up)
+        )
+    }
+
+    /// Whether the loss rate in the current round exceeds [`LOSS_THRESH`]
+    fn is_inflight_too_high(&self) -> bool {
+        self.loss_events_in_round >= FULL_LOSS_COUNT
+            && self.bytes_lost_in_round as f32
+                > LOSS_THRESH * (self.bytes_delivered_in_round + self.bytes_lost_in_round) as f32
+    }
+
+    fn handle_inflight_too_high(&mut self, now: Instant, in_flight: u64) {
+        let target = (BETA * self.bdp(1.0) as f32) as u64;
+        self.inflight_hi = in_flight.max(target).max(self.min_cwnd);
+        match self.mode {
+            Mode::Startup => self.is_at_full_bandwidth = true,
+            Mode::ProbeBw(ProbeBwPhase::Up) => self.start_probe_bw_down(now),
+            _ => {}
+        }
+    }
+
+    /// Back off the short-term bounds after a round with loss
+    fn adapt_lower_bounds(&mut self, now: Instant) {
+        if self.bytes_lost_in_round == 0 || self.is_probing_bw() {
+            return;
+        }
+        let Some(round_start_time) = self.round_start_time else {
+            return;
+        };
+        let bw_latest = BandwidthEstimation::bw_from_delta(
+            self.bytes_delivered_in_round,
+            now.saturating_duration_since(round_start_time),
+        )
+        .unwrap_or(0);
+        let inflight_latest = self.bytes_delivered_in_round;
+
+        if self.bw_lo == u64::MAX {
+            self.bw_lo = self.max_bandwidth.get_estimate();
+        }
+        if self.inflight_lo == u64::MAX {
+            self.inflight_lo = self.cwnd;
+        }
+        self.bw_lo = bw_latest.max((BETA * self.bw_lo as f32) as u64);
+        self.inflight_lo = inflight_latest
+            .max((BETA * self.inflight_lo as f32) as u64)
+            .max(self.min_cwnd);
+    }
+
+    fn reset_lower_bounds(&mut self) {
+        self.bw_lo = u64::MAX;
+        self.inflight_lo = u64::MAX;
+    }
+
+    fn start_round(&mut self, now: Instant) {
+        self.round_start_time = Some(now);
+        self.bytes_delivered_in_round = 0;
+        self.bytes_lost_in_round = 0;
+        self.loss_events_in_round = 0;
+    }
+
+    /// <https://datatracker.ietf.org/doc/html/draft-cardwell-iccrg-bbr-congestion-control#section-4.3.2.2>
+    fn check_full_bw_reached(&mut self, app_limited: bool) {
+        if self.is_at_full_bandwidth || app_limited {
+            return;
+        }
+        let bw = self.max_bandwidth.get_estimate();
+        if bw as f32 >= self.full_bw as f32 * STARTUP_GROWTH_TARGET {
+            self.full_bw = bw;
+            self.full_bw_count = 0;
+            return;
+        }
+        self.full_bw_count += 1;
+        if self.full_bw_count >= ROUNDS_WITHOUT_GROWTH_BEFORE_EXITING_STARTUP {
+            self.is_at_full_bandwidth = true;
+        }
+    }
+
+    fn check_startup_done(&mut self, now: Instant, in_flight: u64) {
+        if self.mode == Mode::Startup && self.is_at_full_bandwidth {
+            self.mode = Mode::Drain;
+            self.pacing_gain = DRAIN_PACING_GAIN;
+            self.cwnd_gain = DEFAULT_CWND_GAIN;
+        }
+        if self.mode == Mode::Drain && in_flight <= self.bdp(1.0) {
+            self.start_probe_bw_down(now);
+        }
+    }
+
+    fn start_probe_bw_down(&mut self, now: Instant) {
+        self.rounds_since_bw_probe = 0;
+        // Randomize the wait so that competing flows don't synchronize their probes
+        self.bw_probe_wait = PROBE_BW_WAIT_BASE
+            + Duration::from_millis(self.random_number_generator.random_range(0..1000));
+        self.set_probe_bw_phase(now, ProbeBwPhase::Down);
+    }
+
+    fn set_probe_bw_phase(&mut self, now: Instant, phase: ProbeBwPhase) {
+        self.mode = Mode::ProbeBw(phase);
+        self.cycle_start = Some(now);
+        self.pacing_gain = match phase {
+            ProbeBwPhase::Down => 0.9,
+            ProbeBwPhase::Cruise | ProbeBwPhase::Refill => 1.0,
+            ProbeBwPhase::Up => 1.25,
+        };
+        self.cwnd_gain = match phase {
+            ProbeBwPhase::Up => 2.25,
+            _ => DEFAULT_CWND_GAIN,
+        };
+        if phase == ProbeBwPhase::Refill {
+            self.reset_lower_bounds();
+            self.bw_probe_up_rounds = 0;
+            self.bw_probe_up_acks = 0;
+        }
+        if phase == ProbeBwPhase::Up {
+            self.raise_inflight_hi_slope();
+        }
+    }
+
+    fn has_elapsed_in_phase(&self, now: Instant, interval: Duration) -> bool {
+        self.cycle_start
+            .is_some_and(|start| now.saturating_duration_since(start) > interval)
+    }
+
+    /// Whether it's time to probe for more bandwidth again
+    ///
+    /// Like Reno, wait roughly as long as it would take a loss-based flow to reach the current
+    /// BDP, to coexist with such flows, but no longer than [`PROBE_BW_WAIT_BASE`] plus jitter.
+    fn is_time_to_probe_bw(&self, now: Instant) -> bool {
+        let reno_rounds = (self.bdp(1.0) / self.current_mtu).min(MAX_RENO_ROUNDS);
+        self.has_elapsed_in_phase(now, self.bw_probe_wait)
+            || self.rounds_since_bw_probe >= reno_rounds
+    }
+
+    fn raise_inflight_hi_slope(&mut self) {
+        let growth_this_round = self.current_mtu << self.bw_probe_up_rounds.min(30);
+        self.bw_probe_up_rounds = (self.bw_probe_up_rounds + 1).min(30);
+        self.bw_probe_up_cnt = (self.cwnd / growth_this_round).max(1);
+    }
+
+    fn probe_inflight_hi_upward(&mut self, bytes_acked: u64, is_round_start: bool) {
+        if self.inflight_hi == u64::MAX || self.cwnd < self.inflight_hi {
+            // Not fully using `inflight_hi`, so don't grow it
+            return;
+        }
+        self.bw_probe_up_acks += bytes_acked;
+        if self.bw_probe_up_acks >= self.bw_probe_up_cnt * self.current_mtu {
+            let delta = self.bw_probe_up_acks / (self.bw_probe_up_cnt * self.current_mtu);
+            self.bw_probe_up_acks -= delta * self.bw_probe_up_cnt * self.current_mtu;
+            self.inflight_hi += delta * self.current_mtu;
+        }
+        if is_round_start {
+            self.raise_inflight_hi_slope();
+        }
+    }
+
+    fn update_probe_bw_cycle_phase(
+        &mut self,
+        now: Instant,
+        in_flight: u64,
+        bytes_acked: u64,
+        is_round_start: bool,
+    ) {
+        let Mode::ProbeBw(phase) = self.mode else {
+            return;
+        };
+        match phase {
+            ProbeBwPhase::Down => {
+                if self.is_time_to_probe_bw(now) {
+                    self.set_probe_bw_phase(now, ProbeBwPhase::Refill);
+                } else if in_flight <= self.inflight_with_headroom().min(self.bdp(1.0)) {
+                    self.set_probe_bw_phase(now, ProbeBwPhase::Cruise);
+                }
+            }
+            ProbeBwPhase::Cruise => {
+                if self.is_time_to_probe_bw(now) {
+                    self.set_probe_bw_phase(now, ProbeBwPhase::Refill);
+                }
+            }
+            ProbeBwPhase::Refill => {
+                // Spend one round refilling the pipe at the estimated bandwidth before probing
+                if is_round_start {
+                    self.set_probe_bw_phase(now, ProbeBwPhase::Up);
+                }
+            }
+            ProbeBwPhase::Up => {
+                self.probe_inflight_hi_upward(bytes_acked, is_round_start);
+                if self.has_elapsed_in_phase(now, self.min_rtt) && in_flight > self.bdp(1.25) {
+                    self.start_probe_bw_down(now);
+                }
+            }
+        }
+    }
+
+    fn update_min_rtt(&mut self, now: Instant, sample: Duration) {
+        let probe_rtt_expired = self
+            .probe_rtt_min_stamp
+            .is_some_and(|stamp| now.saturating_duration_since(stamp) > PROBE_RTT_INTERVAL);
+        self.probe_rtt_expired |= probe_rtt_expired;
+        if sample < self.probe_rtt_min_delay
+            || self.probe_rtt_min_delay.is_zero()
+            || probe_rtt_expired
+        {
+            self.probe_rtt_min_delay = sample;
+            self.probe_rtt_min_stamp = Some(now);
+        }
+
+        let min_rtt_expired = self
+            .min_rtt_stamp
+            .is_none_or(|stamp| now.saturating_duration_since(stamp) > MIN_RTT_FILTER_LEN);
+        if self.probe_rtt_min_delay < self.min_rtt || self.min_rtt.is_zero() || min_rtt_expired {
+            self.min_rtt = self.probe_rtt_min_delay;
+            self.min_rtt_stamp = self.probe_rtt_min_stamp;
+        }
+    }
+
+    fn check_probe_rtt(
+        &mut self,
+        now: Instant,
+        in_flight: u64,
+        is_round_start: bool,
+        app_limited: bool,
+    ) {
+        let probe_rtt_due = mem::take(&mut self.probe_rtt_expired);
+        if self.mode != Mode::ProbeRtt && probe_rtt_due && !app_limited {
+            self.mode = Mode::ProbeRtt;
+            self.pacing_gain = 1.0;
+            self.cwnd_gain = PROBE_RTT_CWND_GAIN;
+            self.prior_cwnd = self.cwnd;
+            self.probe_rtt_done_stamp = None;
+        }
+        if self.mode != Mode::ProbeRtt {
+            return;
+        }
+
+        match self.probe_rtt_done_stamp {
+            None => {
+                // Hold the reduced window for a while once in flight data has drained to it
+                if in_flight <= self.probe_rtt_cwnd() {
+                    self.probe_rtt_done_stamp = Some(now + PROBE_RTT_DURATION);
+                    self.probe_rtt_round_done = false;
+                    self.current_round_trip_end_packet_number = self.max_sent_packet_number;
+                }
+            }
+            Some(done) => {
+                if is_round_start {
+                    self.probe_rtt_round_done = true;
+                }
+                if self.probe_rtt_round_done && now >= done {
+                    self.probe_rtt_min_stamp = Some(now);
+                    self.exit_probe_rtt(now);
+                }
+            }
+        }
+    }
+
+    fn exit_probe_rtt(&mut self, now: Instant) {
+        self.reset_lower_bounds();
+        self.cwnd = self.cwnd.max(self.prior_cwnd);
+        if self.is_at_full_bandwidth {
+            self.start_probe_bw_down(now);
+            self.set_probe_bw_phase(now, ProbeBwPhase::Cruise);
+        } else {
+            self.mode = Mode::Startup;
+            self.pacing_gain = STARTUP_PACING_GAIN;
+            self.cwnd_gain = DEFAULT_CWND_GAIN;
+        }
+    }
+
+    fn probe_rtt_cwnd(&self) -> u64 {
+        self.bdp(PROBE_RTT_CWND_GAIN)
+    }
+
+    fn set_pacing_rate(&mut self) {
+        let bw = self.bw();
+        if bw == 0 {
+            // Pace at the rate of initial_window / RTT as soon as RTT measurements are
+            // available.
+            if self.pacing_rate == 0 && !self.min_rtt.is_zero() {
+                self.pacing_rate =
+                    BandwidthEstimation::bw_from_delta(self.init_cwnd, self.min_rtt).unwrap();
+            }
+            return;
+        }
+        let rate = (bw as f64 * self.pacing_gain as f64 * PACING_MARGIN) as u64;
+        // Do not decrease the pacing rate during startup.
+        if self.is_at_full_bandwidth || rate > self.pacing_rate {
+            self.pacing_rate = rate;
+        }
+    }
+
+    fn set_cwnd(&mut self, bytes_acked: u64) {
+        let target = self.bdp(self.cwnd_gain);
+        if self.is_at_full_bandwidth {
+            self.cwnd = target.min(self.cwnd + bytes_acked);
+        } else if self.cwnd < target || self.acked_bytes < self.init_cwnd {
+            // If the connection is not yet out of startup phase, do not decrease
+            // the window.
+            self.cwnd += bytes_acked;
+        }
+        self.cwnd = self.cwnd.max(self.min_cwnd);
+    }
+
+    /// Upper bound on the window imposed by the loss-based parts of the model
+    fn cwnd_bound(&self) -> u64 {
+        let mut bound = match self.is_probing_bw() {
+            true => self.inflight_hi,
+            false => self.inflight_with_headroom(),
+        };
+        bound = bound.min(self.inflight_lo);
+        if self.mode == Mode::ProbeRtt {
+            bound = bound.min(self.probe_rtt_cwnd());
+        }
+        bound.max(self.min_cwnd)
+    }
+}
+
+impl Controller for BbrV2 {
+    fn on_sent(&mut self, now: Instant, bytes: u64, last_packet_number: u64) {
+        self.max_sent_packet_number = last_packet_number;
+        self.max_bandwidth.on_sent(now, bytes);
+        if self.round_start_time.is_none() {
+            self.round_start_time = Some(now);
+        }
+    }
+
+    fn on_ack(
+        &mut self,
+        now: Instant,
+        sent: Instant,
+        bytes: u64,
+        app_limited: bool,
+        _rtt: &RttEstimator,
+    ) {
+        self.max_bandwidth
+            .on_ack(now, sent, bytes, self.round_count, app_limited);
+        self.acked_bytes += bytes;
+        self.update_min_rtt(now, now.saturating_duration_since(sent));
+    }
+
+    fn on_end_acks(
+        &mut self,
+        now: Instant,
+        in_flight: u64,
+        app_limited: bool,
+        largest_packet_num_acked: Option<u64>,
+    ) {
+        let bytes_acked = self.max_bandwidth.bytes_acked_this_window();
+        self.max_bandwidth.end_acks(self.round_count, app_limited);
+        if let Some(largest_acked_packet) = largest_packet_num_acked {
+            self.max_acked_packet_number = largest_acked_packet;
+        }
+        self.bytes_delivered_in_round += bytes_acked;
+
+        let is_round_start = bytes_acked > 0
+            && self.max_acked_packet_number > self.current_round_trip_end_packet_number;
+
+        if self.is_probing_bw() && self.is_inflight_too_high() {
+            self.handle_inflight_too_high(now, in_flight);
+        }
+
+        if is_round_start {
+            self.current_round_trip_end_packet_number = self.max_sent_packet_number;
+            self.round_count += 1;
+            self.rounds_since_bw_probe += 1;
+            self.adapt_lower_bounds(now);
+            self.check_full_bw_reached(app_limited);
+            self.start_round(now);
+        }
+
+        self.check_startup_done(now, in_flight);
+        self.update_probe_bw_cycle_phase(now, in_flight, bytes_acked, is_round_start);
+        self.check_probe_rtt(now, in_flight, is_round_start, app_limited);
+
+        // After the model is updated, recalculate the pacing rate and congestion window.
+        self.set_pacing_rate();
+        self.set_cwnd(bytes_acked);
+    }
+
+    fn on_congestion_event(
+        &mut self,
+        _now: Instant,
+        _sent: Instant,
+        is_persistent_congestion: bool,
+        is_ecn: bool,
+        lost_bytes: u64,
+    ) {
+        if is_ecn {
+            return;
+        }
+        self.bytes_lost_in_round += lost_bytes;
+        self.loss_events_in_round += 1;
+        if is_persistent_congestion {
+            self.cwnd = self.min_cwnd;
+            self.inflight_lo = self.min_cwnd;
+        }
+    }
+
+    fn on_mtu_update(&mut self, new_mtu: u16) {
+        self.current_mtu = new_mtu as u64;
+        self.min_cwnd = calculate_min_window(self.current_mtu);
+        self.init_cwnd = self.config.initial_window.max(self.min_cwnd);
+        self.cwnd = self.cwnd.max(self.min_cwnd);
+    }
+
+    fn window(&self) -> u64 {
+        self.cwnd.min(self.cwnd_bound())
+    }
+
+    fn metrics(&self) -> ControllerMetrics {
+        ControllerMetrics {
+            congestion_window: self.window(),
+            ssthresh: None,
+            pacing_rate: Some(self.pacing_rate * 8),
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn Controller> {
+        Box::new(self.clone())
+    }
+
+    fn initial_window(&self) -> u64 {
+        self.config.initial_window
+    }
+
+    fn name(&self) -> &'static str {
+        "bbr2"
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// Configuration for the [`BbrV2`] congestion controller
+#[derive(Debug, Clone)]
+pub struct BbrV2Config {
+    initial_window: u64,
+}
+
+impl BbrV2Config {
+    /// Default limit on the amount of outstanding data in bytes.
+    ///
+    /// Recommended value: `min(10 * max_datagram_size, max(2 * max_datagram_size, 14720))`
+    pub fn initial_window(&mut self, value: u64) -> &mut Self {
+        self.initial_window = value;
+        self
+    }
+}
+
+impl Default for BbrV2Config {
+    fn default() -> Self {
+        Self {
+            initial_window: 14720.clamp(2 * BASE_DATAGRAM_SIZE, 10 * BASE_DATAGRAM_SIZE),
+        }
+    }
+}
+
+impl ControllerFactory for BbrV2Config {
+    fn build(self: Arc<Self>, _now: Instant, current_mtu: u16) -> Box<dyn Controller> {
+        Box::new(BbrV2::new(self, current_mtu))
+    }
+
+    fn build_with_initial_window(
+        self: Arc<Self>,
+        now: Instant,
+        current_mtu: u16,
+        initial_window: u64,
+    ) -> Box<dyn Controller> {
+        with_initial_window(self, initial_window, Self::initial_window).build(now, current_mtu)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Mode {
+    // Exponential search for the bottleneck bandwidth.
+    Startup,
+    // Drain the queue built up during startup.
+    Drain,
+    // Steady state, cycling through the phases of bandwidth probing.
+    ProbeBw(ProbeBwPhase),
+    // Temporarily slow down sending in order to empty the buffer and measure
+    // the real minimum RTT.
+    ProbeRtt,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum ProbeBwPhase {
+    // Deceleration: drain any queue created by probing and leave headroom.
+    Down,
+    // Cruise at the estimated bandwidth, within the loss-based bounds.
+    Cruise,
+    // Refill the pipe after cruising below the BDP, before probing.
+    Refill,
+    // Accelerate, probing for additional bandwidth and raising `inflight_hi`.
+    Up,
+}
+
+fn calculate_min_window(current_mtu: u64) -> u64 {
+    4 * current_mtu
+}
+
+// Pacing gain for STARTUP, the minimum needed to double the delivery rate every round.
+const STARTUP_PACING_GAIN: f32 = 2.77;
+// Pacing gain for DRAIN, low enough to drain the queue built in STARTUP within about a round.
+const DRAIN_PACING_GAIN: f32 = 1.0 / 2.885;
+const DEFAULT_CWND_GAIN: f32 = 2.0;
+const PROBE_RTT_CWND_GAIN: f32 = 0.5;
+// Pace slightly below the estimated bandwidth to avoid building a queue.
+const PACING_MARGIN: f64 = 0.99;
+
+const STARTUP_GROWTH_TARGET: f32 = 1.25;
+const ROUNDS_WITHOUT_GROWTH_BEFORE_EXITING_STARTUP: u8 = 3;
+
+// Maximum tolerated fraction of bytes lost in a round.
+const LOSS_THRESH: f32 = 0.02;
+// Minimum number of loss events in a round for the loss rate to be considered.
+const FULL_LOSS_COUNT: u32 = 2;
+// Multiplicative back-off applied to the loss-based bounds.
+const BETA: f32 = 0.7;
+// Fraction of `inflight_hi` left unused while not probing.
+const HEADROOM: f32 = 0.15;
+
+const PROBE_BW_WAIT_BASE: Duration = Duration::from_secs(2);
+const MAX_RENO_ROUNDS: u64 = 63;
+
+const MIN_RTT_FILTER_LEN: Duration = Duration::from_secs(10);
+const PROBE_RTT_INTERVAL: Duration = Duration::from_secs(5);
+const PROBE_RTT_DURATION: Duration = Duration::from_millis(200);
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    fn bbr_v2() -> BbrV2 {
+        BbrV2::new(Arc::new(BbrV2Config::default()), BASE_DATAGRAM_SIZE as u16)
+    }
+
+    const RTT: Duration = Duration::from_millis(100);
+
+    /// Send `packets` full-sized packets at `now`, then acknowledge all of them one RTT later,
+    /// leaving `in_flight` bytes outstanding
+    fn round_trip(bbr: &mut BbrV2, now: &mut Instant, pn: &mut u64, packets: u64, in_flight: u64) {
+        let rtt = RttEstimator::new(RTT);
+        let sent = *now;
+        for _ in 0..packets {
+            bbr.on_sent(sent, BASE_DATAGRAM_SIZE, *pn);
+            *pn += 1;
+        }
+        *now += RTT;
+        bbr.on_ack(*now, sent, packets * BASE_DATAGRAM_SIZE, false, &rtt);
+        bbr.on_end_acks(*now, in_flight, false, Some(*pn - 1));
+    }
+
+    #[test]
+    fn startup_drain_probe_bw() {
+        let mut now = Instant::now();
+        let mut pn = 0;
+        let mut bbr = bbr_v2();
+        let initial_window = bbr.window();
+
+        // A delivery rate that stops growing ends startup, leaving a queue to drain
+        for _ in 0..10 {
+            round_trip(&mut bbr, &mut now, &mut pn, 20, 100 * BASE_DATAGRAM_SIZE);
+            if bbr.mode != Mode::Startup {
+                break;
+            }
+            assert_eq!(bbr.pacing_gain, STARTUP_PACING_GAIN);
+        }
+        assert!(bbr.is_at_full_bandwidth);
+        assert_eq!(bbr.mode, Mode::Drain);
+        assert_eq!(bbr.pacing_gain, DRAIN_PACING_GAIN);
+        assert!(bbr.window() > initial_window);
+
+        // Once the queue has drained, the steady state is entered
+        round_trip(&mut bbr, &mut now, &mut pn, 20, 0);
+        assert_matches!(
+            bbr.mode,
+            Mode::ProbeBw(ProbeBwPhase::Down | ProbeBwPhase::Cruise)
+        );
+        assert!(bbr.pacing_gain <= 1.0);
+
+        // Bandwidth is probed for again after a while
+        now += PROBE_BW_WAIT_BASE + Duration::from_secs(1);
+        round_trip(&mut bbr, &mut now, &mut pn, 20, 0);
+        assert_eq!(bbr.mode, Mode::ProbeBw(ProbeBwPhase::Refill));
+        round_trip(&mut bbr, &mut now, &mut pn, 20, 0);
+        assert_eq!(bbr.mode, Mode::ProbeBw(ProbeBwPhase::Up));
+        assert!(bbr.pacing_gain > 1.0);
+    }
+
+    #[test]
+    fn recovery_from_persistent_congestion() {
+        let mut now = Instant::now();
+        let mut pn = 0;
+        let mut bbr = bbr_v2();
+        for _ in 0..10 {
+            round_trip(&mut bbr, &mut now, &mut pn, 20, 0);
+        }
+        assert_matches!(bbr.mode, Mode::ProbeBw(_));
+        let window = bbr.window();
+        assert!(window > bbr.min_cwnd);
+
+        bbr.on_congestion_event(now, now, true, false, 10 * BASE_DATAGRAM_SIZE);
+        assert_eq!(bbr.window(), bbr.min_cwnd);
+
+        // The short-term bound holds the window down until bandwidth is probed for again
+        round_trip(&mut bbr, &mut now, &mut pn, 4, 0);
+        assert_eq!(bbr.window(), bbr.min_cwnd);
+        now += PROBE_BW_WAIT_BASE + Duration::from_secs(1);
+        for _ in 0..5 {
+            round_trip(&mut bbr, &mut now, &mut pn, 20, 0);
+        }
+        assert!(bbr.window() > bbr.min_cwnd);
+    }
+
+    #[test]
+    fn excessive_loss_ends_startup() {
+        let now = Instant::now();
+        let rtt = RttEstimator::new(Duration::from_millis(100));
+        let mut bbr = bbr_v2();
+        let mss = BASE_DATAGRAM_SIZE;
+
+        for pn in 0..10 {
+            bbr.on_sent(now, mss, pn);
+        }
+        let later = now + Duration::from_millis(100);
+        bbr.on_ack(later, now, 5 * mss, false, &rtt);
+        bbr.on_congestion_event(later, now, false, false, mss);
+        bbr.on_congestion_event(later, now, false, false, mss);
+        bbr.on_end_acks(later, 3 * mss, false, Some(6));
+
+        assert!(bbr.is_at_full_bandwidth);
+        assert_ne!(bbr.mode, Mode::Startup);
+        assert!(bbr.inflight_hi < u64::MAX);
+        assert!(bbr.window() <= bbr.inflight_hi.max(bbr.min_cwnd));
+    }
+
+    #[test]
+    fn loss_in_steady_state_lowers_bounds() {
+        let now = Instant::now();
+        let mut bbr = bbr_v2();
+        bbr.is_at_full_bandwidth = true;
+        bbr.start_probe_bw_down(now);
+        bbr.set_probe_bw_phase(now, ProbeBwPhase::Cruise);
+        bbr.cwnd = 100 * BASE_DATAGRAM_SIZE;
+        bbr.round_start_time = Some(now);
+        bbr.bytes_delivered_in_round = 10 * BASE_DATAGRAM_SIZE;
+        bbr.bytes_lost_in_round = BASE_DATAGRAM_SIZE;
+
+        bbr.adapt_lower_bounds(now + Duration::from_millis(100));
+
+        assert_eq!(
+            bbr.inflight_lo,
+            (BETA * (100 * BASE_DATAGRAM_SIZE) as f32) as u64
+        );
+        assert!(bbr.window() <= bbr.inflight_lo);
+
+        // Probing for bandwidth lifts the short-term bounds again
+        bbr.set_probe_bw_phase(now, ProbeBwPhase::Refill);
+        assert_eq!(bbr.inflight_lo, u64::MAX);
+        assert_eq!(bbr.bw_lo, u64::MAX);
+    }
+}
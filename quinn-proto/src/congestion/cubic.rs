@@ -2,7 +2,7 @@ use std::any::Any;
 use std::cmp;
 use std::sync::Arc;
 
-use super::{BASE_DATAGRAM_SIZE, Controller, ControllerFactory};
+use super::{BASE_DATAGRAM_SIZE, Controller, ControllerFactory, with_initial_window};
 use crate::connection::RttEstimator;
 use crate::{Duration, Instant};
 
@@ -270,6 +270,10 @@ impl Controller for Cubic {
         self.config.initial_window
     }
 
+    fn name(&self) -> &'static str {
+        "cubic"
+    }
+
     fn into_any(self: Box<Self>) -> Box<dyn Any> {
         self
     }
@@ -303,6 +307,15 @@ impl ControllerFactory for CubicConfig {
     fn build(self: Arc<Self>, now: Instant, current_mtu: u16) -> Box<dyn Controller> {
         Box::new(Cubic::new(self, now, current_mtu))
     }
+
+    fn build_with_initial_window(
+        self: Arc<Self>,
+        now: Instant,
+        current_mtu: u16,
+        initial_window: u64,
+    ) -> Box<dyn Controller> {
+        with_initial_window(self, initial_window, Self::initial_window).build(now, current_mtu)
+    }
 }
 #[cfg(test)]
 mod tests {
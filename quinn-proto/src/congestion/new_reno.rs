@@ -1,7 +1,7 @@
 use std::any::Any;
 use std::sync::Arc;
 
-use super::{BASE_DATAGRAM_SIZE, Controller, ControllerFactory};
+use super::{BASE_DATAGRAM_SIZE, Controller, ControllerFactory, with_initial_window};
 use crate::Instant;
 use crate::connection::RttEstimator;
 
@@ -129,6 +129,10 @@ impl Controller for NewReno {
         self.config.initial_window
     }
 
+    fn name(&self) -> &'static str {
+        "newreno"
+    }
+
     fn into_any(self: Box<Self>) -> Box<dyn Any> {
         self
     }
@@ -170,4 +174,13 @@ impl ControllerFactory for NewRenoConfig {
     fn build(self: Arc<Self>, now: Instant, current_mtu: u16) -> Box<dyn Controller> {
         Box::new(NewReno::new(self, now, current_mtu))
     }
+
+    fn build_with_initial_window(
+        self: Arc<Self>,
+        now: Instant,
+        current_mtu: u16,
+        initial_window: u64,
+    ) -> Box<dyn Controller> {
+        with_initial_window(self, initial_window, Self::initial_window).build(now, current_mtu)
+    }
 }
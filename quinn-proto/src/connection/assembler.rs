@@ -98,6 +98,19 @@ impl Assembler {
         }
     }
 
+    /// Whether [`read()`](Self::read) would yield a chunk
+    ///
+    /// In ordered mode, only data at the current read offset counts.
+    pub(super) fn has_readable(&self) -> bool {
+        match self.state {
+            State::Ordered => self.data.iter().any(|chunk| {
+                chunk.offset <= self.bytes_read
+                    && chunk.offset + chunk.bytes.len() as u64 > self.bytes_read
+            }),
+            State::Unordered { .. } => self.data.iter().any(|chunk| !chunk.bytes.is_empty()),
+        }
+    }
+
     /// Copy fragmented chunk data to new chunks backed by a single buffer
     ///
     /// This makes sure we're not unnecessarily holding on to many larger allocations.
@@ -219,6 +232,11 @@ impl Assembler {
         Ok(())
     }
 
+    /// Number of received bytes waiting to be read, including duplicates in ordered mode
+    pub(super) fn buffered(&self) -> usize {
+        self.buffered
+    }
+
     /// Number of bytes consumed by the application
     pub(super) fn bytes_read(&self) -> u64 {
         self.bytes_read
@@ -383,6 +401,20 @@ mod test {
         assert_matches!(next(&mut x, 32), None);
     }
 
+    #[test]
+    fn has_readable() {
+        let mut x = Assembler::new();
+        assert!(!x.has_readable());
+        x.insert(3, Bytes::from_static(b"456"), 3).unwrap();
+        assert!(!x.has_readable());
+        x.insert(0, Bytes::from_static(b"123"), 3).unwrap();
+        assert!(x.has_readable());
+        assert_matches!(next(&mut x, 32), Some(ref y) if &y[..] == b"123");
+        assert!(x.has_readable());
+        assert_matches!(next(&mut x, 32), Some(ref y) if &y[..] == b"456");
+        assert!(!x.has_readable());
+    }
+
     #[test]
     fn assemble_duplicate() {
         let mut x = Assembler::new();
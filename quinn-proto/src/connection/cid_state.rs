@@ -1,10 +1,9 @@
 //! Maintain the state of local connection IDs
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
 
-use rustc_hash::FxHashSet;
 use tracing::{debug, trace};
 
-use crate::{Duration, Instant, TransportError, shared::IssuedCid};
+use crate::{ConnectionId, Duration, Instant, TransportError, shared::IssuedCid};
 
 /// Local connection ID management
 pub(super) struct CidState {
@@ -12,8 +11,8 @@ pub(super) struct CidState {
     retire_timestamp: VecDeque<CidTimestamp>,
     /// Number of local connection IDs that have been issued in NEW_CONNECTION_ID frames.
     issued: u64,
-    /// Sequence numbers of local connection IDs not yet retired by the peer
-    active_seq: FxHashSet<u64>,
+    /// Local connection IDs not yet retired by the peer, keyed by sequence number
+    active_seq: BTreeMap<u64, ConnectionId>,
     /// Sequence number the peer has already retired all CIDs below at our request via `retire_prior_to`
     prev_retire_seq: u64,
     /// Sequence number to set in retire_prior_to field in NEW_CONNECTION_ID frame
@@ -29,13 +28,17 @@ impl CidState {
         cid_len: usize,
         cid_lifetime: Option<Duration>,
         now: Instant,
-        issued: u64,
+        handshake_cid: ConnectionId,
+        pref_addr_cid: Option<ConnectionId>,
     ) -> Self {
-        let mut active_seq = FxHashSet::default();
-        // Add sequence number of CIDs used in handshaking into tracking set
-        for seq in 0..issued {
-            active_seq.insert(seq);
-        }
+        // Add CIDs used in handshaking into tracking set
+        let active_seq = [handshake_cid]
+            .into_iter()
+            .chain(pref_addr_cid)
+            .enumerate()
+            .map(|(seq, cid)| (seq as u64, cid))
+            .collect::<BTreeMap<_, _>>();
+        let issued = active_seq.len() as u64;
         let mut this = Self {
             retire_timestamp: VecDeque::new(),
             issued,
@@ -94,7 +97,7 @@ impl CidState {
     pub(crate) fn on_cid_timeout(&mut self) -> bool {
         // Whether the peer hasn't retired all the CIDs we asked it to yet
         let unretired_ids_found =
-            (self.prev_retire_seq..self.retire_seq).any(|seq| self.active_seq.contains(&seq));
+            (self.prev_retire_seq..self.retire_seq).any(|seq| self.active_seq.contains_key(&seq));
 
         let current_retire_prior_to = self.retire_seq;
         let next_retire_sequence = self
@@ -126,7 +129,7 @@ impl CidState {
         //
         // If yes (return true), a new CID must be pushed with updated `retire_prior_to` field to remote peer.
         // If no (return false), it means CIDs that reach the end of lifetime have been retired already. Do not push a new CID in order to avoid violating above RFC.
-        (current_retire_prior_to..self.retire_seq).any(|seq| self.active_seq.contains(&seq))
+        (current_retire_prior_to..self.retire_seq).any(|seq| self.active_seq.contains_key(&seq))
     }
 
     /// Update cid state when `NewIdentifiers` event is received
@@ -139,7 +142,7 @@ impl CidState {
         // Record the timestamp of CID with the largest seq number
         let sequence = last_cid.sequence;
         ids.iter().for_each(|frame| {
-            self.active_seq.insert(frame.sequence);
+            self.active_seq.insert(frame.sequence, frame.id);
         });
         self.track_lifetime(sequence, now);
     }
@@ -185,11 +188,16 @@ impl CidState {
         self.retire_seq
     }
 
+    /// Local connection IDs not yet retired by the peer, in order of issuance
+    pub(crate) fn active_cids(&self) -> impl Iterator<Item = ConnectionId> + '_ {
+        self.active_seq.values().copied()
+    }
+
     #[cfg(test)]
     pub(crate) fn active_seq(&self) -> (u64, u64) {
         let mut min = u64::MAX;
         let mut max = u64::MIN;
-        for n in self.active_seq.iter() {
+        for n in self.active_seq.keys() {
             if n < &min {
                 min = *n;
             }
@@ -203,7 +211,7 @@ impl CidState {
     #[cfg(test)]
     pub(crate) fn assign_retire_seq(&mut self, v: u64) -> u64 {
         // Cannot retire more CIDs than what have been issued
-        debug_assert!(v <= *self.active_seq.iter().max().unwrap() + 1);
+        debug_assert!(v <= *self.active_seq.keys().max().unwrap() + 1);
         let n = v.checked_sub(self.retire_seq).unwrap();
         self.retire_seq = v;
         n
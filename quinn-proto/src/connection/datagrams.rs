@@ -26,6 +26,32 @@ impl Datagrams<'_> {
     ///
     /// Returns `Err` iff a `len`-byte datagram cannot currently be sent.
     pub fn send(&mut self, data: Bytes, drop: bool) -> Result<(), SendDatagramError> {
+        self.enqueue(data, drop, None)
+    }
+
+    /// Queue an unreliable, unordered datagram, reporting if it is discarded before transmission
+    ///
+    /// Behaves like [`send`](Self::send), but the returned [`DatagramId`] identifies the datagram
+    /// in an [`Event::DatagramDropped`] if it is later evicted from the outgoing queue, either to
+    /// make space for a newer datagram or because a reduction in path MTU left it too large to
+    /// send. No event is emitted for datagrams which are transmitted.
+    pub fn send_tracked(
+        &mut self,
+        data: Bytes,
+        drop: bool,
+    ) -> Result<DatagramId, SendDatagramError> {
+        let id = DatagramId(self.conn.datagrams.next_id);
+        self.enqueue(data, drop, Some(id))?;
+        self.conn.datagrams.next_id += 1;
+        Ok(id)
+    }
+
+    fn enqueue(
+        &mut self,
+        data: Bytes,
+        drop: bool,
+        id: Option<DatagramId>,
+    ) -> Result<(), SendDatagramError> {
         if self.conn.config.datagram_receive_buffer_size.is_none() {
             return Err(SendDatagramError::Disabled);
         }
@@ -49,7 +75,10 @@ impl Datagrams<'_> {
             return Err(SendDatagramError::Blocked(data));
         }
         self.conn.datagrams.outgoing_total += data.len();
-        self.conn.datagrams.outgoing.push_back(Datagram { data });
+        self.conn.datagrams.outgoing.push_back(OutgoingDatagram {
+            datagram: Datagram { data },
+            id,
+        });
         Ok(())
     }
 
@@ -83,6 +112,19 @@ impl Datagrams<'_> {
         self.conn.datagrams.recv()
     }
 
+    /// Bytes available in the incoming datagram buffer
+    ///
+    /// Receiving a datagram larger than this will cause older datagrams which the application has
+    /// not yet [`recv`](Self::recv)'d to be dropped. Zero if datagram support is disabled locally.
+    pub fn recv_buffer_space(&self) -> usize {
+        self.conn
+            .config
+            .datagram_receive_buffer_size
+            .map_or(0, |window| {
+                window.saturating_sub(self.conn.datagrams.recv_buffered)
+            })
+    }
+
     /// Bytes available in the outgoing datagram buffer
     ///
     /// When greater than zero, [`send`](Self::send)ing a datagram of at most this size is
@@ -95,15 +137,30 @@ impl Datagrams<'_> {
     }
 }
 
+/// Identifies an outgoing datagram queued with [`Datagrams::send_tracked`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct DatagramId(u64);
+
+pub(super) struct OutgoingDatagram {
+    pub(super) datagram: Datagram,
+    /// Set if the application must be told when this datagram is discarded without being sent
+    id: Option<DatagramId>,
+}
+
 #[derive(Default)]
 pub(super) struct DatagramState {
     /// Number of bytes of datagrams that have been received by the local transport but not
     /// delivered to the application
     pub(super) recv_buffered: usize,
     pub(super) incoming: VecDeque<Datagram>,
-    pub(super) outgoing: VecDeque<Datagram>,
+    pub(super) outgoing: VecDeque<OutgoingDatagram>,
     pub(super) outgoing_total: usize,
+    /// Tracked outgoing datagrams which were discarded, awaiting report to the application
+    evicted: VecDeque<DatagramId>,
+    next_id: u64,
     pub(super) send_blocked: bool,
+    /// Number of incoming datagrams discarded to make room for newer ones
+    pub(super) dropped_incoming: u64,
 }
 
 impl DatagramState {
@@ -129,6 +186,7 @@ impl DatagramState {
         while datagram.data.len() + self.recv_buffered > window {
             debug!("dropping stale datagram");
             self.recv();
+            self.dropped_incoming += 1;
         }
 
         self.recv_buffered += datagram.data.len();
@@ -141,8 +199,9 @@ impl DatagramState {
             let Some(prev) = self.outgoing.pop_front() else {
                 break;
             };
-            trace!(len = prev.data.len(), "dropping outgoing datagram");
-            self.outgoing_total -= prev.data.len();
+            trace!(len = prev.datagram.data.len(), "dropping outgoing datagram");
+            self.outgoing_total -= prev.datagram.data.len();
+            self.evicted.extend(prev.id);
         }
     }
 
@@ -162,15 +221,16 @@ impl DatagramState {
     /// queued but can't send it.
     pub(super) fn drop_oversized(&mut self, max_payload: usize) -> bool {
         let mut dropped_any = false;
-        self.outgoing.retain(|datagram| {
-            let result = datagram.data.len() < max_payload;
+        self.outgoing.retain(|queued| {
+            let result = queued.datagram.data.len() < max_payload;
             if !result {
                 trace!(
                     "dropping {} byte datagram violating {} byte limit",
-                    datagram.data.len(),
+                    queued.datagram.data.len(),
                     max_payload
                 );
-                self.outgoing_total -= datagram.data.len();
+                self.outgoing_total -= queued.datagram.data.len();
+                self.evicted.extend(queued.id);
                 dropped_any = true;
             }
             result
@@ -183,24 +243,29 @@ impl DatagramState {
     /// Returns whether a frame was written. At most `max_size` bytes will be written, including
     /// framing.
     pub(super) fn write(&mut self, buf: &mut Vec<u8>, max_size: usize) -> bool {
-        let Some(datagram) = self.outgoing.pop_front() else {
+        let Some(queued) = self.outgoing.pop_front() else {
             return false;
         };
 
-        if buf.len() + datagram.size(true) > max_size {
+        if buf.len() + queued.datagram.size(true) > max_size {
             // Future work: we could be more clever about cramming small datagrams into
             // mostly-full packets when a larger one is queued first
-            self.outgoing.push_front(datagram);
+            self.outgoing.push_front(queued);
             return false;
         }
 
-        trace!(len = datagram.data.len(), "DATAGRAM");
+        trace!(len = queued.datagram.data.len(), "DATAGRAM");
 
-        self.outgoing_total -= datagram.data.len();
-        datagram.encode(true, buf);
+        self.outgoing_total -= queued.datagram.data.len();
+        queued.datagram.encode(true, buf);
         true
     }
 
+    /// Take the next tracked datagram which was discarded without being sent
+    pub(super) fn poll_evicted(&mut self) -> Option<DatagramId> {
+        self.evicted.pop_front()
+    }
+
     pub(super) fn recv(&mut self) -> Option<Bytes> {
         let x = self.incoming.pop_front()?.data;
         self.recv_buffered -= x.len();
@@ -215,33 +280,43 @@ mod tests {
     #[test]
     fn make_space_for_accounts_for_new_datagram() {
         let mut state = DatagramState::default();
-        state.outgoing.push_back(Datagram {
-            data: Bytes::from_static(&[0; 7]),
-        });
-        state.outgoing.push_back(Datagram {
-            data: Bytes::from_static(&[0; 2]),
-        });
+        state
+            .outgoing
+            .push_back(outgoing(&[0; 7], Some(DatagramId(0))));
+        state
+            .outgoing
+            .push_back(outgoing(&[0; 2], Some(DatagramId(1))));
         state.outgoing_total = 9;
 
         state.make_space_for(4, 10);
 
         assert_eq!(state.outgoing.len(), 1);
-        assert_eq!(state.outgoing[0].data.len(), 2);
+        assert_eq!(state.outgoing[0].datagram.data.len(), 2);
         assert_eq!(state.outgoing_total, 2);
+        assert_eq!(state.poll_evicted(), Some(DatagramId(0)));
+        assert_eq!(state.poll_evicted(), None);
     }
 
     #[test]
     fn make_space_for_handles_overflowing_capacity_check() {
         let mut state = DatagramState::default();
-        state.outgoing.push_back(Datagram {
-            data: Bytes::from_static(&[0]),
-        });
+        state.outgoing.push_back(outgoing(&[0], None));
         state.outgoing_total = usize::MAX - 1;
 
         state.make_space_for(2, usize::MAX);
 
         assert!(state.outgoing.is_empty());
         assert_eq!(state.outgoing_total, usize::MAX - 2);
+        assert_eq!(state.poll_evicted(), None);
+    }
+
+    fn outgoing(data: &'static [u8], id: Option<DatagramId>) -> OutgoingDatagram {
+        OutgoingDatagram {
+            datagram: Datagram {
+                data: Bytes::from_static(data),
+            },
+            id,
+        }
     }
 }
 
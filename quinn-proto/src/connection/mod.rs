@@ -21,8 +21,8 @@ use crate::{
     cid_generator::ConnectionIdGenerator,
     cid_queue::CidQueue,
     coding::BufMutExt,
-    config::{ServerConfig, TransportConfig},
-    connection::spaces::LostPacket,
+    config::{ConfigError, IdleTimeout, KeepAlivePolicy, ServerConfig, TransportConfig},
+    connection::{qlog::QlogSink, spaces::LostPacket},
     crypto::{self, KeyPair, Keys, PacketKey},
     frame::{self, Close, Datagram, FrameStruct, NewConnectionId, NewToken},
     packet::{
@@ -35,7 +35,7 @@ use crate::{
         EndpointEvent, EndpointEventInner,
     },
     token::{ResetToken, Token, TokenPayload},
-    transport_parameters::TransportParameters,
+    transport_parameters::{ExtraTransportParameters, TransportParameters},
 };
 
 mod ack_frequency;
@@ -49,10 +49,10 @@ use cid_state::CidState;
 
 mod datagrams;
 use datagrams::DatagramState;
-pub use datagrams::{Datagrams, SendDatagramError};
+pub use datagrams::{DatagramId, Datagrams, SendDatagramError};
 
 mod mtud;
-mod pacing;
+pub mod pacing;
 
 mod packet_builder;
 use packet_builder::PacketBuilder;
@@ -61,6 +61,7 @@ mod packet_crypto;
 use packet_crypto::{PrevCrypto, ZeroRttCrypto};
 
 mod paths;
+use pacing::SendRateLimiter;
 pub use paths::RttEstimator;
 use paths::{PathData, PathResponses};
 
@@ -76,7 +77,11 @@ use spaces::Retransmits;
 use spaces::{PacketNumberFilter, PacketSpace, SendableFrames, SentPacket, ThinRetransmits};
 
 mod stats;
-pub use stats::{ConnectionStats, FrameStats, PathStats, UdpStats};
+use stats::PathBytes;
+pub use stats::{
+    ConnectionStats, FlowControlStats, FrameStats, PathStats, PathStatsEntry, RecvStreamStats,
+    SendStreamStats, UdpStats,
+};
 
 mod streams;
 #[cfg(fuzzing)]
@@ -84,12 +89,13 @@ pub use streams::StreamsState;
 #[cfg(not(fuzzing))]
 use streams::StreamsState;
 pub use streams::{
-    Chunks, ClosedStream, FinishError, ReadError, ReadableError, RecvStream, SendStream,
-    ShouldTransmit, StreamEvent, Streams, WriteError, Written,
+    Chunks, ClosedStream, FinishError, ReadError, ReadableError, RecvStream, RecvStreamState,
+    ResetAtError, SendStream, SendStreamState, ShouldTransmit, StreamEvent, StreamInfo, Streams,
+    WriteError, Written,
 };
 
 mod timer;
-use crate::congestion::Controller;
+use crate::congestion::{self, Controller};
 use timer::{Timer, TimerTable};
 
 /// Protocol state and logic for a single QUIC connection
@@ -140,6 +146,8 @@ pub struct Connection {
     handshake_cid: ConnectionId,
     /// The CID the peer initially chose, for use during the handshake
     rem_handshake_cid: ConnectionId,
+    /// The local CID the peer most recently addressed a 1-RTT packet to
+    current_local_cid: ConnectionId,
     /// The "real" local IP address which was was used to receive the initial packet.
     /// This is only populated for the server case, and if known
     local_ip: Option<IpAddr>,
@@ -162,6 +170,13 @@ pub struct Connection {
     key_phase_size: u64,
     /// Transport parameters set by the peer
     peer_params: TransportParameters,
+    /// Transport parameters set by the peer that quinn doesn't otherwise understand
+    peer_extra_params: ExtraTransportParameters,
+    /// Whether `peer_params` were received during this connection's handshake, rather than being
+    /// defaults or remembered from a previous connection
+    peer_params_received: bool,
+    /// Destination for qlog events, initially taken from [`TransportConfig::qlog_stream`]
+    qlog: QlogSink,
     /// Source ConnectionId of the first packet received from the peer
     orig_rem_cid: ConnectionId,
     /// Destination ConnectionId sent by the client on the first Initial
@@ -170,10 +185,12 @@ pub struct Connection {
     /// one was received
     retry_src_cid: Option<ConnectionId>,
     events: VecDeque<Event>,
+    /// Bounded backlog of congestion and path observations, see [`Connection::poll_path_event`]
+    path_events: VecDeque<PathEvent>,
     endpoint_events: VecDeque<EndpointEventInner>,
     /// Whether the spin bit is in use for this connection
     spin_enabled: bool,
-    /// Outgoing spin bit state
+    /// Outgoing spin bit state, or a random value fixed per remote CID if the spin bit is disabled
     spin: bool,
     /// Packet number spaces: initial, handshake, 1-RTT
     spaces: [PacketSpace; 3],
@@ -187,11 +204,17 @@ pub struct Connection {
     /// spoofing key updates.
     next_crypto: Option<KeyPair<Box<dyn PacketKey>>>,
     accepted_0rtt: bool,
+    /// Whether data from rejected 0-RTT packets was queued for retransmission in 1-RTT packets
+    retransmitted_0rtt: bool,
     /// Whether the idle timer should be reset the next time an ack-eliciting packet is transmitted.
     permit_idle_reset: bool,
     /// Negotiated idle timeout
     idle_timeout: Option<Duration>,
+    /// Local contribution to `idle_timeout`, possibly lowered from the configured value at runtime
+    local_max_idle_timeout: Option<VarInt>,
     timers: TimerTable,
+    /// Limit installed by [`Connection::set_max_send_rate`]
+    send_rate_limiter: Option<SendRateLimiter>,
     /// Number of packets received which could not be authenticated
     authentication_failures: u64,
     /// Why the connection was lost, if it has been
@@ -204,6 +227,14 @@ pub struct Connection {
     //
     /// Responses to PATH_CHALLENGE frames
     path_responses: PathResponses,
+    /// Pings queued by [`Connection::ping_tracked`] that have yet to be sent
+    pending_tracked_pings: Vec<PingId>,
+    /// Pings queued by [`Connection::ping_tracked`] that are awaiting acknowledgement
+    tracked_pings: Vec<TrackedPing>,
+    next_ping_id: u64,
+    /// Challenges issued by [`Connection::probe_path`] that have yet to be answered
+    path_probes: Vec<PathProbe>,
+    next_path_probe_id: u64,
     close: bool,
 
     //
@@ -237,6 +268,10 @@ pub struct Connection {
     datagrams: DatagramState,
     /// Connection level statistics
     stats: ConnectionStats,
+    /// Bytes exchanged over each recently used path
+    path_bytes: PathBytes,
+    /// When the first Initial packet was sent (clients) or received (servers)
+    handshake_started: Instant,
     /// QUIC version used for the connection.
     version: u32,
 }
@@ -277,11 +312,13 @@ impl Connection {
             crypto,
             handshake_cid: loc_cid,
             rem_handshake_cid: rem_cid,
+            current_local_cid: loc_cid,
             local_cid_state: CidState::new(
                 cid_gen.cid_len(),
                 cid_gen.cid_lifetime(),
                 now,
-                if pref_addr_cid.is_some() { 2 } else { 1 },
+                loc_cid,
+                pref_addr_cid,
             ),
             path: PathData::new(remote, allow_mtud, None, 0, now, &config),
             path_counter: 0,
@@ -301,10 +338,14 @@ impl Connection {
             // at the 100th short-header packet.
             key_phase_size: rng.random_range(10..1000),
             peer_params: TransportParameters::default(),
+            peer_extra_params: ExtraTransportParameters::default(),
+            peer_params_received: false,
+            qlog: config.qlog_sink.clone(),
             orig_rem_cid: rem_cid,
             initial_dst_cid: init_cid,
             retry_src_cid: None,
             events: VecDeque::new(),
+            path_events: VecDeque::new(),
             endpoint_events: VecDeque::new(),
             spin_enabled: config.allow_spin && rng.random_ratio(7, 8),
             spin: false,
@@ -313,12 +354,15 @@ impl Connection {
             prev_crypto: None,
             next_crypto: None,
             accepted_0rtt: false,
+            retransmitted_0rtt: false,
             permit_idle_reset: true,
             idle_timeout: match config.max_idle_timeout {
                 None | Some(VarInt(0)) => None,
                 Some(dur) => Some(Duration::from_millis(dur.0)),
             },
+            local_max_idle_timeout: config.max_idle_timeout,
             timers: TimerTable::default(),
+            send_rate_limiter: None,
             authentication_failures: 0,
             error: None,
             #[cfg(test)]
@@ -330,6 +374,11 @@ impl Connection {
             packet_number_filter: PacketNumberFilter::new(&mut rng),
 
             path_responses: PathResponses::default(),
+            pending_tracked_pings: Vec::new(),
+            tracked_pings: Vec::new(),
+            next_ping_id: 0,
+            path_probes: Vec::new(),
+            next_path_probe_id: 0,
             close: false,
 
             ack_frequency: AckFrequencyState::new(get_max_ack_delay(
@@ -355,8 +404,23 @@ impl Connection {
             rem_cids: CidQueue::new(rem_cid),
             rng,
             stats: ConnectionStats::default(),
+            path_bytes: PathBytes::default(),
+            handshake_started: now,
             version,
         };
+        if let Some(rtt) = this
+            .endpoint_config
+            .rtt_cache
+            .as_ref()
+            .and_then(|cache| cache.get(remote))
+        {
+            trace!(?rtt, "using cached RTT");
+            this.path.rtt = RttEstimator::new(rtt);
+        }
+        this.reset_spin();
+        if let Some(timeout) = this.config.handshake_timeout {
+            this.timers.set(Timer::Handshake, now + timeout);
+        }
         if path_validated {
             this.on_path_validated();
         }
@@ -395,6 +459,10 @@ impl Connection {
             return Some(Event::Stream(event));
         }
 
+        if let Some(id) = self.datagrams.poll_evicted() {
+            return Some(Event::DatagramDropped { id });
+        }
+
         if let Some(err) = self.error.take() {
             return Some(Event::ConnectionLost { reason: err });
         }
@@ -402,6 +470,22 @@ impl Connection {
         None
     }
 
+    /// Returns observations about congestion control and the network path
+    ///
+    /// Unlike [`poll()`](Self::poll), these events are purely informational and need not be
+    /// polled. Only the most recent few events are retained; older ones are discarded.
+    #[must_use]
+    pub fn poll_path_event(&mut self) -> Option<PathEvent> {
+        self.path_events.pop_front()
+    }
+
+    fn push_path_event(&mut self, event: PathEvent) {
+        if self.path_events.len() == MAX_PATH_EVENTS {
+            self.path_events.pop_front();
+        }
+        self.path_events.push_back(event);
+    }
+
     /// Return endpoint-facing events
     #[must_use]
     pub fn poll_endpoint_events(&mut self) -> Option<EndpointEvent> {
@@ -472,6 +556,10 @@ impl Connection {
             return Some(challenge);
         }
 
+        if let Some(probe) = self.send_path_probe(now, buf) {
+            return Some(probe);
+        }
+
         // If we need to send a probe, make sure we have something to send.
         for space in SpaceId::iter() {
             let request_immediate_ack =
@@ -584,10 +672,10 @@ impl Connection {
                 // for starting another datagram. If there is any anti-amplification
                 // budget left, we always allow a full MTU to be sent
                 // (see https://github.com/quinn-rs/quinn/issues/1082)
-                if self
-                    .path
-                    .anti_amplification_blocked(segment_size as u64 * (num_datagrams as u64) + 1)
-                {
+                if self.path.anti_amplification_blocked(
+                    segment_size as u64 * (num_datagrams as u64) + 1,
+                    self.side.anti_amplification_factor(),
+                ) {
                     trace!("blocked by anti-amplification");
                     break;
                 }
@@ -604,7 +692,7 @@ impl Connection {
                     debug_assert!(untracked_bytes <= segment_size as u64);
 
                     let bytes_to_send = segment_size as u64 + untracked_bytes;
-                    if self.path.in_flight.bytes + bytes_to_send >= self.path.congestion.window() {
+                    if self.path.in_flight.bytes + bytes_to_send >= self.path.send_window() {
                         space_idx += 1;
                         congestion_blocked = true;
                         // We continue instead of breaking here in order to avoid
@@ -616,11 +704,11 @@ impl Connection {
                     // Check whether the next datagram is blocked by pacing
                     let smoothed_rtt = self.path.rtt.get();
                     if let Some(delay) = self.path.pacing.delay(
-                        smoothed_rtt,
                         bytes_to_send,
-                        self.path.current_mtu(),
-                        self.path.congestion.window(),
                         now,
+                        smoothed_rtt,
+                        self.path.send_window(),
+                        self.path.current_mtu(),
                     ) {
                         self.timers.set(Timer::Pacing, delay);
                         congestion_blocked = true;
@@ -629,6 +717,15 @@ impl Connection {
                         trace!("blocked by pacing");
                         break;
                     }
+
+                    if let Some(delay) = self.send_rate_limiter.as_mut().and_then(|limiter| {
+                        limiter.delay(bytes_to_send, now, self.path.current_mtu())
+                    }) {
+                        self.timers.set(Timer::Pacing, delay);
+                        congestion_blocked = true;
+                        trace!("blocked by send rate limit");
+                        break;
+                    }
                 }
 
                 // Finish current packet
@@ -792,6 +889,7 @@ impl Connection {
                     Self::populate_acks(
                         now,
                         self.receiving_ecn,
+                        self.config.ack_delay_exponent,
                         &mut SentFrames::default(),
                         &mut self.spaces[space_id],
                         buf,
@@ -867,7 +965,7 @@ impl Connection {
                         }),
                         buf,
                     );
-                    self.stats.udp_tx.on_sent(1, buf.len());
+                    self.on_datagrams_sent(remote, 1, buf.len());
                     return Some(Transmit {
                         destination: remote,
                         size: buf.len(),
@@ -930,12 +1028,8 @@ impl Connection {
                 .congestion
                 .on_sent(now, buf.len() as u64, last_packet_number);
 
-            self.config.qlog_sink.emit_recovery_metrics(
-                self.pto_count,
-                &mut self.path,
-                now,
-                self.orig_rem_cid,
-            );
+            self.qlog
+                .emit_recovery_metrics(self.pto_count, &mut self.path, now, self.orig_rem_cid);
         }
 
         self.app_limited = buf.is_empty() && !congestion_blocked;
@@ -992,13 +1086,13 @@ impl Connection {
         trace!("sending {} bytes in {} datagrams", buf.len(), num_datagrams);
         self.path.total_sent = self.path.total_sent.saturating_add(buf.len() as u64);
 
-        self.stats.udp_tx.on_sent(num_datagrams as u64, buf.len());
+        self.on_datagrams_sent(self.path.remote, num_datagrams as u64, buf.len());
 
         Some(Transmit {
             destination: self.path.remote,
             size: buf.len(),
             ecn: if self.path.sending_ecn {
-                Some(EcnCodepoint::Ect0)
+                self.config.outgoing_ecn.codepoint()
             } else {
                 None
             },
@@ -1010,6 +1104,58 @@ impl Connection {
         })
     }
 
+    /// Send PATH_CHALLENGE for a path queued by [`Connection::probe_path`] if necessary
+    fn send_path_probe(&mut self, now: Instant, buf: &mut Vec<u8>) -> Option<Transmit> {
+        if !self.state.is_established() {
+            return None;
+        }
+        let idx = self.path_probes.iter().position(|p| p.sent.is_none())?;
+        let PathProbe { token, remote, .. } = self.path_probes[idx];
+        buf.reserve(MIN_INITIAL_SIZE as usize);
+
+        let buf_capacity = buf.capacity();
+        let mut builder = PacketBuilder::new(
+            now,
+            SpaceId::Data,
+            self.rem_cids.active(),
+            buf,
+            buf_capacity,
+            0,
+            false,
+            self,
+        )?;
+        trace!(%remote, "probing path with PATH_CHALLENGE {:08x}", token);
+        buf.write(frame::FrameType::PATH_CHALLENGE);
+        buf.write(token);
+        self.stats.frame_tx.path_challenge += 1;
+        builder.pad_to(MIN_INITIAL_SIZE);
+
+        builder.finish(self, now, buf);
+        self.on_datagrams_sent(remote, 1, buf.len());
+
+        let timeout = 3 * self.pto(SpaceId::Data);
+        let probe = &mut self.path_probes[idx];
+        probe.sent = Some(now);
+        probe.deadline = Some(now + timeout);
+        self.set_path_probe_timer();
+
+        Some(Transmit {
+            destination: remote,
+            size: buf.len(),
+            ecn: None,
+            segment_size: None,
+            src_ip: self.local_ip,
+        })
+    }
+
+    /// Arm [`Timer::PathProbe`] for the earliest outstanding probe, if any
+    fn set_path_probe_timer(&mut self) {
+        match self.path_probes.iter().filter_map(|p| p.deadline).min() {
+            Some(deadline) => self.timers.set(Timer::PathProbe, deadline),
+            None => self.timers.stop(Timer::PathProbe),
+        }
+    }
+
     /// Send PATH_CHALLENGE for a previous path if necessary
     fn send_path_challenge(&mut self, now: Instant, buf: &mut Vec<u8>) -> Option<Transmit> {
         let (prev_cid, prev_path) = self.prev_path.as_mut()?;
@@ -1057,7 +1203,7 @@ impl Connection {
         builder.pad_to(MIN_INITIAL_SIZE);
 
         builder.finish(self, now, buf);
-        self.stats.udp_tx.on_sent(1, buf.len());
+        self.on_datagrams_sent(destination, 1, buf.len());
 
         Some(Transmit {
             destination,
@@ -1096,37 +1242,46 @@ impl Connection {
             Datagram(DatagramConnectionEvent {
                 now,
                 remote,
+                local_ip,
                 ecn,
                 first_decode,
                 remaining,
             }) => {
                 // If this packet could initiate a migration and we're a client or a server that
-                // forbids migration, drop the datagram. This could be relaxed to heuristically
-                // permit NAT-rebinding-like migration.
-                if remote != self.path.remote && !self.side.remote_may_migrate() {
+                // forbids migration, drop the datagram.
+                if remote != self.path.remote
+                    && !self.side.remote_may_migrate(self.path.remote, remote)
+                    && !self.path_probes.iter().any(|p| p.remote == remote)
+                {
                     trace!("discarding packet from unrecognized peer {}", remote);
                     return;
                 }
 
-                let was_anti_amplification_blocked = self.path.anti_amplification_blocked(1);
+                let was_anti_amplification_blocked = self
+                    .path
+                    .anti_amplification_blocked(1, self.side.anti_amplification_factor());
 
                 self.stats.udp_rx.datagrams += 1;
                 self.stats.udp_rx.bytes += first_decode.len() as u64;
                 let data_len = first_decode.len();
 
-                self.handle_decode(now, remote, ecn, first_decode);
+                self.handle_decode(now, remote, local_ip, ecn, first_decode);
                 // The current `path` might have changed inside `handle_decode`,
                 // since the packet could have triggered a migration. Make sure
                 // the data received is accounted for the most recent path by accessing
                 // `path` after `handle_decode`.
                 self.path.total_recvd = self.path.total_recvd.saturating_add(data_len as u64);
 
+                let mut total_len = data_len;
                 if let Some(data) = remaining {
                     self.stats.udp_rx.bytes += data.len() as u64;
-                    self.handle_coalesced(now, remote, ecn, data);
+                    total_len += data.len();
+                    self.handle_coalesced(now, remote, local_ip, ecn, data);
                 }
+                self.path_bytes
+                    .record(remote, self.local_ip, 0, total_len as u64);
 
-                self.config.qlog_sink.emit_recovery_metrics(
+                self.qlog.emit_recovery_metrics(
                     self.pto_count,
                     &mut self.path,
                     now,
@@ -1177,14 +1332,25 @@ impl Connection {
                 Timer::Idle => {
                     self.kill(ConnectionError::TimedOut);
                 }
+                Timer::Handshake => {
+                    debug!("handshake timed out");
+                    self.kill(ConnectionError::HandshakeTimeout);
+                }
                 Timer::KeepAlive => {
+                    if self.config.keep_alive_policy == KeepAlivePolicy::WhenStreamsOpen
+                        && !self.streams.has_open_streams()
+                    {
+                        // Rearmed by the next packet, e.g. once a stream is opened
+                        trace!("no open streams, skipping keep-alive");
+                        continue;
+                    }
                     trace!("sending keep-alive");
                     self.ping();
                 }
                 Timer::LossDetection => {
                     self.on_loss_detection_timeout(now);
 
-                    self.config.qlog_sink.emit_recovery_metrics(
+                    self.qlog.emit_recovery_metrics(
                         self.pto_count,
                         &mut self.path,
                         now,
@@ -1200,6 +1366,8 @@ impl Connection {
                     if let Some((_, prev)) = self.prev_path.take() {
                         self.path = prev;
                         self.set_loss_detection_timer(now);
+                    } else {
+                        self.path.validation_failed = true;
                     }
                     self.path.challenge = None;
                     self.path.challenge_pending = false;
@@ -1224,6 +1392,21 @@ impl Connection {
                         .pending_acks
                         .on_max_ack_delay_timeout()
                 }
+                Timer::PathProbe => {
+                    let events = &mut self.events;
+                    self.path_probes.retain(|probe| {
+                        let expired = probe.deadline.is_some_and(|deadline| deadline <= now);
+                        if expired {
+                            debug!(remote = %probe.remote, "path probe timed out");
+                            events.push_back(Event::PathProbed {
+                                id: probe.id,
+                                rtt: None,
+                            });
+                        }
+                        !expired
+                    });
+                    self.set_path_probe_timer();
+                }
             }
         }
     }
@@ -1266,11 +1449,75 @@ impl Connection {
         let mut stats = self.stats;
         stats.path.rtt = self.path.rtt.get();
         stats.path.cwnd = self.path.congestion.window();
+        stats.path.bytes_in_flight = self.path.in_flight.bytes;
+        stats.path.packets_in_flight = self.path.in_flight.ack_eliciting;
+        stats.path.pto_count = self.pto_count;
         stats.path.current_mtu = self.path.mtud.current_mtu();
+        stats.datagrams_dropped_incoming = self.datagrams.dropped_incoming;
 
         stats
     }
 
+    /// When the connection will time out if no further packets are received
+    ///
+    /// Moves later whenever a packet is received from the peer. `None` if the idle timeout is
+    /// disabled or the connection is closed.
+    pub fn idle_timeout_deadline(&self) -> Option<Instant> {
+        self.timers.get(Timer::Idle)
+    }
+
+    /// Bytes sent and received over each network path the connection has recently used
+    ///
+    /// Entries are kept per combination of peer address and local IP, so traffic that moved to a
+    /// new path through migration remains attributable to the path it actually traveled over. The
+    /// entries are ordered from least to most recently used, and only the last few paths are
+    /// retained.
+    pub fn path_stats(&self) -> Vec<PathStatsEntry> {
+        self.path_bytes.snapshot(self.path.remote)
+    }
+
+    /// Returns the state of connection-level flow control and stream count limits
+    ///
+    /// Helps distinguish a sender stalled by the peer's `MAX_DATA` or `MAX_STREAMS` limits from
+    /// one stalled by per-stream flow control or congestion control.
+    pub fn flow_control_stats(&self) -> FlowControlStats {
+        self.streams.flow_control_stats()
+    }
+
+    /// Whether the peer has acknowledged all data written to send streams
+    ///
+    /// Includes the end of each finished stream. Reset streams are not waited on.
+    pub fn is_send_data_acked(&self) -> bool {
+        self.streams.is_send_data_acked()
+    }
+
+    /// Transport parameters advertised by the peer
+    ///
+    /// `None` until the peer's transport parameters have been received during the handshake.
+    /// Parameters remembered from a previous connection for use with 0-RTT are not reported.
+    pub fn peer_transport_params(&self) -> Option<TransportParameters> {
+        self.peer_params_received.then_some(self.peer_params)
+    }
+
+    /// Transport parameters advertised by the peer that quinn doesn't otherwise understand
+    ///
+    /// Allows inspecting extensions quinn doesn't implement, such as those a peer advertises with
+    /// [`TransportConfig::extra_transport_parameters`]. `None` under the same conditions as
+    /// [`peer_transport_params()`](Self::peer_transport_params).
+    pub fn peer_extra_transport_params(&self) -> Option<&ExtraTransportParameters> {
+        self.peer_params_received.then_some(&self.peer_extra_params)
+    }
+
+    /// Largest UDP payload the peer is willing to receive
+    ///
+    /// Reported from the peer's `max_udp_payload_size` transport parameter, saturating at
+    /// `u16::MAX`, or the protocol default of 65527 until that has been received. Outgoing
+    /// datagrams are additionally bounded by the path MTU, as reflected by
+    /// [`Datagrams::max_size()`].
+    pub fn peer_max_udp_payload_size(&self) -> u16 {
+        u16::try_from(self.peer_params.max_udp_payload_size.into_inner()).unwrap_or(u16::MAX)
+    }
+
     /// Ping the remote endpoint
     ///
     /// Causes an ACK-eliciting packet to be transmitted.
@@ -1278,6 +1525,50 @@ impl Connection {
         self.spaces[self.highest_space].ping_pending = true;
     }
 
+    /// Ping the remote endpoint, measuring the round trip
+    ///
+    /// Like [`ping()`](Self::ping), but once the PING is acknowledged an
+    /// [`Event::PingAcknowledged`] carrying the returned ID reports the round-trip time, less any
+    /// delay the peer reported for the acknowledgement. If the PING is lost, it's sent again.
+    pub fn ping_tracked(&mut self) -> PingId {
+        let id = PingId(self.next_ping_id);
+        self.next_ping_id += 1;
+        self.pending_tracked_pings.push(id);
+        self.ping();
+        id
+    }
+
+    /// Check whether the peer can be reached at `remote`, measuring the round trip
+    ///
+    /// Sends a PATH_CHALLENGE to `remote` once the handshake has completed, without migrating the
+    /// connection, and reports the outcome through an [`Event::PathProbed`] carrying the returned
+    /// ID. `remote` may be the current peer address or a candidate for a later migration. The
+    /// challenge is sent once; if no matching PATH_RESPONSE arrives within three probe timeouts,
+    /// the probe fails and may be retried.
+    pub fn probe_path(&mut self, remote: SocketAddr) -> PathProbeId {
+        let id = PathProbeId(self.next_path_probe_id);
+        self.next_path_probe_id += 1;
+        self.path_probes.push(PathProbe {
+            id,
+            remote,
+            token: self.rng.random(),
+            sent: None,
+            deadline: None,
+        });
+        id
+    }
+
+    /// Cap the rate at which this connection sends, in bytes per second
+    ///
+    /// Applied on top of congestion control and pacing, which may still hold transmissions back
+    /// further: data is only ever deferred, never discarded. Unlike
+    /// [`TransportConfig::max_outgoing_bytes_per_second`], which shapes each path's pacer, the
+    /// limit covers the connection as a whole and can be changed at any time. Packets that don't
+    /// elicit acknowledgements, such as pure ACKs, are exempt. `None` removes the limit.
+    pub fn set_max_send_rate(&mut self, now: Instant, bytes_per_second: Option<u64>) {
+        self.send_rate_limiter = bytes_per_second.map(|rate| SendRateLimiter::new(rate, now));
+    }
+
     /// Update traffic keys spontaneously
     ///
     /// This can be useful for testing key updates, as they otherwise only happen infrequently.
@@ -1310,6 +1601,16 @@ impl Connection {
         self.state.is_handshake()
     }
 
+    /// The application protocol negotiated through ALPN
+    ///
+    /// Returns `None` while the handshake is in progress, or if no protocol was negotiated.
+    pub fn negotiated_alpn(&self) -> Option<&[u8]> {
+        if self.is_handshaking() {
+            return None;
+        }
+        self.crypto.alpn_protocol()
+    }
+
     /// Whether the connection is closed
     ///
     /// Closed connections cannot transport any further data. A connection becomes closed when
@@ -1339,6 +1640,25 @@ impl Connection {
         self.accepted_0rtt
     }
 
+    /// For clients, if 0-RTT was rejected but its stream data is being resent in 1-RTT packets
+    ///
+    /// Only possible when enabled by
+    /// [`ClientConfig::retransmit_rejected_0rtt()`](crate::ClientConfig::retransmit_rejected_0rtt).
+    /// Streams opened before the handshake completed then remain usable. The value is meaningless
+    /// until after the handshake completes.
+    pub fn retransmitted_0rtt(&self) -> bool {
+        self.retransmitted_0rtt
+    }
+
+    /// For clients, the locally-initiated streams whose 0-RTT data was discarded by the peer
+    ///
+    /// Lists streams which had data written, or were finished or reset, before the peer rejected
+    /// 0-RTT, in ascending order of ID within each direction. Empty until the handshake completes,
+    /// and always empty if 0-RTT was accepted or never attempted.
+    pub fn zero_rtt_rejected_streams(&self) -> &[StreamId] {
+        self.streams.zero_rtt_rejected_streams()
+    }
+
     /// Whether 0-RTT is/was possible during the handshake
     pub fn has_0rtt(&self) -> bool {
         self.zero_rtt_enabled
@@ -1354,20 +1674,58 @@ impl Connection {
         self.side.side()
     }
 
+    /// Destination connection ID of the client's Initial packet that established this connection
+    ///
+    /// Unlike the connection IDs actually in use, this never changes over the lifetime of the
+    /// connection, and is the same on both sides unless the server sent a Retry. Useful for
+    /// correlating logs.
+    pub fn initial_dst_cid(&self) -> ConnectionId {
+        self.initial_dst_cid
+    }
+
+    /// Connection ID the peer currently uses to address this connection's packets
+    ///
+    /// This is the destination connection ID of the most recently received 1-RTT packet, or the
+    /// connection ID we chose during the handshake if none has been received yet. It changes as
+    /// the peer switches to other connection IDs we've issued, such as after migrating.
+    pub fn current_local_cid(&self) -> ConnectionId {
+        self.current_local_cid
+    }
+
+    /// Connection IDs issued to the peer that it hasn't yet retired, in order of issuance
+    pub fn local_cids(&self) -> Vec<ConnectionId> {
+        self.local_cid_state.active_cids().collect()
+    }
+
     /// The latest socket address for this connection's peer
     pub fn remote_address(&self) -> SocketAddr {
         self.path.remote
     }
 
-    /// The local IP address which was used when the peer established
-    /// the connection
+    /// Whether the peer has been confirmed reachable at [`remote_address()`](Self::remote_address)
+    ///
+    /// A server's path is validated by the handshake and again by PATH_CHALLENGE/PATH_RESPONSE
+    /// after every migration. If validation of a new path times out, the connection falls back to
+    /// the previous path where possible, so `Failed` is only reported when no such path exists.
+    /// Clients never validate the server's address, so their path is always `Validated`.
+    pub fn path_validation_state(&self) -> PathValidationState {
+        if self.path.validated {
+            PathValidationState::Validated
+        } else if self.path.validation_failed {
+            PathValidationState::Failed
+        } else {
+            PathValidationState::Validating
+        }
+    }
+
+    /// The local IP address to which the peer most recently sent an authenticated packet
     ///
     /// This can be different from the address the endpoint is bound to, in case
     /// the endpoint is bound to a wildcard address like `0.0.0.0` or `::`.
     ///
-    /// This will return `None` for clients, or when no `local_ip` was passed to
-    /// [`Endpoint::handle()`](crate::Endpoint::handle) for the datagrams establishing this
-    /// connection.
+    /// This will return `None` until a packet is received for which a `local_ip` was passed to
+    /// [`Endpoint::handle()`](crate::Endpoint::handle). For servers, that includes the datagrams
+    /// establishing the connection.
     pub fn local_ip(&self) -> Option<IpAddr> {
         self.local_ip
     }
@@ -1377,11 +1735,52 @@ impl Connection {
         self.path.rtt.get()
     }
 
+    /// Recent raw round-trip-time measurements, oldest first
+    ///
+    /// Unlike [`rtt()`](Self::rtt), these are neither smoothed nor adjusted for ack delay, so they
+    /// can be used to compute statistics such as jitter. Only as many as
+    /// [`TransportConfig::rtt_sample_history()`] are retained, so none by default.
+    pub fn rtt_samples(&self) -> impl Iterator<Item = Duration> + '_ {
+        self.path.rtt_samples()
+    }
+
     /// Current state of this connection's congestion controller, for debugging purposes
     pub fn congestion_state(&self) -> &dyn Controller {
         self.path.congestion.as_ref()
     }
 
+    /// Name of the congestion control algorithm in use on the current path
+    ///
+    /// See [`Controller::name()`].
+    pub fn congestion_controller_name(&self) -> &'static str {
+        self.path.congestion.name()
+    }
+
+    /// Replace the congestion controller of the current path
+    ///
+    /// The new controller is built by `factory`, with the current congestion window passed as its
+    /// initial window. The round-trip estimate and bytes in flight are tracked by the connection
+    /// rather than the controller, so they carry over unchanged. Any other algorithm-specific
+    /// state, such as a slow start threshold or bandwidth estimate, is lost; the transfer is
+    /// best-effort.
+    pub fn set_congestion_controller(
+        &mut self,
+        now: Instant,
+        factory: Arc<dyn congestion::ControllerFactory + Send + Sync>,
+    ) {
+        self.path.replace_congestion(now, factory);
+    }
+
+    /// Start or stop writing qlog events for this connection
+    ///
+    /// Replaces any stream configured through [`TransportConfig::qlog_stream`]. Only events
+    /// occurring after the call are written to `stream`; nothing is replayed. Passing `None` stops
+    /// qlog output for this connection.
+    #[cfg(feature = "qlog")]
+    pub fn set_qlog_stream(&mut self, stream: Option<crate::QlogStream>) {
+        self.qlog = stream.into();
+    }
+
     /// Resets path-specific settings.
     ///
     /// This will force-reset several subsystems related to a specific network path.
@@ -1429,6 +1828,42 @@ impl Connection {
         }
     }
 
+    /// Update the local maximum idle timeout for the remainder of the connection
+    ///
+    /// The idle timer is restarted from `now` under the new timeout. The effective timeout is the
+    /// minimum of both peers' values, and the peer continues to enforce the value of
+    /// [`TransportConfig::max_idle_timeout()`] that it was sent during the handshake, so raising
+    /// the timeout above either advertised value has no effect. `None` reverts to the configured
+    /// value.
+    ///
+    /// Fails with [`ConfigError::OutOfBounds`] if `timeout` is zero, which would otherwise
+    /// disable the timeout rather than shorten it.
+    pub fn set_max_idle_timeout(
+        &mut self,
+        now: Instant,
+        timeout: Option<IdleTimeout>,
+    ) -> Result<(), ConfigError> {
+        let requested = timeout.map(VarInt::from);
+        if requested == Some(VarInt(0)) {
+            return Err(ConfigError::OutOfBounds);
+        }
+        let configured = self.config.max_idle_timeout.filter(|x| x.0 != 0);
+        self.local_max_idle_timeout = match (requested, configured) {
+            (Some(x), Some(y)) => Some(cmp::min(x, y)),
+            (x, y) => x.or(y),
+        };
+        self.idle_timeout = negotiate_max_idle_timeout(
+            self.local_max_idle_timeout,
+            Some(self.peer_params.max_idle_timeout),
+        );
+        trace!("updated max idle timeout {:?}", self.idle_timeout);
+        match self.idle_timeout {
+            Some(_) => self.reset_idle_timeout(now, self.highest_space),
+            None => self.timers.stop(Timer::Idle),
+        }
+        Ok(())
+    }
+
     fn on_ack_received(
         &mut self,
         now: Instant,
@@ -1491,6 +1926,7 @@ impl Connection {
                     self.path
                         .congestion
                         .on_mtu_update(self.path.mtud.current_mtu());
+                    self.push_path_event(PathEvent::PathMtuUpdated(self.path.mtud.current_mtu()));
                 }
 
                 // Notify ack frequency that a packet was acked, because it might contain an ACK_FREQUENCY frame
@@ -1507,17 +1943,36 @@ impl Connection {
             self.spaces[space].largest_acked_packet,
         );
 
+        let ack_delay = if space != SpaceId::Data {
+            Duration::from_micros(0)
+        } else {
+            cmp::min(
+                self.ack_frequency.peer_max_ack_delay,
+                Duration::from_micros(ack.delay << self.peer_params.ack_delay_exponent.0),
+            )
+        };
+
+        if !self.tracked_pings.is_empty() {
+            self.tracked_pings.retain(|ping| {
+                let acked = ping.space == space
+                    && newly_acked.iter().any(|range| range.contains(&ping.packet));
+                if acked {
+                    let mut rtt = now.saturating_duration_since(ping.sent);
+                    // The reported delay only applies to the largest acknowledged packet
+                    if ping.packet == ack.largest {
+                        rtt = rtt.saturating_sub(ack_delay);
+                    }
+                    self.events
+                        .push_back(Event::PingAcknowledged { id: ping.id, rtt });
+                }
+                !acked
+            });
+        }
+
         if new_largest && ack_eliciting_acked {
-            let ack_delay = if space != SpaceId::Data {
-                Duration::from_micros(0)
-            } else {
-                cmp::min(
-                    self.ack_frequency.peer_max_ack_delay,
-                    Duration::from_micros(ack.delay << self.peer_params.ack_delay_exponent.0),
-                )
-            };
             let rtt = now.saturating_duration_since(self.spaces[space].largest_acked_packet_sent);
-            self.path.rtt.update(ack_delay, rtt);
+            self.path
+                .update_rtt(ack_delay, rtt, self.config.rtt_sample_history);
             if self.path.first_packet_after_rtt_sample.is_none() {
                 self.path.first_packet_after_rtt_sample =
                     Some((space, self.spaces[space].next_packet_number));
@@ -1549,6 +2004,7 @@ impl Connection {
             }
         }
 
+        self.path.update_pacer();
         self.set_loss_detection_timer(now);
         Ok(())
     }
@@ -1599,7 +2055,10 @@ impl Connection {
         ecn: frame::EcnCounts,
         largest_sent_time: Instant,
     ) {
-        match self.spaces[space].detect_ecn(newly_acked, ecn) {
+        let Some(codepoint) = self.config.outgoing_ecn.codepoint() else {
+            return;
+        };
+        match self.spaces[space].detect_ecn(newly_acked, ecn, codepoint) {
             Err(e) => {
                 debug!("halting ECN due to verification failure: {}", e);
                 self.path.sending_ecn = false;
@@ -1607,12 +2066,16 @@ impl Connection {
                 // future attempts to use ECN on new paths.
                 self.spaces[space].ecn_feedback = frame::EcnCounts::ZERO;
             }
-            Ok(false) => {}
-            Ok(true) => {
+            Ok(0) => {}
+            Ok(ce_count) => {
                 self.stats.path.congestion_events += 1;
+                self.path
+                    .congestion
+                    .on_ecn_ce(now, largest_sent_time, ce_count);
                 self.path
                     .congestion
                     .on_congestion_event(now, largest_sent_time, false, true, 0);
+                self.push_path_event(PathEvent::CongestionEvent);
             }
         }
     }
@@ -1641,7 +2104,8 @@ impl Connection {
         }
 
         for frame in info.stream_frames {
-            self.streams.received_ack_of(frame);
+            self.streams
+                .received_ack_of(frame, &mut self.spaces[SpaceId::Data].pending);
         }
     }
 
@@ -1777,6 +2241,9 @@ impl Connection {
             let largest_lost_sent = self.spaces[pn_space].sent_packets[&largest_lost].time_sent;
             self.stats.path.lost_packets += lost_packets.len() as u64;
             self.stats.path.lost_bytes += size_of_lost_packets;
+            self.push_path_event(PathEvent::LossDetected {
+                packets: lost_packets.len() as u64,
+            });
             trace!(
                 "packets lost: {:?}, bytes lost: {}",
                 lost_packets, size_of_lost_packets
@@ -1784,7 +2251,7 @@ impl Connection {
 
             for &packet in &lost_packets {
                 let info = self.spaces[pn_space].take(packet).unwrap(); // safe: lost_packets is populated just above
-                self.config.qlog_sink.emit_packet_lost(
+                self.qlog.emit_packet_lost(
                     packet,
                     &info,
                     loss_delay,
@@ -1795,10 +2262,18 @@ impl Connection {
 
                 self.remove_in_flight(&info);
                 for frame in info.stream_frames {
-                    self.streams.retransmit(frame);
+                    self.streams
+                        .retransmit(frame, now, &mut self.spaces[pn_space].pending);
                 }
                 self.spaces[pn_space].pending |= info.retransmits;
                 self.path.mtud.on_non_probe_lost(packet, info.size);
+                for ping in self
+                    .tracked_pings
+                    .extract_if(.., |ping| ping.space == pn_space && ping.packet == packet)
+                {
+                    self.pending_tracked_pings.push(ping.id);
+                    self.spaces[self.highest_space].ping_pending = true;
+                }
 
                 self.spaces[pn_space].lost_packets.insert(
                     packet,
@@ -1813,6 +2288,7 @@ impl Connection {
                 self.path
                     .congestion
                     .on_mtu_update(self.path.mtud.current_mtu());
+                self.push_path_event(PathEvent::PathMtuUpdated(self.path.mtud.current_mtu()));
                 if let Some(max_datagram_size) = self.datagrams().max_size() {
                     if self.datagrams.drop_oversized(max_datagram_size)
                         && self.datagrams.send_blocked
@@ -1835,6 +2311,7 @@ impl Connection {
                     false,
                     size_of_lost_packets,
                 );
+                self.push_path_event(PathEvent::CongestionEvent);
             }
         }
 
@@ -1845,6 +2322,8 @@ impl Connection {
             self.path.mtud.on_probe_lost();
             self.stats.path.lost_plpmtud_probes += 1;
         }
+
+        self.path.update_pacer();
     }
 
     fn loss_time_and_space(&self) -> Option<(Instant, SpaceId)> {
@@ -1919,7 +2398,10 @@ impl Connection {
             return;
         }
 
-        if self.path.anti_amplification_blocked(1) {
+        if self
+            .path
+            .anti_amplification_blocked(1, self.side.anti_amplification_factor())
+        {
             // We wouldn't be able to send anything, so don't bother.
             self.timers.stop(Timer::LossDetection);
             return;
@@ -1987,20 +2469,19 @@ impl Connection {
             }
         }
         let space = &mut self.spaces[space_id];
-        space.pending_acks.insert_one(packet, now);
+        space
+            .pending_acks
+            .insert_one(packet, now, self.config.max_ack_ranges);
         if packet >= space.rx_packet {
             space.rx_packet = packet;
-            // Update outgoing spin bit, inverting iff we're the client
-            self.spin = self.side.is_client() ^ spin;
+            if self.spin_enabled {
+                // Update outgoing spin bit, inverting iff we're the client
+                self.spin = self.side.is_client() ^ spin;
+            }
         }
 
-        self.config.qlog_sink.emit_packet_received(
-            packet,
-            space_id,
-            !is_1rtt,
-            now,
-            self.orig_rem_cid,
-        );
+        self.qlog
+            .emit_packet_received(packet, space_id, !is_1rtt, now, self.orig_rem_cid);
     }
 
     fn reset_idle_timeout(&mut self, now: Instant, space: SpaceId) {
@@ -2047,6 +2528,7 @@ impl Connection {
         debug_assert!(self.side.is_server());
         let len = packet.header_data.len() + packet.payload.len();
         self.path.total_recvd = len as u64;
+        self.path_bytes.record(remote, self.local_ip, 0, len as u64);
 
         match self.state {
             State::Handshake(ref mut state) => {
@@ -2066,15 +2548,11 @@ impl Connection {
 
         self.process_decrypted_packet(now, remote, Some(packet_number), packet.into())?;
         if let Some(data) = remaining {
-            self.handle_coalesced(now, remote, ecn, data);
+            self.handle_coalesced(now, remote, self.local_ip, ecn, data);
         }
 
-        self.config.qlog_sink.emit_recovery_metrics(
-            self.pto_count,
-            &mut self.path,
-            now,
-            self.orig_rem_cid,
-        );
+        self.qlog
+            .emit_recovery_metrics(self.pto_count, &mut self.path, now, self.orig_rem_cid);
 
         Ok(())
     }
@@ -2246,6 +2724,14 @@ impl Connection {
         for packet in sent_packets.into_values() {
             self.remove_in_flight(&packet);
         }
+        // Pings sent in this space can no longer be acknowledged, so send them again
+        for ping in self
+            .tracked_pings
+            .extract_if(.., |ping| ping.space == space_id)
+        {
+            self.pending_tracked_pings.push(ping.id);
+            self.spaces[self.highest_space].ping_pending = true;
+        }
         self.set_loss_detection_timer(now)
     }
 
@@ -2253,6 +2739,7 @@ impl Connection {
         &mut self,
         now: Instant,
         remote: SocketAddr,
+        local_ip: Option<IpAddr>,
         ecn: Option<EcnCodepoint>,
         data: BytesMut,
     ) {
@@ -2267,7 +2754,7 @@ impl Connection {
             ) {
                 Ok((partial_decode, rest)) => {
                     remaining = rest;
-                    self.handle_decode(now, remote, ecn, partial_decode);
+                    self.handle_decode(now, remote, local_ip, ecn, partial_decode);
                 }
                 Err(e) => {
                     trace!("malformed header: {}", e);
@@ -2281,6 +2768,7 @@ impl Connection {
         &mut self,
         now: Instant,
         remote: SocketAddr,
+        local_ip: Option<IpAddr>,
         ecn: Option<EcnCodepoint>,
         partial_decode: PartialDecode,
     ) {
@@ -2290,7 +2778,14 @@ impl Connection {
             self.zero_rtt_crypto.as_ref(),
             self.peer_params.stateless_reset_token,
         ) {
-            self.handle_packet(now, remote, ecn, decoded.packet, decoded.stateless_reset);
+            self.handle_packet(
+                now,
+                remote,
+                local_ip,
+                ecn,
+                decoded.packet,
+                decoded.stateless_reset,
+            );
         }
     }
 
@@ -2298,6 +2793,7 @@ impl Connection {
         &mut self,
         now: Instant,
         remote: SocketAddr,
+        local_ip: Option<IpAddr>,
         ecn: Option<EcnCodepoint>,
         packet: Option<Packet>,
         stateless_reset: bool,
@@ -2395,6 +2891,17 @@ impl Connection {
                         );
                     }
 
+                    // Like peer migration, only follow the newest packets so that reordered ones
+                    // don't flip the address back
+                    if local_ip.is_some()
+                        && number == Some(self.spaces[packet.header.space()].rx_packet)
+                    {
+                        self.local_ip = local_ip;
+                    }
+                    if packet.header.is_1rtt() {
+                        self.current_local_cid = packet.header.dst_cid();
+                    }
+
                     self.process_decrypted_packet(now, remote, number, packet)
                 }
             }
@@ -2411,7 +2918,7 @@ impl Connection {
                     code: TransportErrorCode::AEAD_LIMIT_REACHED,
                     ..
                 }) => State::Drained,
-                ConnectionError::TimedOut => {
+                ConnectionError::TimedOut | ConnectionError::HandshakeTimeout => {
                     unreachable!("timeouts aren't generated by packet processing");
                 }
                 ConnectionError::TransportError(err) => {
@@ -2592,28 +3099,45 @@ impl Connection {
 
                 if self.side.is_client() {
                     // Client-only because server params were set from the client's Initial
-                    let params = self.crypto.transport_parameters()?.ok_or_else(|| {
-                        TransportError::new(
-                            TransportErrorCode::crypto(0x6d),
-                            "transport parameters missing".to_owned(),
-                        )
-                    })?;
+                    let (params, extra_params) = self
+                        .crypto
+                        .transport_parameters_with_extra()?
+                        .ok_or_else(|| {
+                            TransportError::new(
+                                TransportErrorCode::crypto(0x6d),
+                                "transport parameters missing".to_owned(),
+                            )
+                        })?;
 
                     if self.has_0rtt() {
                         if !self.crypto.early_data_accepted().unwrap() {
                             debug_assert!(self.side.is_client());
-                            debug!("0-RTT rejected");
                             self.accepted_0rtt = false;
-                            self.streams.zero_rtt_rejected();
-
-                            // Discard already-queued frames
-                            self.spaces[SpaceId::Data].pending = Retransmits::default();
-
-                            // Discard 0-RTT packets
                             let sent_packets =
                                 mem::take(&mut self.spaces[SpaceId::Data].sent_packets);
-                            for packet in sent_packets.into_values() {
-                                self.remove_in_flight(&packet);
+                            if self.side.retransmit_rejected_0rtt()
+                                && params.validate_resumption_from(&self.peer_params).is_ok()
+                            {
+                                // The new limits cover everything sent in 0-RTT, so keep the
+                                // streams and resend their data, much as after a Retry
+                                debug!("0-RTT rejected, retransmitting as 1-RTT");
+                                self.retransmitted_0rtt = true;
+                                for info in sent_packets.into_values() {
+                                    self.remove_in_flight(&info);
+                                    self.spaces[SpaceId::Data].pending |= info.retransmits;
+                                }
+                                self.streams.retransmit_all_for_0rtt();
+                            } else {
+                                debug!("0-RTT rejected");
+                                self.streams.zero_rtt_rejected();
+
+                                // Discard already-queued frames
+                                self.spaces[SpaceId::Data].pending = Retransmits::default();
+
+                                // Discard 0-RTT packets
+                                for packet in sent_packets.into_values() {
+                                    self.remove_in_flight(&packet);
+                                }
                             }
                         } else {
                             self.accepted_0rtt = true;
@@ -2624,16 +3148,17 @@ impl Connection {
                         self.endpoint_events
                             .push_back(EndpointEventInner::ResetToken(self.path.remote, token));
                     }
-                    self.handle_peer_params(params)?;
+                    self.handle_peer_params(params, extra_params)?;
                     self.issue_first_cids(now);
                 } else {
                     // Server-only
                     self.spaces[SpaceId::Data].pending.handshake_done = true;
                     self.discard_space(now, SpaceId::Handshake);
-                    self.events.push_back(Event::HandshakeConfirmed);
+                    self.on_handshake_confirmed(now);
                     trace!("handshake confirmed");
                 }
 
+                self.timers.stop(Timer::Handshake);
                 self.events.push_back(Event::Connected);
                 self.state = State::Established;
                 trace!("established");
@@ -2665,13 +3190,16 @@ impl Connection {
                     && starting_space == SpaceId::Initial
                     && self.highest_space != SpaceId::Initial
                 {
-                    let params = self.crypto.transport_parameters()?.ok_or_else(|| {
-                        TransportError::new(
-                            TransportErrorCode::crypto(0x6d),
-                            "transport parameters missing".to_owned(),
-                        )
-                    })?;
-                    self.handle_peer_params(params)?;
+                    let (params, extra_params) = self
+                        .crypto
+                        .transport_parameters_with_extra()?
+                        .ok_or_else(|| {
+                            TransportError::new(
+                                TransportErrorCode::crypto(0x6d),
+                                "transport parameters missing".to_owned(),
+                            )
+                        })?;
+                    self.handle_peer_params(params, extra_params)?;
                     self.issue_first_cids(now);
                     self.init_0rtt();
                 }
@@ -2827,9 +3355,13 @@ impl Connection {
                     self.read_crypto(SpaceId::Data, &frame, payload_len)?;
                 }
                 Frame::Stream(frame) => {
+                    let (id, end) = (frame.id, frame.offset + frame.data.len() as u64);
                     if self.streams.received(frame, payload_len)?.should_transmit() {
                         self.spaces[SpaceId::Data].pending.max_data = true;
                     }
+                    if packet.header.is_0rtt() {
+                        self.streams.received_0rtt(id, end);
+                    }
                 }
                 Frame::Ack(ack) => {
                     self.on_ack_received(now, SpaceId::Data, ack)?;
@@ -2850,8 +3382,23 @@ impl Connection {
                     }
                 }
                 Frame::PathResponse(token) => {
-                    if self.path.challenge == Some(token) && remote == self.path.remote {
+                    if let Some(idx) = self
+                        .path_probes
+                        .iter()
+                        .position(|p| p.token == token && p.remote == remote && p.sent.is_some())
+                    {
+                        let probe = self.path_probes.swap_remove(idx);
+                        let rtt = now.saturating_duration_since(probe.sent.unwrap());
+                        trace!(%remote, ?rtt, "path probe answered");
+                        self.events.push_back(Event::PathProbed {
+                            id: probe.id,
+                            rtt: Some(rtt),
+                        });
+                        self.set_path_probe_timer();
+                    } else if self.path.challenge == Some(token) && remote == self.path.remote {
                         trace!("new path validated");
+                        // The previous path is retained only if it had been validated itself
+                        let old = self.prev_path.as_ref().map(|(_, prev)| prev.remote);
                         self.timers.stop(Timer::PathValidation);
                         self.path.challenge = None;
                         self.path.validated = true;
@@ -2859,6 +3406,14 @@ impl Connection {
                             prev_path.challenge = None;
                             prev_path.challenge_pending = false;
                         }
+                        self.stats.migration_count += 1;
+                        self.push_path_event(PathEvent::MigrationCompleted);
+                        if let Some(old) = old.filter(|&old| old != remote) {
+                            self.push_path_event(PathEvent::RemoteAddressChanged(AddressChange {
+                                old,
+                                new: remote,
+                            }));
+                        }
                     } else {
                         debug!(token, "ignoring invalid PATH_RESPONSE");
                     }
@@ -3049,7 +3604,7 @@ impl Connection {
                     if self.spaces[SpaceId::Handshake].crypto.is_some() {
                         self.discard_space(now, SpaceId::Handshake);
                     }
-                    self.events.push_back(Event::HandshakeConfirmed);
+                    self.on_handshake_confirmed(now);
                     trace!("handshake confirmed");
                 }
             }
@@ -3080,18 +3635,16 @@ impl Connection {
         if remote != self.path.remote
             && !is_probing_packet
             && number == self.spaces[SpaceId::Data].rx_packet
+            && self.side.remote_may_migrate(self.path.remote, remote)
         {
-            let ConnectionSide::Server { ref server_config } = self.side else {
-                panic!("packets from unknown remote should be dropped by clients");
-            };
             debug_assert!(
-                server_config.migration,
-                "migration-initiating packets should have been dropped immediately"
+                self.side.is_server(),
+                "packets from unknown remote should be dropped by clients"
             );
             self.migrate(now, remote);
             // Break linkability, if possible
             self.update_rem_cid();
-            self.spin = false;
+            self.reset_spin();
         }
 
         Ok(())
@@ -3104,7 +3657,7 @@ impl Connection {
         // Note that the congestion window will not grow until validation terminates. Helps mitigate
         // amplification attacks performed by spoofing source addresses.
         let mut new_path = if remote.is_ipv4() && remote.ip() == self.path.remote.ip() {
-            PathData::from_previous(remote, &self.path, self.path_counter, now)
+            PathData::from_previous(remote, &self.path, self.path_counter, now, &self.config)
         } else {
             let peer_max_udp_payload_size =
                 u16::try_from(self.peer_params.max_udp_payload_size.into_inner())
@@ -3156,6 +3709,18 @@ impl Connection {
             .retire_cids
             .extend(retired);
         self.set_reset_token(reset_token);
+        if !self.spin_enabled {
+            self.reset_spin();
+        }
+    }
+
+    /// Restart the spin bit, or if it's disabled, pick a new random value for it
+    ///
+    /// RFC 9000 §17.4 recommends that a disabled spin bit be randomized per connection ID, so that
+    /// it neither spins nor varies between packets in a way that would fingerprint the
+    /// implementation.
+    fn reset_spin(&mut self) {
+        self.spin = !self.spin_enabled && self.rng.random::<bool>();
     }
 
     fn set_reset_token(&mut self, reset_token: ResetToken) {
@@ -3213,6 +3778,13 @@ impl Connection {
             buf.write(frame::FrameType::PING);
             sent.non_retransmits = true;
             self.stats.frame_tx.ping += 1;
+            self.tracked_pings
+                .extend(self.pending_tracked_pings.drain(..).map(|id| TrackedPing {
+                    space: space_id,
+                    packet: pn,
+                    sent: now,
+                    id,
+                }));
         }
 
         // IMMEDIATE_ACK
@@ -3228,6 +3800,7 @@ impl Connection {
             Self::populate_acks(
                 now,
                 self.receiving_ecn,
+                self.config.ack_delay_exponent,
                 &mut sent,
                 space,
                 buf,
@@ -3436,7 +4009,7 @@ impl Connection {
         if space_id == SpaceId::Data {
             sent.stream_frames =
                 self.streams
-                    .write_stream_frames(buf, max_size, self.config.send_fairness);
+                    .write_stream_frames(buf, max_size, self.config.send_fairness, now);
             self.stats.frame_tx.stream += sent.stream_frames.len() as u64;
         }
 
@@ -3450,6 +4023,7 @@ impl Connection {
     fn populate_acks(
         now: Instant,
         receiving_ecn: bool,
+        ack_delay_exponent: u8,
         sent: &mut SentFrames,
         space: &mut PacketSpace,
         buf: &mut Vec<u8>,
@@ -3467,10 +4041,7 @@ impl Connection {
         sent.largest_acked = space.pending_acks.ranges().max();
 
         let delay_micros = space.pending_acks.ack_delay(now).as_micros() as u64;
-
-        // TODO: This should come from `TransportConfig` if that gets configurable.
-        let ack_delay_exp = TransportParameters::default().ack_delay_exponent;
-        let delay = delay_micros >> ack_delay_exp.into_inner();
+        let delay = delay_micros >> ack_delay_exponent;
 
         trace!(
             "ACK {:?}, Delay = {}us",
@@ -3482,8 +4053,21 @@ impl Connection {
         stats.frame_tx.acks += 1;
     }
 
+    fn on_handshake_confirmed(&mut self, now: Instant) {
+        // Peers may repeat HANDSHAKE_DONE; keep the first measurement
+        self.stats
+            .handshake_duration
+            .get_or_insert_with(|| now.saturating_duration_since(self.handshake_started));
+        self.events.push_back(Event::HandshakeConfirmed);
+    }
+
     fn close_common(&mut self) {
         trace!("connection closed");
+        if let Some(cache) = &self.endpoint_config.rtt_cache {
+            if self.path.rtt.is_measured() {
+                cache.put(self.path.remote, self.path.rtt.get());
+            }
+        }
         for &timer in &Timer::VALUES {
             self.timers.stop(timer);
         }
@@ -3495,7 +4079,11 @@ impl Connection {
     }
 
     /// Handle transport parameters received from the peer
-    fn handle_peer_params(&mut self, params: TransportParameters) -> Result<(), TransportError> {
+    fn handle_peer_params(
+        &mut self,
+        params: TransportParameters,
+        extra_params: ExtraTransportParameters,
+    ) -> Result<(), TransportError> {
         if Some(self.orig_rem_cid) != params.initial_src_cid
             || (self.side.is_client()
                 && (Some(self.initial_dst_cid) != params.original_dst_cid
@@ -3507,6 +4095,8 @@ impl Connection {
         }
 
         self.set_peer_params(params);
+        self.peer_extra_params = extra_params;
+        self.peer_params_received = true;
 
         Ok(())
     }
@@ -3514,7 +4104,7 @@ impl Connection {
     fn set_peer_params(&mut self, params: TransportParameters) {
         self.streams.set_params(&params);
         self.idle_timeout =
-            negotiate_max_idle_timeout(self.config.max_idle_timeout, Some(params.max_idle_timeout));
+            negotiate_max_idle_timeout(self.local_max_idle_timeout, Some(params.max_idle_timeout));
         trace!("negotiated max idle timeout {:?}", self.idle_timeout);
         if let Some(ref info) = params.preferred_address {
             self.rem_cids.insert(NewConnectionId {
@@ -3724,7 +4314,14 @@ impl Connection {
                 .datagrams
                 .outgoing
                 .front()
-                .is_some_and(|x| x.size(true) <= max_size)
+                .is_some_and(|x| x.datagram.size(true) <= max_size)
+    }
+
+    /// Account for datagrams totalling `bytes` sent to `destination`
+    fn on_datagrams_sent(&mut self, destination: SocketAddr, datagrams: u64, bytes: usize) {
+        self.stats.udp_tx.on_sent(datagrams, bytes);
+        self.path_bytes
+            .record(destination, self.local_ip, bytes as u64, 0);
     }
 
     /// Update counters to account for a packet becoming acknowledged, lost, or abandoned
@@ -3816,6 +4413,8 @@ enum ConnectionSide {
         token: Bytes,
         token_store: Arc<dyn TokenStore>,
         server_name: String,
+        /// Whether rejected 0-RTT stream data is resent in 1-RTT packets
+        retransmit_rejected_0rtt: bool,
     },
     Server {
         server_config: Arc<ServerConfig>,
@@ -3823,13 +4422,36 @@ enum ConnectionSide {
 }
 
 impl ConnectionSide {
-    fn remote_may_migrate(&self) -> bool {
+    fn retransmit_rejected_0rtt(&self) -> bool {
+        match self {
+            Self::Client {
+                retransmit_rejected_0rtt,
+                ..
+            } => *retransmit_rejected_0rtt,
+            Self::Server { .. } => false,
+        }
+    }
+
+    /// Whether the peer may move from address `from` to `to`
+    ///
+    /// Servers that forbid migration still tolerate changes in port alone, which are most likely
+    /// due to NAT rebinding rather than active migration.
+    fn remote_may_migrate(&self, from: SocketAddr, to: SocketAddr) -> bool {
         match self {
-            Self::Server { server_config } => server_config.migration,
+            Self::Server { server_config } => server_config.migration || from.ip() == to.ip(),
             Self::Client { .. } => false,
         }
     }
 
+    /// Permitted ratio of bytes sent to bytes received on an unvalidated path
+    fn anti_amplification_factor(&self) -> u64 {
+        match self {
+            Self::Server { server_config } => server_config.anti_amplification_factor.into(),
+            // Clients always consider their path validated
+            Self::Client { .. } => 3,
+        }
+    }
+
     fn is_client(&self) -> bool {
         self.side().is_client()
     }
@@ -3852,10 +4474,12 @@ impl From<SideArgs> for ConnectionSide {
             SideArgs::Client {
                 token_store,
                 server_name,
+                retransmit_rejected_0rtt,
             } => Self::Client {
                 token: token_store.take(&server_name).unwrap_or_default(),
                 token_store,
                 server_name,
+                retransmit_rejected_0rtt,
             },
             SideArgs::Server {
                 server_config,
@@ -3871,6 +4495,7 @@ pub(crate) enum SideArgs {
     Client {
         token_store: Arc<dyn TokenStore>,
         server_name: String,
+        retransmit_rejected_0rtt: bool,
     },
     Server {
         server_config: Arc<ServerConfig>,
@@ -3918,6 +4543,10 @@ pub enum ConnectionError {
     #[error("closed by peer: {0}")]
     ApplicationClosed(frame::ApplicationClose),
     /// The peer is unable to continue processing this connection, usually due to having restarted
+    ///
+    /// Raised only when a stateless reset is received from the peer, which typically indicates that
+    /// it lost the connection's state, or that packets are being routed to a different host than
+    /// before.
     #[error("reset by peer")]
     Reset,
     /// Communication with the peer has lapsed for longer than the negotiated idle timeout
@@ -3927,6 +4556,11 @@ pub enum ConnectionError {
     /// and [`TransportConfig::keep_alive_interval()`].
     #[error("timed out")]
     TimedOut,
+    /// The handshake did not complete within the configured handshake timeout
+    ///
+    /// See [`TransportConfig::handshake_timeout()`].
+    #[error("handshake timed out")]
+    HandshakeTimeout,
     /// The local application closed the connection
     #[error("closed")]
     LocallyClosed,
@@ -3951,7 +4585,7 @@ impl From<ConnectionError> for io::Error {
     fn from(x: ConnectionError) -> Self {
         use ConnectionError::*;
         let kind = match x {
-            TimedOut => io::ErrorKind::TimedOut,
+            TimedOut | HandshakeTimeout => io::ErrorKind::TimedOut,
             Reset => io::ErrorKind::ConnectionReset,
             ApplicationClosed(_) | ConnectionClosed(_) => io::ErrorKind::ConnectionAborted,
             TransportError(_) | VersionMismatch | LocallyClosed | CidsExhausted => {
@@ -4049,8 +4683,112 @@ pub enum Event {
     DatagramReceived,
     /// One or more application datagrams have been sent after blocking
     DatagramsUnblocked,
+    /// A datagram queued with [`Datagrams::send_tracked`] was discarded without being sent
+    DatagramDropped {
+        /// Identifier returned when the datagram was queued
+        id: DatagramId,
+    },
+    /// A PING sent by [`Connection::ping_tracked`] was acknowledged
+    PingAcknowledged {
+        /// Identifier returned when the PING was queued
+        id: PingId,
+        /// Measured round-trip time
+        rtt: Duration,
+    },
+    /// A PATH_CHALLENGE sent by [`Connection::probe_path`] was answered or timed out
+    PathProbed {
+        /// Identifier returned when the probe was queued
+        id: PathProbeId,
+        /// Time until the PATH_RESPONSE arrived, or `None` if none arrived in time
+        rtt: Option<Duration>,
+    },
+}
+
+/// Identifies a PING queued with [`Connection::ping_tracked`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct PingId(u64);
+
+/// Identifies a PATH_CHALLENGE queued with [`Connection::probe_path`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct PathProbeId(u64);
+
+struct PathProbe {
+    id: PathProbeId,
+    remote: SocketAddr,
+    token: u64,
+    /// When the challenge was sent, if it has been
+    sent: Option<Instant>,
+    /// When to give up on a response, set along with `sent`
+    deadline: Option<Instant>,
+}
+
+struct TrackedPing {
+    space: SpaceId,
+    packet: u64,
+    sent: Instant,
+    id: PingId,
+}
+
+/// Informational events about congestion control and the network path
+///
+/// Retrieved with [`Connection::poll_path_event`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum PathEvent {
+    /// Loss detection declared packets lost
+    LossDetected {
+        /// Number of packets declared lost
+        packets: u64,
+    },
+    /// The congestion controller reacted to loss or ECN congestion marks
+    CongestionEvent,
+    /// The path MTU changed due to MTU discovery or black hole detection
+    PathMtuUpdated(u16),
+    /// The peer was confirmed reachable at its new address following a migration
+    MigrationCompleted,
+    /// The validated address of the peer changed
+    ///
+    /// Follows [`MigrationCompleted`](Self::MigrationCompleted) unless the peer migrated back to
+    /// the address it was last validated at.
+    RemoteAddressChanged(AddressChange),
+}
+
+/// Change of a peer's validated address, reported by [`PathEvent::RemoteAddressChanged`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct AddressChange {
+    /// The address the peer was previously validated at
+    pub old: SocketAddr,
+    /// The address the peer has just been validated at
+    pub new: SocketAddr,
 }
 
+impl AddressChange {
+    /// Whether only the port changed
+    ///
+    /// Typical of a NAT rebinding, where the peer's NAT assigned a new port, rather than the peer
+    /// moving to a different network.
+    pub fn is_port_only(&self) -> bool {
+        self.old.ip() == self.new.ip()
+    }
+}
+
+/// Validation status of a connection's current network path
+///
+/// Retrieved with [`Connection::path_validation_state`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PathValidationState {
+    /// The peer has been confirmed reachable at the current address
+    Validated,
+    /// Confirmation that the peer is reachable at the current address is outstanding
+    Validating,
+    /// The peer did not respond to path validation and no validated path was available
+    Failed,
+}
+
+/// Number of [`PathEvent`]s retained until they are polled
+const MAX_PATH_EVENTS: usize = 32;
+
 fn get_max_ack_delay(params: &TransportParameters) -> Duration {
     Duration::from_micros(params.max_ack_delay.0 * 1000)
 }
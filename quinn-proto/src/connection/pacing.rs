@@ -1,9 +1,82 @@
 //! Pacing of packet transmissions.
 
+use std::sync::Arc;
+
 use crate::{Duration, Instant};
 
 use tracing::warn;
 
+/// Common interface for algorithms which spread transmissions over time
+///
+/// A pacer decides when the connection may send the next datagram, given that congestion control
+/// already permits it. [`TokenBucket`] is used unless another implementation is installed with
+/// [`TransportConfig::pacer_factory`](crate::TransportConfig::pacer_factory).
+pub trait Pacer: Send + Sync {
+    /// Record that a packet of `packet_length` bytes has been transmitted
+    fn on_transmit(&mut self, packet_length: u16);
+
+    /// Return when `bytes_to_send` more bytes may be transmitted
+    ///
+    /// If they can be sent right away, returns `None`. Otherwise, returns `Some(t)`, where `t` is
+    /// the time at which this function should be called again.
+    fn delay(
+        &mut self,
+        bytes_to_send: u64,
+        now: Instant,
+        smoothed_rtt: Duration,
+        window: u64,
+        mtu: u16,
+    ) -> Option<Instant>;
+
+    /// The congestion window or MTU may have changed
+    ///
+    /// Called after acknowledgements and losses are processed.
+    #[allow(unused_variables)]
+    fn on_window_update(&mut self, smoothed_rtt: Duration, window: u64, mtu: u16) {}
+}
+
+/// Constructs pacers on demand
+pub trait PacerFactory {
+    /// Construct a fresh `Pacer` for a new path
+    ///
+    /// `max_bytes_per_second` is the value of
+    /// [`TransportConfig::max_outgoing_bytes_per_second`](crate::TransportConfig::max_outgoing_bytes_per_second),
+    /// which implementations should respect.
+    fn build(
+        self: Arc<Self>,
+        now: Instant,
+        smoothed_rtt: Duration,
+        window: u64,
+        mtu: u16,
+        max_bytes_per_second: Option<u64>,
+    ) -> Box<dyn Pacer>;
+}
+
+/// Configuration for the [`TokenBucket`] pacer
+#[derive(Debug, Clone, Default)]
+pub struct TokenBucketConfig {
+    _private: (),
+}
+
+impl PacerFactory for TokenBucketConfig {
+    fn build(
+        self: Arc<Self>,
+        now: Instant,
+        smoothed_rtt: Duration,
+        window: u64,
+        mtu: u16,
+        max_bytes_per_second: Option<u64>,
+    ) -> Box<dyn Pacer> {
+        Box::new(TokenBucket::new(
+            smoothed_rtt,
+            window,
+            mtu,
+            max_bytes_per_second,
+            now,
+        ))
+    }
+}
+
 /// A simple token-bucket pacer
 ///
 /// The pacer's capacity is derived on a fraction of the congestion window
@@ -12,7 +85,7 @@ use tracing::warn;
 /// The bucket refills at a rate slightly faster
 /// than one congestion window per RTT, as recommended in
 /// <https://tools.ietf.org/html/draft-ietf-quic-recovery-34#section-7.7>
-pub(super) struct Pacer {
+pub struct TokenBucket {
     capacity: u64,
     last_window: u64,
     last_mtu: u16,
@@ -21,9 +94,9 @@ pub(super) struct Pacer {
     prev: Instant,
 }
 
-impl Pacer {
-    /// Obtains a new [`Pacer`].
-    pub(super) fn new(
+impl TokenBucket {
+    /// Obtains a new [`TokenBucket`].
+    pub fn new(
         smoothed_rtt: Duration,
         window: u64,
         mtu: u16,
@@ -41,46 +114,30 @@ impl Pacer {
             prev: now,
         }
     }
+}
 
-    /// Obtains the `max_bytes_per_second` used when this [`Pacer`] was constructed.
-    pub(crate) fn max_bytes_per_second(&self) -> Option<u64> {
-        self.max_bytes_per_second
-    }
-
-    /// Record that a packet has been transmitted.
-    pub(super) fn on_transmit(&mut self, packet_length: u16) {
+impl Pacer for TokenBucket {
+    fn on_transmit(&mut self, packet_length: u16) {
         self.tokens = self.tokens.saturating_sub(packet_length.into())
     }
 
-    /// Return how long we need to wait before sending `bytes_to_send`
-    ///
-    /// If we can send a packet right away, this returns `None`. Otherwise, returns `Some(d)`,
-    /// where `d` is the time before this function should be called again.
-    ///
     /// The 5/4 ratio used here comes from the suggestion that N = 1.25 in the draft IETF RFC for
     /// QUIC.
-    pub(super) fn delay(
+    fn delay(
         &mut self,
-        smoothed_rtt: Duration,
         bytes_to_send: u64,
-        mtu: u16,
-        window: u64,
         now: Instant,
+        smoothed_rtt: Duration,
+        window: u64,
+        mtu: u16,
     ) -> Option<Instant> {
         debug_assert_ne!(
             window, 0,
             "zero-sized congestion control window is nonsense"
         );
 
-        let window = rate_limited_window(smoothed_rtt, window, self.max_bytes_per_second);
-        if window != self.last_window || mtu != self.last_mtu {
-            self.capacity = optimal_capacity(smoothed_rtt, window, mtu);
-
-            // Clamp the tokens
-            self.tokens = self.capacity.min(self.tokens);
-            self.last_window = window;
-            self.last_mtu = mtu;
-        }
+        self.on_window_update(smoothed_rtt, window, mtu);
+        let window = self.last_window;
 
         // if we can already send a packet, there is no need for delay
         if self.tokens >= bytes_to_send {
@@ -127,6 +184,18 @@ impl Pacer {
         // this is the time at which the pacing window becomes empty
         Some(now + (unscaled_delay / 5) * 4)
     }
+
+    fn on_window_update(&mut self, smoothed_rtt: Duration, window: u64, mtu: u16) {
+        let window = rate_limited_window(smoothed_rtt, window, self.max_bytes_per_second);
+        if window != self.last_window || mtu != self.last_mtu {
+            self.capacity = optimal_capacity(smoothed_rtt, window, mtu);
+
+            // Clamp the tokens
+            self.tokens = self.capacity.min(self.tokens);
+            self.last_window = window;
+            self.last_mtu = mtu;
+        }
+    }
 }
 
 /// Calculates a pacer capacity for a certain window and RTT
@@ -183,6 +252,56 @@ fn rate_limited_window(
     Ord::min(window, Ord::max(adjusted_rate_window as u64, 1))
 }
 
+/// Application-imposed cap on a connection's sending rate
+///
+/// Unlike a [`Pacer`], which belongs to a path and spreads out what congestion control permits,
+/// this follows the connection across migrations and only ever defers transmissions further.
+pub(super) struct SendRateLimiter {
+    bytes_per_second: u64,
+    tokens: u64,
+    prev: Instant,
+}
+
+impl SendRateLimiter {
+    pub(super) fn new(bytes_per_second: u64, now: Instant) -> Self {
+        Self {
+            bytes_per_second: bytes_per_second.max(1),
+            tokens: 0,
+            prev: now,
+        }
+    }
+
+    pub(super) fn on_transmit(&mut self, packet_length: u16) {
+        self.tokens = self.tokens.saturating_sub(packet_length.into());
+    }
+
+    /// Return when `bytes_to_send` more bytes may be transmitted, if not right away
+    pub(super) fn delay(&mut self, bytes_to_send: u64, now: Instant, mtu: u16) -> Option<Instant> {
+        let time_elapsed = now.checked_duration_since(self.prev).unwrap_or_default();
+        let new_tokens =
+            (u128::from(self.bytes_per_second) * time_elapsed.as_nanos() / 1_000_000_000) as u64;
+        // Allow bursts worth `TARGET_BURST_INTERVAL`, but at least enough for the largest batch
+        // `poll_transmit` asks about so that sending can always make progress
+        let capacity = Ord::max(
+            (u128::from(self.bytes_per_second) * TARGET_BURST_INTERVAL.as_nanos() / 1_000_000_000)
+                as u64,
+            2 * u64::from(mtu),
+        );
+        self.tokens = self.tokens.saturating_add(new_tokens).min(capacity);
+        if new_tokens > 0 {
+            self.prev = now;
+        }
+
+        if self.tokens >= bytes_to_send {
+            return None;
+        }
+
+        let missing = u128::from(bytes_to_send - self.tokens);
+        let nanos = (missing * 1_000_000_000).div_ceil(u128::from(self.bytes_per_second));
+        Some(now + Duration::from_nanos(nanos.min(u128::from(u64::MAX)) as u64))
+    }
+}
+
 /// Period of traffic to batch together on a reasonably fast connection
 const TARGET_BURST_INTERVAL: Duration = Duration::from_millis(2);
 
@@ -209,18 +328,18 @@ mod tests {
         let rtt = Duration::from_micros(400);
 
         assert!(
-            Pacer::new(rtt, 30000, 1500, None, new_instant)
-                .delay(Duration::from_micros(0), 0, 1500, 1, old_instant)
+            TokenBucket::new(rtt, 30000, 1500, None, new_instant)
+                .delay(0, old_instant, Duration::from_micros(0), 1, 1500)
                 .is_none()
         );
         assert!(
-            Pacer::new(rtt, 30000, 1500, None, new_instant)
-                .delay(Duration::from_micros(0), 1600, 1500, 1, old_instant)
+            TokenBucket::new(rtt, 30000, 1500, None, new_instant)
+                .delay(1600, old_instant, Duration::from_micros(0), 1, 1500)
                 .is_none()
         );
         assert!(
-            Pacer::new(rtt, 30000, 1500, None, new_instant)
-                .delay(Duration::from_micros(0), 1500, 1500, 3000, old_instant)
+            TokenBucket::new(rtt, 30000, 1500, None, new_instant)
+                .delay(1500, old_instant, Duration::from_micros(0), 3000, 1500)
                 .is_none()
         );
     }
@@ -232,18 +351,18 @@ mod tests {
         let rtt = Duration::from_millis(50);
         let now = Instant::now();
 
-        let pacer = Pacer::new(rtt, window, mtu, None, now);
+        let pacer = TokenBucket::new(rtt, window, mtu, None, now);
         assert_eq!(
             pacer.capacity,
             (window as u128 * TARGET_BURST_INTERVAL.as_nanos() / rtt.as_nanos()) as u64
         );
         assert_eq!(pacer.tokens, pacer.capacity);
 
-        let pacer = Pacer::new(Duration::from_millis(0), window, mtu, None, now);
+        let pacer = TokenBucket::new(Duration::from_millis(0), window, mtu, None, now);
         assert_eq!(pacer.capacity, MAX_BURST_SIZE * mtu as u64);
         assert_eq!(pacer.tokens, pacer.capacity);
 
-        let pacer = Pacer::new(rtt, 1, mtu, None, now);
+        let pacer = TokenBucket::new(rtt, 1, mtu, None, now);
         assert_eq!(pacer.capacity, mtu as u64);
         assert_eq!(pacer.tokens, pacer.capacity);
     }
@@ -255,7 +374,7 @@ mod tests {
         let rtt = Duration::from_millis(50);
         let now = Instant::now();
 
-        let mut pacer = Pacer::new(rtt, window, mtu, None, now);
+        let mut pacer = TokenBucket::new(rtt, window, mtu, None, now);
         assert_eq!(
             pacer.capacity,
             (window as u128 * TARGET_BURST_INTERVAL.as_nanos() / rtt.as_nanos()) as u64
@@ -263,27 +382,27 @@ mod tests {
         assert_eq!(pacer.tokens, pacer.capacity);
         let initial_tokens = pacer.tokens;
 
-        pacer.delay(rtt, mtu as u64, mtu, window * 2, now);
+        pacer.delay(mtu as u64, now, rtt, window * 2, mtu);
         assert_eq!(
             pacer.capacity,
             (2 * window as u128 * TARGET_BURST_INTERVAL.as_nanos() / rtt.as_nanos()) as u64
         );
         assert_eq!(pacer.tokens, initial_tokens);
 
-        pacer.delay(rtt, mtu as u64, mtu, window / 2, now);
+        pacer.delay(mtu as u64, now, rtt, window / 2, mtu);
         assert_eq!(
             pacer.capacity,
             (window as u128 / 2 * TARGET_BURST_INTERVAL.as_nanos() / rtt.as_nanos()) as u64
         );
         assert_eq!(pacer.tokens, initial_tokens / 2);
 
-        pacer.delay(rtt, mtu as u64, mtu * 2, window, now);
+        pacer.delay(mtu as u64, now, rtt, window, mtu * 2);
         assert_eq!(
             pacer.capacity,
             (window as u128 * TARGET_BURST_INTERVAL.as_nanos() / rtt.as_nanos()) as u64
         );
 
-        pacer.delay(rtt, mtu as u64, 20_000, window, now);
+        pacer.delay(mtu as u64, now, rtt, window, 20_000);
         assert_eq!(pacer.capacity, 20_000_u64 * MIN_BURST_SIZE);
     }
 
@@ -294,12 +413,12 @@ mod tests {
         let rtt = Duration::from_millis(50);
         let old_instant = Instant::now();
 
-        let mut pacer = Pacer::new(rtt, window, mtu, None, old_instant);
+        let mut pacer = TokenBucket::new(rtt, window, mtu, None, old_instant);
         let packet_capacity = pacer.capacity / mtu as u64;
 
         for _ in 0..packet_capacity {
             assert_eq!(
-                pacer.delay(rtt, mtu as u64, mtu, window, old_instant),
+                pacer.delay(mtu as u64, old_instant, rtt, window, mtu),
                 None,
                 "When capacity is available packets should be sent immediately"
             );
@@ -310,7 +429,7 @@ mod tests {
         let pace_duration = Duration::from_nanos((TARGET_BURST_INTERVAL.as_nanos() * 4 / 5) as u64);
 
         let actual_delay = pacer
-            .delay(rtt, mtu as u64, mtu, window, old_instant)
+            .delay(mtu as u64, old_instant, rtt, window, mtu)
             .expect("Send must be delayed")
             .duration_since(old_instant);
 
@@ -324,11 +443,11 @@ mod tests {
         // Refill half of the tokens
         assert_eq!(
             pacer.delay(
-                rtt,
                 mtu as u64,
-                mtu,
+                old_instant + pace_duration / 2,
+                rtt,
                 window,
-                old_instant + pace_duration / 2
+                mtu
             ),
             None
         );
@@ -336,7 +455,7 @@ mod tests {
 
         for _ in 0..packet_capacity / 2 {
             assert_eq!(
-                pacer.delay(rtt, mtu as u64, mtu, window, old_instant),
+                pacer.delay(mtu as u64, old_instant, rtt, window, mtu),
                 None,
                 "When capacity is available packets should be sent immediately"
             );
@@ -347,11 +466,11 @@ mod tests {
         // Refill all capacity by waiting more than the expected duration
         assert_eq!(
             pacer.delay(
-                rtt,
                 mtu as u64,
-                mtu,
+                old_instant + pace_duration * 3 / 2,
+                rtt,
                 window,
-                old_instant + pace_duration * 3 / 2
+                mtu
             ),
             None
         );
@@ -365,16 +484,16 @@ mod tests {
         let rtt = Duration::from_millis(50);
         let old_instant = Instant::now();
 
-        let mut pacer = Pacer::new(rtt, window, mtu, Some(2_000), old_instant);
+        let mut pacer = TokenBucket::new(rtt, window, mtu, Some(2_000), old_instant);
         assert_eq!(
-            pacer.delay(rtt, 1_000, mtu, window, old_instant),
+            pacer.delay(1_000, old_instant, rtt, window, mtu),
             None,
             "When capacity is available packets should be sent immediately"
         );
         pacer.on_transmit(mtu);
 
         let actual_delay = pacer
-            .delay(rtt, 1_000, mtu, window, old_instant)
+            .delay(1_000, old_instant, rtt, window, mtu)
             .expect("Send must be delayed")
             .duration_since(old_instant);
 
@@ -389,6 +508,6 @@ mod tests {
 
         // Should be able to send after a while
         let now = old_instant + expected_delay / 2;
-        assert_eq!(pacer.delay(rtt, 500, mtu, window, now), None);
+        assert_eq!(pacer.delay(500, now, rtt, window, mtu), None);
     }
 }
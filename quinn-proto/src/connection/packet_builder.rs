@@ -91,11 +91,7 @@ impl PacketBuilder {
             SpaceId::Data if space.crypto.is_some() => Header::Short {
                 dst_cid,
                 number,
-                spin: if conn.spin_enabled {
-                    conn.spin
-                } else {
-                    conn.rng.random()
-                },
+                spin: conn.spin,
                 key_phase: conn.key_phase,
             },
             SpaceId::Data => Header::Long {
@@ -225,6 +221,9 @@ impl PacketBuilder {
             }
             conn.set_loss_detection_timer(now);
             conn.path.pacing.on_transmit(size);
+            if let Some(limiter) = &mut conn.send_rate_limiter {
+                limiter.on_transmit(size);
+            }
         }
     }
 
@@ -267,7 +266,7 @@ impl PacketBuilder {
         );
 
         let len = buffer.len() - encode_start;
-        conn.config.qlog_sink.emit_packet_sent(
+        conn.qlog.emit_packet_sent(
             self.exact_number,
             len,
             self.space,
@@ -1,4 +1,4 @@
-use std::{cmp, net::SocketAddr};
+use std::{cmp, collections::VecDeque, net::SocketAddr, sync::Arc};
 
 use tracing::trace;
 
@@ -16,12 +16,16 @@ use qlog::events::{ExData, quic::RecoveryMetricsUpdated};
 pub(super) struct PathData {
     pub(super) remote: SocketAddr,
     pub(super) rtt: RttEstimator,
+    /// Most recent raw RTT measurements, up to [`TransportConfig::rtt_sample_history()`]
+    rtt_samples: VecDeque<Duration>,
     /// Whether we're enabling ECN on outgoing packets
     pub(super) sending_ecn: bool,
     /// Congestion controller state
     pub(super) congestion: Box<dyn congestion::Controller>,
     /// Pacing state
-    pub(super) pacing: Pacer,
+    pub(super) pacing: Box<dyn Pacer>,
+    /// Sending rate to sustain regardless of congestion control, in bytes per second
+    min_pacing_rate: Option<u64>,
     pub(super) challenge: Option<u64>,
     pub(super) challenge_pending: bool,
     /// Whether we're certain the peer can both send and receive on this address
@@ -29,6 +33,8 @@ pub(super) struct PathData {
     /// Initially equal to `use_stateless_retry` for servers, and becomes false again on every
     /// migration. Always true for clients.
     pub(super) validated: bool,
+    /// Whether a PATH_CHALLENGE on this path went unanswered with no previous path to fall back on
+    pub(super) validation_failed: bool,
     /// Total size of all UDP datagrams sent on this path
     pub(super) total_sent: u64,
     /// Total size of all UDP datagrams received on this path
@@ -63,25 +69,25 @@ impl PathData {
         now: Instant,
         config: &TransportConfig,
     ) -> Self {
-        let congestion = config
-            .congestion_controller_factory
-            .clone()
-            .build(now, config.get_initial_mtu());
+        let congestion = build_congestion(config, now);
         Self {
             remote,
             rtt: RttEstimator::new(config.initial_rtt),
-            sending_ecn: true,
-            pacing: Pacer::new(
+            rtt_samples: VecDeque::new(),
+            sending_ecn: config.outgoing_ecn.codepoint().is_some(),
+            pacing: config.pacer_factory.clone().build(
+                now,
                 config.initial_rtt,
                 congestion.initial_window(),
                 config.get_initial_mtu(),
                 config.max_outgoing_bytes_per_second,
-                now,
             ),
+            min_pacing_rate: config.min_pacing_rate,
             congestion,
             challenge: None,
             challenge_pending: false,
             validated: false,
+            validation_failed: false,
             total_sent: 0,
             total_recvd: 0,
             mtud: config
@@ -113,24 +119,28 @@ impl PathData {
         prev: &Self,
         generation: u64,
         now: Instant,
+        config: &TransportConfig,
     ) -> Self {
         let congestion = prev.congestion.clone_box();
         let smoothed_rtt = prev.rtt.get();
         Self {
             remote,
             rtt: prev.rtt,
-            pacing: Pacer::new(
+            rtt_samples: prev.rtt_samples.clone(),
+            pacing: config.pacer_factory.clone().build(
+                now,
                 smoothed_rtt,
                 congestion.window(),
                 prev.current_mtu(),
-                prev.pacing.max_bytes_per_second(),
-                now,
+                config.max_outgoing_bytes_per_second,
             ),
-            sending_ecn: true,
+            min_pacing_rate: config.min_pacing_rate,
+            sending_ecn: config.outgoing_ecn.codepoint().is_some(),
             congestion,
             challenge: None,
             challenge_pending: false,
             validated: false,
+            validation_failed: false,
             total_sent: 0,
             total_recvd: 0,
             mtud: prev.mtud.clone(),
@@ -148,17 +158,62 @@ impl PathData {
     /// This is useful when it is known the underlying path has changed.
     pub(super) fn reset(&mut self, now: Instant, config: &TransportConfig) {
         self.rtt = RttEstimator::new(config.initial_rtt);
-        self.congestion = config
-            .congestion_controller_factory
-            .clone()
-            .build(now, config.get_initial_mtu());
+        self.rtt_samples.clear();
+        self.congestion = build_congestion(config, now);
         self.mtud.reset(config.get_initial_mtu(), config.min_mtu);
     }
 
+    /// Update the RTT estimate with a new measurement, retaining up to `history` raw samples
+    pub(super) fn update_rtt(&mut self, ack_delay: Duration, rtt: Duration, history: usize) {
+        self.rtt.update(ack_delay, rtt);
+        if history == 0 {
+            return;
+        }
+        if self.rtt_samples.len() >= history {
+            self.rtt_samples.pop_front();
+        }
+        self.rtt_samples.push_back(rtt);
+    }
+
+    /// Most recent raw RTT measurements, oldest first
+    pub(super) fn rtt_samples(&self) -> impl Iterator<Item = Duration> + '_ {
+        self.rtt_samples.iter().copied()
+    }
+
+    /// Replace the congestion controller, seeding the new one with the current window
+    pub(super) fn replace_congestion(
+        &mut self,
+        now: Instant,
+        factory: Arc<dyn congestion::ControllerFactory + Send + Sync>,
+    ) {
+        let window = self.congestion.window();
+        self.congestion = factory.build_with_initial_window(now, self.current_mtu(), window);
+        self.update_pacer();
+    }
+
+    /// Inform the pacer of the current congestion window and MTU
+    pub(super) fn update_pacer(&mut self) {
+        self.pacing
+            .on_window_update(self.rtt.get(), self.send_window(), self.current_mtu());
+    }
+
+    /// Number of bytes that may be in flight, i.e. the congestion window, raised if necessary to
+    /// sustain the minimum pacing rate
+    pub(super) fn send_window(&self) -> u64 {
+        let window = self.congestion.window();
+        let Some(rate) = self.min_pacing_rate else {
+            return window;
+        };
+        let floor = (rate as f64 * self.rtt.get().as_secs_f64()) as u64;
+        window.max(floor)
+    }
+
     /// Indicates whether we're a server that hasn't validated the peer's address and hasn't
     /// received enough data from the peer to permit sending `bytes_to_send` additional bytes
-    pub(super) fn anti_amplification_blocked(&self, bytes_to_send: u64) -> bool {
-        !self.validated && self.total_recvd * 3 < self.total_sent + bytes_to_send
+    ///
+    /// `factor` is the permitted ratio of bytes sent to bytes received.
+    pub(super) fn anti_amplification_blocked(&self, bytes_to_send: u64, factor: u64) -> bool {
+        !self.validated && self.total_recvd * factor < self.total_sent + bytes_to_send
     }
 
     /// Returns the path's current MTU
@@ -218,6 +273,16 @@ impl PathData {
     }
 }
 
+/// Construct the congestion controller for a new path, honoring
+/// [`TransportConfig::initial_window()`]
+fn build_congestion(config: &TransportConfig, now: Instant) -> Box<dyn congestion::Controller> {
+    let factory = config.congestion_controller_factory.clone();
+    match config.initial_window {
+        Some(window) => factory.build_with_initial_window(now, config.get_initial_mtu(), window),
+        None => factory.build(now, config.get_initial_mtu()),
+    }
+}
+
 /// Congestion metrics as described in [`recovery_metrics_updated`].
 ///
 /// [`recovery_metrics_updated`]: https://datatracker.ietf.org/doc/html/draft-ietf-quic-qlog-quic-events.html#name-recovery_metrics_updated
@@ -338,6 +403,11 @@ impl RttEstimator {
         self.min
     }
 
+    /// Whether any RTT measurement has been made, as opposed to relying on the initial RTT
+    pub(crate) fn is_measured(&self) -> bool {
+        self.smoothed.is_some()
+    }
+
     // PTO computed as described in RFC9002#6.2.1
     pub(crate) fn pto_base(&self) -> Duration {
         self.get() + cmp::max(4 * self.var, TIMER_GRANULARITY)
@@ -23,6 +23,8 @@ pub(super) struct SendBuffer {
     acks: RangeSet,
     /// Previously transmitted ranges deemed lost
     retransmits: RangeSet,
+    /// Total size of ranges sent again after being deemed lost
+    retransmitted: u64,
 }
 
 impl SendBuffer {
@@ -107,6 +109,7 @@ impl SendBuffer {
             if end != range.end {
                 self.retransmits.insert(end..range.end);
             }
+            self.retransmitted += end - range.start;
             return (range.start..end, encode_length);
         }
 
@@ -161,6 +164,27 @@ impl SendBuffer {
         self.retransmits.insert(range);
     }
 
+    /// Discard data at and beyond `offset`, none of which may have been sent yet
+    pub(super) fn truncate(&mut self, offset: u64) {
+        debug_assert!(self.unsent <= offset && offset <= self.offset);
+        let mut excess = (self.offset - offset) as usize;
+        self.offset = offset;
+        self.unacked_len -= excess;
+        while excess > 0 {
+            let back = self
+                .unacked_segments
+                .back_mut()
+                .expect("Expected buffered data");
+            if back.len() <= excess {
+                excess -= back.len();
+                self.unacked_segments.pop_back();
+            } else {
+                back.truncate(back.len() - excess);
+                excess = 0;
+            }
+        }
+    }
+
     pub(super) fn retransmit_all_for_0rtt(&mut self) {
         debug_assert_eq!(self.offset, self.unacked_len as u64);
         self.unsent = 0;
@@ -172,6 +196,21 @@ impl SendBuffer {
         self.offset
     }
 
+    /// First stream offset that has never been sent
+    pub(super) fn sent_offset(&self) -> u64 {
+        self.unsent
+    }
+
+    /// First stream offset that hasn't been acknowledged, even if later data has been
+    pub(super) fn acked_offset(&self) -> u64 {
+        self.offset - self.unacked_len as u64
+    }
+
+    /// Total amount of data sent again after being deemed lost
+    pub(super) fn retransmitted(&self) -> u64 {
+        self.retransmitted
+    }
+
     /// Whether all sent data has been acknowledged
     pub(super) fn is_fully_acked(&self) -> bool {
         self.unacked_len == 0
@@ -384,6 +423,20 @@ mod tests {
         assert!(buf.acks.is_empty());
     }
 
+    #[test]
+    fn truncate() {
+        let mut buf = SendBuffer::new();
+        buf.write(b"Hello, "[..].into());
+        buf.write(b"world!"[..].into());
+        assert_eq!(buf.poll_transmit(16), (0..8, true));
+        buf.truncate(10);
+        assert_eq!(buf.offset(), 10);
+        assert_eq!(aggregate_unacked(&buf), b"Hello, wor");
+        assert_eq!(buf.poll_transmit(16), (8..10, true));
+        buf.ack(0..10);
+        assert!(buf.is_fully_acked());
+    }
+
     fn aggregate_unacked(buf: &SendBuffer) -> Vec<u8> {
         let mut result = Vec::new();
         for segment in buf.unacked_segments.iter() {
@@ -11,8 +11,9 @@ use tracing::trace;
 
 use super::assembler::Assembler;
 use crate::{
-    Dir, Duration, Instant, SocketAddr, StreamId, TransportError, VarInt, connection::StreamsState,
-    crypto::Keys, frame, packet::SpaceId, range_set::ArrayRangeSet, shared::IssuedCid,
+    Dir, Duration, EcnCodepoint, Instant, SocketAddr, StreamId, TransportError, VarInt,
+    connection::StreamsState, crypto::Keys, frame, packet::SpaceId, range_set::ArrayRangeSet,
+    shared::IssuedCid,
 };
 
 pub(super) struct PacketSpace {
@@ -174,12 +175,13 @@ impl PacketSpace {
         SendableFrames { acks, other }
     }
 
-    /// Verifies sanity of an ECN block and returns whether congestion was encountered.
+    /// Verifies sanity of an ECN block and returns the number of newly reported CE marks
     pub(super) fn detect_ecn(
         &mut self,
         newly_acked: u64,
         ecn: frame::EcnCounts,
-    ) -> Result<bool, &'static str> {
+        codepoint: EcnCodepoint,
+    ) -> Result<u64, &'static str> {
         let ect0_increase = ecn
             .ect0
             .checked_sub(self.ecn_feedback.ect0)
@@ -196,7 +198,11 @@ impl PacketSpace {
         if total_increase < newly_acked {
             return Err("ECN bleaching");
         }
-        if (ect0_increase + ce_increase) < newly_acked || ect1_increase != 0 {
+        let (marked_increase, other_increase) = match codepoint {
+            EcnCodepoint::Ect1 => (ect1_increase, ect0_increase),
+            _ => (ect0_increase, ect1_increase),
+        };
+        if (marked_increase + ce_increase) < newly_acked || other_increase != 0 {
             return Err("ECN corruption");
         }
         // If total_increase > newly_acked (which happens when ACKs are lost), this is required by
@@ -204,7 +210,7 @@ impl PacketSpace {
         // to count CE packets as CE or ECT0. Recording them as CE is more consistent and keeps the
         // congestion check obvious.
         self.ecn_feedback = ecn;
-        Ok(ce_increase != 0)
+        Ok(ce_increase)
     }
 
     /// Stop tracking sent packet `number`, and return what we knew about it
@@ -772,14 +778,16 @@ impl PendingAcks {
     }
 
     /// Insert one packet that needs to be acknowledged
-    pub(super) fn insert_one(&mut self, packet: u64, now: Instant) {
+    ///
+    /// If this results in more than `max_ranges` ranges, the lowest is forgotten.
+    pub(super) fn insert_one(&mut self, packet: u64, now: Instant, max_ranges: usize) {
         self.ranges.insert_one(packet);
 
         if self.largest_packet.is_none_or(|(pn, _)| packet > pn) {
             self.largest_packet = Some((packet, now));
         }
 
-        if self.ranges.len() > MAX_ACK_BLOCKS {
+        if self.ranges.len() > max_ranges {
             self.ranges.pop_min();
         }
     }
@@ -901,12 +909,10 @@ impl PacketNumberFilter {
     }
 }
 
-/// Ensures we can always fit all our ACKs in a single minimum-MTU packet with room to spare
-const MAX_ACK_BLOCKS: usize = 64;
-
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::MAX_ACK_BLOCKS;
 
     #[test]
     fn sanity() {
@@ -1047,7 +1053,7 @@ mod test {
         // Receive ack-eliciting packet
         dedup.insert(0);
         let now = Instant::now();
-        acks.insert_one(0, now);
+        acks.insert_one(0, now, MAX_ACK_BLOCKS);
         acks.packet_received(now, 0, true, &dedup);
 
         // Sanity check
@@ -1073,7 +1079,7 @@ mod test {
 
         // In-order packet
         dedup.insert(0);
-        acks.insert_one(0, t1);
+        acks.insert_one(0, t1, MAX_ACK_BLOCKS);
         acks.packet_received(t1, 0, true, &dedup);
         assert_eq!(acks.ack_delay(t1), Duration::from_millis(0));
         assert_eq!(acks.ack_delay(t2), Duration::from_millis(2));
@@ -1081,18 +1087,36 @@ mod test {
 
         // Out of order (higher than expected)
         dedup.insert(3);
-        acks.insert_one(3, t2);
+        acks.insert_one(3, t2, MAX_ACK_BLOCKS);
         acks.packet_received(t2, 3, true, &dedup);
         assert_eq!(acks.ack_delay(t2), Duration::from_millis(0));
         assert_eq!(acks.ack_delay(t3), Duration::from_millis(5));
 
         // Out of order (lower than expected, so previous instant is kept)
         dedup.insert(2);
-        acks.insert_one(2, t3);
+        acks.insert_one(2, t3, MAX_ACK_BLOCKS);
         acks.packet_received(t3, 2, true, &dedup);
         assert_eq!(acks.ack_delay(t3), Duration::from_millis(5));
     }
 
+    #[test]
+    fn pending_acks_max_ranges() {
+        let mut acks = PendingAcks::new();
+        let now = Instant::now();
+
+        // Every other packet number arrives, so each forms its own range
+        for packet in (0..20).step_by(2) {
+            acks.insert_one(packet, now, 4);
+            assert!(acks.ranges().len() <= 4);
+        }
+
+        // Only the most recent ranges are retained
+        assert_eq!(
+            acks.ranges().iter().collect::<Vec<_>>(),
+            [12..13, 14..15, 16..17, 18..19]
+        );
+    }
+
     #[test]
     fn sent_packet_size() {
         // The tracking state of sent packets should be minimal, and not grow
@@ -1,5 +1,7 @@
 //! Connection statistics
 
+use std::net::{IpAddr, SocketAddr};
+
 use crate::{Dir, Duration, frame::Frame};
 
 /// Statistics about UDP datagrams transmitted or received on a connection
@@ -130,6 +132,61 @@ impl std::fmt::Debug for FrameStats {
     }
 }
 
+/// Statistics about data sent on a single stream
+#[derive(Debug, Default, Copy, Clone)]
+#[non_exhaustive]
+pub struct SendStreamStats {
+    /// Bytes written to the stream by the application
+    pub bytes_written: u64,
+    /// Bytes acknowledged by the peer
+    pub bytes_acked: u64,
+    /// Bytes sent again after being declared lost
+    pub bytes_retransmitted: u64,
+}
+
+/// Statistics about data received on a single stream
+#[derive(Debug, Default, Copy, Clone)]
+#[non_exhaustive]
+pub struct RecvStreamStats {
+    /// Bytes of stream data received from the peer, including duplicates
+    pub bytes_received: u64,
+    /// Bytes received beyond a gap in the stream, i.e. ahead of earlier data
+    pub bytes_reordered: u64,
+}
+
+/// Connection-level flow control and stream count limits
+///
+/// Counts of opened streams are cumulative over the lifetime of the connection, like the limits
+/// they're compared against.
+#[derive(Debug, Default, Copy, Clone)]
+#[non_exhaustive]
+pub struct FlowControlStats {
+    /// Total stream data the peer is currently permitted to send
+    pub max_data_local: u64,
+    /// Total stream data received from the peer, including gaps
+    pub data_received: u64,
+    /// Total stream data the peer currently permits us to send
+    pub max_data_remote: u64,
+    /// Total stream data written by the application
+    pub data_sent: u64,
+    /// Unidirectional streams opened locally
+    pub local_streams_uni: u64,
+    /// Unidirectional streams the peer permits us to open
+    pub max_local_streams_uni: u64,
+    /// Bidirectional streams opened locally
+    pub local_streams_bidi: u64,
+    /// Bidirectional streams the peer permits us to open
+    pub max_local_streams_bidi: u64,
+    /// Unidirectional streams opened by the peer
+    pub remote_streams_uni: u64,
+    /// Unidirectional streams the peer is permitted to open
+    pub max_remote_streams_uni: u64,
+    /// Bidirectional streams opened by the peer
+    pub remote_streams_bidi: u64,
+    /// Bidirectional streams the peer is permitted to open
+    pub max_remote_streams_bidi: u64,
+}
+
 /// Statistics related to a transmission path
 #[derive(Debug, Default, Copy, Clone)]
 #[non_exhaustive]
@@ -138,6 +195,15 @@ pub struct PathStats {
     pub rtt: Duration,
     /// Current congestion window of the connection
     pub cwnd: u64,
+    /// Bytes sent in ack-eliciting packets which have not yet been acknowledged or declared lost
+    pub bytes_in_flight: u64,
+    /// Ack-eliciting packets which have not yet been acknowledged or declared lost
+    pub packets_in_flight: u64,
+    /// Consecutive probe timeouts that fired without an acknowledgement arriving in between
+    ///
+    /// Reset to 0 whenever new data is acknowledged. A steadily increasing count is a strong sign
+    /// that the path is no longer delivering packets.
+    pub pto_count: u32,
     /// Congestion events on the connection
     pub congestion_events: u64,
     /// Spurious congestion events on the connection
@@ -159,6 +225,93 @@ pub struct PathStats {
     pub current_mtu: u16,
 }
 
+/// Bytes exchanged over one network path, identified by its addresses
+///
+/// See `Connection::path_stats`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PathStatsEntry {
+    /// The peer's address on this path
+    pub remote: SocketAddr,
+    /// The local IP address on this path, if known
+    ///
+    /// Only available where the platform reports the destination address of received datagrams.
+    pub local_ip: Option<IpAddr>,
+    /// Whether this is the path the connection is currently using
+    pub active: bool,
+    /// Total size of the UDP datagrams sent on this path
+    pub bytes_sent: u64,
+    /// Total size of the UDP datagrams received on this path
+    pub bytes_received: u64,
+}
+
+impl PathStatsEntry {
+    fn new(remote: SocketAddr, local_ip: Option<IpAddr>) -> Self {
+        Self {
+            remote,
+            local_ip,
+            active: false,
+            bytes_sent: 0,
+            bytes_received: 0,
+        }
+    }
+}
+
+/// Byte counts for the paths a connection has recently used, most recently used last
+#[derive(Debug, Default)]
+pub(super) struct PathBytes(Vec<PathStatsEntry>);
+
+impl PathBytes {
+    /// Record traffic exchanged with `remote` while the local address was `local_ip`
+    ///
+    /// A local IP learned after traffic was already counted for `remote` is attributed to the
+    /// existing entry rather than starting a new one.
+    pub(super) fn record(
+        &mut self,
+        remote: SocketAddr,
+        local_ip: Option<IpAddr>,
+        sent: u64,
+        received: u64,
+    ) {
+        let pos = self.0.iter().rposition(|e| {
+            e.remote == remote
+                && (e.local_ip == local_ip || e.local_ip.is_none() || local_ip.is_none())
+        });
+        let entry = match pos {
+            Some(i) if i == self.0.len() - 1 => &mut self.0[i],
+            Some(i) => {
+                let entry = self.0.remove(i);
+                self.0.push(entry);
+                self.0.last_mut().unwrap()
+            }
+            None => {
+                if self.0.len() == MAX_PATH_STATS_ENTRIES {
+                    self.0.remove(0);
+                }
+                self.0.push(PathStatsEntry::new(remote, local_ip));
+                self.0.last_mut().unwrap()
+            }
+        };
+        if entry.local_ip.is_none() {
+            entry.local_ip = local_ip;
+        }
+        entry.bytes_sent = entry.bytes_sent.saturating_add(sent);
+        entry.bytes_received = entry.bytes_received.saturating_add(received);
+    }
+
+    /// Snapshot of all entries, marking the one for `active` as such
+    pub(super) fn snapshot(&self, active: SocketAddr) -> Vec<PathStatsEntry> {
+        let mut entries = self.0.clone();
+        if let Some(entry) = entries.iter_mut().rev().find(|e| e.remote == active) {
+            entry.active = true;
+        }
+        entries
+    }
+}
+
+/// Number of paths to retain byte counts for, including the active one
+const MAX_PATH_STATS_ENTRIES: usize = 8;
+
 /// Connection statistics
 #[derive(Debug, Default, Copy, Clone)]
 #[non_exhaustive]
@@ -173,4 +326,19 @@ pub struct ConnectionStats {
     pub frame_rx: FrameStats,
     /// Statistics related to the current transmission path
     pub path: PathStats,
+    /// Number of received application datagrams discarded because the receive buffer was full
+    ///
+    /// See [`TransportConfig::datagram_receive_buffer_size`](crate::TransportConfig::datagram_receive_buffer_size).
+    pub datagrams_dropped_incoming: u64,
+    /// Time from the first Initial packet until the handshake was confirmed
+    ///
+    /// Measured from when a client sent, or a server received, the first Initial packet, until a
+    /// client received HANDSHAKE_DONE or a server completed the handshake and queued it. For
+    /// clients, this includes any Retry round trip; a server that sent a Retry measures from the
+    /// Initial packet carrying the retry token. `None` until the handshake is confirmed.
+    pub handshake_duration: Option<Duration>,
+    /// Number of times the peer migrated to a new address that was then validated
+    ///
+    /// Only counted by the endpoint validating the new path, i.e. the server.
+    pub migration_count: u64,
 }
@@ -9,8 +9,11 @@ use tracing::trace;
 
 use super::spaces::{Retransmits, ThinRetransmits};
 use crate::{
-    Dir, StreamId, VarInt,
-    connection::streams::state::{get_or_insert_recv, get_or_insert_send},
+    Dir, Duration, StreamId, VarInt,
+    connection::{
+        stats::{RecvStreamStats, SendStreamStats},
+        streams::state::{get_or_insert_recv, get_or_insert_send},
+    },
     frame,
 };
 
@@ -20,8 +23,8 @@ pub use recv::{Chunks, ReadError, ReadableError};
 
 mod send;
 pub(crate) use send::{ByteSlice, BytesArray};
-use send::{BytesSource, Send, SendState};
-pub use send::{FinishError, WriteError, Written};
+use send::{BytesSource, Expiry, Send, SendState};
+pub use send::{FinishError, ResetAtError, WriteError, Written};
 
 mod state;
 #[allow(unreachable_pub)] // fuzzing only
@@ -60,6 +63,23 @@ impl<'a> Streams<'a> {
         Some(id)
     }
 
+    /// Open a single stream with an initial priority if possible
+    ///
+    /// Like [`open()`](Self::open), but the stream's send half starts out with `priority` rather
+    /// than 0, as though [`SendStream::set_priority()`] had been called before anything else.
+    pub fn open_with_priority(&mut self, dir: Dir, priority: i32) -> Option<StreamId> {
+        let id = self.open(dir)?;
+        let max_send_data = self.state.max_send_data(id);
+        let stream = self
+            .state
+            .send
+            .get_mut(&id)
+            .map(get_or_insert_send(max_send_data))
+            .expect("newly opened stream must be sendable");
+        stream.priority = priority;
+        Some(id)
+    }
+
     /// Accept a remotely initiated stream of a certain directionality, if possible
     ///
     /// Returns `None` if there are no new incoming streams for this connection.
@@ -88,6 +108,49 @@ impl<'a> Streams<'a> {
         self.state.send_streams
     }
 
+    /// Describe every open stream, in ascending order of ID
+    ///
+    /// Intended for introspection and debugging. Streams are omitted once both of their halves
+    /// have been closed.
+    pub fn snapshot(&self) -> Vec<StreamInfo> {
+        let mut ids = self
+            .state
+            .send
+            .keys()
+            .chain(self.state.recv.keys())
+            .copied()
+            // Remote streams are allocated as soon as the peer may open them, so skip those it
+            // hasn't opened yet
+            .filter(|id| {
+                let next = if id.initiator() == self.state.side {
+                    &self.state.next
+                } else {
+                    &self.state.next_remote
+                };
+                id.index() < next[id.dir() as usize]
+            })
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids.dedup();
+
+        ids.into_iter()
+            .map(|id| {
+                // Halves are only allocated once used, so a missing one is in its initial state
+                let send = self.state.send.get(&id);
+                let recv = self.state.recv.get(&id);
+                let open_send = send.and_then(|s| s.as_deref());
+                let open_recv = recv.and_then(|s| s.as_ref()?.as_open_recv());
+                StreamInfo {
+                    id,
+                    send: send.map(|_| open_send.map_or(SendStreamState::Send, Send::info_state)),
+                    recv: recv.map(|_| open_recv.map_or(RecvStreamState::Recv, Recv::info_state)),
+                    bytes_queued: open_send.map_or(0, |s| s.pending.unacked()),
+                    bytes_buffered: open_recv.map_or(0, |r| r.assembler.buffered() as u64),
+                }
+            })
+            .collect()
+    }
+
     /// The number of remotely initiated open streams of a certain directionality.
     ///
     /// Includes remotely initiated streams, which have not been accepted via [`accept`](Self::accept).
@@ -163,6 +226,112 @@ impl RecvStream<'_> {
         Ok(())
     }
 
+    /// Grow the flow control window of this stream beyond
+    /// [`TransportConfig::stream_receive_window()`](crate::TransportConfig::stream_receive_window)
+    ///
+    /// The window can only grow: a `window` smaller than the stream's current window is ignored,
+    /// because credit already granted to the peer can't be withdrawn. The peer also remains bound
+    /// by the connection-level receive window.
+    pub fn set_receive_window(&mut self, window: VarInt) -> Result<(), ClosedStream> {
+        let default_window = self.state.stream_receive_window;
+        let hash_map::Entry::Occupied(mut entry) = self.state.recv.entry(self.id) else {
+            return Err(ClosedStream { _private: () });
+        };
+        let stream = get_or_insert_recv(default_window)(entry.get_mut());
+        if stream.stopped {
+            return Err(ClosedStream { _private: () });
+        }
+
+        stream.set_receive_window(window.into());
+        if stream.max_stream_data(default_window).1.should_transmit() {
+            self.pending.max_stream_data.insert(self.id);
+        }
+        Ok(())
+    }
+
+    /// Limit the flow control window of this stream, overriding both
+    /// [`TransportConfig::stream_receive_window()`](crate::TransportConfig::stream_receive_window)
+    /// and [`set_receive_window()`](Self::set_receive_window)
+    ///
+    /// Caps the amount of unread data the peer may send on this stream, so that a slow reader
+    /// throttles the sender. Credit already granted to the peer can't be withdrawn, so a lower
+    /// limit takes effect only once the application has read past it.
+    pub fn set_max_receive_window(&mut self, window: VarInt) -> Result<(), ClosedStream> {
+        let default_window = self.state.stream_receive_window;
+        let hash_map::Entry::Occupied(mut entry) = self.state.recv.entry(self.id) else {
+            return Err(ClosedStream { _private: () });
+        };
+        let stream = get_or_insert_recv(default_window)(entry.get_mut());
+        if stream.stopped {
+            return Err(ClosedStream { _private: () });
+        }
+
+        stream.set_max_receive_window(window.into());
+        if stream.max_stream_data(default_window).1.should_transmit() {
+            self.pending.max_stream_data.insert(self.id);
+        }
+        Ok(())
+    }
+
+    /// Check whether [`read()`](Self::read) would make progress, without consuming anything
+    ///
+    /// True if data is buffered at the current read offset (or anywhere, once unordered reads
+    /// have been used), or if the next read would report the end of the stream or a reset.
+    pub fn is_readable(&self) -> Result<bool, ClosedStream> {
+        let Some(entry) = self.state.recv.get(&self.id) else {
+            return Err(ClosedStream { _private: () });
+        };
+        let Some(s) = entry.as_ref().and_then(|s| s.as_open_recv()) else {
+            return Ok(false);
+        };
+        if s.stopped {
+            return Err(ClosedStream { _private: () });
+        }
+        Ok(s.is_readable())
+    }
+
+    /// Statistics about the data received on this stream
+    pub fn stats(&self) -> Result<RecvStreamStats, ClosedStream> {
+        let Some(entry) = self.state.recv.get(&self.id) else {
+            return Err(ClosedStream { _private: () });
+        };
+        Ok(entry
+            .as_ref()
+            .and_then(|s| s.as_open_recv())
+            .map(|s| s.stats)
+            .unwrap_or_default())
+    }
+
+    /// Stream offset below which data may have been received in 0-RTT packets
+    ///
+    /// 0-RTT data may be replayed by an attacker, so servers should only act on idempotent
+    /// requests contained in data before this offset. Data at or beyond it has only been received
+    /// in 1-RTT packets. Zero if no data on this stream was received in 0-RTT packets. May increase
+    /// for as long as 0-RTT packets are still being received, i.e. until shortly after the
+    /// handshake completes.
+    pub fn early_data_end(&self) -> Result<u64, ClosedStream> {
+        let Some(entry) = self.state.recv.get(&self.id) else {
+            return Err(ClosedStream { _private: () });
+        };
+        Ok(entry
+            .as_ref()
+            .and_then(|s| s.as_open_recv())
+            .map_or(0, |s| s.early_data_end))
+    }
+
+    /// Number of bytes received on this stream but not yet read by the application
+    ///
+    /// Counts the data actually held in memory, including any duplicate bytes received before the
+    /// stream was first read, for use in memory accounting. Zero for streams that are closed or
+    /// whose buffered data was discarded due to a reset.
+    pub fn bytes_buffered(&self) -> u64 {
+        self.state
+            .recv
+            .get(&self.id)
+            .and_then(|s| s.as_ref()?.as_open_recv())
+            .map_or(0, |s| s.assembler.buffered() as u64)
+    }
+
     /// Check whether this stream has been reset by the peer, returning the reset error code if so
     ///
     /// After returning `Ok(Some(_))` once, stream state will be discarded and all future calls will
@@ -335,6 +504,86 @@ impl<'a> SendStream<'a> {
         Ok(())
     }
 
+    /// Abandon transmitting data on a stream beyond `final_size`
+    ///
+    /// Written data below `final_size` continues to be sent and retransmitted as usual, and the
+    /// `RESET_STREAM` frame, carrying `final_size` as the stream's final size, is only sent once
+    /// all of it has been acknowledged. Data at and beyond `final_size` is discarded. As with
+    /// [`reset()`](Self::reset), which uses the amount of data written so far, no more data may be
+    /// written afterwards.
+    ///
+    /// Note that acknowledgement only means the peer's transport received the data: receivers,
+    /// including this implementation, may discard data the application hasn't read yet when the
+    /// reset arrives.
+    ///
+    /// Fails with [`ResetAtError::InvalidFinalSize`] if `final_size` is less than the amount of
+    /// data already sent, which the peer may have received, or more than has been written. Fails
+    /// with [`ResetAtError::ClosedStream`] if the stream was already finished or reset.
+    ///
+    /// # Panics
+    /// - when applied to a receive stream
+    pub fn reset_at(&mut self, error_code: VarInt, final_size: u64) -> Result<(), ResetAtError> {
+        let max_send_data = self.state.max_send_data(self.id);
+        let stream = self
+            .state
+            .send
+            .get_mut(&self.id)
+            .map(get_or_insert_send(max_send_data))
+            .ok_or(ResetAtError::ClosedStream)?;
+
+        let discarded = stream.reset_at(error_code, final_size)?;
+        // Discarded data never consumes the peer's flow control credit
+        self.state.data_sent -= discarded;
+        self.state.unacked_data -= discarded;
+        if stream.pending.is_fully_acked() {
+            stream.reset();
+            self.pending.reset_stream.push((self.id, error_code));
+        }
+        Ok(())
+    }
+
+    /// Reset the stream with `error_code` if lost data is older than `expiry`
+    ///
+    /// Intended for data that loses its value with age, such as live media. Data is aged from
+    /// its first transmission, and expiry is only checked when loss detection declares a packet
+    /// carrying the stream's data lost: if that data is older than `expiry`, the stream is reset
+    /// with `error_code` instead of retransmitted, as though by [`reset()`](Self::reset), and
+    /// subsequent writes fail. Expired data that is never lost is still delivered.
+    ///
+    /// QUIC has no means of telling the peer to skip part of a stream: STREAM frames must cover
+    /// every offset, and `RESET_STREAM` (or the reliable variant, which still delivers a prefix)
+    /// ends the stream. Partial reliability is thus only available at the granularity of whole
+    /// streams, and applications should send each independently expendable message on its own
+    /// stream. `None` disables expiry.
+    ///
+    /// # Panics
+    /// - when applied to a receive stream
+    pub fn set_reset_on_expiry(
+        &mut self,
+        expiry: Option<Duration>,
+        error_code: VarInt,
+    ) -> Result<(), ClosedStream> {
+        let max_send_data = self.state.max_send_data(self.id);
+        let stream = self
+            .state
+            .send
+            .get_mut(&self.id)
+            .map(get_or_insert_send(max_send_data))
+            .ok_or(ClosedStream { _private: () })?;
+
+        stream.expiry = match (expiry, stream.expiry.take()) {
+            // Keep track of when outstanding data was sent
+            (Some(max_age), Some(mut current)) => {
+                current.max_age = max_age;
+                current.error_code = error_code;
+                Some(current)
+            }
+            (Some(max_age), None) => Some(Expiry::new(max_age, error_code)),
+            (None, _) => None,
+        };
+        Ok(())
+    }
+
     /// Set the priority of a stream
     ///
     /// # Panics
@@ -365,6 +614,31 @@ impl<'a> SendStream<'a> {
 
         Ok(stream.as_ref().map(|s| s.priority).unwrap_or_default())
     }
+
+    /// Statistics about the data sent on this stream
+    pub fn stats(&self) -> Result<SendStreamStats, ClosedStream> {
+        let stream = self
+            .state
+            .send
+            .get(&self.id)
+            .ok_or(ClosedStream { _private: () })?;
+
+        Ok(stream.as_ref().map(|s| s.stats()).unwrap_or_default())
+    }
+
+    /// Number of bytes written to the stream that have not yet been transmitted
+    ///
+    /// Excludes data that has been sent but not yet acknowledged, including data awaiting
+    /// retransmission.
+    pub fn bytes_in_send_buffer(&self) -> Result<u64, ClosedStream> {
+        let stream = self
+            .state
+            .send
+            .get(&self.id)
+            .ok_or(ClosedStream { _private: () })?;
+
+        Ok(stream.as_ref().map_or(0, |s| s.unsent()))
+    }
 }
 
 /// A queue of streams with pending outgoing data, sorted by priority
@@ -454,6 +728,50 @@ struct PendingStream {
     id: StreamId,
 }
 
+/// State of a single stream, as reported by [`Streams::snapshot`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct StreamInfo {
+    /// The stream's ID, which also encodes its directionality and initiator
+    pub id: StreamId,
+    /// State of the sending half, or `None` if the stream has none or it has been closed
+    pub send: Option<SendStreamState>,
+    /// State of the receiving half, or `None` if the stream has none or it has been closed
+    pub recv: Option<RecvStreamState>,
+    /// Bytes written by the application that the peer has yet to acknowledge
+    pub bytes_queued: u64,
+    /// Bytes received from the peer that the application has yet to read
+    ///
+    /// May count data received more than once.
+    pub bytes_buffered: u64,
+}
+
+/// State of the sending half of a stream, following RFC 9000 §3.1
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SendStreamState {
+    /// Accepting new data from the application
+    Send,
+    /// Finished by the application; retransmitting until all data is acknowledged
+    DataSent,
+    /// All data and the end of the stream have been acknowledged
+    DataRecvd,
+    /// Reset by the application
+    ResetSent,
+}
+
+/// State of the receiving half of a stream, following RFC 9000 §3.2
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RecvStreamState {
+    /// Receiving data whose final size is not yet known
+    Recv,
+    /// The peer has finished the stream, so its final size is known
+    SizeKnown,
+    /// The peer has reset the stream
+    ResetRecvd,
+}
+
 /// Application events about streams
 #[derive(Debug, PartialEq, Eq)]
 pub enum StreamEvent {
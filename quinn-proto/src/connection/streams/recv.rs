@@ -5,8 +5,9 @@ use thiserror::Error;
 use tracing::debug;
 
 use super::state::get_or_insert_recv;
-use super::{ClosedStream, Retransmits, ShouldTransmit, StreamId, StreamsState};
+use super::{ClosedStream, RecvStreamState, Retransmits, ShouldTransmit, StreamId, StreamsState};
 use crate::connection::assembler::{Assembler, Chunk, IllegalOrderedRead};
+use crate::connection::stats::RecvStreamStats;
 use crate::connection::streams::state::StreamRecv;
 use crate::{TransportError, VarInt, frame};
 
@@ -16,8 +17,15 @@ pub(super) struct Recv {
     state: RecvState,
     pub(super) assembler: Assembler,
     sent_max_stream_data: u64,
+    /// Receive window requested for this stream specifically, if larger than the default
+    receive_window: u64,
+    /// Upper bound on the receive window of this stream, overriding the above
+    max_receive_window: u64,
     pub(super) end: u64,
+    /// Highest stream offset received in 0-RTT packets
+    pub(super) early_data_end: u64,
     pub(super) stopped: bool,
+    pub(super) stats: RecvStreamStats,
 }
 
 impl Recv {
@@ -26,8 +34,12 @@ impl Recv {
             state: RecvState::default(),
             assembler: Assembler::new(),
             sent_max_stream_data: initial_max_data,
+            receive_window: 0,
+            max_receive_window: u64::MAX,
             end: 0,
+            early_data_end: 0,
             stopped: false,
+            stats: RecvStreamStats::default(),
         })
     }
 
@@ -36,8 +48,12 @@ impl Recv {
         self.state = RecvState::default();
         self.assembler.reinit();
         self.sent_max_stream_data = initial_max_data;
+        self.receive_window = 0;
+        self.max_receive_window = u64::MAX;
         self.end = 0;
+        self.early_data_end = 0;
         self.stopped = false;
+        self.stats = RecvStreamStats::default();
     }
 
     /// Process a STREAM frame
@@ -74,6 +90,10 @@ impl Recv {
             }
         }
 
+        self.stats.bytes_received += frame.data.len() as u64;
+        if frame.offset > self.end {
+            self.stats.bytes_reordered += frame.data.len() as u64;
+        }
         self.end = self.end.max(end);
         // Don't bother storing data or releasing stream-level flow control credit if the stream's
         // already stopped
@@ -110,7 +130,12 @@ impl Recv {
     /// `false` the new window should only be transmitted if a previous transmission
     /// had failed.
     pub(super) fn max_stream_data(&mut self, stream_receive_window: u64) -> (u64, ShouldTransmit) {
-        let max_stream_data = self.assembler.bytes_read() + stream_receive_window;
+        let stream_receive_window = stream_receive_window
+            .max(self.receive_window)
+            .min(self.max_receive_window);
+        // Credit already granted can't be withdrawn
+        let max_stream_data =
+            (self.assembler.bytes_read() + stream_receive_window).max(self.sent_max_stream_data);
 
         // Only announce a window update if it's significant enough
         // to make it worthwhile sending a MAX_STREAM_DATA frame.
@@ -120,10 +145,21 @@ impl Recv {
         // smaller than `stream_receive_window` in order to make sure the stream
         // does not get stuck.
         let diff = max_stream_data - self.sent_max_stream_data;
-        let transmit = self.can_send_flow_control() && diff >= (stream_receive_window / 8);
+        let transmit =
+            self.can_send_flow_control() && diff > 0 && diff >= (stream_receive_window / 8);
         (max_stream_data, ShouldTransmit(transmit))
     }
 
+    /// Grow the window advertised for this stream to at least `window`
+    pub(super) fn set_receive_window(&mut self, window: u64) {
+        self.receive_window = self.receive_window.max(window);
+    }
+
+    /// Limit the window advertised for this stream to at most `window`
+    pub(super) fn set_max_receive_window(&mut self, window: u64) {
+        self.max_receive_window = window;
+    }
+
     /// Records that a `MAX_STREAM_DATA` announcing a certain window was sent
     ///
     /// This will suppress enqueuing further `MAX_STREAM_DATA` frames unless
@@ -158,6 +194,25 @@ impl Recv {
         matches!(self.state, RecvState::Recv { .. })
     }
 
+    /// Whether a read would yield data, the end of the stream, or the peer's reset
+    pub(super) fn is_readable(&self) -> bool {
+        match self.state {
+            RecvState::ResetRecvd { .. } => true,
+            RecvState::Recv { size } => {
+                self.assembler.has_readable()
+                    || (size == Some(self.end) && self.assembler.bytes_read() == self.end)
+            }
+        }
+    }
+
+    pub(super) fn info_state(&self) -> RecvStreamState {
+        match self.state {
+            RecvState::Recv { size: None } => RecvStreamState::Recv,
+            RecvState::Recv { size: Some(_) } => RecvStreamState::SizeKnown,
+            RecvState::ResetRecvd { .. } => RecvStreamState::ResetRecvd,
+        }
+    }
+
     fn final_offset(&self) -> Option<u64> {
         match self.state {
             RecvState::Recv { size } => size,
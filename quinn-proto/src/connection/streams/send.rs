@@ -1,7 +1,14 @@
+use std::collections::VecDeque;
+
 use bytes::Bytes;
 use thiserror::Error;
 
-use crate::{VarInt, connection::send_buffer::SendBuffer, frame};
+use super::SendStreamState;
+use crate::{
+    Duration, Instant, VarInt,
+    connection::{send_buffer::SendBuffer, stats::SendStreamStats},
+    frame,
+};
 
 #[derive(Debug)]
 pub(super) struct Send {
@@ -15,6 +22,8 @@ pub(super) struct Send {
     pub(super) connection_blocked: bool,
     /// The reason the peer wants us to stop, if `STOP_SENDING` was received
     pub(super) stop_reason: Option<VarInt>,
+    /// Set by [`SendStream::set_reset_on_expiry`](super::SendStream::set_reset_on_expiry)
+    pub(super) expiry: Option<Box<Expiry>>,
 }
 
 impl Send {
@@ -27,9 +36,23 @@ impl Send {
             fin_pending: false,
             connection_blocked: false,
             stop_reason: None,
+            expiry: None,
         })
     }
 
+    pub(super) fn stats(&self) -> SendStreamStats {
+        SendStreamStats {
+            bytes_written: self.pending.offset(),
+            bytes_acked: self.pending.offset() - self.pending.unacked(),
+            bytes_retransmitted: self.pending.retransmitted(),
+        }
+    }
+
+    /// Amount of data written by the application that has never been sent
+    pub(super) fn unsent(&self) -> u64 {
+        self.pending.offset() - self.pending.sent_offset()
+    }
+
     /// Whether the stream has been reset
     pub(super) fn is_reset(&self) -> bool {
         matches!(self.state, SendState::ResetSent)
@@ -86,11 +109,35 @@ impl Send {
     /// Update stream state due to a reset sent by the local application
     pub(super) fn reset(&mut self) {
         use SendState::*;
-        if let DataSent { .. } | Ready = self.state {
+        if let DataSent { .. } | Ready | ResetPending { .. } = self.state {
             self.state = ResetSent;
         }
     }
 
+    /// Discard written data beyond `final_size` and prepare to reset the stream there
+    ///
+    /// Returns the number of bytes discarded. The caller is responsible for sending the reset once
+    /// [`ack()`](Self::ack) reports that everything below `final_size` has been acknowledged, or
+    /// immediately if it already has been.
+    pub(super) fn reset_at(
+        &mut self,
+        error_code: VarInt,
+        final_size: u64,
+    ) -> Result<u64, ResetAtError> {
+        if !self.is_writable() {
+            return Err(ResetAtError::ClosedStream);
+        }
+        // The peer may already have received anything we've sent, and we can't retroactively
+        // claim to have written data we haven't
+        if final_size < self.pending.sent_offset() || final_size > self.pending.offset() {
+            return Err(ResetAtError::InvalidFinalSize);
+        }
+        let discarded = self.pending.offset() - final_size;
+        self.pending.truncate(final_size);
+        self.state = SendState::ResetPending { error_code };
+        Ok(discarded)
+    }
+
     /// Handle STOP_SENDING
     ///
     /// Returns true if the stream was stopped due to this frame, and false
@@ -104,9 +151,39 @@ impl Send {
         }
     }
 
+    /// Record the transmission of a STREAM frame ending at `end`
+    pub(super) fn on_sent(&mut self, end: u64, now: Instant) {
+        let Some(expiry) = &mut self.expiry else {
+            return;
+        };
+        // Only the first transmission of data determines its age
+        if expiry.sent.back().is_none_or(|&(prev, _)| end > prev) {
+            expiry.sent.push_back((end, now));
+        }
+    }
+
+    /// Whether unacknowledged data has been outstanding for longer than the configured expiry
+    pub(super) fn is_expired(&self, now: Instant) -> bool {
+        self.expiry.as_ref().is_some_and(|expiry| {
+            expiry
+                .sent
+                .front()
+                .is_some_and(|&(_, sent)| now.saturating_duration_since(sent) >= expiry.max_age)
+        })
+    }
+
     /// Returns whether the stream has been finished and all data has been acknowledged by the peer
+    ///
+    /// For streams awaiting a [`reset_at()`](Self::reset_at), returns whether all data below the
+    /// final size has been acknowledged instead.
     pub(super) fn ack(&mut self, frame: frame::StreamMeta) -> bool {
         self.pending.ack(frame.offsets);
+        if let Some(expiry) = &mut self.expiry {
+            let acked = self.pending.acked_offset();
+            while expiry.sent.front().is_some_and(|&(end, _)| end <= acked) {
+                expiry.sent.pop_front();
+            }
+        }
         match self.state {
             SendState::DataSent {
                 ref mut finish_acked,
@@ -114,6 +191,7 @@ impl Send {
                 *finish_acked |= frame.fin;
                 *finish_acked && self.pending.is_fully_acked()
             }
+            SendState::ResetPending { .. } => self.pending.is_fully_acked(),
             _ => false,
         }
     }
@@ -141,6 +219,49 @@ impl Send {
     pub(super) fn is_writable(&self) -> bool {
         matches!(self.state, SendState::Ready)
     }
+
+    pub(super) fn info_state(&self) -> SendStreamState {
+        match self.state {
+            SendState::Ready => SendStreamState::Send,
+            SendState::DataSent {
+                finish_acked: false,
+            } => SendStreamState::DataSent,
+            SendState::DataSent { finish_acked: true } => SendStreamState::DataRecvd,
+            SendState::ResetPending { .. } | SendState::ResetSent => SendStreamState::ResetSent,
+        }
+    }
+
+    /// Whether everything written so far, including any FIN, has been acknowledged
+    ///
+    /// Reset streams are never retransmitted, so they count as acknowledged.
+    pub(super) fn is_acked(&self) -> bool {
+        match self.state {
+            SendState::Ready => self.pending.is_fully_acked(),
+            SendState::DataSent { finish_acked } => finish_acked && self.pending.is_fully_acked(),
+            SendState::ResetPending { .. } => self.pending.is_fully_acked(),
+            SendState::ResetSent => true,
+        }
+    }
+}
+
+/// Age after which unacknowledged data abandons a stream
+#[derive(Debug)]
+pub(super) struct Expiry {
+    pub(super) max_age: Duration,
+    /// Error code to reset the stream with once data expires
+    pub(super) error_code: VarInt,
+    /// End offset and time of the first transmission of data that isn't fully acknowledged yet
+    sent: VecDeque<(u64, Instant)>,
+}
+
+impl Expiry {
+    pub(super) fn new(max_age: Duration, error_code: VarInt) -> Box<Self> {
+        Box::new(Self {
+            max_age,
+            error_code,
+            sent: VecDeque::new(),
+        })
+    }
 }
 
 /// A [`BytesSource`] implementation for `&'a mut [Bytes]`
@@ -284,6 +405,8 @@ pub(super) enum SendState {
     Ready,
     /// Stream was finished; now sending retransmits only
     DataSent { finish_acked: bool },
+    /// Stream was reset at a specific offset; sending data up to it before sending RESET
+    ResetPending { error_code: VarInt },
     /// Sent RESET
     ResetSent,
 }
@@ -304,6 +427,17 @@ pub enum FinishError {
     ClosedStream,
 }
 
+/// Reasons why attempting to reset a stream at a specific offset might fail
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum ResetAtError {
+    /// The final size is less than the amount of data already sent, or more than has been written
+    #[error("invalid final size")]
+    InvalidFinalSize,
+    /// The stream has not been opened or was already finished or reset
+    #[error("closed stream")]
+    ClosedStream,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -13,9 +13,9 @@ use super::{
     StreamHalf, ThinRetransmits,
 };
 use crate::{
-    Dir, MAX_STREAM_COUNT, Side, StreamId, TransportError, VarInt,
+    Dir, Instant, MAX_STREAM_COUNT, Side, StreamId, TransportError, VarInt,
     coding::BufMutExt,
-    connection::stats::FrameStats,
+    connection::stats::{FlowControlStats, FrameStats},
     frame::{self, FrameStruct, StreamMetaVec},
     transport_parameters::TransportParameters,
 };
@@ -139,6 +139,8 @@ pub struct StreamsState {
     receive_window_shrink_debt: u64,
     /// Whether the locally-initiated stream limit has been hit, per direction
     pub(super) streams_blocked: [bool; 2],
+    /// Locally-initiated streams whose data was discarded when the peer rejected 0-RTT
+    rejected_0rtt: Vec<StreamId>,
 }
 
 impl StreamsState {
@@ -184,6 +186,7 @@ impl StreamsState {
             initial_max_stream_data_bidi_remote: 0u32.into(),
             receive_window_shrink_debt: 0,
             streams_blocked: [false, false],
+            rejected_0rtt: Vec::new(),
         };
 
         for dir in Dir::iter() {
@@ -230,7 +233,11 @@ impl StreamsState {
                 // We don't bother calling `stream_freed` here because we explicitly reset affected
                 // counters below.
                 let id = StreamId::new(self.side, dir, i);
-                self.send.remove(&id).unwrap();
+                if let Some(stream) = self.send.remove(&id).unwrap() {
+                    if stream.offset() > 0 || !stream.is_writable() {
+                        self.rejected_0rtt.push(id);
+                    }
+                }
                 if let Dir::Bi = dir {
                     self.recv.remove(&id).unwrap();
                 }
@@ -250,6 +257,20 @@ impl StreamsState {
         self.connection_blocked.clear();
     }
 
+    /// Whether any stream, in either direction, is currently open
+    pub(crate) fn has_open_streams(&self) -> bool {
+        self.send.values().any(Option::is_some)
+            || self
+                .recv
+                .values()
+                .any(|s| s.as_ref().and_then(StreamRecv::as_open_recv).is_some())
+    }
+
+    /// Streams which had data written, or were finished or reset, before 0-RTT was rejected
+    pub(crate) fn zero_rtt_rejected_streams(&self) -> &[StreamId] {
+        &self.rejected_0rtt
+    }
+
     /// Process incoming stream frame
     ///
     /// If successful, returns whether a `MAX_DATA` frame needs to be transmitted
@@ -296,6 +317,18 @@ impl StreamsState {
         Ok(self.add_read_credits(new_bytes))
     }
 
+    /// Record that data up to `end` on stream `id` was received in a 0-RTT packet
+    pub(crate) fn received_0rtt(&mut self, id: StreamId, end: u64) {
+        if let Some(rs) = self
+            .recv
+            .get_mut(&id)
+            .and_then(|s| s.as_mut())
+            .and_then(|s| s.as_open_recv_mut())
+        {
+            rs.early_data_end = rs.early_data_end.max(end);
+        }
+    }
+
     /// Process incoming RESET_STREAM frame
     ///
     /// If successful, returns whether a `MAX_DATA` frame needs to be transmitted
@@ -387,12 +420,13 @@ impl StreamsState {
 
     /// Whether any stream data is queued, regardless of control frames
     pub(crate) fn can_send_stream_data(&self) -> bool {
-        // Reset streams may linger in the pending stream list, but will never produce stream frames
+        // Reset streams, and streams whose unsent data was discarded by `reset_at`, may linger in
+        // the pending stream list, but will never produce stream frames
         self.pending.iter().any(|stream| {
             self.send
                 .get(&stream.id)
                 .and_then(|s| s.as_ref())
-                .is_some_and(|s| !s.is_reset())
+                .is_some_and(|s| !s.is_reset() && s.is_pending())
         })
     }
 
@@ -561,6 +595,7 @@ impl StreamsState {
         buf: &mut Vec<u8>,
         max_buf_size: usize,
         fair: bool,
+        now: Instant,
     ) -> StreamMetaVec {
         let mut stream_frames = StreamMetaVec::new();
         while buf.len() + frame::Stream::SIZE_BOUND < max_buf_size {
@@ -586,8 +621,9 @@ impl StreamsState {
 
             // Reset streams aren't removed from the pending list and still exist while the peer
             // hasn't acknowledged the reset, but should not generate STREAM frames, so we need to
-            // check for them explicitly.
-            if stream.is_reset() {
+            // check for them explicitly. The same goes for streams whose unsent data was discarded
+            // by `reset_at`.
+            if stream.is_reset() || !stream.is_pending() {
                 continue;
             }
 
@@ -600,6 +636,7 @@ impl StreamsState {
             if fin {
                 stream.fin_pending = false;
             }
+            stream.on_sent(offsets.end, now);
 
             if stream.is_pending() {
                 // If the stream still has pending data, reinsert it, possibly with an updated priority value
@@ -650,7 +687,7 @@ impl StreamsState {
         }
     }
 
-    pub(crate) fn received_ack_of(&mut self, frame: frame::StreamMeta) {
+    pub(crate) fn received_ack_of(&mut self, frame: frame::StreamMeta, pending: &mut Retransmits) {
         let mut entry = match self.send.entry(frame.id) {
             hash_map::Entry::Vacant(_) => return,
             hash_map::Entry::Occupied(e) => e,
@@ -674,17 +711,41 @@ impl StreamsState {
             // The stream is unfinished or may still need retransmits
             return;
         }
+        if let SendState::ResetPending { error_code } = stream.state {
+            // Everything below the final size has been delivered
+            stream.reset();
+            pending.reset_stream.push((id, error_code));
+            return;
+        }
 
         entry.remove_entry();
         self.stream_freed(id, StreamHalf::Send);
         self.events.push_back(StreamEvent::Finished { id });
     }
 
-    pub(crate) fn retransmit(&mut self, frame: frame::StreamMeta) {
+    pub(crate) fn retransmit(
+        &mut self,
+        frame: frame::StreamMeta,
+        now: Instant,
+        pending: &mut Retransmits,
+    ) {
         let Some(stream) = self.send.get_mut(&frame.id).and_then(|s| s.as_mut()) else {
             // Loss of data on a closed stream is a noop
             return;
         };
+        if !stream.is_reset() && stream.is_expired(now) {
+            // STREAM frames can't skip over data, so the only way to stop retransmitting it is to
+            // abandon the stream entirely
+            debug!(id = %frame.id, "unacknowledged data expired, resetting stream");
+            let error_code = stream.expiry.as_ref().unwrap().error_code;
+            self.unacked_data -= stream.pending.unacked();
+            stream.reset();
+            pending.reset_stream.push((frame.id, error_code));
+            // Wake up writers so they observe the reset
+            self.events
+                .push_back(StreamEvent::Writable { id: frame.id });
+            return;
+        }
         if !stream.is_pending() {
             self.pending.push_pending(frame.id, stream.priority);
         }
@@ -692,6 +753,29 @@ impl StreamsState {
         stream.pending.retransmit(frame.offsets);
     }
 
+    /// Whether all data written to send streams has been acknowledged by the peer
+    pub(crate) fn is_send_data_acked(&self) -> bool {
+        self.send.values().flatten().all(|s| s.is_acked())
+    }
+
+    pub(crate) fn flow_control_stats(&self) -> FlowControlStats {
+        let (uni, bi) = (Dir::Uni as usize, Dir::Bi as usize);
+        FlowControlStats {
+            max_data_local: self.local_max_data,
+            data_received: self.data_recvd,
+            max_data_remote: self.max_data,
+            data_sent: self.data_sent,
+            local_streams_uni: self.next[uni],
+            max_local_streams_uni: self.max[uni],
+            local_streams_bidi: self.next[bi],
+            max_local_streams_bidi: self.max[bi],
+            remote_streams_uni: self.next_remote[uni],
+            max_remote_streams_uni: self.max_remote[uni],
+            remote_streams_bidi: self.next_remote[bi],
+            max_remote_streams_bidi: self.max_remote[bi],
+        }
+    }
+
     pub(crate) fn retransmit_all_for_0rtt(&mut self) {
         for dir in Dir::iter() {
             for index in 0..self.next[dir as usize] {
@@ -1397,7 +1481,7 @@ mod tests {
         high.write(b"high").unwrap();
 
         let mut buf = Vec::with_capacity(40);
-        let meta = server.write_stream_frames(&mut buf, 40, true);
+        let meta = server.write_stream_frames(&mut buf, 40, true, Instant::now());
         assert_eq!(meta[0].id, id_high);
         assert_eq!(meta[1].id, id_mid);
         assert_eq!(meta[2].id, id_low);
@@ -1406,6 +1490,65 @@ mod tests {
         assert_eq!(server.pending.len(), 0);
     }
 
+    #[test]
+    fn open_with_priority() {
+        let mut server = make(Side::Server);
+        server.set_params(&TransportParameters {
+            initial_max_streams_bidi: 8u32.into(),
+            initial_max_streams_uni: 8u32.into(),
+            initial_max_data: 100u32.into(),
+            initial_max_stream_data_bidi_remote: 10u32.into(),
+            initial_max_stream_data_uni: 10u32.into(),
+            ..TransportParameters::default()
+        });
+
+        let (mut pending, state) = (Retransmits::default(), ConnState::Established);
+        let mut streams = Streams {
+            state: &mut server,
+            conn_state: &state,
+        };
+
+        // Open streams in an order unrelated to their priority
+        let priorities = [0, 3, -2, 7, 1, -5];
+        let ids = priorities
+            .iter()
+            .enumerate()
+            .map(|(i, &priority)| {
+                let dir = if i % 2 == 0 { Dir::Bi } else { Dir::Uni };
+                streams.open_with_priority(dir, priority).unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        for &id in &ids {
+            let mut stream = SendStream {
+                id,
+                state: &mut server,
+                pending: &mut pending,
+                conn_state: &state,
+            };
+            stream.write(b"data").unwrap();
+        }
+        for (&id, &priority) in ids.iter().zip(&priorities) {
+            let stream = SendStream {
+                id,
+                state: &mut server,
+                pending: &mut pending,
+                conn_state: &state,
+            };
+            assert_eq!(stream.priority().unwrap(), priority);
+        }
+
+        let mut buf = Vec::with_capacity(100);
+        let meta = server.write_stream_frames(&mut buf, 100, true, Instant::now());
+        let mut expected = ids.iter().zip(&priorities).collect::<Vec<_>>();
+        expected.sort_by_key(|&(_, &priority)| std::cmp::Reverse(priority));
+        assert_eq!(
+            meta.iter().map(|m| m.id).collect::<Vec<_>>(),
+            expected.into_iter().map(|(&id, _)| id).collect::<Vec<_>>()
+        );
+        assert!(!server.can_send_stream_data());
+    }
+
     #[test]
     fn requeue_stream_priority() {
         let mut server = make(Side::Server);
@@ -1456,7 +1599,7 @@ mod tests {
         high.set_priority(-1).unwrap();
 
         let mut buf = Vec::with_capacity(1000);
-        let meta = server.write_stream_frames(&mut buf, 40, true);
+        let meta = server.write_stream_frames(&mut buf, 40, true, Instant::now());
         assert_eq!(meta.len(), 1);
         assert_eq!(meta[0].id, id_high);
 
@@ -1464,7 +1607,7 @@ mod tests {
         assert_eq!(server.pending.len(), 2);
 
         // Send the remaining data. The initial mid priority one should go first now
-        let meta = server.write_stream_frames(&mut buf, 1000, true);
+        let meta = server.write_stream_frames(&mut buf, 1000, true, Instant::now());
         assert_eq!(meta.len(), 2);
         assert_eq!(meta[0].id, id_mid);
         assert_eq!(meta[1].id, id_high);
@@ -1525,7 +1668,7 @@ mod tests {
             // loop until all the streams are written
             loop {
                 let buf_len = buf.len();
-                let meta = server.write_stream_frames(&mut buf, buf_len + 40, fair);
+                let meta = server.write_stream_frames(&mut buf, buf_len + 40, fair, Instant::now());
                 if meta.is_empty() {
                     break;
                 }
@@ -1596,7 +1739,7 @@ mod tests {
 
         // Write the first chunk of stream_a
         let buf_len = buf.len();
-        let meta = server.write_stream_frames(&mut buf, buf_len + 40, false);
+        let meta = server.write_stream_frames(&mut buf, buf_len + 40, false, Instant::now());
         assert!(!meta.is_empty());
         metas.extend(meta);
 
@@ -1613,7 +1756,7 @@ mod tests {
         // loop until all the streams are written
         loop {
             let buf_len = buf.len();
-            let meta = server.write_stream_frames(&mut buf, buf_len + 40, false);
+            let meta = server.write_stream_frames(&mut buf, buf_len + 40, false, Instant::now());
             if meta.is_empty() {
                 break;
             }
@@ -2010,11 +2153,14 @@ mod tests {
         assert_eq!(stream.state.poll(), None);
 
         // Ack the data
-        stream.state.received_ack_of(frame::StreamMeta {
-            id: stream_id,
-            offsets: 0..larger_send_window,
-            fin: false,
-        });
+        stream.state.received_ack_of(
+            frame::StreamMeta {
+                id: stream_id,
+                offsets: 0..larger_send_window,
+                fin: false,
+            },
+            stream.pending,
+        );
 
         assert_eq!(
             stream.state.poll(),
@@ -2082,20 +2228,26 @@ mod tests {
         assert_eq!(stream.write(&data), Err(WriteError::Blocked));
 
         // Ack some data, assert that writes are still not accepted due to outstanding sends
-        stream.state.received_ack_of(frame::StreamMeta {
-            id: stream_id,
-            offsets: 0..smaller_send_window,
-            fin: false,
-        });
+        stream.state.received_ack_of(
+            frame::StreamMeta {
+                id: stream_id,
+                offsets: 0..smaller_send_window,
+                fin: false,
+            },
+            stream.pending,
+        );
 
         assert_eq!(stream.write(&data), Err(WriteError::Blocked));
 
         // Ack the rest of the data
-        stream.state.received_ack_of(frame::StreamMeta {
-            id: stream_id,
-            offsets: smaller_send_window..initial_send_window,
-            fin: false,
-        });
+        stream.state.received_ack_of(
+            frame::StreamMeta {
+                id: stream_id,
+                offsets: smaller_send_window..initial_send_window,
+                fin: false,
+            },
+            stream.pending,
+        );
 
         // This should generate a `Writable` event
         assert_eq!(
@@ -20,10 +20,16 @@ pub(crate) enum Timer {
     PushNewCid = 7,
     /// When to send an immediate ACK if there are unacked ack-eliciting packets of the peer
     MaxAckDelay = 8,
+    /// When to give up on the earliest outstanding [`Connection::probe_path`] challenge
+    ///
+    /// [`Connection::probe_path`]: super::Connection::probe_path
+    PathProbe = 9,
+    /// When to abandon the connection if the handshake has not completed
+    Handshake = 10,
 }
 
 impl Timer {
-    pub(crate) const VALUES: [Self; 9] = [
+    pub(crate) const VALUES: [Self; 11] = [
         Self::LossDetection,
         Self::Idle,
         Self::Close,
@@ -33,13 +39,15 @@ impl Timer {
         Self::Pacing,
         Self::PushNewCid,
         Self::MaxAckDelay,
+        Self::PathProbe,
+        Self::Handshake,
     ];
 }
 
 /// A table of data associated with each distinct kind of `Timer`
 #[derive(Debug, Copy, Clone, Default)]
 pub(crate) struct TimerTable {
-    data: [Option<Instant>; 10],
+    data: [Option<Instant>; 11],
 }
 
 impl TimerTable {
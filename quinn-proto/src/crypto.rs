@@ -13,8 +13,9 @@ use std::{any::Any, str, sync::Arc};
 use bytes::BytesMut;
 
 use crate::{
-    ConnectError, Side, TransportError, shared::ConnectionId,
-    transport_parameters::TransportParameters,
+    ConnectError, Side, TransportError,
+    shared::ConnectionId,
+    transport_parameters::{ExtraTransportParameters, TransportParameters},
 };
 
 /// Cryptography interface based on *ring*
@@ -37,6 +38,14 @@ pub trait Session: Send + Sync + 'static {
     /// Get the peer's identity, if available
     fn peer_identity(&self) -> Option<Box<dyn Any>>;
 
+    /// Get the application protocol selected through ALPN, if any
+    ///
+    /// Returns `None` if no protocol has been selected (yet), or if the session doesn't support
+    /// ALPN.
+    fn alpn_protocol(&self) -> Option<&[u8]> {
+        None
+    }
+
     /// Get the 0-RTT keys if available (clients only)
     ///
     /// On the client side, this method can be used to see if 0-RTT key material is available
@@ -67,6 +76,20 @@ pub trait Session: Send + Sync + 'static {
     /// These are only available after the first flight from the peer has been received.
     fn transport_parameters(&self) -> Result<Option<TransportParameters>, TransportError>;
 
+    /// The peer's QUIC transport parameters, along with any not otherwise understood by quinn
+    ///
+    /// Available under the same conditions as [`transport_parameters()`]. The default
+    /// implementation reports no extra parameters.
+    ///
+    /// [`transport_parameters()`]: Self::transport_parameters
+    fn transport_parameters_with_extra(
+        &self,
+    ) -> Result<Option<(TransportParameters, ExtraTransportParameters)>, TransportError> {
+        Ok(self
+            .transport_parameters()?
+            .map(|params| (params, ExtraTransportParameters::default())))
+    }
+
     /// Writes handshake bytes into the given buffer and optionally returns the negotiated keys
     ///
     /// When the handshake proceeds to the next phase, this method will return a new set of
@@ -84,7 +107,8 @@ pub trait Session: Send + Sync + 'static {
     /// separation.
     ///
     /// This function will fail, returning [ExportKeyingMaterialError],
-    /// if the requested output length is too large.
+    /// if the requested output length is too large or the handshake has
+    /// not yet completed.
     fn export_keying_material(
         &self,
         output: &mut [u8],
@@ -118,6 +142,20 @@ pub trait ClientConfig: Send + Sync {
         server_name: &str,
         params: &TransportParameters,
     ) -> Result<Box<dyn Session>, ConnectError>;
+
+    /// Start a client session with this configuration, additionally advertising `extra_params`
+    ///
+    /// The default implementation ignores `extra_params`.
+    fn start_session_with_extra(
+        self: Arc<Self>,
+        version: u32,
+        server_name: &str,
+        params: &TransportParameters,
+        extra_params: &ExtraTransportParameters,
+    ) -> Result<Box<dyn Session>, ConnectError> {
+        let _ = extra_params;
+        self.start_session(version, server_name, params)
+    }
 }
 
 /// Server-side configuration for the crypto protocol
@@ -139,6 +177,20 @@ pub trait ServerConfig: Send + Sync {
         version: u32,
         params: &TransportParameters,
     ) -> Box<dyn Session>;
+
+    /// Start a server session with this configuration, additionally advertising `extra_params`
+    ///
+    /// Never called if `initial_keys` rejected `version`. The default implementation ignores
+    /// `extra_params`.
+    fn start_session_with_extra(
+        self: Arc<Self>,
+        version: u32,
+        params: &TransportParameters,
+        extra_params: &ExtraTransportParameters,
+    ) -> Box<dyn Session> {
+        let _ = extra_params;
+        self.start_session(version, params)
+    }
 }
 
 /// Keys used to protect packet payloads
@@ -183,7 +235,8 @@ pub trait HmacKey: Send + Sync {
 
 /// Error returned by [Session::export_keying_material].
 ///
-/// This error occurs if the requested output length is too large.
+/// This error occurs if the requested output length is too large, or if keying
+/// material is requested before the handshake has completed.
 #[derive(Debug, PartialEq, Eq)]
 pub struct ExportKeyingMaterialError;
 
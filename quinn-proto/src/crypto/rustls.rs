@@ -1,8 +1,8 @@
-use std::{any::Any, io, str, sync::Arc};
+use std::{any::Any, io, mem, str, sync::Arc};
 
 #[cfg(all(feature = "aws-lc-rs", not(feature = "ring")))]
 use aws_lc_rs::aead;
-use bytes::BytesMut;
+use bytes::{Buf, BytesMut};
 #[cfg(feature = "ring")]
 use ring::aead;
 pub use rustls::Error;
@@ -22,7 +22,7 @@ use crate::{
     crypto::{
         self, CryptoError, ExportKeyingMaterialError, HeaderKey, KeyPair, Keys, UnsupportedVersion,
     },
-    transport_parameters::TransportParameters,
+    transport_parameters::{ExtraTransportParameters, TransportParameters},
 };
 
 impl From<Side> for rustls::Side {
@@ -38,12 +38,35 @@ impl From<Side> for rustls::Side {
 pub struct TlsSession {
     version: Version,
     got_handshake_data: bool,
+    /// Raw ClientHello received so far, retained on incoming connections until it's complete
+    client_hello: Vec<u8>,
+    /// Application protocols offered in the ClientHello of an incoming connection
+    offered_protocols: Vec<Vec<u8>>,
     next_secrets: Option<Secrets>,
     inner: Connection,
     suite: Suite,
 }
 
 impl TlsSession {
+    fn server(
+        config: Arc<rustls::ServerConfig>,
+        version: Version,
+        params: Vec<u8>,
+        suite: Suite,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            version,
+            got_handshake_data: false,
+            client_hello: Vec::new(),
+            offered_protocols: Vec::new(),
+            next_secrets: None,
+            inner: Connection::Server(rustls::quic::ServerConnection::new(
+                config, version, params,
+            )?),
+            suite,
+        })
+    }
+
     fn side(&self) -> Side {
         match self.inner {
             Connection::Client(_) => Side::Client,
@@ -67,6 +90,7 @@ impl crypto::Session for TlsSession {
                 Connection::Client(_) => None,
                 Connection::Server(session) => session.server_name().map(|x| x.into()),
             },
+            offered_protocols: self.offered_protocols.clone(),
             protocol_version: match &self.inner {
                 Connection::Client(session) => session.protocol_version(),
                 Connection::Server(session) => session.protocol_version(),
@@ -77,6 +101,10 @@ impl crypto::Session for TlsSession {
                 Connection::Server(session) => session.negotiated_cipher_suite(),
             }
             .map(|suite| -> Box<dyn Any> { Box::new(suite.suite()) }),
+            key_exchange_group: self
+                .inner
+                .negotiated_key_exchange_group()
+                .map(|group| -> Box<dyn Any> { Box::new(group.name()) }),
             #[cfg(feature = "__rustls-post-quantum-test")]
             negotiated_key_exchange_group: self
                 .inner
@@ -97,6 +125,10 @@ impl crypto::Session for TlsSession {
         })
     }
 
+    fn alpn_protocol(&self) -> Option<&[u8]> {
+        self.inner.alpn_protocol()
+    }
+
     fn early_crypto(&self) -> Option<(Box<dyn HeaderKey>, Box<dyn crypto::PacketKey>)> {
         let keys = self.inner.zero_rtt_keys()?;
         Some((Box::new(keys.header), Box::new(keys.packet)))
@@ -114,6 +146,9 @@ impl crypto::Session for TlsSession {
     }
 
     fn read_handshake(&mut self, buf: &[u8]) -> Result<bool, TransportError> {
+        if !self.got_handshake_data && self.side().is_server() {
+            buffer_client_hello(&mut self.client_hello, buf);
+        }
         self.inner.read_hs(buf).map_err(|e| {
             if let Some(alert) = self.inner.alert() {
                 TransportError {
@@ -136,6 +171,8 @@ impl crypto::Session for TlsSession {
             };
             if self.inner.alpn_protocol().is_some() || have_server_name || !self.is_handshaking() {
                 self.got_handshake_data = true;
+                let client_hello = mem::take(&mut self.client_hello);
+                self.offered_protocols = offered_protocols(&client_hello).unwrap_or_default();
                 return Ok(true);
             }
         }
@@ -143,12 +180,22 @@ impl crypto::Session for TlsSession {
     }
 
     fn transport_parameters(&self) -> Result<Option<TransportParameters>, TransportError> {
+        Ok(self
+            .transport_parameters_with_extra()?
+            .map(|(params, _)| params))
+    }
+
+    fn transport_parameters_with_extra(
+        &self,
+    ) -> Result<Option<(TransportParameters, ExtraTransportParameters)>, TransportError> {
         match self.inner.quic_transport_parameters() {
             None => Ok(None),
-            Some(buf) => match TransportParameters::read(self.side(), &mut io::Cursor::new(buf)) {
-                Ok(params) => Ok(Some(params)),
-                Err(e) => Err(e.into()),
-            },
+            Some(buf) => {
+                match TransportParameters::read_with_extra(self.side(), &mut io::Cursor::new(buf)) {
+                    Ok(params) => Ok(Some(params)),
+                    Err(e) => Err(e.into()),
+                }
+            }
         }
     }
 
@@ -265,6 +312,122 @@ impl HeaderKey for Box<dyn HeaderProtectionKey> {
     }
 }
 
+/// Append `buf` to `client_hello`, stopping at the end of the first handshake message
+///
+/// Returns the number of bytes of `buf` consumed. Whatever follows the ClientHello, such as the
+/// rest of the client's flight, isn't retained, nor is anything beyond the largest message rustls
+/// accepts.
+fn buffer_client_hello(client_hello: &mut Vec<u8>, buf: &[u8]) -> usize {
+    let mut consumed = 0;
+    loop {
+        let end = client_hello_len(client_hello)
+            .unwrap_or(4)
+            .min(4 + MAX_HANDSHAKE_SIZE);
+        let n = end
+            .saturating_sub(client_hello.len())
+            .min(buf.len() - consumed);
+        if n == 0 {
+            return consumed;
+        }
+        client_hello.extend_from_slice(&buf[consumed..consumed + n]);
+        consumed += n;
+    }
+}
+
+/// Largest handshake message body rustls accepts
+const MAX_HANDSHAKE_SIZE: usize = 0xffff;
+
+/// Total length of the handshake message starting `msg`, once its header has been received
+fn client_hello_len(msg: &[u8]) -> Option<usize> {
+    // Handshake messages are prefixed by a 1-byte type and 3-byte length
+    let mut len = msg.get(1..4)?;
+    Some(4 + len.get_uint(3) as usize)
+}
+
+/// Extract the protocol names from the ALPN extension of a ClientHello handshake message
+///
+/// rustls does not expose the offered protocols on QUIC server connections, so they're recovered
+/// from the raw message. Returns `None` if the message is malformed.
+fn offered_protocols(msg: &[u8]) -> Option<Vec<Vec<u8>>> {
+    const ALPN: u16 = 16;
+
+    let Some(mut data) = client_hello_extension(msg, ALPN)? else {
+        return Some(Vec::new());
+    };
+    let mut list = field(&mut data, 2)?;
+    let mut protocols = Vec::new();
+    while list.has_remaining() {
+        protocols.push(field(&mut list, 1)?.to_vec());
+    }
+    Some(protocols)
+}
+
+/// Extract the host name from the SNI extension of a ClientHello handshake message
+///
+/// Returns `None` if the message is malformed or no host name was requested.
+fn requested_server_name(msg: &[u8]) -> Option<String> {
+    const SERVER_NAME: u16 = 0;
+    const HOST_NAME: u8 = 0;
+
+    let mut data = client_hello_extension(msg, SERVER_NAME)??;
+    let mut list = field(&mut data, 2)?;
+    while list.has_remaining() {
+        let ty = list.get_u8();
+        let name = field(&mut list, 2)?;
+        if ty == HOST_NAME {
+            return str::from_utf8(name).ok().map(|x| x.to_ascii_lowercase());
+        }
+    }
+    None
+}
+
+/// Find the body of the extension of type `ty` in a ClientHello handshake message
+///
+/// Returns `None` if the message is malformed, or `Some(None)` if the extension is absent.
+fn client_hello_extension(mut msg: &[u8], ty: u16) -> Option<Option<&[u8]>> {
+    const CLIENT_HELLO: u8 = 1;
+
+    if msg.remaining() < 1 || msg.get_u8() != CLIENT_HELLO {
+        return None;
+    }
+    let mut body = field(&mut msg, 3)?;
+    // legacy_version and random
+    if body.remaining() < 2 + 32 {
+        return None;
+    }
+    body.advance(2 + 32);
+    field(&mut body, 1)?; // legacy_session_id
+    field(&mut body, 2)?; // cipher_suites
+    field(&mut body, 1)?; // legacy_compression_methods
+    let mut extensions = field(&mut body, 2)?;
+    while extensions.has_remaining() {
+        if extensions.remaining() < 2 {
+            return None;
+        }
+        let extension_ty = extensions.get_u16();
+        let data = field(&mut extensions, 2)?;
+        if extension_ty == ty {
+            return Some(Some(data));
+        }
+    }
+    Some(None)
+}
+
+/// Read a length-prefixed field, `len_bytes` wide
+fn field<'a>(buf: &mut &'a [u8], len_bytes: usize) -> Option<&'a [u8]> {
+    let mut data = *buf;
+    if data.remaining() < len_bytes {
+        return None;
+    }
+    let len = data.get_uint(len_bytes) as usize;
+    if data.remaining() < len {
+        return None;
+    }
+    let (value, rest) = data.split_at(len);
+    *buf = rest;
+    Some(value)
+}
+
 /// Authentication data for (rustls) TLS session
 #[non_exhaustive]
 pub struct HandshakeData {
@@ -276,10 +439,23 @@ pub struct HandshakeData {
     ///
     /// Always `None` for outgoing connections
     pub server_name: Option<String>,
+    /// The application protocols offered by the client, in order of preference
+    ///
+    /// Available as soon as the ClientHello has been processed, before 0-RTT data is read. Always
+    /// empty for outgoing connections.
+    pub offered_protocols: Vec<Vec<u8>>,
     /// The protocol version negotiated with the peer, if any
+    ///
+    /// Downcasts to [`rustls::ProtocolVersion`].
     pub protocol_version: Option<Box<dyn Any>>,
     /// The cipher suite negotiated with the peer, if any
+    ///
+    /// Downcasts to [`rustls::CipherSuite`].
     pub cipher_suite: Option<Box<dyn Any>>,
+    /// The key exchange group negotiated with the peer, if any
+    ///
+    /// Downcasts to [`rustls::NamedGroup`].
+    pub key_exchange_group: Option<Box<dyn Any>>,
     /// The key exchange group negotiated with the peer
     #[cfg(feature = "__rustls-post-quantum-test")]
     pub negotiated_key_exchange_group: NamedGroup,
@@ -299,7 +475,11 @@ pub struct HandshakeData {
 /// The object in the `resumption` field of the inner [`rustls::ClientConfig`] determines whether
 /// calling `into_0rtt` on outgoing connections returns `Ok` or `Err`. It typically allows
 /// `into_0rtt` to proceed if it recognizes the server name, and defaults to an in-memory cache of
-/// 256 server names.
+/// 256 server names. Session tickets can be shared between client configurations, and thus
+/// between endpoints, by constructing them with a common
+/// [`rustls::client::ClientSessionStore`] via [`rustls::client::Resumption::store()`]. rustls
+/// does not support serializing the stored sessions, so they cannot be persisted or shared across
+/// processes.
 ///
 /// [root_certs]: crate::config::ClientConfig::with_root_certificates()
 /// [platform]: crate::config::ClientConfig::try_with_platform_verifier()
@@ -374,11 +554,28 @@ impl crypto::ClientConfig for QuicClientConfig {
         version: u32,
         server_name: &str,
         params: &TransportParameters,
+    ) -> Result<Box<dyn crypto::Session>, ConnectError> {
+        self.start_session_with_extra(
+            version,
+            server_name,
+            params,
+            &ExtraTransportParameters::default(),
+        )
+    }
+
+    fn start_session_with_extra(
+        self: Arc<Self>,
+        version: u32,
+        server_name: &str,
+        params: &TransportParameters,
+        extra_params: &ExtraTransportParameters,
     ) -> Result<Box<dyn crypto::Session>, ConnectError> {
         let version = interpret_version(version)?;
         Ok(Box::new(TlsSession {
             version,
             got_handshake_data: false,
+            client_hello: Vec::new(),
+            offered_protocols: Vec::new(),
             next_secrets: None,
             inner: Connection::Client(
                 rustls::quic::ClientConnection::new(
@@ -387,7 +584,7 @@ impl crypto::ClientConfig for QuicClientConfig {
                     ServerName::try_from(server_name)
                         .map_err(|_| ConnectError::InvalidServerName(server_name.into()))?
                         .to_owned(),
-                    to_vec(params),
+                    to_vec(params, extra_params),
                 )
                 .unwrap(),
             ),
@@ -440,6 +637,144 @@ impl std::fmt::Display for NoInitialCipherSuite {
 
 impl std::error::Error for NoInitialCipherSuite {}
 
+/// Selects the TLS configuration for an incoming connection based on its ClientHello
+///
+/// Unlike rustls' [`ResolvesServerCert`](rustls::server::ResolvesServerCert), which only chooses
+/// the certificate presented, this chooses the entire [`rustls::ServerConfig`], so that client
+/// authentication requirements and trust anchors can also vary with the requested server name.
+/// Used with [`QuicServerConfig::with_resolver()`].
+pub trait ResolvesServerConfig: Send + Sync {
+    /// Select the configuration for a connection which requested `server_name`, if any
+    ///
+    /// Returning `None` aborts the handshake with an `unrecognized_name` alert.
+    fn resolve(&self, server_name: Option<&str>) -> Option<Arc<rustls::ServerConfig>>;
+}
+
+/// A server TLS session that defers choosing its configuration until the ClientHello is received
+struct ResolvingTlsSession {
+    resolver: Arc<dyn ResolvesServerConfig>,
+    version: Version,
+    /// Encoded local transport parameters
+    params: Vec<u8>,
+    suite: Suite,
+    /// Raw ClientHello received so far
+    client_hello: Vec<u8>,
+    /// The session using the resolved configuration, once the ClientHello is complete
+    session: Option<TlsSession>,
+}
+
+impl crypto::Session for ResolvingTlsSession {
+    fn initial_keys(&self, dst_cid: ConnectionId, side: Side) -> Keys {
+        initial_keys(self.version, dst_cid, side, &self.suite)
+    }
+
+    fn handshake_data(&self) -> Option<Box<dyn Any>> {
+        self.session.as_ref()?.handshake_data()
+    }
+
+    fn peer_identity(&self) -> Option<Box<dyn Any>> {
+        self.session.as_ref()?.peer_identity()
+    }
+
+    fn alpn_protocol(&self) -> Option<&[u8]> {
+        self.session.as_ref()?.alpn_protocol()
+    }
+
+    fn early_crypto(&self) -> Option<(Box<dyn HeaderKey>, Box<dyn crypto::PacketKey>)> {
+        self.session.as_ref()?.early_crypto()
+    }
+
+    fn early_data_accepted(&self) -> Option<bool> {
+        None
+    }
+
+    fn is_handshaking(&self) -> bool {
+        self.session.as_ref().is_none_or(|x| x.is_handshaking())
+    }
+
+    fn read_handshake(&mut self, buf: &[u8]) -> Result<bool, TransportError> {
+        if let Some(session) = &mut self.session {
+            return session.read_handshake(buf);
+        }
+
+        let consumed = buffer_client_hello(&mut self.client_hello, buf);
+        let Some(len) = client_hello_len(&self.client_hello) else {
+            return Ok(false);
+        };
+        if len - 4 > MAX_HANDSHAKE_SIZE {
+            return Err(TransportError::CRYPTO_BUFFER_EXCEEDED(
+                "ClientHello exceeds the maximum handshake message size",
+            ));
+        }
+        if self.client_hello.len() < len {
+            return Ok(false);
+        }
+
+        let server_name = requested_server_name(&self.client_hello);
+        let Some(config) = self.resolver.resolve(server_name.as_deref()) else {
+            return Err(TransportError {
+                code: TransportErrorCode::crypto(rustls::AlertDescription::UnrecognisedName.into()),
+                frame: None,
+                reason: "no configuration for the requested server name".into(),
+                crypto: None,
+            });
+        };
+        let session = TlsSession::server(
+            config,
+            self.version,
+            mem::take(&mut self.params),
+            self.suite,
+        )
+        .map_err(|e| TransportError::INTERNAL_ERROR(format!("invalid TLS configuration: {e}")))?;
+        let client_hello = mem::take(&mut self.client_hello);
+        let session = self.session.insert(session);
+        let ready = session.read_handshake(&client_hello)?;
+        let rest = &buf[consumed..];
+        Ok(ready | (!rest.is_empty() && session.read_handshake(rest)?))
+    }
+
+    fn transport_parameters(&self) -> Result<Option<TransportParameters>, TransportError> {
+        match &self.session {
+            Some(session) => session.transport_parameters(),
+            None => Ok(None),
+        }
+    }
+
+    fn transport_parameters_with_extra(
+        &self,
+    ) -> Result<Option<(TransportParameters, ExtraTransportParameters)>, TransportError> {
+        match &self.session {
+            Some(session) => session.transport_parameters_with_extra(),
+            None => Ok(None),
+        }
+    }
+
+    fn write_handshake(&mut self, buf: &mut Vec<u8>) -> Option<Keys> {
+        self.session.as_mut()?.write_handshake(buf)
+    }
+
+    fn next_1rtt_keys(&mut self) -> Option<KeyPair<Box<dyn crypto::PacketKey>>> {
+        self.session.as_mut()?.next_1rtt_keys()
+    }
+
+    fn is_valid_retry(&self, _orig_dst_cid: ConnectionId, _header: &[u8], _payload: &[u8]) -> bool {
+        // Only clients receive Retry packets
+        false
+    }
+
+    fn export_keying_material(
+        &self,
+        output: &mut [u8],
+        label: &[u8],
+        context: &[u8],
+    ) -> Result<(), ExportKeyingMaterialError> {
+        self.session
+            .as_ref()
+            .ok_or(ExportKeyingMaterialError)?
+            .export_keying_material(output, label, context)
+    }
+}
+
 /// A QUIC-compatible TLS server configuration
 ///
 /// Quinn implicitly constructs a `QuicServerConfig` with reasonable defaults within
@@ -453,10 +788,16 @@ impl std::error::Error for NoInitialCipherSuite {}
 ///
 /// [single]: crate::config::ServerConfig::with_single_cert()
 pub struct QuicServerConfig {
-    inner: Arc<rustls::ServerConfig>,
+    inner: ServerConfigSource,
     initial: Suite,
 }
 
+/// Where a [`QuicServerConfig`] gets the rustls configuration for each connection
+enum ServerConfigSource {
+    Fixed(Arc<rustls::ServerConfig>),
+    Resolver(Arc<dyn ResolvesServerConfig>),
+}
+
 impl QuicServerConfig {
     pub(crate) fn new(
         cert_chain: Vec<CertificateDer<'static>>,
@@ -467,7 +808,7 @@ impl QuicServerConfig {
             // We're confident that the *ring* default provider contains TLS13_AES_128_GCM_SHA256
             initial: initial_suite_from_provider(inner.crypto_provider())
                 .expect("no initial cipher suite found"),
-            inner: Arc::new(inner),
+            inner: ServerConfigSource::Fixed(Arc::new(inner)),
         })
     }
 
@@ -479,11 +820,29 @@ impl QuicServerConfig {
         initial: Suite,
     ) -> Result<Self, NoInitialCipherSuite> {
         match initial.suite.common.suite {
-            CipherSuite::TLS13_AES_128_GCM_SHA256 => Ok(Self { inner, initial }),
+            CipherSuite::TLS13_AES_128_GCM_SHA256 => Ok(Self {
+                inner: ServerConfigSource::Fixed(inner),
+                initial,
+            }),
             _ => Err(NoInitialCipherSuite { specific: true }),
         }
     }
 
+    /// Initialize a configuration that selects a [`rustls::ServerConfig`] for each connection
+    ///
+    /// `resolver` is consulted once the client's ClientHello has been received, allowing the
+    /// certificate presented and the client authentication requirements to depend on the server
+    /// name the client requested. Every config it returns must satisfy the requirements described
+    /// above.
+    pub fn with_resolver(resolver: Arc<dyn ResolvesServerConfig>) -> Self {
+        Self {
+            // We're confident that the *ring* default provider contains TLS13_AES_128_GCM_SHA256
+            initial: initial_suite_from_provider(&configured_provider())
+                .expect("no initial cipher suite found"),
+            inner: ServerConfigSource::Resolver(resolver),
+        }
+    }
+
     /// Initialize a sane QUIC-compatible TLS server configuration
     ///
     /// QUIC requires that TLS 1.3 be enabled, and that the maximum early data size is either 0 or
@@ -519,7 +878,7 @@ impl TryFrom<Arc<rustls::ServerConfig>> for QuicServerConfig {
         Ok(Self {
             initial: initial_suite_from_provider(inner.crypto_provider())
                 .ok_or(NoInitialCipherSuite { specific: false })?,
-            inner,
+            inner: ServerConfigSource::Fixed(inner),
         })
     }
 }
@@ -529,19 +888,37 @@ impl crypto::ServerConfig for QuicServerConfig {
         self: Arc<Self>,
         version: u32,
         params: &TransportParameters,
+    ) -> Box<dyn crypto::Session> {
+        self.start_session_with_extra(version, params, &ExtraTransportParameters::default())
+    }
+
+    fn start_session_with_extra(
+        self: Arc<Self>,
+        version: u32,
+        params: &TransportParameters,
+        extra_params: &ExtraTransportParameters,
     ) -> Box<dyn crypto::Session> {
         // Safe: `start_session()` is never called if `initial_keys()` rejected `version`
         let version = interpret_version(version).unwrap();
-        Box::new(TlsSession {
-            version,
-            got_handshake_data: false,
-            next_secrets: None,
-            inner: Connection::Server(
-                rustls::quic::ServerConnection::new(self.inner.clone(), version, to_vec(params))
-                    .unwrap(),
+        match self.inner {
+            ServerConfigSource::Fixed(ref inner) => Box::new(
+                TlsSession::server(
+                    inner.clone(),
+                    version,
+                    to_vec(params, extra_params),
+                    self.initial,
+                )
+                .unwrap(),
             ),
-            suite: self.initial,
-        })
+            ServerConfigSource::Resolver(ref resolver) => Box::new(ResolvingTlsSession {
+                resolver: resolver.clone(),
+                version,
+                params: to_vec(params, extra_params),
+                suite: self.initial,
+                client_hello: Vec::new(),
+                session: None,
+            }),
+        }
     }
 
     fn initial_keys(
@@ -600,9 +977,10 @@ pub(crate) fn configured_provider() -> Arc<rustls::crypto::CryptoProvider> {
     Arc::new(provider)
 }
 
-fn to_vec(params: &TransportParameters) -> Vec<u8> {
+fn to_vec(params: &TransportParameters, extra_params: &ExtraTransportParameters) -> Vec<u8> {
     let mut bytes = Vec::new();
     params.write(&mut bytes);
+    extra_params.write(&mut bytes);
     bytes
 }
 
@@ -94,6 +94,11 @@ impl Endpoint {
         self.server_config = server_config;
     }
 
+    /// The server configuration used for incoming connections, if any
+    pub fn server_config(&self) -> Option<&Arc<ServerConfig>> {
+        self.server_config.as_ref()
+    }
+
     /// Process `EndpointEvent`s emitted from related `Connection`s
     ///
     /// In turn, processing this event may return a `ConnectionEvent` for the same `Connection`.
@@ -159,6 +164,7 @@ impl Endpoint {
             Ok((first_decode, remaining)) => DatagramConnectionEvent {
                 now,
                 remote,
+                local_ip,
                 ecn,
                 first_decode,
                 remaining,
@@ -352,9 +358,12 @@ impl Endpoint {
             None,
             &mut self.rng,
         );
-        let tls = config
-            .crypto
-            .start_session(config.version, server_name, &params)?;
+        let tls = config.crypto.start_session_with_extra(
+            config.version,
+            server_name,
+            &params,
+            &config.transport.extra_transport_parameters,
+        )?;
 
         let conn = self.add_connection(
             ch,
@@ -372,6 +381,7 @@ impl Endpoint {
             SideArgs::Client {
                 token_store: config.token_store,
                 server_name: server_name.into(),
+                retransmit_rejected_0rtt: config.retransmit_rejected_0rtt,
             },
         );
         Ok((ch, conn))
@@ -401,8 +411,15 @@ impl Endpoint {
 
     /// Generate a connection ID for `ch`
     fn new_cid(&mut self, ch: ConnectionHandle) -> ConnectionId {
+        // The connection isn't registered yet while its first CIDs are being generated
+        let active_cids = self
+            .connections
+            .get(ch.0)
+            .map_or(0, |meta| meta.loc_cids.len());
         loop {
-            let cid = self.local_cid_generator.generate_cid();
+            let cid = self
+                .local_cid_generator
+                .generate_cid_for_connection(active_cids);
             if cid.is_empty() {
                 // Zero-length CID; nothing to track
                 debug_assert_eq!(self.local_cid_generator.cid_len(), 0);
@@ -447,6 +464,29 @@ impl Endpoint {
             return None;
         }
 
+        let rate_limited = server_config
+            .connection_rate_limiter
+            .as_ref()
+            .is_some_and(|limiter| !limiter.should_accept(addresses.remote, event.now));
+        if rate_limited {
+            debug!(
+                "rate limiting initial for connection {} from {}",
+                dst_cid, addresses.remote
+            );
+            if !server_config.refuse_rate_limited {
+                return None;
+            }
+        }
+
+        let at_capacity = self.connections.len() + self.incoming_buffers.len()
+            >= server_config.max_concurrent_connections;
+        if at_capacity {
+            debug!(
+                "refusing initial for connection {} due to connection limit",
+                dst_cid
+            );
+        }
+
         let crypto = match server_config.crypto.initial_keys(header.version, dst_cid) {
             Ok(keys) => keys,
             Err(UnsupportedVersion) => {
@@ -460,6 +500,17 @@ impl Endpoint {
             }
         };
 
+        if rate_limited || at_capacity {
+            return Some(DatagramEvent::Response(self.initial_close(
+                header.version,
+                addresses,
+                &crypto,
+                header.src_cid,
+                TransportError::CONNECTION_REFUSED(""),
+                buf,
+            )));
+        }
+
         if let Err(reason) = self.early_validate_first_packet(header) {
             return Some(DatagramEvent::Response(self.initial_close(
                 header.version,
@@ -625,7 +676,11 @@ impl Endpoint {
             });
         }
 
-        let tls = server_config.crypto.clone().start_session(version, &params);
+        let tls = server_config.crypto.clone().start_session_with_extra(
+            version,
+            &params,
+            &server_config.transport.extra_transport_parameters,
+        );
         let transport_config = server_config.transport.clone();
         let mut conn = self.add_connection(
             ch,
@@ -742,12 +797,20 @@ impl Endpoint {
         // retried by the application layer.
         let loc_cid = self.local_cid_generator.generate_cid();
 
-        let payload = TokenPayload::Retry {
-            address: incoming.addresses.remote,
-            orig_dst_cid: incoming.packet.header.dst_cid,
-            issued: server_config.time_source.now(),
+        let address = incoming.addresses.remote;
+        let orig_dst_cid = incoming.packet.header.dst_cid;
+        let issued = server_config.time_source.now();
+        let token = match &server_config.retry_token_generator {
+            Some(generator) => generator.generate_retry_token(address, orig_dst_cid, issued),
+            None => {
+                let payload = TokenPayload::Retry {
+                    address,
+                    orig_dst_cid,
+                    issued,
+                };
+                Token::new(payload, &mut self.rng).encode(&*server_config.token_key)
+            }
         };
-        let token = Token::new(payload, &mut self.rng).encode(&*server_config.token_key);
 
         let header = Header::Retry {
             src_cid: loc_cid,
@@ -1,4 +1,5 @@
 use std::{
+    borrow::Cow,
     fmt::{self, Write},
     mem,
     ops::{Range, RangeInclusive},
@@ -19,10 +20,26 @@ use crate::{
 use arbitrary::Arbitrary;
 
 /// A QUIC frame type
+///
+/// Reported by [`ConnectionClose::frame_type`] and [`TransportError::frame`] to identify the frame
+/// that triggered a transport error. Formats as the frame's name from the specification, e.g.
+/// `STREAM` or `MAX_DATA`.
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub struct FrameType(u64);
 
 impl FrameType {
+    /// Whether this is one of the `STREAM` frame types
+    ///
+    /// `STREAM` frames encode their flags in the frame type, so no single constant covers them.
+    pub fn is_stream(self) -> bool {
+        self.stream().is_some()
+    }
+
+    /// Whether this is one of the `DATAGRAM` frame types
+    pub fn is_datagram(self) -> bool {
+        self.datagram().is_some()
+    }
+
     fn stream(self) -> Option<StreamInfo> {
         if STREAM_TYS.contains(&self.0) {
             Some(StreamInfo(self.0 as u8))
@@ -39,6 +56,12 @@ impl FrameType {
     }
 }
 
+impl From<FrameType> for u64 {
+    fn from(x: FrameType) -> Self {
+        x.0
+    }
+}
+
 impl coding::Codec for FrameType {
     fn decode<B: Buf>(buf: &mut B) -> coding::Result<Self> {
         Ok(Self(buf.get_var()?))
@@ -56,7 +79,7 @@ pub(crate) trait FrameStruct {
 macro_rules! frame_types {
     {$($name:ident = $val:expr,)*} => {
         impl FrameType {
-            $(pub(crate) const $name: FrameType = FrameType($val);)*
+            $(#[doc = concat!("`", stringify!($name), "` frame")] pub const $name: Self = FrameType($val);)*
         }
 
         impl fmt::Debug for FrameType {
@@ -262,6 +285,9 @@ pub struct ConnectionClose {
 impl fmt::Display for ConnectionClose {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.error_code.fmt(f)?;
+        if let Some(frame) = self.frame_type {
+            write!(f, " in {frame}")?;
+        }
         if !self.reason.as_ref().is_empty() {
             f.write_str(": ")?;
             f.write_str(&String::from_utf8_lossy(&self.reason))?;
@@ -291,7 +317,8 @@ impl ConnectionClose {
         let ty = self.frame_type.map_or(0, |x| x.0);
         out.write_var(ty); // <= 8 bytes
         let max_len = max_len
-            - 3
+            - 1
+            - VarInt::from_u64(self.error_code.into()).unwrap().size()
             - VarInt::from_u64(ty).unwrap().size()
             - VarInt::from_u64(self.reason.len() as u64).unwrap().size();
         let actual_len = self.reason.len().min(max_len);
@@ -312,7 +339,7 @@ pub struct ApplicationClose {
 impl fmt::Display for ApplicationClose {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if !self.reason.as_ref().is_empty() {
-            f.write_str(&String::from_utf8_lossy(&self.reason))?;
+            f.write_str(&self.reason_str())?;
             f.write_str(" (code ")?;
             self.error_code.fmt(f)?;
             f.write_str(")")?;
@@ -328,10 +355,18 @@ impl FrameStruct for ApplicationClose {
 }
 
 impl ApplicationClose {
+    /// The reason as text, with any invalid UTF-8 replaced by U+FFFD
+    pub fn reason_str(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.reason)
+    }
+
     pub(crate) fn encode<W: BufMut>(&self, out: &mut W, max_len: usize) {
         out.write(FrameType::APPLICATION_CLOSE); // 1 byte
         out.write(self.error_code); // <= 8 bytes
-        let max_len = max_len - 3 - VarInt::from_u64(self.reason.len() as u64).unwrap().size();
+        let max_len = max_len
+            - 1
+            - self.error_code.size()
+            - VarInt::from_u64(self.reason.len() as u64).unwrap().size();
         let actual_len = self.reason.len().min(max_len);
         out.write_var(actual_len as u64); // <= 8 bytes
         out.put_slice(&self.reason[0..actual_len]); // whatever's left
@@ -1005,4 +1040,27 @@ mod test {
         assert_eq!(frames.len(), 1);
         assert_matches!(&frames[0], Frame::ImmediateAck);
     }
+
+    #[test]
+    fn connection_close_frame_type() {
+        let mut buf = Vec::new();
+        let original = ConnectionClose {
+            error_code: TransportErrorCode::PROTOCOL_VIOLATION,
+            frame_type: Some(FrameType(0x0a)),
+            reason: Bytes::from_static(b"bad offset"),
+        };
+        original.encode(&mut buf, usize::MAX);
+        let frames = frames(buf);
+        assert_eq!(frames.len(), 1);
+        match &frames[0] {
+            Frame::Close(Close::Connection(decoded)) => {
+                assert_eq!(decoded, &original);
+                let frame_type = decoded.frame_type.unwrap();
+                assert!(frame_type.is_stream());
+                assert_eq!(u64::from(frame_type), 0x0a);
+                assert!(decoded.to_string().ends_with(" in STREAM: bad offset"));
+            }
+            x => panic!("incorrect frame {x:?}"),
+        }
+    }
 }
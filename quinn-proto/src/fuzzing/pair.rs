@@ -0,0 +1,230 @@
+use std::{
+    collections::VecDeque,
+    net::{Ipv6Addr, SocketAddr},
+    sync::Arc,
+};
+
+use bytes::{Bytes, BytesMut};
+use rustc_hash::FxHashMap;
+
+use crate::{
+    ClientConfig, Connection, ConnectionHandle, DatagramEvent, Endpoint, EndpointConfig, Instant,
+    ServerConfig, Transmit,
+};
+
+/// Maximum number of datagrams requested from each call to `poll_transmit`
+const MAX_DATAGRAMS: usize = 10;
+
+/// A client and a server endpoint connected through an in-memory network
+///
+/// Nothing is delivered automatically: datagrams emitted by either side accumulate in that side's
+/// [`Peer::outbound`] queue, where a harness can inspect, drop, reorder or mutate them before
+/// handing them to the other side with [`Pair::deliver_to_server`] or
+/// [`Pair::deliver_to_client`]. Arbitrary datagrams can be injected the same way.
+///
+/// For reproducible runs, set [`EndpointConfig::rng_seed`] and advance [`Pair::time`] only by
+/// amounts derived from the fuzzer's input.
+pub struct Pair {
+    /// The endpoint initiating the connection
+    pub client: Peer,
+    /// The endpoint accepting the connection
+    pub server: Peer,
+    /// Current simulated time, passed to both endpoints
+    pub time: Instant,
+    /// Handle of the client's connection
+    pub client_ch: ConnectionHandle,
+}
+
+impl Pair {
+    /// Create both endpoints and begin connecting the client to the server
+    ///
+    /// The client's first flight is left in the client's outbound queue.
+    pub fn new(
+        endpoint_config: Arc<EndpointConfig>,
+        server_config: Arc<ServerConfig>,
+        client_config: ClientConfig,
+        server_name: &str,
+    ) -> Self {
+        let mut client = Peer::new(endpoint_config.clone(), None, 1);
+        let server = Peer::new(endpoint_config, Some(server_config), 2);
+        let time = Instant::now();
+        let (client_ch, conn) = client
+            .endpoint
+            .connect(time, client_config, server.addr, server_name)
+            .expect("failed to begin connecting");
+        client.connections.insert(client_ch, conn);
+        let mut pair = Self {
+            client,
+            server,
+            time,
+            client_ch,
+        };
+        pair.client.drive(pair.time);
+        pair
+    }
+
+    /// Hand `datagram` to the server, as if sent by the client, and collect the server's response
+    pub fn deliver_to_server(&mut self, datagram: &[u8]) {
+        self.server
+            .handle_datagram(self.time, self.client.addr, datagram);
+        self.server.drive(self.time);
+    }
+
+    /// Hand `datagram` to the client, as if sent by the server, and collect the client's response
+    pub fn deliver_to_client(&mut self, datagram: &[u8]) {
+        self.client
+            .handle_datagram(self.time, self.server.addr, datagram);
+        self.client.drive(self.time);
+    }
+
+    /// Deliver every queued datagram in both directions, without advancing time
+    ///
+    /// Returns whether any datagrams were delivered.
+    pub fn step(&mut self) -> bool {
+        let mut delivered = false;
+        while let Some(datagram) = self.client.outbound.pop_front() {
+            self.deliver_to_server(&datagram);
+            delivered = true;
+        }
+        while let Some(datagram) = self.server.outbound.pop_front() {
+            self.deliver_to_client(&datagram);
+            delivered = true;
+        }
+        delivered
+    }
+
+    /// Advance time to the earliest pending timer of either side and fire it
+    ///
+    /// Returns `false` if no timers are pending.
+    pub fn advance_to_next_timeout(&mut self) -> bool {
+        let next = match (self.client.next_timeout(), self.server.next_timeout()) {
+            (Some(a), Some(b)) => a.min(b),
+            (Some(t), None) | (None, Some(t)) => t,
+            (None, None) => return false,
+        };
+        self.time = self.time.max(next);
+        self.client.drive(self.time);
+        self.server.drive(self.time);
+        true
+    }
+
+    /// The client's connection, unless it has been drained
+    pub fn client_conn(&mut self) -> Option<&mut Connection> {
+        self.client.connections.get_mut(&self.client_ch)
+    }
+}
+
+/// One endpoint of a [`Pair`], along with its connections
+pub struct Peer {
+    /// The underlying endpoint
+    pub endpoint: Endpoint,
+    /// The address this endpoint appears to send from
+    pub addr: SocketAddr,
+    /// Connections which haven't yet been drained
+    pub connections: FxHashMap<ConnectionHandle, Connection>,
+    /// Datagrams emitted by this endpoint that haven't been delivered yet
+    ///
+    /// Segmented transmits are split, so each entry is exactly one UDP datagram.
+    pub outbound: VecDeque<Bytes>,
+}
+
+impl Peer {
+    fn new(
+        endpoint_config: Arc<EndpointConfig>,
+        server_config: Option<Arc<ServerConfig>>,
+        port: u16,
+    ) -> Self {
+        Self {
+            endpoint: Endpoint::new(endpoint_config, server_config, true),
+            addr: SocketAddr::new(Ipv6Addr::LOCALHOST.into(), port),
+            connections: FxHashMap::default(),
+            outbound: VecDeque::new(),
+        }
+    }
+
+    /// Process a single UDP datagram received from `remote`, accepting any new connection
+    pub fn handle_datagram(&mut self, now: Instant, remote: SocketAddr, datagram: &[u8]) {
+        let mut buf = Vec::new();
+        let event =
+            self.endpoint
+                .handle(now, remote, None, None, BytesMut::from(datagram), &mut buf);
+        match event {
+            Some(DatagramEvent::NewConnection(incoming)) => {
+                buf.clear();
+                match self.endpoint.accept(incoming, now, &mut buf, None) {
+                    Ok((ch, conn)) => {
+                        self.connections.insert(ch, conn);
+                    }
+                    Err(error) => {
+                        if let Some(transmit) = error.response {
+                            self.push_transmit(&transmit, &buf);
+                        }
+                    }
+                }
+            }
+            Some(DatagramEvent::ConnectionEvent(ch, event)) => {
+                if let Some(conn) = self.connections.get_mut(&ch) {
+                    conn.handle_event(event);
+                }
+            }
+            Some(DatagramEvent::Response(transmit)) => self.push_transmit(&transmit, &buf),
+            None => {}
+        }
+    }
+
+    /// Fire timers due at `now` and queue every datagram the connections want to send
+    pub fn drive(&mut self, now: Instant) {
+        let mut buf = Vec::new();
+        loop {
+            let mut endpoint_events = Vec::new();
+            for (&ch, conn) in self.connections.iter_mut() {
+                if conn.poll_timeout().is_some_and(|t| t <= now) {
+                    conn.handle_timeout(now);
+                }
+                while let Some(event) = conn.poll_endpoint_events() {
+                    endpoint_events.push((ch, event));
+                }
+                while let Some(transmit) = conn.poll_transmit(now, MAX_DATAGRAMS, &mut buf) {
+                    split_transmit(&transmit, &buf, &mut self.outbound);
+                    buf.clear();
+                }
+            }
+
+            if endpoint_events.is_empty() {
+                break;
+            }
+
+            for (ch, event) in endpoint_events {
+                let drained = event.is_drained();
+                if let Some(event) = self.endpoint.handle_event(ch, event) {
+                    if let Some(conn) = self.connections.get_mut(&ch) {
+                        conn.handle_event(event);
+                    }
+                }
+                if drained {
+                    self.connections.remove(&ch);
+                }
+            }
+        }
+    }
+
+    /// Earliest time at which any connection needs [`drive`](Self::drive) to be called
+    pub fn next_timeout(&self) -> Option<Instant> {
+        self.connections
+            .values()
+            .filter_map(|conn| conn.poll_timeout())
+            .min()
+    }
+
+    fn push_transmit(&mut self, transmit: &Transmit, buf: &[u8]) {
+        split_transmit(transmit, buf, &mut self.outbound);
+    }
+}
+
+fn split_transmit(transmit: &Transmit, buf: &[u8], out: &mut VecDeque<Bytes>) {
+    let contents = &buf[..transmit.size];
+    match transmit.segment_size {
+        Some(segment_size) => out.extend(contents.chunks(segment_size).map(Bytes::copy_from_slice)),
+        None => out.push_back(Bytes::copy_from_slice(contents)),
+    }
+}
@@ -44,11 +44,14 @@ pub use bloom_token_log::BloomTokenLog;
 
 mod connection;
 pub use crate::connection::{
-    Chunk, Chunks, ClosedStream, Connection, ConnectionError, ConnectionStats, Datagrams, Event,
-    FinishError, FrameStats, PathStats, ReadError, ReadableError, RecvStream, RttEstimator,
-    SendDatagramError, SendStream, ShouldTransmit, StreamEvent, Streams, UdpStats, WriteError,
-    Written,
+    AddressChange, Chunk, Chunks, ClosedStream, Connection, ConnectionError, ConnectionStats,
+    DatagramId, Datagrams, Event, FinishError, FlowControlStats, FrameStats, PathEvent,
+    PathProbeId, PathStats, PathStatsEntry, PathValidationState, PingId, ReadError, ReadableError,
+    RecvStream, RecvStreamState, RecvStreamStats, ResetAtError, RttEstimator, SendDatagramError,
+    SendStream, SendStreamState, SendStreamStats, ShouldTransmit, StreamEvent, StreamInfo, Streams,
+    UdpStats, WriteError, Written,
 };
+pub use connection::pacing;
 #[cfg(feature = "qlog")]
 pub use connection::qlog::QlogStream;
 
@@ -59,8 +62,9 @@ mod config;
 #[cfg(feature = "qlog")]
 pub use config::QlogConfig;
 pub use config::{
-    AckFrequencyConfig, ClientConfig, ConfigError, EndpointConfig, IdleTimeout, MtuDiscoveryConfig,
-    ServerConfig, StdSystemTime, TimeSource, TransportConfig, ValidationTokenConfig,
+    AckFrequencyConfig, ClientConfig, ConfigError, ConnectionRateLimiter, EcnPolicy,
+    EndpointConfig, IdleTimeout, KeepAlivePolicy, MtuDiscoveryConfig, ServerConfig, StdSystemTime,
+    TimeSource, TransportConfig, ValidationTokenConfig,
 };
 
 pub mod crypto;
@@ -91,15 +95,22 @@ pub mod congestion;
 mod cid_generator;
 pub use crate::cid_generator::{
     ConnectionIdGenerator, HashedConnectionIdGenerator, InvalidCid, RandomConnectionIdGenerator,
+    ShardedConnectionIdGenerator,
 };
 
 mod token;
 use token::ResetToken;
-pub use token::{NoneTokenLog, NoneTokenStore, TokenLog, TokenReuseError, TokenStore};
+pub use token::{
+    NoneTokenLog, NoneTokenStore, TokenGenerator, TokenLog, TokenReuseError, TokenStore,
+    TokenValidation, TokenValidator,
+};
 
 mod token_memory_cache;
 pub use token_memory_cache::TokenMemoryCache;
 
+mod rtt_cache;
+pub use rtt_cache::{RttCache, RttMemoryCache};
+
 #[cfg(feature = "arbitrary")]
 use arbitrary::Arbitrary;
 
@@ -111,6 +122,9 @@ pub(crate) use web_time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 #[cfg(fuzzing)]
 pub mod fuzzing {
+    mod pair;
+    pub use pair::{Pair, Peer};
+
     pub use crate::connection::{Retransmits, State as ConnectionState, StreamsState};
     pub use crate::frame::ResetStream;
     pub use crate::packet::PartialDecode;
@@ -334,3 +348,5 @@ const MAX_UDP_PAYLOAD: u16 = 65527;
 const TIMER_GRANULARITY: Duration = Duration::from_millis(1);
 /// Maximum number of streams that can be uniquely identified by a stream ID
 const MAX_STREAM_COUNT: u64 = 1 << 60;
+/// Ensures we can always fit all our ACKs in a single minimum-MTU packet with room to spare
+const MAX_ACK_BLOCKS: usize = 64;
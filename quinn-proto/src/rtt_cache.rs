@@ -0,0 +1,134 @@
+//! Remembering round-trip times to peers and using them to seed subsequent connections
+
+use std::{collections::HashMap, net::SocketAddr, sync::Mutex};
+
+use lru_slab::LruSlab;
+use tracing::trace;
+
+use crate::Duration;
+
+/// Responsible for storing the round-trip times observed to peers and retrieving them to seed the
+/// RTT estimate of subsequent connections to the same peers
+///
+/// Installed with [`EndpointConfig::rtt_cache()`](crate::EndpointConfig::rtt_cache).
+pub trait RttCache: Send + Sync {
+    /// Look up the RTT last observed to `remote`
+    ///
+    /// Called when a connection to or from `remote` is created. If this returns `Some`, it's used
+    /// in place of [`TransportConfig::initial_rtt`](crate::TransportConfig::initial_rtt).
+    fn get(&self, remote: SocketAddr) -> Option<Duration>;
+
+    /// Store the RTT observed to `remote`
+    ///
+    /// Called when a connection closes, if it measured the RTT at least once.
+    fn put(&self, remote: SocketAddr, rtt: Duration);
+}
+
+/// `RttCache` implementation that stores the RTT of up to a limited number of peers, in-memory,
+/// evicting the least recently used
+#[derive(Debug)]
+pub struct RttMemoryCache(Mutex<State>);
+
+impl RttMemoryCache {
+    /// Construct empty
+    pub fn new(max_peers: u32) -> Self {
+        Self(Mutex::new(State {
+            max_peers,
+            lookup: HashMap::new(),
+            lru: LruSlab::default(),
+        }))
+    }
+}
+
+impl RttCache for RttMemoryCache {
+    fn get(&self, remote: SocketAddr) -> Option<Duration> {
+        let mut state = self.0.lock().unwrap();
+        let slab_key = *state.lookup.get(&remote)?;
+        let rtt = state.lru.get_mut(slab_key).rtt;
+        trace!(%remote, ?rtt, "found cached RTT");
+        Some(rtt)
+    }
+
+    fn put(&self, remote: SocketAddr, rtt: Duration) {
+        trace!(%remote, ?rtt, "caching RTT");
+        self.0.lock().unwrap().put(remote, rtt)
+    }
+}
+
+/// Defaults to a maximum of 256 peers
+impl Default for RttMemoryCache {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+/// Lockable inner state of `RttMemoryCache`
+#[derive(Debug)]
+struct State {
+    max_peers: u32,
+    // map from peer address to index in lru
+    lookup: HashMap<SocketAddr, u32>,
+    lru: LruSlab<CacheEntry>,
+}
+
+impl State {
+    fn put(&mut self, remote: SocketAddr, rtt: Duration) {
+        if let Some(&slab_key) = self.lookup.get(&remote) {
+            self.lru.get_mut(slab_key).rtt = rtt;
+            return;
+        }
+        if self.max_peers == 0 {
+            return;
+        }
+        if self.lru.len() >= self.max_peers {
+            // unwrap safety: max_peers is > 0, so there's at least one entry, so lru() is some
+            let evicted = self.lru.remove(self.lru.lru().unwrap());
+            self.lookup.remove(&evicted.remote);
+        }
+        let slab_key = self.lru.insert(CacheEntry { remote, rtt });
+        self.lookup.insert(remote, slab_key);
+    }
+}
+
+/// Cache entry within `RttMemoryCache`'s LRU slab
+#[derive(Debug)]
+struct CacheEntry {
+    remote: SocketAddr,
+    rtt: Duration,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+
+    use super::*;
+
+    fn addr(port: u16) -> SocketAddr {
+        (Ipv4Addr::LOCALHOST, port).into()
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let cache = RttMemoryCache::new(2);
+        cache.put(addr(1), Duration::from_millis(10));
+        cache.put(addr(2), Duration::from_millis(20));
+        // Touch the first peer so the second is evicted instead
+        assert_eq!(cache.get(addr(1)), Some(Duration::from_millis(10)));
+        cache.put(addr(3), Duration::from_millis(30));
+        assert_eq!(cache.get(addr(1)), Some(Duration::from_millis(10)));
+        assert_eq!(cache.get(addr(2)), None);
+        assert_eq!(cache.get(addr(3)), Some(Duration::from_millis(30)));
+
+        // Updating doesn't evict
+        cache.put(addr(3), Duration::from_millis(40));
+        assert_eq!(cache.get(addr(1)), Some(Duration::from_millis(10)));
+        assert_eq!(cache.get(addr(3)), Some(Duration::from_millis(40)));
+    }
+
+    #[test]
+    fn zero_capacity() {
+        let cache = RttMemoryCache::new(0);
+        cache.put(addr(1), Duration::from_millis(10));
+        assert_eq!(cache.get(addr(1)), None);
+    }
+}
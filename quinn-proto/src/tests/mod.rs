@@ -2,7 +2,10 @@ use std::{
     convert::TryInto,
     mem,
     net::{Ipv4Addr, Ipv6Addr, SocketAddr},
-    sync::{Arc, Mutex},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
 };
 
 use assert_matches::assert_matches;
@@ -28,7 +31,8 @@ use super::*;
 use crate::{
     Duration, Instant,
     cid_generator::{ConnectionIdGenerator, RandomConnectionIdGenerator},
-    crypto::rustls::QuicServerConfig,
+    coding::BufMutExt,
+    crypto::rustls::{QuicServerConfig, ResolvesServerConfig},
     frame::FrameStruct,
     transport_parameters::TransportParameters,
 };
@@ -72,6 +76,39 @@ fn version_negotiate_server() {
     }));
 }
 
+#[test]
+fn version_negotiate_configured_versions() {
+    let _guard = subscribe();
+    let client_addr = "[::2]:7890".parse().unwrap();
+    let mut endpoint_config = EndpointConfig::default();
+    endpoint_config.supported_versions(vec![0xff00_001d, 0x0000_0001]);
+    let mut server = Endpoint::new(
+        Arc::new(endpoint_config),
+        Some(Arc::new(server_config())),
+        true,
+    );
+    let mut buf = Vec::with_capacity(server.config().get_max_udp_payload_size() as usize);
+    let event = server.handle(
+        Instant::now(),
+        client_addr,
+        None,
+        None,
+        // Long-header packet with reserved version number
+        hex!("80 0a1a2a3a 04 00000000 04 00000000 00")[..].into(),
+        &mut buf,
+    );
+    let Some(DatagramEvent::Response(Transmit { .. })) = event else {
+        panic!("expected a response");
+    };
+
+    // A greased version precedes exactly the configured versions, in order
+    let versions = buf[15..]
+        .chunks(4)
+        .map(|x| u32::from_be_bytes(x.try_into().unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(versions, [0x0a1a_2a4a, 0xff00_001d, 0x0000_0001]);
+}
+
 #[test]
 fn version_negotiate_client() {
     let _guard = subscribe();
@@ -145,6 +182,28 @@ fn lifecycle() {
     assert_eq!(pair.server.known_cids(), 0);
 }
 
+#[test]
+fn long_close_reason() {
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+    let (client_ch, server_ch) = pair.connect();
+
+    let reason = "goodbye ".repeat(100);
+    let now = pair.time;
+    pair.client_conn_mut(client_ch)
+        .close(now, VarInt(42), reason.clone().into());
+    pair.drive();
+    match pair.server_conn_mut(server_ch).poll() {
+        Some(Event::ConnectionLost {
+            reason: ConnectionError::ApplicationClosed(close),
+        }) => {
+            assert_eq!(close.error_code, VarInt(42));
+            assert_eq!(close.reason_str(), reason);
+        }
+        x => panic!("unexpected event {x:?}"),
+    }
+}
+
 #[test]
 fn draft_version_compat() {
     let _guard = subscribe();
@@ -310,6 +369,21 @@ fn export_keying_material() {
     assert_eq!(&client_buf[..], &server_buf[..]);
 }
 
+#[test]
+fn export_keying_material_before_handshake() {
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+    let client_ch = pair.begin_connect(client_config());
+
+    let mut buf = [0u8; 64];
+    assert_eq!(
+        pair.client_conn_mut(client_ch)
+            .crypto_session()
+            .export_keying_material(&mut buf, b"test_label", b"test_context"),
+        Err(crypto::ExportKeyingMaterialError)
+    );
+}
+
 #[test]
 fn finish_stream_simple() {
     let _guard = subscribe();
@@ -350,6 +424,235 @@ fn finish_stream_simple() {
     let _ = chunks.finalize();
 }
 
+#[test]
+fn peer_transport_params() {
+    let _guard = subscribe();
+    let mut server_config = server_config();
+    Arc::get_mut(&mut server_config.transport)
+        .unwrap()
+        .max_concurrent_bidi_streams(7u32.into())
+        .datagram_receive_buffer_size(None);
+    server_config.migration(false);
+    let mut pair = Pair::new(Default::default(), server_config);
+
+    let client_ch = pair.begin_connect(client_config());
+    assert_eq!(
+        pair.client_conn_mut(client_ch).peer_transport_params(),
+        None
+    );
+    pair.drive();
+    let server_ch = pair.server.assert_accept();
+
+    let params = pair
+        .client_conn_mut(client_ch)
+        .peer_transport_params()
+        .unwrap();
+    assert_eq!(params.initial_max_streams_bidi(), VarInt(7));
+    assert!(params.disable_active_migration());
+    assert_eq!(params.max_datagram_frame_size(), None);
+
+    let params = pair
+        .server_conn_mut(server_ch)
+        .peer_transport_params()
+        .unwrap();
+    assert_eq!(
+        params.initial_max_streams_bidi(),
+        TransportConfig::default().max_concurrent_bidi_streams
+    );
+    assert!(!params.disable_active_migration());
+    assert!(params.max_datagram_frame_size().is_some());
+}
+
+#[test]
+fn handshake_duration() {
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+    pair.latency = Duration::from_millis(10);
+    let client_ch = pair.begin_connect(client_config());
+    assert_eq!(
+        pair.client_conn_mut(client_ch).stats().handshake_duration,
+        None
+    );
+    pair.drive();
+    let server_ch = pair.server.assert_accept();
+
+    // The server confirms the handshake one round trip after receiving the first Initial, and the
+    // client a further half round trip after that
+    let client = pair
+        .client_conn_mut(client_ch)
+        .stats()
+        .handshake_duration
+        .unwrap();
+    let server = pair
+        .server_conn_mut(server_ch)
+        .stats()
+        .handshake_duration
+        .unwrap();
+    assert!(server >= 2 * pair.latency, "{server:?}");
+    assert!(client > server, "client {client:?}, server {server:?}");
+
+    // Later traffic doesn't affect the measurement
+    pair.client_conn_mut(client_ch).ping();
+    pair.drive();
+    assert_eq!(
+        pair.client_conn_mut(client_ch).stats().handshake_duration,
+        Some(client)
+    );
+    assert_eq!(
+        pair.server_conn_mut(server_ch).stats().handshake_duration,
+        Some(server)
+    );
+}
+
+#[test]
+fn flow_control_stats() {
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+    let (client_ch, server_ch) = pair.connect();
+
+    let s = pair.client_streams(client_ch).open(Dir::Uni).unwrap();
+    pair.client_send(client_ch, s).write(b"hello").unwrap();
+    pair.drive();
+
+    let client = pair.client_conn_mut(client_ch).flow_control_stats();
+    let server = pair.server_conn_mut(server_ch).flow_control_stats();
+    assert_eq!(client.data_sent, 5);
+    assert_eq!(server.data_received, 5);
+    assert_eq!(client.max_data_remote, server.max_data_local);
+    assert_eq!(client.local_streams_uni, 1);
+    assert_eq!(server.remote_streams_uni, 1);
+    assert_eq!(client.local_streams_bidi, 0);
+    assert_eq!(client.max_local_streams_uni, server.max_remote_streams_uni);
+    assert_eq!(
+        client.max_local_streams_bidi,
+        server.max_remote_streams_bidi
+    );
+}
+
+#[test]
+fn stream_stats() {
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+    let (client_ch, server_ch) = pair.connect();
+
+    const LEN: usize = 2000;
+    let s = pair.client_streams(client_ch).open(Dir::Uni).unwrap();
+    pair.client_send(client_ch, s).write(&[0xab; LEN]).unwrap();
+    pair.drive_client();
+    // Lose the datagram carrying the start of the stream, so the rest arrives out of order
+    pair.server.inbound.pop_front().unwrap();
+    pair.drive();
+
+    let stats = pair.client_send(client_ch, s).stats().unwrap();
+    assert_eq!(stats.bytes_written, LEN as u64);
+    assert_eq!(stats.bytes_acked, LEN as u64);
+    assert!(stats.bytes_retransmitted > 0);
+
+    assert_eq!(pair.server_streams(server_ch).accept(Dir::Uni), Some(s));
+    let stats = pair.server_recv(server_ch, s).stats().unwrap();
+    assert!(stats.bytes_received >= LEN as u64);
+    assert!(stats.bytes_reordered > 0);
+}
+
+#[test]
+fn bytes_in_send_buffer() {
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+    let (client_ch, _) = pair.connect();
+
+    let s = pair.client_streams(client_ch).open(Dir::Uni).unwrap();
+    assert_eq!(pair.client_send(client_ch, s).bytes_in_send_buffer(), Ok(0));
+    pair.client_send(client_ch, s).write(&[0xab; 1000]).unwrap();
+    assert_eq!(
+        pair.client_send(client_ch, s).bytes_in_send_buffer(),
+        Ok(1000)
+    );
+    // Data accumulates while it isn't being transmitted
+    pair.client_send(client_ch, s).write(&[0xab; 1000]).unwrap();
+    assert_eq!(
+        pair.client_send(client_ch, s).bytes_in_send_buffer(),
+        Ok(2000)
+    );
+
+    pair.drive_client();
+    assert_eq!(pair.client_send(client_ch, s).bytes_in_send_buffer(), Ok(0));
+}
+
+#[test]
+fn unordered_read_skips_gaps() {
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+    let (client_ch, server_ch) = pair.connect();
+
+    const LEN: u64 = 2000;
+    let s = pair.client_streams(client_ch).open(Dir::Uni).unwrap();
+    pair.client_send(client_ch, s)
+        .write(&[0xab; LEN as usize])
+        .unwrap();
+    pair.drive_client();
+    // Lose the datagram carrying the start of the stream
+    pair.server.inbound.pop_front().unwrap();
+    pair.drive_server();
+
+    // The data that did arrive is available immediately, despite the gap before it
+    assert_eq!(pair.server_streams(server_ch).accept(Dir::Uni), Some(s));
+    let mut recv = pair.server_recv(server_ch, s);
+    let mut chunks = recv.read(false).unwrap();
+    assert_matches!(
+        chunks.next(usize::MAX),
+        Ok(Some(chunk)) if chunk.offset > 0 && chunk.offset + chunk.bytes.len() as u64 == LEN
+    );
+    let _ = chunks.finalize();
+
+    // The missing data follows once retransmitted
+    pair.drive();
+    let mut recv = pair.server_recv(server_ch, s);
+    let mut chunks = recv.read(false).unwrap();
+    assert_matches!(chunks.next(usize::MAX), Ok(Some(chunk)) if chunk.offset == 0);
+    let _ = chunks.finalize();
+}
+
+#[test]
+fn stream_reset_on_expiry() {
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+    pair.latency = Duration::from_millis(10);
+    let (client_ch, server_ch) = pair.connect();
+
+    let s = pair.client_streams(client_ch).open(Dir::Uni).unwrap();
+    const ERROR: VarInt = VarInt(42);
+    pair.client_send(client_ch, s)
+        .set_reset_on_expiry(Some(Duration::from_millis(5)), ERROR)
+        .unwrap();
+
+    info!("losing stream data");
+    const MSG: &[u8] = b"hello";
+    pair.client_send(client_ch, s).write(MSG).unwrap();
+    pair.drive_client();
+    pair.server.inbound.clear();
+    pair.drive();
+
+    // By the time the loss is detected the data is too old to be worth retransmitting
+    assert_matches!(
+        pair.client_conn_mut(client_ch).poll(),
+        Some(Event::Stream(StreamEvent::Writable { id })) if id == s
+    );
+    assert_eq!(
+        pair.client_send(client_ch, s).write(MSG),
+        Err(WriteError::ClosedStream)
+    );
+
+    assert_matches!(
+        pair.server_conn_mut(server_ch).poll(),
+        Some(Event::Stream(StreamEvent::Opened { dir: Dir::Uni }))
+    );
+    assert_matches!(pair.server_streams(server_ch).accept(Dir::Uni), Some(stream) if stream == s);
+    let mut recv = pair.server_recv(server_ch, s);
+    let mut chunks = recv.read(false).unwrap();
+    assert_matches!(chunks.next(usize::MAX), Err(ReadError::Reset(ERROR)));
+    let _ = chunks.finalize();
+}
+
 #[test]
 fn reset_stream() {
     let _guard = subscribe();
@@ -380,73 +683,271 @@ fn reset_stream() {
 }
 
 #[test]
-fn stop_stream() {
+fn reset_stream_at() {
     let _guard = subscribe();
     let mut pair = Pair::default();
     let (client_ch, server_ch) = pair.connect();
 
     let s = pair.client_streams(client_ch).open(Dir::Uni).unwrap();
-    const MSG: &[u8] = b"hello";
-    pair.client_send(client_ch, s).write(MSG).unwrap();
+    pair.client_send(client_ch, s).write(&[0xAB; 1000]).unwrap();
     pair.drive();
+    pair.client_send(client_ch, s).write(&[0xCD; 1000]).unwrap();
 
-    info!("stopping stream");
+    info!("resetting stream");
     const ERROR: VarInt = VarInt(42);
-    pair.server_recv(server_ch, s).stop(ERROR).unwrap();
-    pair.drive();
+    // Data that has already been sent can't be taken back, nor can unwritten data be claimed
+    assert_eq!(
+        pair.client_send(client_ch, s).reset_at(ERROR, 999),
+        Err(ResetAtError::InvalidFinalSize)
+    );
+    assert_eq!(
+        pair.client_send(client_ch, s).reset_at(ERROR, 2001),
+        Err(ResetAtError::InvalidFinalSize)
+    );
+    pair.client_send(client_ch, s)
+        .reset_at(ERROR, 1500)
+        .unwrap();
+    assert_eq!(
+        pair.client_send(client_ch, s).write(&[0; 1]),
+        Err(WriteError::ClosedStream)
+    );
+    assert_eq!(
+        pair.client_send(client_ch, s).reset_at(ERROR, 1500),
+        Err(ResetAtError::ClosedStream)
+    );
 
+    // Data below the final size is still delivered before the reset
+    pair.drive_client();
+    pair.drive_server();
     assert_matches!(
         pair.server_conn_mut(server_ch).poll(),
         Some(Event::Stream(StreamEvent::Opened { dir: Dir::Uni }))
     );
     assert_matches!(pair.server_streams(server_ch).accept(Dir::Uni), Some(stream) if stream == s);
+    let mut recv = pair.server_recv(server_ch, s);
+    let mut chunks = recv.read(true).unwrap();
+    let mut received = Vec::new();
+    while let Ok(Some(chunk)) = chunks.next(usize::MAX) {
+        received.extend(chunk.bytes);
+    }
+    let _ = chunks.finalize();
+    assert_eq!(received.len(), 1500);
+    assert!(received[..1000].iter().all(|&b| b == 0xAB));
+    assert!(received[1000..].iter().all(|&b| b == 0xCD));
 
-    assert_matches!(
-        pair.client_send(client_ch, s).write(b"foo"),
-        Err(WriteError::Stopped(ERROR))
+    pair.drive();
+    let mut recv = pair.server_recv(server_ch, s);
+    let mut chunks = recv.read(true).unwrap();
+    assert_matches!(chunks.next(usize::MAX), Err(ReadError::Reset(ERROR)));
+    let _ = chunks.finalize();
+    // The peer accounts for exactly the final size against flow control
+    assert_eq!(
+        pair.server_conn_mut(server_ch)
+            .flow_control_stats()
+            .data_received,
+        1500
     );
-    assert_matches!(
-        pair.client_send(client_ch, s).finish(),
-        Err(FinishError::Stopped(ERROR))
+    assert_eq!(
+        pair.client_conn_mut(client_ch)
+            .flow_control_stats()
+            .data_sent,
+        1500
     );
 }
 
 #[test]
-fn reject_self_signed_server_cert() {
+fn recv_bytes_buffered() {
     let _guard = subscribe();
     let mut pair = Pair::default();
-    info!("connecting");
+    let (client_ch, server_ch) = pair.connect();
 
-    // Create a self-signed certificate with a different distinguished name than the default one,
-    // such that path building cannot confuse the default root the server is using and the one
-    // the client is trusting (in which case we'd get a different error).
-    let mut cert = rcgen::CertificateParams::new(["localhost".into()]).unwrap();
-    let mut issuer = rcgen::DistinguishedName::new();
-    issuer.push(
-        rcgen::DnType::OrganizationName,
-        "Crazy Quinn's House of Certificates",
-    );
-    cert.distinguished_name = issuer;
-    let cert = cert
-        .self_signed(&rcgen::KeyPair::generate().unwrap())
-        .unwrap();
-    let client_ch = pair.begin_connect(client_config_with_certs(vec![cert.into()]));
+    let s = pair.client_streams(client_ch).open(Dir::Uni).unwrap();
+    pair.client_send(client_ch, s).write(&[0xAB; 3000]).unwrap();
+    pair.drive();
 
+    assert_matches!(pair.server_streams(server_ch).accept(Dir::Uni), Some(stream) if stream == s);
+    assert_eq!(pair.server_recv(server_ch, s).bytes_buffered(), 3000);
+
+    pair.client_send(client_ch, s).write(&[0xCD; 1000]).unwrap();
     pair.drive();
+    assert_eq!(pair.server_recv(server_ch, s).bytes_buffered(), 4000);
 
-    assert_matches!(pair.client_conn_mut(client_ch).poll(),
-                    Some(Event::ConnectionLost { reason: ConnectionError::TransportError(ref error)})
-                    if error.code == TransportErrorCode::crypto(AlertDescription::UnknownCA.into()));
+    // Reading releases the buffered data
+    let mut recv = pair.server_recv(server_ch, s);
+    let mut chunks = recv.read(true).unwrap();
+    let mut read = 0;
+    while read < 1500 {
+        read += chunks.next(1500 - read).unwrap().unwrap().bytes.len();
+    }
+    let _ = chunks.finalize();
+    assert_eq!(pair.server_recv(server_ch, s).bytes_buffered(), 2500);
 }
 
 #[test]
-fn reject_missing_client_cert() {
+fn stream_snapshot() {
     let _guard = subscribe();
+    let mut pair = Pair::default();
+    let (client_ch, server_ch) = pair.connect();
 
-    let mut store = RootCertStore::empty();
-    // `WebPkiClientVerifier` requires a non-empty store, so we stick our own certificate into it
-    // because it's convenient.
-    store.add(CERTIFIED_KEY.cert.der().clone()).unwrap();
+    let open = pair.client_streams(client_ch).open(Dir::Uni).unwrap();
+    pair.client_send(client_ch, open).write(&[0; 100]).unwrap();
+    let finished = pair.client_streams(client_ch).open(Dir::Bi).unwrap();
+    pair.client_send(client_ch, finished)
+        .write(&[0; 50])
+        .unwrap();
+    pair.client_send(client_ch, finished).finish().unwrap();
+    let reset = pair.client_streams(client_ch).open(Dir::Uni).unwrap();
+    pair.client_send(client_ch, reset).write(&[0; 10]).unwrap();
+    pair.client_send(client_ch, reset)
+        .reset(VarInt(42))
+        .unwrap();
+
+    let snapshot = pair.client_streams(client_ch).snapshot();
+    assert_eq!(
+        snapshot.iter().map(|s| s.id).collect::<Vec<_>>(),
+        [finished, open, reset]
+    );
+    assert_matches!(
+        snapshot[0],
+        StreamInfo {
+            send: Some(SendStreamState::DataSent),
+            recv: Some(RecvStreamState::Recv),
+            bytes_queued: 50,
+            bytes_buffered: 0,
+            ..
+        }
+    );
+    assert_matches!(
+        snapshot[1],
+        StreamInfo {
+            send: Some(SendStreamState::Send),
+            recv: None,
+            bytes_queued: 100,
+            ..
+        }
+    );
+    assert_matches!(
+        snapshot[2],
+        StreamInfo {
+            send: Some(SendStreamState::ResetSent),
+            recv: None,
+            ..
+        }
+    );
+
+    pair.drive();
+
+    // Nothing has been read, so all data remains buffered
+    let snapshot = pair.server_streams(server_ch).snapshot();
+    assert_eq!(
+        snapshot.iter().map(|s| s.id).collect::<Vec<_>>(),
+        [finished, open, reset]
+    );
+    assert_matches!(
+        snapshot[0],
+        StreamInfo {
+            send: Some(SendStreamState::Send),
+            recv: Some(RecvStreamState::SizeKnown),
+            bytes_queued: 0,
+            bytes_buffered: 50,
+            ..
+        }
+    );
+    assert_matches!(
+        snapshot[1],
+        StreamInfo {
+            send: None,
+            recv: Some(RecvStreamState::Recv),
+            bytes_buffered: 100,
+            ..
+        }
+    );
+    assert_matches!(
+        snapshot[2],
+        StreamInfo {
+            recv: Some(RecvStreamState::ResetRecvd),
+            ..
+        }
+    );
+
+    let snapshot = pair.client_streams(client_ch).snapshot();
+    assert_matches!(
+        snapshot.iter().find(|s| s.id == open),
+        Some(StreamInfo {
+            bytes_queued: 0,
+            ..
+        })
+    );
+}
+
+#[test]
+fn stop_stream() {
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+    let (client_ch, server_ch) = pair.connect();
+
+    let s = pair.client_streams(client_ch).open(Dir::Uni).unwrap();
+    const MSG: &[u8] = b"hello";
+    pair.client_send(client_ch, s).write(MSG).unwrap();
+    pair.drive();
+
+    info!("stopping stream");
+    const ERROR: VarInt = VarInt(42);
+    pair.server_recv(server_ch, s).stop(ERROR).unwrap();
+    pair.drive();
+
+    assert_matches!(
+        pair.server_conn_mut(server_ch).poll(),
+        Some(Event::Stream(StreamEvent::Opened { dir: Dir::Uni }))
+    );
+    assert_matches!(pair.server_streams(server_ch).accept(Dir::Uni), Some(stream) if stream == s);
+
+    assert_matches!(
+        pair.client_send(client_ch, s).write(b"foo"),
+        Err(WriteError::Stopped(ERROR))
+    );
+    assert_matches!(
+        pair.client_send(client_ch, s).finish(),
+        Err(FinishError::Stopped(ERROR))
+    );
+}
+
+#[test]
+fn reject_self_signed_server_cert() {
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+    info!("connecting");
+
+    // Create a self-signed certificate with a different distinguished name than the default one,
+    // such that path building cannot confuse the default root the server is using and the one
+    // the client is trusting (in which case we'd get a different error).
+    let mut cert = rcgen::CertificateParams::new(["localhost".into()]).unwrap();
+    let mut issuer = rcgen::DistinguishedName::new();
+    issuer.push(
+        rcgen::DnType::OrganizationName,
+        "Crazy Quinn's House of Certificates",
+    );
+    cert.distinguished_name = issuer;
+    let cert = cert
+        .self_signed(&rcgen::KeyPair::generate().unwrap())
+        .unwrap();
+    let client_ch = pair.begin_connect(client_config_with_certs(vec![cert.into()]));
+
+    pair.drive();
+
+    assert_matches!(pair.client_conn_mut(client_ch).poll(),
+                    Some(Event::ConnectionLost { reason: ConnectionError::TransportError(ref error)})
+                    if error.code == TransportErrorCode::crypto(AlertDescription::UnknownCA.into()));
+}
+
+#[test]
+fn reject_missing_client_cert() {
+    let _guard = subscribe();
+
+    let mut store = RootCertStore::empty();
+    // `WebPkiClientVerifier` requires a non-empty store, so we stick our own certificate into it
+    // because it's convenient.
+    store.add(CERTIFIED_KEY.cert.der().clone()).unwrap();
 
     let key = PrivatePkcs8KeyDer::from(CERTIFIED_KEY.signing_key.serialize_der());
     let cert = CERTIFIED_KEY.cert.der().clone();
@@ -497,6 +998,225 @@ fn reject_missing_client_cert() {
                     if error.code == TransportErrorCode::crypto(AlertDescription::CertificateRequired.into()));
 }
 
+#[test]
+fn server_config_per_server_name() {
+    let _guard = subscribe();
+
+    /// Requires client certificates for `a.example` only
+    struct Resolver {
+        a: Arc<rustls::ServerConfig>,
+        b: Arc<rustls::ServerConfig>,
+    }
+
+    impl ResolvesServerConfig for Resolver {
+        fn resolve(&self, server_name: Option<&str>) -> Option<Arc<rustls::ServerConfig>> {
+            match server_name? {
+                "a.example" => Some(self.a.clone()),
+                "b.example" => Some(self.b.clone()),
+                _ => None,
+            }
+        }
+    }
+
+    let certified =
+        rcgen::generate_simple_self_signed(vec!["a.example".into(), "b.example".into()]).unwrap();
+    let cert = certified.cert.der().clone();
+    let key = PrivateKeyDer::from(PrivatePkcs8KeyDer::from(
+        certified.signing_key.serialize_der(),
+    ));
+
+    let mut store = RootCertStore::empty();
+    store.add(CERTIFIED_KEY.cert.der().clone()).unwrap();
+    let provider = Arc::new(default_provider());
+    let a = rustls::ServerConfig::builder_with_provider(provider.clone())
+        .with_protocol_versions(&[&rustls::version::TLS13])
+        .unwrap()
+        .with_client_cert_verifier(
+            WebPkiClientVerifier::builder_with_provider(Arc::new(store), provider.clone())
+                .build()
+                .unwrap(),
+        )
+        .with_single_cert(vec![cert.clone()], key.clone_key())
+        .unwrap();
+    let b = rustls::ServerConfig::builder_with_provider(provider)
+        .with_protocol_versions(&[&rustls::version::TLS13])
+        .unwrap()
+        .with_no_client_auth()
+        .with_single_cert(vec![cert.clone()], key)
+        .unwrap();
+    let resolver = Resolver {
+        a: Arc::new(a),
+        b: Arc::new(b),
+    };
+
+    let mut pair = Pair::new(
+        Default::default(),
+        ServerConfig::with_cert_resolver(Arc::new(resolver)),
+    );
+    let client_config = client_config_with_certs(vec![cert]);
+    let connect = |pair: &mut Pair, server_name: &str| {
+        let (client_ch, client_conn) = pair
+            .client
+            .connect(
+                pair.time,
+                client_config.clone(),
+                pair.server.addr,
+                server_name,
+            )
+            .unwrap();
+        pair.client.connections.insert(client_ch, client_conn);
+        pair.drive();
+        client_ch
+    };
+
+    info!("connecting to b.example");
+    let client_ch = connect(&mut pair, "b.example");
+    assert_matches!(
+        pair.client_conn_mut(client_ch).poll(),
+        Some(Event::HandshakeDataReady)
+    );
+    assert_matches!(
+        pair.client_conn_mut(client_ch).poll(),
+        Some(Event::Connected)
+    );
+    let server_ch = pair.server.assert_accept();
+    assert!(!pair.server_conn_mut(server_ch).is_closed());
+    // The test harness drives one connection at a time
+    pair.client.connections.clear();
+    pair.server.connections.clear();
+
+    info!("connecting to a.example");
+    let client_ch = connect(&mut pair, "a.example");
+    assert_matches!(
+        pair.client_conn_mut(client_ch).poll(),
+        Some(Event::HandshakeDataReady)
+    );
+    assert_matches!(
+        pair.client_conn_mut(client_ch).poll(),
+        Some(Event::Connected)
+    );
+    assert_matches!(pair.client_conn_mut(client_ch).poll(),
+                    Some(Event::ConnectionLost { reason: ConnectionError::ConnectionClosed(ref close)})
+                    if close.error_code == TransportErrorCode::crypto(AlertDescription::CertificateRequired.into()));
+    pair.server.assert_accept();
+    pair.client.connections.clear();
+    pair.server.connections.clear();
+
+    info!("connecting to c.example");
+    let client_ch = connect(&mut pair, "c.example");
+    assert_matches!(pair.client_conn_mut(client_ch).poll(),
+                    Some(Event::ConnectionLost { reason: ConnectionError::ConnectionClosed(ref close)})
+                    if close.error_code == TransportErrorCode::crypto(AlertDescription::UnrecognisedName.into()));
+}
+
+/// Ensures a ClientHello awaiting a server configuration can't claim more than rustls accepts
+#[test]
+fn server_config_resolver_oversized_client_hello() {
+    let _guard = subscribe();
+
+    struct Resolver;
+
+    impl ResolvesServerConfig for Resolver {
+        fn resolve(&self, _: Option<&str>) -> Option<Arc<rustls::ServerConfig>> {
+            unreachable!("the ClientHello is never complete")
+        }
+    }
+
+    let mut pair = Pair::new(
+        Default::default(),
+        ServerConfig::with_cert_resolver(Arc::new(Resolver)),
+    );
+    // A ClientHello claiming to be 16 MiB long
+    let mut frames = Vec::new();
+    frame::Crypto {
+        offset: 0,
+        data: Bytes::from_static(&[1, 0xff, 0xff, 0xff, 3, 3]),
+    }
+    .encode(&mut frames);
+    let packet = client_initial(&frames);
+
+    pair.server
+        .inbound
+        .push_back((pair.time, None, packet[..].into()));
+    pair.drive_server();
+    assert_matches!(
+        pair.server.assert_accept_error(),
+        ConnectionError::TransportError(err)
+            if err.code == TransportErrorCode::CRYPTO_BUFFER_EXCEEDED
+    );
+}
+
+#[test]
+fn custom_pacer() {
+    /// Never delays transmission, counting the bytes it was told about
+    struct CountingPacer(Arc<Mutex<u64>>);
+
+    impl pacing::Pacer for CountingPacer {
+        fn on_transmit(&mut self, packet_length: u16) {
+            *self.0.lock().unwrap() += u64::from(packet_length);
+        }
+
+        fn delay(&mut self, _: u64, _: Instant, _: Duration, _: u64, _: u16) -> Option<Instant> {
+            None
+        }
+    }
+
+    struct CountingPacerFactory(Arc<Mutex<u64>>);
+
+    impl pacing::PacerFactory for CountingPacerFactory {
+        fn build(
+            self: Arc<Self>,
+            _: Instant,
+            _: Duration,
+            _: u64,
+            _: u16,
+            _: Option<u64>,
+        ) -> Box<dyn pacing::Pacer> {
+            Box::new(CountingPacer(self.0.clone()))
+        }
+    }
+
+    let _guard = subscribe();
+    let transmitted = Arc::new(Mutex::new(0));
+    let mut transport = TransportConfig::default();
+    transport.pacer_factory(Arc::new(CountingPacerFactory(transmitted.clone())));
+    let mut client_config = client_config();
+    client_config.transport_config(Arc::new(transport));
+    let mut pair = Pair::default();
+    let (client_ch, _) = pair.connect_with(client_config);
+
+    let before = *transmitted.lock().unwrap();
+    let s = pair.client_streams(client_ch).open(Dir::Uni).unwrap();
+    pair.client_send(client_ch, s).write(&[42; 4096]).unwrap();
+    pair.drive();
+    assert!(*transmitted.lock().unwrap() > before + 4096);
+}
+
+#[test]
+fn stats_cwnd_grows_during_bulk_transfer() {
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+    let (client_ch, _) = pair.connect();
+    pair.drive();
+
+    let initial = pair.client_conn_mut(client_ch).stats().path;
+    assert_eq!(initial.bytes_in_flight, 0);
+
+    // Queue more than the initial window so that the transfer is congestion-limited
+    let s = pair.client_streams(client_ch).open(Dir::Uni).unwrap();
+    let msg = vec![42; 256 * 1024];
+    assert_eq!(pair.client_send(client_ch, s).write(&msg), Ok(msg.len()));
+    pair.drive_client();
+    let conn = pair.client_conn_mut(client_ch);
+    assert!(conn.bytes_in_flight() > 0);
+    assert_eq!(conn.stats().path.bytes_in_flight, conn.bytes_in_flight());
+    pair.drive();
+
+    let after = pair.client_conn_mut(client_ch).stats().path;
+    assert!(after.cwnd > initial.cwnd);
+    assert_eq!(after.bytes_in_flight, 0);
+}
+
 #[test]
 fn congestion() {
     let _guard = subscribe();
@@ -518,6 +1238,62 @@ fn congestion() {
     pair.client_send(client_ch, s).write(&[42; 1024]).unwrap();
 }
 
+#[test]
+fn set_congestion_controller() {
+    let _guard = subscribe();
+    const SIZE: usize = 200_000;
+    let mut pair = Pair::default();
+    let (client_ch, server_ch) = pair.connect();
+
+    let s = pair.client_streams(client_ch).open(Dir::Uni).unwrap();
+    let msg = vec![0xAB; SIZE];
+    assert_eq!(pair.client_send(client_ch, s).write(&msg), Ok(SIZE));
+    pair.client_send(client_ch, s).finish().unwrap();
+    pair.drive_client();
+
+    // Switch controllers mid-transfer, keeping the current window
+    let window = pair.client_conn_mut(client_ch).congestion_state().window();
+    let now = pair.time;
+    pair.client_conn_mut(client_ch)
+        .set_congestion_controller(now, Arc::new(congestion::NewRenoConfig::default()));
+    let controller = pair.client_conn_mut(client_ch).congestion_state();
+    assert_eq!(controller.window(), window);
+    assert!(
+        controller
+            .clone_box()
+            .into_any()
+            .downcast::<congestion::NewReno>()
+            .is_ok()
+    );
+
+    pair.drive();
+    assert_matches!(pair.server_streams(server_ch).accept(Dir::Uni), Some(stream) if stream == s);
+    assert_eq!(stream_chunks(pair.server_recv(server_ch, s)).len(), SIZE);
+}
+
+#[test]
+fn congestion_controller_name() {
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+    let (client_ch, server_ch) = pair.connect();
+    assert_eq!(
+        pair.client_conn_mut(client_ch).congestion_controller_name(),
+        "cubic"
+    );
+    assert_eq!(
+        pair.server_conn_mut(server_ch).congestion_controller_name(),
+        "cubic"
+    );
+
+    let now = pair.time;
+    pair.client_conn_mut(client_ch)
+        .set_congestion_controller(now, Arc::new(congestion::NewRenoConfig::default()));
+    assert_eq!(
+        pair.client_conn_mut(client_ch).congestion_controller_name(),
+        "newreno"
+    );
+}
+
 #[test]
 fn high_latency_handshake() {
     let _guard = subscribe();
@@ -601,15 +1377,59 @@ fn zero_rtt_happypath() {
 }
 
 #[test]
-fn zero_rtt_rejection() {
+fn zero_rtt_early_data_end() {
     let _guard = subscribe();
-    let server_config = ServerConfig::with_crypto(Arc::new(server_crypto_with_alpn(vec![
-        "foo".into(),
-        "bar".into(),
-    ])));
-    let mut pair = Pair::new(Arc::new(EndpointConfig::default()), server_config);
-    let mut client_crypto = Arc::new(client_crypto_with_alpn(vec!["foo".into()]));
-    let client_config = ClientConfig::new(client_crypto.clone());
+    let mut pair = Pair::default();
+    pair.server.handle_incoming = Box::new(validate_incoming);
+    let config = client_config();
+
+    // Establish normal connection to obtain a session ticket
+    let client_ch = pair.begin_connect(config.clone());
+    pair.drive();
+    pair.server.assert_accept();
+    pair.client
+        .connections
+        .get_mut(&client_ch)
+        .unwrap()
+        .close(pair.time, VarInt(0), [][..].into());
+    pair.drive();
+
+    pair.client.addr = SocketAddr::new(
+        Ipv6Addr::LOCALHOST.into(),
+        CLIENT_PORTS.lock().unwrap().next().unwrap(),
+    );
+    let client_ch = pair.begin_connect(config);
+    let s = pair.client_streams(client_ch).open(Dir::Uni).unwrap();
+    const EARLY: &[u8] = b"GET /";
+    pair.client_send(client_ch, s).write(EARLY).unwrap();
+    pair.drive();
+    assert!(pair.client_conn_mut(client_ch).accepted_0rtt());
+    let server_ch = pair.server.assert_accept();
+
+    pair.client_send(client_ch, s).write(b" HTTP/1.1").unwrap();
+    pair.drive();
+
+    let mut recv = pair.server_recv(server_ch, s);
+    assert_eq!(recv.early_data_end(), Ok(EARLY.len() as u64));
+    let mut chunks = recv.read(true).unwrap();
+    let mut data = Vec::new();
+    while let Ok(Some(chunk)) = chunks.next(usize::MAX) {
+        data.extend_from_slice(&chunk.bytes);
+    }
+    let _ = chunks.finalize();
+    assert_eq!(data, b"GET / HTTP/1.1");
+}
+
+#[test]
+fn zero_rtt_rejection() {
+    let _guard = subscribe();
+    let server_config = ServerConfig::with_crypto(Arc::new(server_crypto_with_alpn(vec![
+        "foo".into(),
+        "bar".into(),
+    ])));
+    let mut pair = Pair::new(Arc::new(EndpointConfig::default()), server_config);
+    let mut client_crypto = Arc::new(client_crypto_with_alpn(vec!["foo".into()]));
+    let client_config = ClientConfig::new(client_crypto.clone());
 
     // Establish normal connection
     let client_ch = pair.begin_connect(client_config);
@@ -659,6 +1479,10 @@ fn zero_rtt_rejection() {
     pair.client_send(client_ch, s).write(MSG).unwrap();
     pair.drive();
     assert!(!pair.client_conn_mut(client_ch).accepted_0rtt());
+    assert_eq!(
+        pair.client_conn_mut(client_ch).zero_rtt_rejected_streams(),
+        &[s]
+    );
     let server_ch = pair.server.assert_accept();
     assert_matches!(
         pair.server_conn_mut(server_ch).poll(),
@@ -683,6 +1507,65 @@ fn zero_rtt_rejection() {
     assert_eq!(pair.client_conn_mut(client_ch).stats().path.lost_packets, 0);
 }
 
+#[test]
+fn zero_rtt_rejection_retransmit() {
+    let _guard = subscribe();
+    let server_config = ServerConfig::with_crypto(Arc::new(server_crypto_with_alpn(vec![
+        "foo".into(),
+        "bar".into(),
+    ])));
+    let mut pair = Pair::new(Arc::new(EndpointConfig::default()), server_config);
+    let mut client_crypto = Arc::new(client_crypto_with_alpn(vec!["foo".into()]));
+
+    // Establish normal connection
+    let client_ch = pair.begin_connect(ClientConfig::new(client_crypto.clone()));
+    pair.drive();
+    pair.server.assert_accept();
+    let now = pair.time;
+    pair.client_conn_mut(client_ch)
+        .close(now, VarInt(0), [][..].into());
+    pair.drive();
+    pair.client.connections.clear();
+    pair.server.connections.clear();
+
+    // Changing protocols invalidates 0-RTT, as in `zero_rtt_rejection`
+    let this = Arc::get_mut(&mut client_crypto).expect("QuicClientConfig is shared");
+    let inner = Arc::get_mut(&mut this.inner).expect("QuicClientConfig.inner is shared");
+    inner.alpn_protocols = vec!["bar".into()];
+    let mut client_config = ClientConfig::new(client_crypto);
+    client_config.retransmit_rejected_0rtt(true);
+
+    info!("resuming session");
+    let client_ch = pair.begin_connect(client_config);
+    assert!(pair.client_conn_mut(client_ch).has_0rtt());
+    let s = pair.client_streams(client_ch).open(Dir::Uni).unwrap();
+    const MSG: &[u8] = b"Hello, 0-RTT!";
+    pair.client_send(client_ch, s).write(MSG).unwrap();
+    pair.client_send(client_ch, s).finish().unwrap();
+    pair.drive();
+    assert!(!pair.client_conn_mut(client_ch).accepted_0rtt());
+    assert!(pair.client_conn_mut(client_ch).retransmitted_0rtt());
+    assert!(
+        pair.client_conn_mut(client_ch)
+            .zero_rtt_rejected_streams()
+            .is_empty()
+    );
+
+    let server_ch = pair.server.assert_accept();
+    assert_matches!(
+        pair.server_streams(server_ch).accept(Dir::Uni),
+        Some(stream) if stream == s
+    );
+    let mut recv = pair.server_recv(server_ch, s);
+    let mut chunks = recv.read(false).unwrap();
+    assert_matches!(
+        chunks.next(usize::MAX),
+        Ok(Some(chunk)) if chunk.offset == 0 && chunk.bytes == MSG
+    );
+    assert_matches!(chunks.next(usize::MAX), Ok(None));
+    let _ = chunks.finalize();
+}
+
 fn test_zero_rtt_incoming_limit<F: FnOnce(&mut ServerConfig)>(configure_server: F) {
     // caller sets the server limit to 4000 bytes
     // the client writes 8000 bytes
@@ -819,6 +1702,7 @@ fn alpn_success() {
 
     // Establish normal connection
     let client_ch = pair.begin_connect(client_config);
+    assert_eq!(pair.client_conn_mut(client_ch).negotiated_alpn(), None);
     pair.drive();
     let server_ch = pair.server.assert_accept();
     assert_matches!(
@@ -842,6 +1726,26 @@ fn alpn_success() {
         .downcast::<crypto::rustls::HandshakeData>()
         .unwrap();
     assert_eq!(hd.protocol.unwrap(), &b"bar"[..]);
+    assert_eq!(
+        pair.client_conn_mut(client_ch).negotiated_alpn(),
+        Some(&b"bar"[..])
+    );
+    assert_eq!(
+        pair.server_conn_mut(server_ch).negotiated_alpn(),
+        Some(&b"bar"[..])
+    );
+
+    let hd = pair
+        .server_conn_mut(server_ch)
+        .crypto_session()
+        .handshake_data()
+        .unwrap()
+        .downcast::<crypto::rustls::HandshakeData>()
+        .unwrap();
+    assert_eq!(
+        hd.offered_protocols,
+        [&b"bar"[..], &b"quux"[..], &b"corge"[..]]
+    );
     assert_eq!(
         hd.protocol_version
             .unwrap()
@@ -854,6 +1758,12 @@ fn alpn_success() {
             .downcast_ref::<rustls::CipherSuite>()
             .is_some()
     );
+    assert!(
+        hd.key_exchange_group
+            .unwrap()
+            .downcast_ref::<rustls::NamedGroup>()
+            .is_some()
+    );
 }
 
 #[test]
@@ -998,6 +1908,50 @@ fn stream_id_limit() {
     let _ = chunks.finalize();
 }
 
+#[test]
+fn lower_max_concurrent_streams() {
+    let _guard = subscribe();
+    let server = ServerConfig {
+        transport: Arc::new(TransportConfig {
+            max_concurrent_uni_streams: 3u32.into(),
+            ..TransportConfig::default()
+        }),
+        ..server_config()
+    };
+    let mut pair = Pair::new(Default::default(), server);
+    let (client_ch, server_ch) = pair.connect();
+
+    let mut streams = Vec::new();
+    for _ in 0..3 {
+        let s = pair.client_streams(client_ch).open(Dir::Uni).unwrap();
+        pair.client_send(client_ch, s).write(b"hello").unwrap();
+        pair.client_send(client_ch, s).finish().unwrap();
+        streams.push(s);
+    }
+    assert_eq!(pair.client_streams(client_ch).open(Dir::Uni), None);
+    pair.drive();
+    for &s in &streams {
+        assert_eq!(pair.server_streams(server_ch).accept(Dir::Uni), Some(s));
+    }
+
+    // Existing streams are unaffected, but no credit is issued until fewer than one remains open
+    pair.server_conn_mut(server_ch)
+        .set_max_concurrent_streams(Dir::Uni, 1u32.into());
+    for &s in &streams[..2] {
+        assert_eq!(stream_chunks(pair.server_recv(server_ch, s)), b"hello");
+    }
+    pair.drive();
+    assert_eq!(pair.client_streams(client_ch).open(Dir::Uni), None);
+
+    assert_eq!(
+        stream_chunks(pair.server_recv(server_ch, streams[2])),
+        b"hello"
+    );
+    pair.drive();
+    assert!(pair.client_streams(client_ch).open(Dir::Uni).is_some());
+    assert_eq!(pair.client_streams(client_ch).open(Dir::Uni), None);
+}
+
 #[test]
 fn streams_blocked() {
     let _guard = subscribe();
@@ -1263,6 +2217,78 @@ fn instant_server_close() {
     );
 }
 
+#[test]
+fn idle_timeout_deadline() {
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+    let (client_ch, _) = pair.connect();
+    let deadline = pair
+        .client_conn_mut(client_ch)
+        .idle_timeout_deadline()
+        .unwrap();
+    assert!(deadline > pair.time);
+
+    pair.time += Duration::from_secs(1);
+    let s = pair.client_streams(client_ch).open(Dir::Uni).unwrap();
+    pair.client_send(client_ch, s).write(b"hello").unwrap();
+    pair.drive();
+    let new_deadline = pair
+        .client_conn_mut(client_ch)
+        .idle_timeout_deadline()
+        .unwrap();
+    assert!(new_deadline >= deadline + Duration::from_secs(1));
+
+    // No deadline once the idle timeout is disabled
+    let mut transport = TransportConfig::default();
+    transport.max_idle_timeout(None);
+    let transport = Arc::new(transport);
+    let server = ServerConfig {
+        transport: transport.clone(),
+        ..server_config()
+    };
+    let mut pair = Pair::new(Default::default(), server);
+    let mut config = client_config();
+    config.transport_config(transport);
+    let (client_ch, _) = pair.connect_with(config);
+    assert_eq!(
+        pair.client_conn_mut(client_ch).idle_timeout_deadline(),
+        None
+    );
+}
+
+#[test]
+fn min_pacing_rate() {
+    let _guard = subscribe();
+
+    /// Bytes the client sends over 500ms while receiving no acknowledgements at all
+    fn sent_without_acks(min_pacing_rate: Option<u64>) -> u64 {
+        let mut pair = Pair::default();
+        pair.latency = Duration::from_millis(50);
+        let mut transport = TransportConfig::default();
+        transport.min_pacing_rate(min_pacing_rate);
+        let mut config = client_config();
+        config.transport_config(Arc::new(transport));
+        let (client_ch, _) = pair.connect_with(config);
+
+        let s = pair.client_streams(client_ch).open(Dir::Uni).unwrap();
+        pair.client_send(client_ch, s)
+            .write(&[0; 1_000_000])
+            .unwrap();
+        let sent_before = pair.client_conn_mut(client_ch).stats().udp_tx.bytes;
+        let start = pair.time;
+        while pair.time < start + Duration::from_millis(500) && pair.step() {
+            pair.client.inbound.clear(); // Simulate total S->C packet loss
+        }
+        pair.client_conn_mut(client_ch).stats().udp_tx.bytes - sent_before
+    }
+
+    // With a round trip time of 100ms, the floor keeps about 100kB in flight
+    let with_floor = sent_without_acks(Some(1_000_000));
+    let without_floor = sent_without_acks(None);
+    assert!(with_floor >= 80_000, "sent only {with_floor} bytes");
+    assert!(with_floor > 2 * without_floor);
+}
+
 #[test]
 fn idle_timeout() {
     let _guard = subscribe();
@@ -1305,6 +2331,62 @@ fn idle_timeout() {
     );
 }
 
+#[test]
+fn rtt_samples() {
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+    let (client_ch, _) = pair.connect();
+    assert_eq!(pair.client_conn_mut(client_ch).rtt_samples().count(), 0);
+
+    let mut transport = TransportConfig::default();
+    transport.rtt_sample_history(64);
+    let mut client_config = client_config();
+    client_config.transport_config(Arc::new(transport));
+    let mut pair = Pair::default();
+    let (client_ch, _) = pair.connect_with(client_config);
+    assert!(pair.client_conn_mut(client_ch).rtt_samples().count() > 0);
+
+    for _ in 0..100 {
+        pair.client_conn_mut(client_ch).ping();
+        pair.drive();
+    }
+    assert_eq!(pair.client_conn_mut(client_ch).rtt_samples().count(), 64);
+}
+
+#[test]
+fn set_max_idle_timeout() {
+    let _guard = subscribe();
+    const IDLE_TIMEOUT: u64 = 100;
+    let mut pair = Pair::default();
+    let (client_ch, _) = pair.connect();
+    let start = pair.time;
+    assert_matches!(
+        pair.client_conn_mut(client_ch)
+            .set_max_idle_timeout(start, Some(VarInt(0).into())),
+        Err(ConfigError::OutOfBounds)
+    );
+    pair.client_conn_mut(client_ch)
+        .set_max_idle_timeout(start, Some(VarInt(IDLE_TIMEOUT).into()))
+        .unwrap();
+
+    while !pair.client_conn_mut(client_ch).is_closed() {
+        if !pair.step() {
+            if let Some(t) = pair.client.next_wakeup() {
+                pair.time = t;
+            }
+        }
+        pair.client.inbound.clear(); // Simulate total S->C packet loss
+    }
+
+    assert!(pair.time - start < Duration::from_millis(2 * IDLE_TIMEOUT));
+    assert_matches!(
+        pair.client_conn_mut(client_ch).poll(),
+        Some(Event::ConnectionLost {
+            reason: ConnectionError::TimedOut,
+        })
+    );
+}
+
 #[test]
 fn connection_close_sends_acks() {
     let _guard = subscribe();
@@ -1348,6 +2430,146 @@ fn server_hs_retransmit() {
     );
 }
 
+/// Embeds a fixed server identifier in every CID, as a CID-routing load balancer would require
+struct ServerIdCidGenerator {
+    hints: Arc<Mutex<Vec<usize>>>,
+}
+
+impl ServerIdCidGenerator {
+    const SERVER_ID: u8 = 0x42;
+}
+
+impl ConnectionIdGenerator for ServerIdCidGenerator {
+    fn generate_cid(&mut self) -> ConnectionId {
+        let mut bytes = [Self::SERVER_ID; 8];
+        rand::rng().fill_bytes(&mut bytes[1..]);
+        ConnectionId::new(&bytes)
+    }
+
+    fn generate_cid_for_connection(&mut self, active_cids: usize) -> ConnectionId {
+        self.hints.lock().unwrap().push(active_cids);
+        self.generate_cid()
+    }
+
+    fn validate(&self, cid: ConnectionId) -> Result<(), InvalidCid> {
+        match cid[0] == Self::SERVER_ID {
+            true => Ok(()),
+            false => Err(InvalidCid),
+        }
+    }
+
+    fn cid_len(&self) -> usize {
+        8
+    }
+
+    fn cid_lifetime(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[test]
+fn custom_cid_generator_migration() {
+    let _guard = subscribe();
+    let hints = Arc::new(Mutex::new(Vec::new()));
+    let mut endpoint_config = EndpointConfig::default();
+    let generator_hints = hints.clone();
+    endpoint_config.cid_generator(Arc::new(move || {
+        Box::new(ServerIdCidGenerator {
+            hints: generator_hints.clone(),
+        })
+    }));
+    let mut pair = Pair::new(Arc::new(endpoint_config), server_config());
+    let (client_ch, server_ch) = pair.connect();
+    pair.drive();
+
+    // Each CID issued after the first is generated knowing how many came before it
+    let issued = hints.lock().unwrap().clone();
+    assert!(issued.contains(&0));
+    assert!(issued.iter().any(|&n| n > 0));
+
+    // Packets from the client's new address still reach the connection by its full CID
+    pair.client.addr = SocketAddr::new(
+        Ipv4Addr::new(127, 0, 0, 1).into(),
+        CLIENT_PORTS.lock().unwrap().next().unwrap(),
+    );
+    pair.client_conn_mut(client_ch).ping();
+    pair.drive();
+    assert_eq!(
+        pair.server_conn_mut(server_ch).remote_address(),
+        pair.client.addr
+    );
+    assert_eq!(
+        pair.server_conn_mut(server_ch).path_validation_state(),
+        PathValidationState::Validated
+    );
+}
+
+#[test]
+fn local_ip_follows_received_packets() {
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+    let first = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+    pair.server.local_ip = Some(first);
+    let (client_ch, server_ch) = pair.connect();
+    assert_eq!(pair.server_conn_mut(server_ch).local_ip(), Some(first));
+    assert_eq!(pair.client_conn_mut(client_ch).local_ip(), None);
+
+    let second = IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1));
+    pair.server.local_ip = Some(second);
+    pair.client_conn_mut(client_ch).ping();
+    pair.drive();
+    assert_eq!(pair.server_conn_mut(server_ch).local_ip(), Some(second));
+
+    // Platforms which can't report the destination address don't clobber a known one
+    pair.server.local_ip = None;
+    pair.client_conn_mut(client_ch).ping();
+    pair.drive();
+    assert_eq!(pair.server_conn_mut(server_ch).local_ip(), Some(second));
+
+    // A reordered packet sent to the old address doesn't undo the change
+    pair.client_conn_mut(client_ch).ping();
+    pair.drive_client();
+    let old = pair.server.inbound.pop_front().unwrap();
+    pair.client_conn_mut(client_ch).ping();
+    pair.drive_client();
+    let third = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 3));
+    pair.server.local_ip = Some(third);
+    pair.drive_server();
+    assert_eq!(pair.server_conn_mut(server_ch).local_ip(), Some(third));
+    pair.server.inbound.push_back(old);
+    pair.server.local_ip = Some(second);
+    pair.drive_server();
+    assert_eq!(pair.server_conn_mut(server_ch).local_ip(), Some(third));
+}
+
+#[test]
+fn disabled_spin_bit_is_fixed() {
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+    let mut client_config = client_config();
+    Arc::get_mut(&mut client_config.transport)
+        .unwrap()
+        .allow_spin(false);
+    let (client_ch, _) = pair.connect_with(client_config);
+    pair.drive();
+
+    let mut spins = Vec::new();
+    for _ in 0..10 {
+        pair.client_conn_mut(client_ch).ping();
+        pair.drive_client();
+        spins.extend(
+            pair.server
+                .inbound
+                .iter()
+                .filter(|(_, _, x)| x[0] & packet::LONG_HEADER_FORM == 0)
+                .map(|(_, _, x)| x[0] & packet::SPIN_BIT != 0),
+        );
+        pair.drive();
+    }
+    assert!(spins.len() >= 10);
+    assert!(spins.iter().all(|&x| x == spins[0]), "{spins:?}");
+}
+
 #[test]
 fn migration() {
     let _guard = subscribe();
@@ -1356,6 +2578,10 @@ fn migration() {
     pair.drive();
 
     let client_stats_after_connect = pair.client_conn_mut(client_ch).stats();
+    assert_eq!(
+        pair.server_conn_mut(server_ch).path_validation_state(),
+        PathValidationState::Validated
+    );
 
     pair.client.addr = SocketAddr::new(
         Ipv4Addr::new(127, 0, 0, 1).into(),
@@ -1368,6 +2594,10 @@ fn migration() {
     pair.drive_client();
     pair.drive_server();
     assert_ne!(pair.server_conn_mut(server_ch).total_recvd(), 0);
+    assert_eq!(
+        pair.server_conn_mut(server_ch).path_validation_state(),
+        PathValidationState::Validating
+    );
 
     pair.drive();
     assert_matches!(pair.client_conn_mut(client_ch).poll(), None);
@@ -1375,6 +2605,10 @@ fn migration() {
         pair.server_conn_mut(server_ch).remote_address(),
         pair.client.addr
     );
+    assert_eq!(
+        pair.server_conn_mut(server_ch).path_validation_state(),
+        PathValidationState::Validated
+    );
 
     // Assert that the client's response to the PATH_CHALLENGE was an IMMEDIATE_ACK, instead of a
     // second ping
@@ -1390,6 +2624,258 @@ fn migration() {
     );
 }
 
+#[test]
+fn migration_count() {
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+    let (client_ch, server_ch) = pair.connect();
+    assert_eq!(pair.server_conn_mut(server_ch).stats().migration_count, 0);
+
+    for expected in 1..=2 {
+        pair.client.addr = SocketAddr::new(
+            Ipv4Addr::LOCALHOST.into(),
+            CLIENT_PORTS.lock().unwrap().next().unwrap(),
+        );
+        pair.client_conn_mut(client_ch).ping();
+        pair.drive();
+        assert_eq!(
+            pair.server_conn_mut(server_ch).remote_address(),
+            pair.client.addr
+        );
+        assert_eq!(
+            pair.server_conn_mut(server_ch).stats().migration_count,
+            expected
+        );
+    }
+}
+
+#[test]
+fn migration_disabled() {
+    let _guard = subscribe();
+    let mut server_config = server_config();
+    server_config.migration(false);
+    let mut pair = Pair::new(Default::default(), server_config);
+    let (client_ch, server_ch) = pair.connect();
+    let original = pair.client.addr;
+
+    // Packets from a different address are ignored
+    pair.client.addr = SocketAddr::new(
+        Ipv4Addr::LOCALHOST.into(),
+        CLIENT_PORTS.lock().unwrap().next().unwrap(),
+    );
+    pair.client_conn_mut(client_ch).ping();
+    pair.drive_client();
+    pair.drive_server();
+    assert_eq!(pair.server_conn_mut(server_ch).remote_address(), original);
+    assert_eq!(
+        pair.server_conn_mut(server_ch).path_validation_state(),
+        PathValidationState::Validated
+    );
+
+    // The original path keeps working
+    pair.client.addr = original;
+    pair.client_conn_mut(client_ch).ping();
+    pair.drive();
+    assert!(!pair.client_conn_mut(client_ch).is_closed());
+    assert!(!pair.server_conn_mut(server_ch).is_closed());
+    assert_eq!(pair.server_conn_mut(server_ch).remote_address(), original);
+
+    // NAT rebinding, which only changes the port, is still followed
+    pair.client.addr = SocketAddr::new(original.ip(), CLIENT_PORTS.lock().unwrap().next().unwrap());
+    pair.client_conn_mut(client_ch).ping();
+    pair.drive();
+    assert_eq!(
+        pair.server_conn_mut(server_ch).remote_address(),
+        pair.client.addr
+    );
+    assert_eq!(
+        pair.server_conn_mut(server_ch).path_validation_state(),
+        PathValidationState::Validated
+    );
+}
+
+#[test]
+fn path_stats_across_migration() {
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+    let (client_ch, server_ch) = pair.connect();
+    pair.drive();
+    let old_addr = pair.client.addr;
+
+    pair.client.addr = SocketAddr::new(
+        Ipv4Addr::new(127, 0, 0, 1).into(),
+        CLIENT_PORTS.lock().unwrap().next().unwrap(),
+    );
+    pair.client_conn_mut(client_ch).ping();
+    pair.drive();
+    assert_eq!(
+        pair.server_conn_mut(server_ch).remote_address(),
+        pair.client.addr
+    );
+
+    let paths = pair.server_conn_mut(server_ch).path_stats();
+    assert_eq!(paths.len(), 2);
+    let (old, new) = (&paths[0], &paths[1]);
+    assert_eq!(old.remote, old_addr);
+    assert!(!old.active);
+    assert_eq!(new.remote, pair.client.addr);
+    assert!(new.active);
+    for path in [old, new] {
+        assert_ne!(path.bytes_sent, 0);
+        assert_ne!(path.bytes_received, 0);
+    }
+
+    // Every datagram is attributed to exactly one path
+    let stats = pair.server_conn_mut(server_ch).stats();
+    assert_eq!(old.bytes_sent + new.bytes_sent, stats.udp_tx.bytes);
+}
+
+#[test]
+fn rtt_cache_seeds_new_connections() {
+    let _guard = subscribe();
+    let cache = Arc::new(RttMemoryCache::default());
+    let mut endpoint_config = EndpointConfig::default();
+    endpoint_config.rtt_cache(Some(cache.clone()));
+    let mut pair = Pair::new(Arc::new(endpoint_config), server_config());
+    pair.latency = Duration::from_millis(10);
+    let (client_ch, _) = pair.connect();
+
+    assert_eq!(cache.get(pair.server.addr), None);
+    let now = pair.time;
+    pair.client_conn_mut(client_ch)
+        .close(now, VarInt(0), Bytes::new());
+    pair.drive();
+    let cached = cache.get(pair.server.addr).expect("RTT cached on close");
+    assert!(cached >= 2 * pair.latency);
+    assert!(cached < TransportConfig::default().initial_rtt);
+
+    let client_ch = pair.begin_connect(client_config());
+    assert_eq!(pair.client_conn_mut(client_ch).rtt(), cached);
+}
+
+#[test]
+fn initial_window() {
+    let _guard = subscribe();
+    const WINDOW: u64 = 100 * 1200;
+    let mut transport = TransportConfig::default();
+    transport.initial_window(WINDOW);
+    let mut pair = Pair::new(
+        Default::default(),
+        ServerConfig {
+            transport: Arc::new(transport),
+            ..server_config()
+        },
+    );
+    let (client_ch, server_ch) = pair.connect();
+    assert_eq!(
+        pair.server_conn_mut(server_ch)
+            .congestion_state()
+            .initial_window(),
+        WINDOW
+    );
+
+    // Migrating to an unrelated address starts over with a fresh controller
+    pair.client.addr = SocketAddr::new(
+        Ipv4Addr::new(127, 0, 0, 2).into(),
+        CLIENT_PORTS.lock().unwrap().next().unwrap(),
+    );
+    pair.client_conn_mut(client_ch).ping();
+    pair.drive();
+    assert_eq!(
+        pair.server_conn_mut(server_ch).remote_address(),
+        pair.client.addr
+    );
+    let congestion = pair.server_conn_mut(server_ch).congestion_state();
+    assert_eq!(congestion.initial_window(), WINDOW);
+    assert!(congestion.window() >= WINDOW);
+}
+
+#[test]
+fn max_send_rate() {
+    let _guard = subscribe();
+    const RATE: u64 = 100_000;
+    const SIZE: usize = 200_000;
+    let mut pair = Pair::default();
+    let (client_ch, server_ch) = pair.connect();
+    let now = pair.time;
+    pair.client_conn_mut(client_ch)
+        .set_max_send_rate(now, Some(RATE));
+
+    let start = pair.time;
+    let tx_start = pair.client_conn_mut(client_ch).stats().udp_tx.bytes;
+    let s = pair.client_streams(client_ch).open(Dir::Uni).unwrap();
+    let msg = vec![0xAB; SIZE];
+    assert_eq!(pair.client_send(client_ch, s).write(&msg), Ok(SIZE));
+    pair.client_send(client_ch, s).finish().unwrap();
+    pair.drive();
+
+    assert_matches!(pair.server_streams(server_ch).accept(Dir::Uni), Some(stream) if stream == s);
+    assert_eq!(stream_chunks(pair.server_recv(server_ch, s)).len(), SIZE);
+
+    // Every byte was delivered, but no faster than the configured rate
+    let elapsed = pair.time - start;
+    let tx = pair.client_conn_mut(client_ch).stats().udp_tx.bytes - tx_start;
+    assert!(elapsed >= Duration::from_secs_f64(SIZE as f64 / RATE as f64 * 0.95));
+    assert!(tx as f64 / elapsed.as_secs_f64() <= RATE as f64 * 1.05);
+}
+
+#[test]
+fn outgoing_ecn() {
+    let _guard = subscribe();
+    let mut transport = TransportConfig::default();
+    transport.outgoing_ecn(EcnPolicy::Ect1);
+    let mut config = client_config();
+    config.transport_config(Arc::new(transport));
+    let mut pair = Pair::default();
+    let (client_ch, server_ch) = pair.connect_with(config);
+    // ECT(1) feedback from the peer passes validation
+    assert!(pair.client_conn_mut(client_ch).using_ecn());
+    assert!(pair.server_conn_mut(server_ch).using_ecn());
+
+    let s = pair.client_streams(client_ch).open(Dir::Uni).unwrap();
+    pair.client_send(client_ch, s).write(&[42; 4096]).unwrap();
+    pair.drive_client();
+    assert!(!pair.server.inbound.is_empty());
+    assert!(
+        pair.server
+            .inbound
+            .iter()
+            .all(|(_, ecn, _)| *ecn == Some(EcnCodepoint::Ect1))
+    );
+    pair.drive();
+    assert!(pair.client_conn_mut(client_ch).using_ecn());
+
+    // The policy carries over to new paths
+    pair.client.addr = SocketAddr::new(
+        Ipv4Addr::new(127, 0, 0, 2).into(),
+        CLIENT_PORTS.lock().unwrap().next().unwrap(),
+    );
+    pair.client_conn_mut(client_ch).ping();
+    pair.drive_client();
+    assert!(!pair.server.inbound.is_empty());
+    assert!(
+        pair.server
+            .inbound
+            .iter()
+            .all(|(_, ecn, _)| *ecn == Some(EcnCodepoint::Ect1))
+    );
+    pair.drive();
+    assert!(pair.client_conn_mut(client_ch).using_ecn());
+
+    let mut transport = TransportConfig::default();
+    transport.outgoing_ecn(EcnPolicy::Disabled);
+    let mut config = client_config();
+    config.transport_config(Arc::new(transport));
+    let mut pair = Pair::default();
+    let (client_ch, server_ch) = pair.connect_with(config);
+    assert!(!pair.client_conn_mut(client_ch).using_ecn());
+    assert!(pair.server_conn_mut(server_ch).using_ecn());
+    pair.client_conn_mut(client_ch).ping();
+    pair.drive_client();
+    assert!(!pair.server.inbound.is_empty());
+    assert!(pair.server.inbound.iter().all(|(_, ecn, _)| ecn.is_none()));
+}
+
 fn test_flow_control(config: TransportConfig, window_size: usize) {
     let _guard = subscribe();
     let mut pair = Pair::new(
@@ -1511,6 +2997,205 @@ fn conn_flow_control() {
     );
 }
 
+#[test]
+fn set_receive_window() {
+    let _guard = subscribe();
+    let mut pair = Pair::new(
+        Default::default(),
+        ServerConfig {
+            transport: Arc::new(TransportConfig {
+                stream_receive_window: 2000u32.into(),
+                ..TransportConfig::default()
+            }),
+            ..server_config()
+        },
+    );
+    let (client_ch, server_ch) = pair.connect();
+    let msg = vec![0xAB; 5000];
+
+    let s = pair.client_streams(client_ch).open(Dir::Uni).unwrap();
+    assert_eq!(pair.client_send(client_ch, s).write(&msg), Ok(2000));
+    pair.drive();
+    assert_eq!(pair.server_streams(server_ch).accept(Dir::Uni), Some(s));
+
+    // Growing the window grants credit without any data being read
+    pair.server_recv(server_ch, s)
+        .set_receive_window(4000u32.into())
+        .unwrap();
+    pair.drive();
+    assert_eq!(pair.client_send(client_ch, s).write(&msg[2000..]), Ok(2000));
+    assert_eq!(
+        pair.client_send(client_ch, s).write(&msg[4000..]),
+        Err(WriteError::Blocked)
+    );
+
+    // Shrinking is ignored
+    pair.server_recv(server_ch, s)
+        .set_receive_window(1000u32.into())
+        .unwrap();
+    pair.drive();
+    let mut recv = pair.server_recv(server_ch, s);
+    let mut chunks = recv.read(false).unwrap();
+    let mut read = 0;
+    while let Ok(Some(chunk)) = chunks.next(usize::MAX) {
+        read += chunk.bytes.len();
+    }
+    assert_eq!(read, 4000);
+    let _ = chunks.finalize();
+    pair.drive();
+    assert_eq!(pair.client_send(client_ch, s).write(&msg[4000..]), Ok(1000));
+
+    pair.server_recv(server_ch, s).stop(0u32.into()).unwrap();
+    assert!(
+        pair.server_recv(server_ch, s)
+            .set_receive_window(8000u32.into())
+            .is_err()
+    );
+}
+
+#[test]
+fn set_max_receive_window() {
+    let _guard = subscribe();
+    let mut pair = Pair::new(
+        Default::default(),
+        ServerConfig {
+            transport: Arc::new(TransportConfig {
+                stream_receive_window: 2000u32.into(),
+                ..TransportConfig::default()
+            }),
+            ..server_config()
+        },
+    );
+    let (client_ch, server_ch) = pair.connect();
+    let msg = vec![0xAB; 5000];
+
+    let s = pair.client_streams(client_ch).open(Dir::Uni).unwrap();
+    assert_eq!(pair.client_send(client_ch, s).write(&msg), Ok(2000));
+    pair.drive();
+    assert_eq!(pair.server_streams(server_ch).accept(Dir::Uni), Some(s));
+
+    // The cap wins over a larger per-stream window
+    pair.server_recv(server_ch, s)
+        .set_receive_window(4000u32.into())
+        .unwrap();
+    pair.server_recv(server_ch, s)
+        .set_max_receive_window(500u32.into())
+        .unwrap();
+    pair.drive();
+    assert_eq!(
+        pair.client_send(client_ch, s).write(&msg[2000..]),
+        Err(WriteError::Blocked)
+    );
+
+    // Reading only grants credit up to the capped window past the read offset
+    let mut recv = pair.server_recv(server_ch, s);
+    let mut chunks = recv.read(true).unwrap();
+    let mut read = 0;
+    while read < 2000 {
+        read += chunks.next(2000 - read).unwrap().unwrap().bytes.len();
+    }
+    let _ = chunks.finalize();
+    pair.drive();
+    assert_eq!(pair.client_send(client_ch, s).write(&msg[2000..]), Ok(500));
+
+    pair.server_recv(server_ch, s).stop(0u32.into()).unwrap();
+    assert!(
+        pair.server_recv(server_ch, s)
+            .set_max_receive_window(8000u32.into())
+            .is_err()
+    );
+}
+
+#[test]
+fn ping_tracked() {
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+    let (client_ch, _) = pair.connect();
+    pair.drive();
+
+    let first = pair.client_conn_mut(client_ch).ping_tracked();
+    let second = pair.client_conn_mut(client_ch).ping_tracked();
+    pair.drive();
+    let mut acked = Vec::new();
+    while let Some(event) = pair.client_conn_mut(client_ch).poll() {
+        if let Event::PingAcknowledged { id, .. } = event {
+            acked.push(id);
+        }
+    }
+    assert_eq!(acked, [first, second]);
+
+    // A lost PING is sent again
+    let third = pair.client_conn_mut(client_ch).ping_tracked();
+    pair.drive_client();
+    pair.server.inbound.clear();
+    pair.drive();
+    assert_matches!(
+        pair.client_conn_mut(client_ch).poll(),
+        Some(Event::PingAcknowledged { id, .. }) if id == third
+    );
+}
+
+#[test]
+fn ping_tracked_across_discarded_space() {
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+    let client_ch = pair.begin_connect(client_config());
+    pair.drive_client();
+
+    // Lose the PING sent in the Initial space, which is discarded before it could be retransmitted
+    let id = pair.client_conn_mut(client_ch).ping_tracked();
+    while pair.server.inbound.len() < 2 {
+        // Wait out the pacer
+        pair.time += Duration::from_millis(1);
+        pair.drive_client();
+    }
+    pair.server.inbound.pop_back();
+    pair.drive();
+
+    let mut acked = false;
+    while let Some(event) = pair.client_conn_mut(client_ch).poll() {
+        acked |= matches!(event, Event::PingAcknowledged { id: x, .. } if x == id);
+    }
+    assert!(acked);
+}
+
+#[test]
+fn probe_path() {
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+    let (client_ch, _) = pair.connect();
+    pair.drive();
+    pair.latency = Duration::from_millis(10);
+
+    let server_addr = pair.server.addr;
+    let unreachable = SocketAddr::new(Ipv6Addr::LOCALHOST.into(), 1);
+    let reachable = pair.client_conn_mut(client_ch).probe_path(server_addr);
+    let lost = pair.client_conn_mut(client_ch).probe_path(unreachable);
+    pair.drive();
+
+    let mut results = Vec::new();
+    while let Some(event) = pair.client_conn_mut(client_ch).poll() {
+        if let Event::PathProbed { id, rtt } = event {
+            results.push((id, rtt));
+        }
+    }
+    assert_matches!(
+        results[..],
+        [(a, Some(rtt)), (b, None)] if a == reachable && b == lost && rtt >= 2 * pair.latency
+    );
+    assert_eq!(
+        pair.client_conn_mut(client_ch).remote_address(),
+        server_addr
+    );
+    assert_eq!(
+        pair.client_conn_mut(client_ch)
+            .stats()
+            .frame_tx
+            .path_challenge,
+        2
+    );
+}
+
 #[test]
 fn stop_opens_bidi() {
     let _guard = subscribe();
@@ -1630,6 +3315,54 @@ fn keep_alive() {
     }
 }
 
+#[test]
+fn keep_alive_when_streams_open() {
+    let _guard = subscribe();
+    const IDLE_TIMEOUT: u64 = 10;
+    let server = ServerConfig {
+        transport: Arc::new(TransportConfig {
+            keep_alive_interval: Some(Duration::from_millis(IDLE_TIMEOUT / 2)),
+            keep_alive_policy: KeepAlivePolicy::WhenStreamsOpen,
+            max_idle_timeout: Some(VarInt(IDLE_TIMEOUT)),
+            ..TransportConfig::default()
+        }),
+        ..server_config()
+    };
+    let mut pair = Pair::new(Default::default(), server);
+    let (client_ch, server_ch) = pair.connect();
+
+    // Keep-alives are sent while a stream is open
+    let s = pair.client_streams(client_ch).open(Dir::Uni).unwrap();
+    pair.client_send(client_ch, s).write(b"hello").unwrap();
+    pair.drive();
+    assert_eq!(pair.server_streams(server_ch).accept(Dir::Uni), Some(s));
+    let end = pair.time + Duration::from_millis(20 * IDLE_TIMEOUT);
+    while pair.time < end {
+        if !pair.step() {
+            if let Some(time) = min_opt(pair.client.next_wakeup(), pair.server.next_wakeup()) {
+                pair.time = time;
+            }
+        }
+        assert!(!pair.server_conn_mut(server_ch).is_closed());
+    }
+    assert!(pair.server_conn_mut(server_ch).stats().frame_tx.ping > 0);
+
+    // Once every stream is closed, the connection is left to time out
+    pair.client_send(client_ch, s).finish().unwrap();
+    pair.drive();
+    assert_eq!(stream_chunks(pair.server_recv(server_ch, s)), b"hello");
+    pair.drive();
+    let pings = pair.server_conn_mut(server_ch).stats().frame_tx.ping;
+    while !pair.server_conn_mut(server_ch).is_closed() {
+        if !pair.step() {
+            if let Some(time) = min_opt(pair.client.next_wakeup(), pair.server.next_wakeup()) {
+                pair.time = time;
+            }
+        }
+    }
+    assert_eq!(pair.server_conn_mut(server_ch).stats().frame_tx.ping, pings);
+}
+
 #[test]
 fn cid_rotation() {
     let _guard = subscribe();
@@ -1724,6 +3457,85 @@ fn cid_retirement() {
     );
 }
 
+#[test]
+fn extra_transport_parameters() {
+    let _guard = subscribe();
+    const ID: u64 = 0x7a_5e_01;
+
+    let mut transport = TransportConfig::default();
+    assert_matches!(
+        transport.extra_transport_parameters(vec![(0x03, vec![0x40, 0x00])]),
+        Err(ConfigError::UnavailableTransportParameter)
+    );
+    assert_matches!(
+        transport.extra_transport_parameters(vec![(ID, vec![1]), (ID, vec![2])]),
+        Err(ConfigError::UnavailableTransportParameter)
+    );
+    transport
+        .extra_transport_parameters(vec![(ID, b"experimental".to_vec())])
+        .unwrap();
+    let mut client_config = client_config();
+    client_config.transport_config(Arc::new(transport));
+
+    let mut pair = Pair::default();
+    let (client_ch, server_ch) = pair.connect_with(client_config);
+
+    let params = pair
+        .server_conn_mut(server_ch)
+        .peer_extra_transport_params()
+        .unwrap();
+    assert_eq!(params.get(ID), Some(&b"experimental"[..]));
+    let params = pair
+        .client_conn_mut(client_ch)
+        .peer_extra_transport_params()
+        .unwrap();
+    assert!(params.is_empty());
+}
+
+#[test]
+fn current_local_cid() {
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+    let (client_ch, server_ch) = pair.connect();
+
+    let handshake_cid = pair.server_conn_mut(server_ch).current_local_cid();
+    let local_cids = pair.server_conn_mut(server_ch).local_cids();
+    assert_eq!(local_cids[0], handshake_cid);
+    assert!(local_cids.len() > 1);
+
+    // Ask the client to move off the handshake CID
+    pair.server_conn_mut(server_ch)
+        .rotate_local_cid(1, Instant::now());
+    pair.drive();
+    assert!(
+        !pair
+            .server_conn_mut(server_ch)
+            .local_cids()
+            .contains(&handshake_cid)
+    );
+
+    pair.client_conn_mut(client_ch).ping();
+    pair.client.drive(pair.time, pair.server.addr);
+    let sent_cids = pair
+        .client
+        .outbound
+        .iter()
+        .filter(|(_, buffer)| buffer[0] & packet::LONG_HEADER_FORM == 0)
+        .map(|(_, buffer)| ConnectionId::new(&buffer[1..1 + handshake_cid.len()]))
+        .collect::<Vec<_>>();
+    assert!(!sent_cids.is_empty());
+    pair.drive();
+
+    let current = pair.server_conn_mut(server_ch).current_local_cid();
+    assert_ne!(current, handshake_cid);
+    assert!(sent_cids.iter().all(|&cid| cid == current));
+    assert!(
+        pair.server_conn_mut(server_ch)
+            .local_cids()
+            .contains(&current)
+    );
+}
+
 #[test]
 fn finish_stream_flow_control_reordered() {
     let _guard = subscribe();
@@ -2029,9 +3841,20 @@ fn datagram_recv_buffer_overflow() {
         pair.server_conn_mut(server_ch).poll(),
         Some(Event::DatagramReceived)
     );
+    assert_eq!(
+        pair.server_conn_mut(server_ch)
+            .stats()
+            .datagrams_dropped_incoming,
+        1
+    );
+    assert_eq!(
+        pair.server_datagrams(server_ch).recv_buffer_space(),
+        WINDOW - DATA2.len() - DATA3.len()
+    );
     assert_eq!(pair.server_datagrams(server_ch).recv().unwrap(), DATA2);
     assert_eq!(pair.server_datagrams(server_ch).recv().unwrap(), DATA3);
     assert_matches!(pair.server_datagrams(server_ch).recv(), None);
+    assert_eq!(pair.server_datagrams(server_ch).recv_buffer_space(), WINDOW);
 
     pair.client_datagrams(client_ch)
         .send(DATA1.into(), true)
@@ -2041,6 +3864,38 @@ fn datagram_recv_buffer_overflow() {
     assert_matches!(pair.server_datagrams(server_ch).recv(), None);
 }
 
+#[test]
+fn datagram_send_tracked_reports_eviction() {
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+    let mut client_config = client_config();
+    let mut transport_config = TransportConfig::default();
+    transport_config.datagram_send_buffer_size(10);
+    client_config.transport_config(transport_config.into());
+    let (client_ch, server_ch) = pair.connect_with(client_config);
+
+    const DATA1: &[u8] = &[0xAB; 6];
+    const DATA2: &[u8] = &[0xBC; 6];
+    let first = pair
+        .client_datagrams(client_ch)
+        .send_tracked(DATA1.into(), true)
+        .unwrap();
+    let second = pair
+        .client_datagrams(client_ch)
+        .send_tracked(DATA2.into(), true)
+        .unwrap();
+    assert_ne!(first, second);
+    assert_matches!(
+        pair.client_conn_mut(client_ch).poll(),
+        Some(Event::DatagramDropped { id }) if id == first
+    );
+
+    pair.drive();
+    assert_matches!(pair.client_conn_mut(client_ch).poll(), None);
+    assert_eq!(pair.server_datagrams(server_ch).recv().unwrap(), DATA2);
+    assert_matches!(pair.server_datagrams(server_ch).recv(), None);
+}
+
 #[test]
 fn datagram_larger_than_send_buffer_is_too_large() {
     let _guard = subscribe();
@@ -2275,11 +4130,48 @@ fn repeated_request_response() {
 
 /// Ensures that the client sends an anti-deadlock probe after an incomplete server's first flight
 #[test]
-fn handshake_anti_deadlock_probe() {
+fn handshake_anti_deadlock_probe() {
+    let _guard = subscribe();
+
+    let (cert, key) = big_cert_and_key();
+    let server = server_config_with_cert(cert.clone(), key);
+    let client = client_config_with_certs(vec![cert]);
+    let mut pair = Pair::new(Default::default(), server);
+
+    let client_ch = pair.begin_connect(client);
+    // Client sends initial
+    pair.drive_client();
+    // Server sends first flight, gets blocked on anti-amplification
+    pair.drive_server();
+    // Client acks...
+    pair.drive_client();
+    // ...but it's lost, so the server doesn't get anti-amplification credit from it
+    pair.server.inbound.clear();
+    // Client sends an anti-deadlock probe, and the handshake completes as usual.
+    pair.drive();
+    assert_matches!(
+        pair.client_conn_mut(client_ch).poll(),
+        Some(Event::HandshakeDataReady)
+    );
+    assert_matches!(
+        pair.client_conn_mut(client_ch).poll(),
+        Some(Event::Connected)
+    );
+}
+
+/// Ensures that the server can respond with 3 initial packets during the handshake
+/// before the anti-amplification limit kicks in when MTUs are similar.
+#[test]
+fn server_can_send_3_inital_packets() {
     let _guard = subscribe();
+    let mut transport = TransportConfig::default();
+    // Assume a low-latency connection so pacing doesn't interfere with the test
+    transport.initial_rtt(Duration::from_millis(10));
+    let transport = Arc::new(transport);
 
     let (cert, key) = big_cert_and_key();
-    let server = server_config_with_cert(cert.clone(), key);
+    let mut server = server_config_with_cert(cert.clone(), key);
+    server.transport_config(transport);
     let client = client_config_with_certs(vec![cert]);
     let mut pair = Pair::new(Default::default(), server);
 
@@ -2288,11 +4180,9 @@ fn handshake_anti_deadlock_probe() {
     pair.drive_client();
     // Server sends first flight, gets blocked on anti-amplification
     pair.drive_server();
-    // Client acks...
-    pair.drive_client();
-    // ...but it's lost, so the server doesn't get anti-amplification credit from it
-    pair.server.inbound.clear();
-    // Client sends an anti-deadlock probe, and the handshake completes as usual.
+    // Server should have queued 3 packets at this time
+    assert_eq!(pair.client.inbound.len(), 3);
+
     pair.drive();
     assert_matches!(
         pair.client_conn_mut(client_ch).poll(),
@@ -2304,10 +4194,10 @@ fn handshake_anti_deadlock_probe() {
     );
 }
 
-/// Ensures that the server can respond with 3 initial packets during the handshake
-/// before the anti-amplification limit kicks in when MTUs are similar.
+/// Ensures that raising the anti-amplification factor lets the server send proportionally more
+/// before the client's address is validated
 #[test]
-fn server_can_send_3_inital_packets() {
+fn anti_amplification_factor() {
     let _guard = subscribe();
     let mut transport = TransportConfig::default();
     // Assume a low-latency connection so pacing doesn't interfere with the test
@@ -2317,16 +4207,16 @@ fn server_can_send_3_inital_packets() {
     let (cert, key) = big_cert_and_key();
     let mut server = server_config_with_cert(cert.clone(), key);
     server.transport_config(transport);
+    server.insecure_anti_amplification_factor(5);
     let client = client_config_with_certs(vec![cert]);
     let mut pair = Pair::new(Default::default(), server);
 
     let client_ch = pair.begin_connect(client);
     // Client sends initial
     pair.drive_client();
-    // Server sends first flight, gets blocked on anti-amplification
+    // Server sends first flight, gets blocked on the raised anti-amplification limit
     pair.drive_server();
-    // Server should have queued 3 packets at this time
-    assert_eq!(pair.client.inbound.len(), 3);
+    assert_eq!(pair.client.inbound.len(), 5);
 
     pair.drive();
     assert_matches!(
@@ -2339,6 +4229,30 @@ fn server_can_send_3_inital_packets() {
     );
 }
 
+/// Ensures that errors caused by a frame identify its type
+#[test]
+fn illegal_frame_type_reported() {
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+
+    // MAX_DATA is only permitted in 1-RTT packets
+    let mut frames = Vec::new();
+    frames.write(FrameType::MAX_DATA);
+    frames.write(VarInt(1));
+    let packet = client_initial(&frames);
+
+    pair.server
+        .inbound
+        .push_back((pair.time, None, packet[..].into()));
+    pair.drive_server();
+    assert_matches!(
+        pair.server.assert_accept_error(),
+        ConnectionError::TransportError(err)
+            if err.code == TransportErrorCode::PROTOCOL_VIOLATION
+                && err.frame == Some(FrameType::MAX_DATA)
+    );
+}
+
 /// Generate a big fat certificate that can't fit inside the initial anti-amplification limit
 fn big_cert_and_key() -> (CertificateDer<'static>, PrivateKeyDer<'static>) {
     let cert = rcgen::generate_simple_self_signed(
@@ -2397,6 +4311,34 @@ fn loss_probe_requests_immediate_ack() {
     );
 }
 
+#[test]
+fn pto_count_stats() {
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+    let (client_ch, _) = pair.connect();
+    pair.drive();
+    assert_eq!(pair.client_conn_mut(client_ch).stats().path.pto_count, 0);
+
+    // Lose everything from now on
+    pair.mtu = 0;
+    pair.client_conn_mut(client_ch).ping();
+    pair.drive_client();
+    let stats = pair.client_conn_mut(client_ch).stats();
+    assert_eq!(stats.path.packets_in_flight, 1);
+    assert_eq!(stats.path.pto_count, 0);
+
+    for expected in 1..=3 {
+        // Step through any unrelated timers until the next probe timeout fires
+        while pair.client_conn_mut(client_ch).stats().path.pto_count < expected {
+            pair.time = pair.time.max(pair.client.next_wakeup().unwrap());
+            pair.drive_client();
+        }
+        let stats = pair.client_conn_mut(client_ch).stats();
+        assert_eq!(stats.path.pto_count, expected);
+        assert!(stats.path.packets_in_flight > u64::from(expected));
+    }
+}
+
 #[test]
 /// This is mostly a sanity check to ensure our testing code is correctly dropping packets above the
 /// pmtu
@@ -2451,6 +4393,58 @@ fn connect_detects_mtu() {
     }
 }
 
+#[test]
+fn connect_detects_jumbo_mtu() {
+    let _guard = subscribe();
+    let mut endpoint_config = EndpointConfig::default();
+    endpoint_config.max_udp_payload_size(9000).unwrap();
+    let mut mtud = MtuDiscoveryConfig::default();
+    mtud.upper_bound(9000).minimum_change(10);
+    let mut transport = TransportConfig::default();
+    transport.mtu_discovery_config(Some(mtud));
+    let transport = Arc::new(transport);
+
+    let mut server_config = server_config();
+    server_config.transport = transport.clone();
+    let mut pair = Pair::new(Arc::new(endpoint_config), server_config);
+    pair.mtu = 9000;
+    let mut client_config = client_config();
+    client_config.transport_config(transport);
+    let (client_ch, server_ch) = pair.connect_with(client_config);
+    pair.drive();
+
+    for mtu in [
+        pair.client_conn_mut(client_ch).path_mtu(),
+        pair.server_conn_mut(server_ch).path_mtu(),
+    ] {
+        assert!((9000 - 20..=9000).contains(&mtu), "discovered {mtu}");
+    }
+}
+
+#[test]
+fn peer_max_udp_payload_size() {
+    let _guard = subscribe();
+    let mut endpoint_config = EndpointConfig::default();
+    endpoint_config.max_udp_payload_size(1300).unwrap();
+    let mut pair = Pair::new(Arc::new(endpoint_config), server_config());
+
+    let client_ch = pair.begin_connect(client_config());
+    assert_eq!(
+        pair.client_conn_mut(client_ch).peer_max_udp_payload_size(),
+        65527
+    );
+    pair.drive();
+    let server_ch = pair.server.assert_accept();
+    assert_eq!(
+        pair.client_conn_mut(client_ch).peer_max_udp_payload_size(),
+        1300
+    );
+    assert_eq!(
+        pair.server_conn_mut(server_ch).peer_max_udp_payload_size(),
+        1300
+    );
+}
+
 #[test]
 fn migrate_detects_new_mtu_and_respects_original_peer_max_udp_payload_size() {
     let _guard = subscribe();
@@ -2715,6 +4709,48 @@ fn single_ack_eliciting_packet_triggers_ack_after_delay() {
     );
 }
 
+#[test]
+fn max_ack_ranges() {
+    let _guard = subscribe();
+    let mut server_config = server_config();
+    Arc::get_mut(&mut server_config.transport)
+        .unwrap()
+        .max_ack_ranges(4);
+    let mut pair = Pair::new(Default::default(), server_config);
+    let (client_ch, _) = pair.connect();
+
+    // Drop every other ack-eliciting packet, fragmenting the server's set of received packets
+    pair.client.capture_inbound_packets = true;
+    for i in 0..20 {
+        pair.client_conn_mut(client_ch).ping();
+        pair.client.drive(pair.time, pair.server.addr);
+        if i % 2 == 1 {
+            pair.client.outbound.clear();
+        }
+        pair.drive_client();
+        pair.drive_server();
+    }
+    pair.drive();
+
+    let ranges = pair
+        .client
+        .captured_packets
+        .drain(..)
+        .flat_map(|packet| {
+            frame::Iter::new(packet.into())
+                .unwrap()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+        })
+        .filter_map(|frame| match frame {
+            Frame::Ack(ack) => Some(ack.iter().count()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert!(ranges.iter().all(|&n| n <= 4), "{ranges:?}");
+    assert!(ranges.contains(&4), "{ranges:?}");
+}
+
 #[test]
 fn immediate_ack_triggers_ack() {
     let _guard = subscribe();
@@ -2792,6 +4828,101 @@ fn out_of_order_ack_eliciting_packet_triggers_ack() {
     );
 }
 
+#[test]
+fn congestion_controller_sees_ecn_ce() {
+    /// Delegates to NewReno, recording every CE count it's told about
+    struct CeRecorder {
+        inner: Box<dyn congestion::Controller>,
+        marks: Arc<Mutex<Vec<u64>>>,
+    }
+
+    impl congestion::Controller for CeRecorder {
+        fn on_ecn_ce(&mut self, _: Instant, _: Instant, ce_count: u64) {
+            self.marks.lock().unwrap().push(ce_count);
+        }
+
+        fn on_congestion_event(
+            &mut self,
+            now: Instant,
+            sent: Instant,
+            is_persistent_congestion: bool,
+            is_ecn: bool,
+            lost_bytes: u64,
+        ) {
+            self.inner
+                .on_congestion_event(now, sent, is_persistent_congestion, is_ecn, lost_bytes)
+        }
+
+        fn on_mtu_update(&mut self, new_mtu: u16) {
+            self.inner.on_mtu_update(new_mtu)
+        }
+
+        fn window(&self) -> u64 {
+            self.inner.window()
+        }
+
+        fn clone_box(&self) -> Box<dyn congestion::Controller> {
+            Box::new(Self {
+                inner: self.inner.clone_box(),
+                marks: self.marks.clone(),
+            })
+        }
+
+        fn initial_window(&self) -> u64 {
+            self.inner.initial_window()
+        }
+
+        fn into_any(self: Box<Self>) -> Box<dyn std::any::Any> {
+            self
+        }
+    }
+
+    struct CeRecorderFactory(Arc<Mutex<Vec<u64>>>);
+
+    impl congestion::ControllerFactory for CeRecorderFactory {
+        fn build(
+            self: Arc<Self>,
+            now: Instant,
+            current_mtu: u16,
+        ) -> Box<dyn congestion::Controller> {
+            Box::new(CeRecorder {
+                inner: Arc::new(congestion::NewRenoConfig::default()).build(now, current_mtu),
+                marks: self.0.clone(),
+            })
+        }
+    }
+
+    let _guard = subscribe();
+    let marks = Arc::new(Mutex::new(Vec::new()));
+    let mut transport = TransportConfig::default();
+    transport.congestion_controller_factory(Arc::new(CeRecorderFactory(marks.clone())));
+    let mut client_config = client_config();
+    client_config.transport_config(Arc::new(transport));
+    let mut pair = Pair::default();
+    let (client_ch, _) = pair.connect_with(client_config);
+    pair.drive();
+    assert!(marks.lock().unwrap().is_empty());
+    let events_before = pair
+        .client_conn_mut(client_ch)
+        .stats()
+        .path
+        .congestion_events;
+
+    pair.client_conn_mut(client_ch).ping();
+    pair.congestion_experienced = true;
+    pair.drive_client();
+    pair.congestion_experienced = false;
+    pair.drive();
+    assert_eq!(*marks.lock().unwrap(), [1]);
+    assert_eq!(
+        pair.client_conn_mut(client_ch)
+            .stats()
+            .path
+            .congestion_events,
+        events_before + 1
+    );
+}
+
 #[test]
 fn single_ack_eliciting_packet_with_ce_bit_triggers_immediate_ack() {
     let _guard = subscribe();
@@ -3003,6 +5134,61 @@ fn ack_frequency_ack_sent_after_packets_above_threshold() {
     );
 }
 
+#[test]
+fn ack_frequency_reduces_acks_during_bulk_transfer() {
+    let _guard = subscribe();
+
+    /// Count the ACK frames sent by a client downloading a large stream from the server
+    fn client_acks(ack_frequency_config: Option<AckFrequencyConfig>) -> u64 {
+        let mut server_config = server_config();
+        Arc::get_mut(&mut server_config.transport)
+            .unwrap()
+            .ack_frequency_config(ack_frequency_config)
+            .mtu_discovery_config(None);
+        let mut pair = Pair::new(Default::default(), server_config);
+        let (client_ch, server_ch) = pair.connect();
+        pair.drive();
+        let acks_before = pair.client_conn_mut(client_ch).stats().frame_tx.acks;
+
+        const LEN: usize = 512 * 1024;
+        let s = pair.server_streams(server_ch).open(Dir::Uni).unwrap();
+        assert_eq!(pair.server_send(server_ch, s).write(&[0xab; LEN]), Ok(LEN));
+        // Hand the client one datagram at a time, so that it could acknowledge each of them
+        loop {
+            for datagram in mem::take(&mut pair.client.inbound) {
+                pair.client.inbound.push_back(datagram);
+                pair.drive_client();
+            }
+            if !pair.step() {
+                break;
+            }
+        }
+
+        let s = pair.client_streams(client_ch).accept(Dir::Uni).unwrap();
+        let mut recv = pair.client_recv(client_ch, s);
+        let mut chunks = recv.read(false).unwrap();
+        let mut received = 0;
+        while let Ok(Some(chunk)) = chunks.next(usize::MAX) {
+            received += chunk.bytes.len();
+        }
+        let _ = chunks.finalize();
+        assert_eq!(received, LEN);
+
+        pair.client_conn_mut(client_ch).stats().frame_tx.acks - acks_before
+    }
+
+    let default_acks = client_acks(None);
+    let mut ack_freq_config = AckFrequencyConfig::default();
+    ack_freq_config
+        .ack_eliciting_threshold(20u32.into())
+        .max_ack_delay(Some(Duration::from_millis(50)));
+    let reduced_acks = client_acks(Some(ack_freq_config));
+    assert!(
+        reduced_acks < default_acks,
+        "{reduced_acks} ACKs sent with a high threshold, {default_acks} by default"
+    );
+}
+
 #[test]
 fn ack_frequency_ack_sent_after_reordered_packets_below_threshold() {
     let _guard = subscribe();
@@ -3215,6 +5401,109 @@ fn silently_drop_rejected_initials() {
     );
 }
 
+struct RejectAll;
+
+impl ConnectionRateLimiter for RejectAll {
+    fn should_accept(&self, _remote: SocketAddr, _now: Instant) -> bool {
+        false
+    }
+}
+
+#[test]
+fn rate_limited_initials() {
+    let _guard = subscribe();
+    let mut config = server_config();
+    config.connection_rate_limiter(Some(Arc::new(RejectAll)), false);
+    let mut pair = Pair::new(Arc::new(EndpointConfig::default()), config);
+
+    let client_ch = pair.begin_connect(client_config());
+    pair.drive();
+    pair.server.assert_no_accept();
+    pair.time += Duration::from_secs(60);
+    pair.drive();
+    assert_matches!(
+        pair.client_conn_mut(client_ch).poll(),
+        Some(Event::ConnectionLost {
+            reason: ConnectionError::TimedOut,
+        })
+    );
+
+    let mut config = server_config();
+    config.connection_rate_limiter(Some(Arc::new(RejectAll)), true);
+    let mut pair = Pair::new(Arc::new(EndpointConfig::default()), config);
+
+    let client_ch = pair.begin_connect(client_config());
+    pair.drive();
+    pair.server.assert_no_accept();
+    assert_matches!(
+        pair.client_conn_mut(client_ch).poll(),
+        Some(Event::ConnectionLost {
+            reason: ConnectionError::ConnectionClosed(close)
+        }) if close.error_code == TransportErrorCode::CONNECTION_REFUSED
+    );
+}
+
+/// Admits a fixed number of connection attempts, then rejects the rest
+struct Burst(AtomicUsize);
+
+impl ConnectionRateLimiter for Burst {
+    fn should_accept(&self, _remote: SocketAddr, _now: Instant) -> bool {
+        self.0
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |x| x.checked_sub(1))
+            .is_ok()
+    }
+}
+
+#[test]
+fn rate_limited_burst() {
+    let _guard = subscribe();
+    let mut config = server_config();
+    config.connection_rate_limiter(Some(Arc::new(Burst(AtomicUsize::new(2)))), true);
+    let mut pair = Pair::new(Arc::new(EndpointConfig::default()), config);
+
+    // The test harness drives one connection at a time, so set each admitted one aside
+    for _ in 0..2 {
+        pair.connect();
+        pair.client.connections.clear();
+        pair.server.connections.clear();
+    }
+    let client_ch = pair.begin_connect(client_config());
+    pair.drive();
+    pair.server.assert_no_accept();
+    assert_matches!(
+        pair.client_conn_mut(client_ch).poll(),
+        Some(Event::ConnectionLost {
+            reason: ConnectionError::ConnectionClosed(close)
+        }) if close.error_code == TransportErrorCode::CONNECTION_REFUSED
+    );
+}
+
+#[test]
+fn max_concurrent_connections() {
+    let _guard = subscribe();
+    let mut server_config = server_config();
+    server_config.max_concurrent_connections(2);
+    let mut pair = Pair::new(Arc::new(EndpointConfig::default()), server_config);
+
+    // The test harness drives one connection at a time, so set each accepted one aside. The
+    // endpoint keeps counting them until they're drained.
+    for _ in 0..2 {
+        pair.connect();
+        pair.client.connections.clear();
+        pair.server.connections.clear();
+    }
+
+    let client_ch = pair.begin_connect(client_config());
+    pair.drive();
+    pair.server.assert_no_accept();
+    assert_matches!(
+        pair.client_conn_mut(client_ch).poll(),
+        Some(Event::ConnectionLost {
+            reason: ConnectionError::ConnectionClosed(close)
+        }) if close.error_code == TransportErrorCode::CONNECTION_REFUSED
+    );
+}
+
 #[test]
 fn reject_manually() {
     let _guard = subscribe();
@@ -3687,3 +5976,44 @@ fn handshake_confirmation_no_resumption_shortcut() {
     );
     assert_matches!(pair.client_conn_mut(ch).poll(), None);
 }
+
+#[cfg(feature = "qlog")]
+#[test]
+fn set_qlog_stream() {
+    /// Appends everything written to a shared buffer
+    struct SharedWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for SharedWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let _guard = subscribe();
+    let mut pair = Pair::default();
+    let (client_ch, _) = pair.connect();
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut config = QlogConfig::default();
+    config.writer(Box::new(SharedWriter(log.clone())));
+    pair.client_conn_mut(client_ch)
+        .set_qlog_stream(config.into_stream());
+    pair.client_conn_mut(client_ch).ping();
+    pair.drive();
+
+    // Only events after the stream was attached are logged
+    let written = String::from_utf8(log.lock().unwrap().clone()).unwrap();
+    assert!(written.contains("packet_sent"));
+    assert!(!written.contains("\"initial\""));
+
+    let len = written.len();
+    pair.client_conn_mut(client_ch).set_qlog_stream(None);
+    pair.client_conn_mut(client_ch).ping();
+    pair.drive();
+    assert_eq!(log.lock().unwrap().len(), len);
+}
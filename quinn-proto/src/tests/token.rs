@@ -1,5 +1,7 @@
 //! Tests specifically for tokens
 
+use std::sync::atomic::{AtomicUsize, Ordering};
+
 use super::*;
 
 #[cfg(all(target_family = "wasm", target_os = "unknown"))]
@@ -60,6 +62,73 @@ fn retry_token_expired() {
     assert_eq!(pair.server.known_cids(), 0);
 }
 
+const PLAIN_RETRY_PREFIX: &[u8] = b"retry:";
+
+/// Retry tokens that carry the original destination CID in the clear, for testing only
+#[derive(Default)]
+struct PlainRetryTokens {
+    generated: AtomicUsize,
+    validated: AtomicUsize,
+}
+
+impl TokenGenerator for PlainRetryTokens {
+    fn generate_retry_token(
+        &self,
+        _address: SocketAddr,
+        orig_dst_cid: ConnectionId,
+        _issued: SystemTime,
+    ) -> Vec<u8> {
+        self.generated.fetch_add(1, Ordering::Relaxed);
+        [PLAIN_RETRY_PREFIX, &orig_dst_cid[..]].concat()
+    }
+}
+
+impl TokenValidator for PlainRetryTokens {
+    fn validate_retry_token(
+        &self,
+        token: &[u8],
+        _address: SocketAddr,
+        _now: SystemTime,
+    ) -> TokenValidation {
+        let Some(cid) = token.strip_prefix(PLAIN_RETRY_PREFIX) else {
+            return TokenValidation::Unrecognized;
+        };
+        self.validated.fetch_add(1, Ordering::Relaxed);
+        TokenValidation::Valid {
+            orig_dst_cid: ConnectionId::new(cid),
+        }
+    }
+}
+
+#[test]
+fn custom_retry_tokens() {
+    let _guard = subscribe();
+    let tokens = Arc::new(PlainRetryTokens::default());
+    let mut pair = Pair::default();
+    pair.server.handle_incoming = Box::new(validate_incoming);
+
+    let mut config = server_config();
+    config
+        .retry_token_generator(Some(tokens.clone()))
+        .retry_token_validator(Some(tokens.clone()));
+    pair.server.set_server_config(Some(Arc::new(config)));
+
+    let client_ch = pair.begin_connect(client_config());
+    pair.drive();
+    let server_ch = pair.server.assert_accept();
+    assert_matches!(
+        pair.client_conn_mut(client_ch).poll(),
+        Some(Event::HandshakeDataReady)
+    );
+    assert_matches!(
+        pair.client_conn_mut(client_ch).poll(),
+        Some(Event::Connected)
+    );
+    assert!(!pair.server_conn_mut(server_ch).is_closed());
+    assert_eq!(tokens.generated.load(Ordering::Relaxed), 1);
+    assert_eq!(tokens.validated.load(Ordering::Relaxed), 1);
+}
+
 #[test]
 fn use_token() {
     let _guard = subscribe();
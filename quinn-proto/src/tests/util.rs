@@ -4,7 +4,7 @@ use std::{
     env,
     io::{self, Write},
     mem,
-    net::{Ipv6Addr, SocketAddr, UdpSocket},
+    net::{IpAddr, Ipv6Addr, SocketAddr, UdpSocket},
     ops::RangeFrom,
     str,
     sync::{Arc, LazyLock, Mutex},
@@ -294,6 +294,8 @@ pub(super) struct TestEndpoint {
     pub(super) outbound: VecDeque<(Transmit, Bytes)>,
     delayed: VecDeque<(Transmit, Bytes)>,
     pub(super) inbound: VecDeque<(Instant, Option<EcnCodepoint>, BytesMut)>,
+    /// Local IP reported to the endpoint for incoming datagrams
+    pub(super) local_ip: Option<IpAddr>,
     accepted: Option<Result<ConnectionHandle, ConnectionError>>,
     pub(super) connections: HashMap<ConnectionHandle, Connection>,
     conn_events: HashMap<ConnectionHandle, VecDeque<ConnectionEvent>>,
@@ -338,6 +340,7 @@ impl TestEndpoint {
             outbound: VecDeque::new(),
             delayed: VecDeque::new(),
             inbound: VecDeque::new(),
+            local_ip: None,
             accepted: None,
             connections: HashMap::default(),
             conn_events: HashMap::default(),
@@ -367,9 +370,9 @@ impl TestEndpoint {
 
         while self.inbound.front().is_some_and(|x| x.0 <= now) {
             let (recv_time, ecn, packet) = self.inbound.pop_front().unwrap();
-            if let Some(event) = self
-                .endpoint
-                .handle(recv_time, remote, None, ecn, packet, &mut buf)
+            if let Some(event) =
+                self.endpoint
+                    .handle(recv_time, remote, self.local_ip, ecn, packet, &mut buf)
             {
                 match event {
                     DatagramEvent::NewConnection(incoming) => {
@@ -664,6 +667,31 @@ fn client_crypto_inner(
     inner.try_into().unwrap()
 }
 
+/// Hand-build a client's first Initial packet carrying `frames`, padded to the minimum size
+pub(super) fn client_initial(frames: &[u8]) -> Vec<u8> {
+    let dcid = ConnectionId::new(&[0xab; 8]);
+    let keys =
+        crypto::ServerConfig::initial_keys(&server_crypto(), DEFAULT_SUPPORTED_VERSIONS[0], dcid)
+            .unwrap();
+    let header = packet::Header::Initial(packet::InitialHeader {
+        number: packet::PacketNumber::U8(0),
+        src_cid: ConnectionId::new(&[0xcd; 8]),
+        dst_cid: dcid,
+        token: Bytes::new(),
+        version: DEFAULT_SUPPORTED_VERSIONS[0],
+    });
+    let mut buf = Vec::new();
+    let encode = header.encode(&mut buf);
+    buf.extend_from_slice(frames);
+    buf.resize(1200, 0);
+    encode.finish(
+        &mut buf,
+        &*keys.header.remote,
+        Some((0, &*keys.packet.remote)),
+    );
+    buf
+}
+
 pub(super) fn min_opt<T: Ord>(x: Option<T>, y: Option<T>) -> Option<T> {
     match (x, y) {
         (Some(x), Some(y)) => Some(cmp::min(x, y)),
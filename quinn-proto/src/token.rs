@@ -102,6 +102,64 @@ impl TokenStore for NoneTokenStore {
     }
 }
 
+/// Mints the tokens sent to clients in Retry packets
+///
+/// Replaces the built-in tokens, which are encrypted with [`ServerConfig::token_key`], e.g. so
+/// that tokens can be validated by a different node behind a load balancer. Usually paired with a
+/// [`TokenValidator`] that understands the generated tokens.
+///
+/// [`ServerConfig::token_key`]: crate::ServerConfig::token_key
+pub trait TokenGenerator: Send + Sync {
+    /// Create a token for a client at `address` whose first Initial packet had the destination
+    /// connection ID `orig_dst_cid`
+    ///
+    /// `issued` is taken from [`ServerConfig::time_source`](crate::ServerConfig::time_source).
+    /// Tokens are sent to the client in the clear, so must be protected against tampering.
+    fn generate_retry_token(
+        &self,
+        address: SocketAddr,
+        orig_dst_cid: ConnectionId,
+        issued: SystemTime,
+    ) -> Vec<u8>;
+}
+
+/// Validates tokens presented by clients following a Retry packet
+///
+/// See [`TokenGenerator`].
+pub trait TokenValidator: Send + Sync {
+    /// Check whether `token` was issued to a client at `address` and is still valid at `now`
+    ///
+    /// Implementations are responsible for enforcing their own expiry;
+    /// [`ServerConfig::retry_token_lifetime`](crate::ServerConfig::retry_token_lifetime) only
+    /// applies to built-in tokens.
+    fn validate_retry_token(
+        &self,
+        token: &[u8],
+        address: SocketAddr,
+        now: SystemTime,
+    ) -> TokenValidation;
+}
+
+/// Outcome of [`TokenValidator::validate_retry_token()`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum TokenValidation {
+    /// The token was issued in a Retry packet to this address and hasn't expired
+    Valid {
+        /// The `orig_dst_cid` the token was generated for
+        orig_dst_cid: ConnectionId,
+    },
+    /// The token was issued in a Retry packet, but not to this address or it has expired
+    ///
+    /// The connection attempt is refused with an `INVALID_TOKEN` error.
+    Invalid,
+    /// The token wasn't issued by the corresponding [`TokenGenerator`]
+    ///
+    /// The token is handled as if no validator was configured, e.g. as a token from a NEW_TOKEN
+    /// frame.
+    Unrecognized,
+}
+
 /// State in an `Incoming` determined by a token or lack thereof
 #[derive(Debug)]
 pub(crate) struct IncomingToken {
@@ -129,6 +187,24 @@ impl IncomingToken {
             return Ok(unvalidated);
         }
 
+        if let Some(validator) = &server_config.retry_token_validator {
+            match validator.validate_retry_token(
+                &header.token,
+                remote_address,
+                server_config.time_source.now(),
+            ) {
+                TokenValidation::Valid { orig_dst_cid } => {
+                    return Ok(Self {
+                        retry_src_cid: Some(header.dst_cid),
+                        orig_dst_cid,
+                        validated: true,
+                    });
+                }
+                TokenValidation::Invalid => return Err(InvalidRetryTokenError),
+                TokenValidation::Unrecognized => {}
+            }
+        }
+
         // In cases where a token cannot be decrypted/decoded, we must allow for the possibility
         // that this is caused not by client malfeasance, but by the token having been generated by
         // an incompatible endpoint, e.g. a different version or a neighbor behind the same load
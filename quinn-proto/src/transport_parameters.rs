@@ -7,6 +7,7 @@
 //! implementations of the `crypto::Session` trait.
 
 use std::{
+    collections::{BTreeMap, BTreeSet, btree_map},
     convert::TryFrom,
     net::{Ipv4Addr, Ipv6Addr, SocketAddrV4, SocketAddrV6},
 };
@@ -135,6 +136,10 @@ macro_rules! make_struct {
                     write_order: None,
                 }
             }
+
+            $($(#[$doc])* pub fn $name(&self) -> VarInt {
+                self.$name
+            })*
         }
     }
 }
@@ -160,6 +165,7 @@ impl TransportParameters {
             initial_max_stream_data_uni: config.stream_receive_window,
             max_udp_payload_size: endpoint_config.max_udp_payload_size,
             max_idle_timeout: config.max_idle_timeout.unwrap_or(VarInt(0)),
+            ack_delay_exponent: config.ack_delay_exponent.into(),
             disable_active_migration: server_config.is_some_and(|c| !c.migration),
             active_connection_id_limit: if cid_gen.cid_len() == 0 {
                 2 // i.e. default, i.e. unsent
@@ -211,6 +217,83 @@ impl TransportParameters {
     pub(crate) fn issue_cids_limit(&self) -> u64 {
         self.active_connection_id_limit.0.min(LOC_CID_COUNT)
     }
+
+    /// Whether the endpoint forbids active connection migration
+    pub fn disable_active_migration(&self) -> bool {
+        self.disable_active_migration
+    }
+
+    /// Maximum size of DATAGRAM frames the endpoint is willing to receive, if it supports them
+    pub fn max_datagram_frame_size(&self) -> Option<VarInt> {
+        self.max_datagram_frame_size
+    }
+
+    /// Whether the endpoint accepts QUIC packets containing any value for the fixed bit
+    pub fn grease_quic_bit(&self) -> bool {
+        self.grease_quic_bit
+    }
+
+    /// Minimum amount of time in microseconds by which the endpoint is able to delay sending
+    /// acknowledgments, if it supports QUIC Acknowledgement Frequency
+    pub fn min_ack_delay(&self) -> Option<VarInt> {
+        self.min_ack_delay
+    }
+
+    /// The IPv4 address the server would prefer clients migrate to after the handshake
+    pub fn preferred_address_v4(&self) -> Option<SocketAddrV4> {
+        self.preferred_address.as_ref()?.address_v4
+    }
+
+    /// The IPv6 address the server would prefer clients migrate to after the handshake
+    pub fn preferred_address_v6(&self) -> Option<SocketAddrV6> {
+        self.preferred_address.as_ref()?.address_v6
+    }
+}
+
+/// Transport parameters not otherwise understood by quinn, by ID
+///
+/// Carries extensions quinn doesn't implement natively: those configured with
+/// [`TransportConfig::extra_transport_parameters`] on the way out, and those a peer sent that
+/// quinn didn't recognize on the way in, excluding parameters reserved for greasing.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ExtraTransportParameters(BTreeMap<u64, Vec<u8>>);
+
+impl ExtraTransportParameters {
+    /// The value of the parameter with ID `id`, if present
+    pub fn get(&self, id: u64) -> Option<&[u8]> {
+        self.0.get(&id).map(|value| &value[..])
+    }
+
+    /// Iterate over the parameters as `(id, value)` pairs, in ascending order of ID
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &[u8])> {
+        self.0.iter().map(|(&id, value)| (id, &value[..]))
+    }
+
+    /// Whether there are no parameters
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Add a parameter, returning `false` if one with the same ID was already present
+    pub(crate) fn insert(&mut self, id: u64, value: Vec<u8>) -> bool {
+        match self.0.entry(id) {
+            btree_map::Entry::Vacant(entry) => {
+                entry.insert(value);
+                true
+            }
+            btree_map::Entry::Occupied(_) => false,
+        }
+    }
+
+    /// Encode the parameters into `w`, for appending to the output of
+    /// [`TransportParameters::write()`]
+    pub fn write<W: BufMut>(&self, w: &mut W) {
+        for (&id, value) in &self.0 {
+            w.write_var(id);
+            w.write_var(value.len() as u64);
+            w.put_slice(value);
+        }
+    }
 }
 
 /// A server's preferred address
@@ -406,8 +489,20 @@ impl TransportParameters {
 
     /// Decode `TransportParameters` from buffer
     pub fn read<R: Buf>(side: Side, r: &mut R) -> Result<Self, Error> {
+        Self::read_with_extra(side, r).map(|(params, _)| params)
+    }
+
+    /// Decode `TransportParameters` from buffer, along with any parameters not otherwise
+    /// understood by quinn
+    pub fn read_with_extra<R: Buf>(
+        side: Side,
+        r: &mut R,
+    ) -> Result<(Self, ExtraTransportParameters), Error> {
         // Initialize to protocol-specified defaults
         let mut params = Self::default();
+        let mut extra = ExtraTransportParameters::default();
+        // Reserved parameters aren't retained, but must not be repeated either
+        let mut reserved = BTreeSet::new();
 
         // State to check for duplicate transport parameters.
         macro_rules! param_state {
@@ -431,8 +526,19 @@ impl TransportParameters {
             }
             let len = len as usize;
             let Ok(id) = TransportParameterId::try_from(id) else {
-                // unknown transport parameters are ignored
-                r.advance(len);
+                // unknown transport parameters are ignored, but retained for inspection unless
+                // they're reserved
+                let fresh = if TransportParameterId::is_managed(id) {
+                    r.advance(len);
+                    reserved.insert(id)
+                } else {
+                    let mut value = vec![0; len];
+                    r.copy_to_slice(&mut value);
+                    extra.insert(id, value)
+                };
+                if !fresh {
+                    return Err(Error::Malformed);
+                }
                 continue;
             };
 
@@ -477,6 +583,12 @@ impl TransportParameters {
                     _ => return Err(Error::Malformed),
                 },
                 TransportParameterId::MinAckDelayDraft07 => params.min_ack_delay = Some(r.get()?),
+                TransportParameterId::ReservedTransportParameter => {
+                    if !reserved.insert(id as u64) {
+                        return Err(Error::Malformed);
+                    }
+                    r.advance(len);
+                }
                 _ => {
                     macro_rules! parse {
                         {$($(#[$doc:meta])* $name:ident ($id:ident) = $default:expr,)*} => {
@@ -527,7 +639,7 @@ impl TransportParameters {
             return Err(Error::IllegalValue);
         }
 
-        Ok(params)
+        Ok((params, extra))
     }
 }
 
@@ -666,6 +778,12 @@ impl TransportParameterId {
         Self::GreaseQuicBit,
         Self::MinAckDelayDraft07,
     ];
+
+    /// Whether `id` is reserved by quinn, either because quinn implements it or because it has
+    /// the form of a reserved parameter used for greasing
+    pub(crate) fn is_managed(id: u64) -> bool {
+        Self::try_from(id).is_ok() || id % 31 == 27
+    }
 }
 
 impl PartialEq<u64> for TransportParameterId {
@@ -754,6 +872,45 @@ mod test {
         );
     }
 
+    #[test]
+    fn extra_parameters_coding() {
+        let mut buf = Vec::new();
+        let params = TransportParameters {
+            grease_transport_parameter: Some(ReservedTransportParameter::random(&mut rand::rng())),
+            ..TransportParameters::default()
+        };
+        let mut extra = ExtraTransportParameters::default();
+        assert!(extra.insert(0x1234, b"hello".to_vec()));
+        assert!(extra.insert(0x42, Vec::new()));
+        assert!(!extra.insert(0x42, vec![1]));
+        params.write(&mut buf);
+        extra.write(&mut buf);
+        let (_, read_extra) =
+            TransportParameters::read_with_extra(Side::Client, &mut buf.as_slice()).unwrap();
+        assert_eq!(read_extra.get(0x1234), Some(&b"hello"[..]));
+        assert_eq!(read_extra.get(0x42), Some(&[][..]));
+        assert_eq!(read_extra.get(0x1235), None);
+        // The reserved parameter isn't retained
+        assert_eq!(read_extra, extra);
+    }
+
+    #[test]
+    fn duplicate_extra_parameters() {
+        for id in [0x1234, 27, 31 + 27] {
+            let mut buf = Vec::new();
+            TransportParameters::default().write(&mut buf);
+            for value in [1, 2] {
+                buf.write_var(id);
+                buf.write_var(1);
+                buf.put_u8(value);
+            }
+            assert_eq!(
+                TransportParameters::read(Side::Client, &mut buf.as_slice()),
+                Err(Error::Malformed)
+            );
+        }
+    }
+
     #[test]
     fn reserved_transport_parameter_generate_reserved_id() {
         let mut rngs = [
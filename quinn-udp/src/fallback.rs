@@ -82,11 +82,24 @@ impl UdpSocketState {
         1
     }
 
+    #[inline]
+    pub fn gso_enabled(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn disable_gso(&self) {}
+
     #[inline]
     pub fn gro_segments(&self) -> usize {
         1
     }
 
+    #[inline]
+    pub fn set_gro_segments(&self, _socket: UdpSockRef<'_>, _max: usize) -> io::Result<()> {
+        Ok(())
+    }
+
     /// Resize the send buffer of `socket` to `bytes`
     #[inline]
     pub fn set_send_buffer_size(&self, socket: UdpSockRef<'_>, bytes: usize) -> io::Result<()> {
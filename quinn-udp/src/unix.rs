@@ -32,7 +32,10 @@ use super::linux::{LinuxError, gso};
 pub struct UdpSocketState {
     last_send_error: Mutex<Instant>,
     max_gso_segments: AtomicUsize,
-    gro_segments: usize,
+    gro_segments: AtomicUsize,
+    /// Whether the kernel accepted `UDP_GRO`, allowing it to be toggled later
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    gro_supported: bool,
     may_fragment: bool,
 
     /// True if we have received EINVAL error from `sendmsg` system call at least once.
@@ -102,6 +105,8 @@ impl UdpSocketState {
             expect(unused_mut)
         )]
         let mut gro_segments = 1;
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        let mut gro_supported = false;
 
         #[cfg(any(target_os = "linux", target_os = "android"))]
         {
@@ -127,14 +132,12 @@ impl UdpSocketState {
             }
 
             if set_socket_option(&*io, libc::SOL_UDP, libc::UDP_GRO, OPTION_ON).is_ok() {
-                // As defined in net/ipv4/udp_offload.c
-                // #define UDP_GRO_CNT_MAX 64
-                //
                 // NOTE: this MUST be set to UDP_GRO_CNT_MAX to ensure that the receive buffer size
                 // (get_max_udp_payload_size() * gro_segments()) is large enough to hold the largest GRO
                 // list the kernel might potentially produce. See
                 // https://github.com/quinn-rs/quinn/pull/1354.
-                gro_segments = 64
+                gro_segments = UDP_GRO_CNT_MAX;
+                gro_supported = true;
             }
 
             if let Err(_err) =
@@ -201,7 +204,9 @@ impl UdpSocketState {
         Ok(Self {
             last_send_error: Mutex::new(now.checked_sub(2 * IO_ERROR_LOG_INTERVAL).unwrap_or(now)),
             max_gso_segments: AtomicUsize::new(gso::max_gso_segments(&*io)),
-            gro_segments,
+            gro_segments: AtomicUsize::new(gro_segments),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            gro_supported,
             may_fragment,
             sendmsg_einval: AtomicBool::new(false),
             #[cfg(apple_fast)]
@@ -320,6 +325,28 @@ impl UdpSocketState {
         self.max_gso_segments.load(Ordering::Relaxed)
     }
 
+    /// Whether sends are currently batched into multiple segments
+    ///
+    /// On Linux, this reflects whether `UDP_SEGMENT` was found to work on this socket, and becomes
+    /// `false` if GSO is later disabled due to send errors. On Apple platforms, it's `true` only
+    /// once [`set_apple_fast_path()`](Self::set_apple_fast_path) has been called.
+    #[inline]
+    pub fn gso_enabled(&self) -> bool {
+        self.max_gso_segments() > 1
+    }
+
+    /// Stop using Generic Send Offload (GSO) on this socket, regardless of what was probed
+    ///
+    /// Afterwards [`max_gso_segments()`](Self::max_gso_segments) is 1, so callers that respect it
+    /// put a single datagram in each [`Transmit`]. On Linux, a transmit that still contains
+    /// several segments is sent with one `sendmsg` call per datagram instead of `UDP_SEGMENT`.
+    ///
+    /// An escape hatch for environments where GSO is reported as available but silently drops
+    /// packets, as seen with some virtualized network devices on Linux.
+    pub fn disable_gso(&self) {
+        self.max_gso_segments.store(1, Ordering::Relaxed);
+    }
+
     /// The number of segments to read when GRO is enabled
     ///
     /// Used as a factor to compute the receive buffer size.
@@ -327,7 +354,41 @@ impl UdpSocketState {
     /// Returns 1 if the platform doesn't support GRO.
     #[inline]
     pub fn gro_segments(&self) -> usize {
-        self.gro_segments
+        self.gro_segments.load(Ordering::Relaxed)
+    }
+
+    /// Enable or disable GRO on `socket` by setting the number of segments to read at once
+    ///
+    /// `max` must be either 1, which disables GRO, or 64, the most segments current Linux kernels
+    /// will coalesce into a single receive (`UDP_GRO_CNT_MAX`), which enables it. The kernel can't
+    /// be told to coalesce fewer, so a receive buffer sized from any other [`gro_segments()`] could
+    /// be too small for the batches it produces. Other values fail with
+    /// [`io::ErrorKind::InvalidInput`].
+    ///
+    /// No-op on platforms that don't support GRO.
+    ///
+    /// [`gro_segments()`]: Self::gro_segments
+    #[cfg_attr(
+        not(any(target_os = "linux", target_os = "android")),
+        expect(unused_variables)
+    )]
+    pub fn set_gro_segments(&self, socket: UdpSockRef<'_>, max: usize) -> io::Result<()> {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        if self.gro_supported {
+            let value = match max {
+                1 => OPTION_OFF,
+                UDP_GRO_CNT_MAX => OPTION_ON,
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "GRO segments must be 1 or UDP_GRO_CNT_MAX",
+                    ));
+                }
+            };
+            set_socket_option(&*socket.0, libc::SOL_UDP, libc::UDP_GRO, value)?;
+            self.gro_segments.store(max, Ordering::Relaxed);
+        }
+        Ok(())
     }
 
     /// Resize the send buffer of `socket` to `bytes`
@@ -423,6 +484,23 @@ fn send(
     io: SockRef<'_>,
     transmit: &Transmit<'_>,
 ) -> io::Result<()> {
+    // Batches prepared before GSO was disabled are sent a datagram at a time. If sending stops
+    // partway, the caller may resend earlier datagrams, which QUIC tolerates as duplicates.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    if let Some(segment_size) = transmit.effective_segment_size() {
+        if state.max_gso_segments() == 1 {
+            for contents in transmit.contents.chunks(segment_size) {
+                let single = Transmit {
+                    contents,
+                    segment_size: None,
+                    ..*transmit
+                };
+                send(state, SockRef::from(&*io), &single)?;
+            }
+            return Ok(());
+        }
+    }
+
     #[allow(unused_mut)] // only mutable on FreeBSD
     let mut encode_src_ip = true;
     #[cfg(target_os = "freebsd")]
@@ -931,6 +1009,12 @@ pub(crate) fn set_socket_option(
 }
 
 const OPTION_ON: libc::c_int = 1;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const OPTION_OFF: libc::c_int = 0;
+
+/// As defined in net/ipv4/udp_offload.c
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const UDP_GRO_CNT_MAX: usize = 64;
 
 /// Calls `f` in a loop, retrying on `EINTR`
 ///
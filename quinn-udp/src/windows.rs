@@ -341,6 +341,20 @@ impl UdpSocketState {
         self.max_gso_segments.load(Ordering::Relaxed)
     }
 
+    /// Whether sends are currently batched into multiple segments using UDP Send Offload (USO)
+    #[inline]
+    pub fn gso_enabled(&self) -> bool {
+        self.max_gso_segments() > 1
+    }
+
+    /// Stop using UDP Send Offload (USO) on this socket, regardless of what was probed
+    ///
+    /// Afterwards [`max_gso_segments()`](Self::max_gso_segments) is 1, so callers that respect it
+    /// put a single datagram in each [`Transmit`].
+    pub fn disable_gso(&self) {
+        self.max_gso_segments.store(1, Ordering::Relaxed);
+    }
+
     /// The number of segments to read when GRO is enabled. Used as a factor to
     /// compute the receive buffer size.
     ///
@@ -351,6 +365,14 @@ impl UdpSocketState {
         64
     }
 
+    /// Set the number of segments to read when GRO is enabled
+    ///
+    /// No-op on Windows, where the coalesced size is configured in bytes by [`Self::set_gro()`].
+    #[inline]
+    pub fn set_gro_segments(&self, _socket: UdpSockRef<'_>, _max: usize) -> io::Result<()> {
+        Ok(())
+    }
+
     /// Resize the send buffer of `socket` to `bytes`
     #[inline]
     pub fn set_send_buffer_size(&self, socket: UdpSockRef<'_>, bytes: usize) -> io::Result<()> {
@@ -191,9 +191,9 @@ fn gso() {
     let recv = UdpSocket::bind((Ipv6Addr::LOCALHOST, 0))
         .or_else(|_| UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)))
         .unwrap();
-    let max_segments = UdpSocketState::new((&send).into())
-        .unwrap()
-        .max_gso_segments();
+    let state = UdpSocketState::new((&send).into()).unwrap();
+    let max_segments = state.max_gso_segments();
+    assert_eq!(state.gso_enabled(), max_segments > 1);
     let dst_addr = recv.local_addr().unwrap();
     const SEGMENT_SIZE: usize = 128;
     let msg = vec![0xAB; SEGMENT_SIZE * max_segments];
@@ -210,6 +210,76 @@ fn gso() {
     );
 }
 
+#[test]
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn gro_segments() {
+    let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let state = UdpSocketState::new((&socket).into()).unwrap();
+    if state.gro_segments() == 1 {
+        // GRO unsupported by this kernel
+        return;
+    }
+
+    state.set_gro_segments((&socket).into(), 1).unwrap();
+    assert_eq!(state.gro_segments(), 1);
+    state.set_gro_segments((&socket).into(), 64).unwrap();
+    assert_eq!(state.gro_segments(), 64);
+    // The kernel may coalesce up to 64 segments regardless, so nothing in between is accepted
+    for max in [0, 8, 1000] {
+        let err = state.set_gro_segments((&socket).into(), max).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+    assert_eq!(state.gro_segments(), 64);
+}
+
+#[test]
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn gso_disabled() {
+    let send = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let recv = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let send_state = UdpSocketState::new((&send).into()).unwrap();
+    let recv_state = UdpSocketState::new((&recv).into()).unwrap();
+    // Keep the receiver from coalescing datagrams, so each one is observed separately
+    recv_state.set_gro_segments((&recv).into(), 1).unwrap();
+    recv.set_nonblocking(false).unwrap();
+
+    send_state.disable_gso();
+    assert_eq!(send_state.max_gso_segments(), 1);
+    assert!(!send_state.gso_enabled());
+
+    // A batch prepared as if GSO were available is still sent, one datagram at a time
+    const SEGMENT_SIZE: usize = 128;
+    let msg = (0..3 * SEGMENT_SIZE).map(|i| i as u8).collect::<Vec<_>>();
+    send_state
+        .try_send(
+            (&send).into(),
+            &Transmit {
+                destination: recv.local_addr().unwrap(),
+                ecn: None,
+                contents: &msg,
+                segment_size: Some(SEGMENT_SIZE),
+                src_ip: None,
+            },
+        )
+        .unwrap();
+
+    let mut buf = [0; u16::MAX as usize];
+    for expected in msg.chunks(SEGMENT_SIZE) {
+        let mut meta = RecvMeta::default();
+        let n = recv_state
+            .recv(
+                (&recv).into(),
+                &mut [IoSliceMut::new(&mut buf)],
+                slice::from_mut(&mut meta),
+            )
+            .unwrap();
+        assert_eq!(n, 1);
+        assert_eq!(meta.len, SEGMENT_SIZE);
+        assert_eq!(meta.stride, SEGMENT_SIZE);
+        assert_eq!(&buf[..meta.len], expected);
+    }
+}
+
 #[test]
 fn socket_buffers() {
     const BUFFER_SIZE: usize = 123456;
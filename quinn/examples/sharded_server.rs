@@ -0,0 +1,85 @@
+//! This example demonstrates how to spread a server over several endpoints sharing one address.
+//!
+//! Checkout the `README.md` for guidance.
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod common;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    use std::{
+        net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+        sync::Arc,
+    };
+
+    use common::{configure_server, make_client_endpoint};
+    use quinn::{Endpoint, EndpointConfig, TokioRuntime};
+
+    const SHARDS: usize = 4;
+
+    let (server_config, server_cert) = configure_server()?;
+    let shards = Endpoint::new_sharded(
+        EndpointConfig::default(),
+        server_config,
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 5000),
+        SHARDS,
+        Arc::new(TokioRuntime),
+    )?;
+    // every shard accepts the connections whose first packet the kernel delivered to it
+    for (index, shard) in shards.iter().enumerate() {
+        let shard = shard.clone();
+        tokio::spawn(async move {
+            while let Some(incoming) = shard.accept().await {
+                let conn = incoming.await.unwrap();
+                println!(
+                    "[server] shard {index} accepted connection: addr={}",
+                    conn.remote_address()
+                );
+                tokio::spawn(async move {
+                    while let Ok((mut send, mut recv)) = conn.accept_bi().await {
+                        let msg = recv.read_to_end(64).await.unwrap();
+                        send.write_all(&msg).await.unwrap();
+                        send.finish().unwrap();
+                    }
+                });
+            }
+        });
+    }
+
+    let server_addr = shards[0].local_addr()?;
+    let endpoint = make_client_endpoint("127.0.0.1:0".parse().unwrap(), &[&server_cert])?;
+    let connection = endpoint.connect(server_addr, "localhost")?.await?;
+
+    // Each new port is hashed to a shard anew, but the connection keeps being served by the shard
+    // that accepted it
+    for _ in 0..4 {
+        endpoint.rebind(UdpSocket::bind("127.0.0.1:0")?)?;
+        let (mut send, mut recv) = connection.open_bi().await?;
+        send.write_all(b"ping").await?;
+        send.finish()?;
+        let echoed = recv.read_to_end(64).await?;
+        println!(
+            "[client] migrated to {}, echoed {} bytes",
+            endpoint.local_addr()?,
+            echoed.len()
+        );
+    }
+
+    for (index, shard) in shards.iter().enumerate() {
+        println!(
+            "[server] shard {index} forwarded {} datagrams",
+            shard.io_stats().datagrams_forwarded
+        );
+    }
+
+    connection.close(0u32.into(), b"done");
+    endpoint.wait_idle().await;
+
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn main() {
+    println!("sharding endpoints with SO_REUSEPORT is only supported on Linux");
+}
@@ -1,10 +1,10 @@
 use std::{
     any::Any,
     fmt,
-    future::Future,
-    io,
+    future::{Future, poll_fn},
+    io, iter,
     net::{IpAddr, SocketAddr},
-    pin::Pin,
+    pin::{Pin, pin},
     sync::{
         Arc,
         atomic::{AtomicUsize, Ordering},
@@ -21,19 +21,26 @@ use tokio::sync::{
     futures::{Notified, OwnedNotified},
     mpsc, oneshot,
 };
-use tracing::{Instrument, Span, debug_span};
+use tracing::{Instrument, Span, debug_span, info_span};
 
+#[cfg(feature = "metrics")]
+use crate::metrics::MetricsCounters;
 use crate::{
     ConnectionEvent, Duration, Instant, VarInt,
     mutex::Mutex,
     recv_stream::RecvStream,
     runtime::{AsyncTimer, Runtime, UdpSender},
+    send_rate_limiter::SendRateLimiter,
     send_stream::SendStream,
     udp_transmit,
 };
 use proto::{
-    ConnectionError, ConnectionHandle, ConnectionStats, Dir, EndpointEvent, Side, StreamEvent,
-    StreamId, TransportError, TransportErrorCode, congestion::Controller,
+    AddressChange, ConfigError, ConnectionError, ConnectionHandle, ConnectionId, ConnectionStats,
+    DatagramId, Dir, EndpointEvent, FlowControlStats, IdleTimeout, PathEvent, PathProbeId,
+    PathStatsEntry, PathValidationState, PingId, Side, StreamEvent, StreamId, TransportError,
+    TransportErrorCode,
+    congestion::{Controller, ControllerFactory},
+    transport_parameters::TransportParameters,
 };
 
 /// In-progress connection attempt future
@@ -45,6 +52,7 @@ pub struct Connecting {
 }
 
 impl Connecting {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         handle: ConnectionHandle,
         conn: proto::Connection,
@@ -52,9 +60,16 @@ impl Connecting {
         conn_events: mpsc::UnboundedReceiver<ConnectionEvent>,
         sender: Pin<Box<dyn UdpSender>>,
         runtime: Arc<dyn Runtime>,
+        send_rate: Option<Arc<SendRateLimiter>>,
+        #[cfg(feature = "metrics")] metrics: Arc<MetricsCounters>,
     ) -> Self {
         let (on_handshake_data_send, on_handshake_data_recv) = oneshot::channel();
 
+        let span = info_span!(
+            "connection",
+            id = %conn.initial_dst_cid(),
+            remote = %conn.remote_address(),
+        );
         let conn = ConnectionRef(Arc::new(ConnectionInner {
             state: Mutex::new(State::new(
                 conn,
@@ -64,8 +79,12 @@ impl Connecting {
                 on_handshake_data_send,
                 sender,
                 runtime.clone(),
+                send_rate,
+                #[cfg(feature = "metrics")]
+                metrics,
             )),
             shared: Shared::default(),
+            span,
         }));
         let connected = Box::pin(conn.shared.connected.clone().notified_owned());
 
@@ -76,7 +95,7 @@ impl Connecting {
                     tracing::error!("I/O error: {e}");
                 }
             }
-            .instrument(Span::current()),
+            .instrument(conn.span.clone()),
         ));
 
         Self {
@@ -153,6 +172,10 @@ impl Connecting {
     /// [`Session`](proto::crypto::Session). For the default `rustls` session, the return value can
     /// be [`downcast`](Box::downcast) to a
     /// [`crypto::rustls::HandshakeData`](crate::crypto::rustls::HandshakeData).
+    ///
+    /// On incoming connections, this resolves as soon as the client's ClientHello has been
+    /// processed, before any 0-RTT data is read, so the server name and offered protocols can
+    /// inform whether to proceed with the connection.
     pub async fn handshake_data(&mut self) -> Result<Box<dyn Any>, ConnectionError> {
         // Taking &mut self allows us to use a single oneshot channel rather than dealing with
         // potentially many tasks waiting on the same event. It's a bit of a hack, but keeps things
@@ -180,9 +203,10 @@ impl Connecting {
     /// This can be different from the address the endpoint is bound to, in case
     /// the endpoint is bound to a wildcard address like `0.0.0.0` or `::`.
     ///
-    /// This will return `None` for clients, or when the platform does not expose this
-    /// information. See [`quinn_udp::RecvMeta::dst_ip`](udp::RecvMeta::dst_ip) for a list of
-    /// supported platforms when using [`quinn_udp`](udp) for I/O, which is the default.
+    /// This will return `None` for clients until a packet has been received from the server, or
+    /// when the platform does not expose this information. See
+    /// [`quinn_udp::RecvMeta::dst_ip`](udp::RecvMeta::dst_ip) for a list of supported platforms
+    /// when using [`quinn_udp`](udp) for I/O, which is the default.
     ///
     /// Will panic if called after `poll` has returned `Ready`.
     pub fn local_ip(&self) -> Option<IpAddr> {
@@ -199,6 +223,24 @@ impl Connecting {
         let conn_ref: &ConnectionRef = self.conn.as_ref().expect("used after yielding Ready");
         conn_ref.state.lock("remote_address").inner.remote_address()
     }
+
+    /// Initiate a new outgoing bidirectional stream before the handshake completes
+    ///
+    /// Resolves as soon as the peer's limits permit opening the stream: immediately if 0-RTT is
+    /// available, or else when the handshake completes.
+    ///
+    /// Unlike [`into_0rtt()`](Self::into_0rtt), this never sends data in 0-RTT packets unless 0-RTT
+    /// is available. See [`Connection::open_bi()`] for details on the returned streams.
+    ///
+    /// Will panic if called after `poll` has returned `Ready`.
+    pub fn open_bi(&self) -> OpenBi<'_> {
+        let conn: &ConnectionRef = self.conn.as_ref().expect("used after yielding Ready");
+        OpenBi {
+            conn,
+            notify: conn.shared.stream_budget_available[Dir::Bi as usize].notified(),
+            priority: None,
+        }
+    }
 }
 
 impl Future for Connecting {
@@ -298,6 +340,19 @@ impl Connection {
         OpenUni {
             conn: &self.0,
             notify: self.0.shared.stream_budget_available[Dir::Uni as usize].notified(),
+            priority: None,
+        }
+    }
+
+    /// Initiate a new outgoing unidirectional stream with an initial priority
+    ///
+    /// Like [`open_uni()`](Self::open_uni), but the stream is created with `priority` already
+    /// set, so none of its data is ever scheduled at the default priority. See
+    /// [`SendStream::set_priority()`] for the meaning of priorities.
+    pub fn open_uni_with_priority(&self, priority: i32) -> OpenUni<'_> {
+        OpenUni {
+            priority: Some(priority),
+            ..self.open_uni()
         }
     }
 
@@ -315,9 +370,43 @@ impl Connection {
         OpenBi {
             conn: &self.0,
             notify: self.0.shared.stream_budget_available[Dir::Bi as usize].notified(),
+            priority: None,
+        }
+    }
+
+    /// Initiate a new outgoing bidirectional stream with an initial priority
+    ///
+    /// Like [`open_bi()`](Self::open_bi), but the stream is created with `priority` already set,
+    /// so none of its data is ever scheduled at the default priority. See
+    /// [`SendStream::set_priority()`] for the meaning of priorities.
+    pub fn open_bi_with_priority(&self, priority: i32) -> OpenBi<'_> {
+        OpenBi {
+            priority: Some(priority),
+            ..self.open_bi()
         }
     }
 
+    /// Initiate a new outgoing unidirectional stream, giving up after `timeout`
+    ///
+    /// Like [`open_uni()`](Self::open_uni), but fails with [`OpenStreamError::TimedOut`] if the
+    /// peer doesn't grant credit for another stream within `timeout`, rather than waiting
+    /// indefinitely.
+    pub async fn open_uni_timeout(&self, timeout: Duration) -> Result<SendStream, OpenStreamError> {
+        open_with_timeout(&self.0, timeout, self.open_uni()).await
+    }
+
+    /// Initiate a new outgoing bidirectional stream, giving up after `timeout`
+    ///
+    /// Like [`open_bi()`](Self::open_bi), but fails with [`OpenStreamError::TimedOut`] if the
+    /// peer doesn't grant credit for another stream within `timeout`, rather than waiting
+    /// indefinitely.
+    pub async fn open_bi_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<(SendStream, RecvStream), OpenStreamError> {
+        open_with_timeout(&self.0, timeout, self.open_bi()).await
+    }
+
     /// Accept the next incoming uni-directional stream
     pub fn accept_uni(&self) -> AcceptUni<'_> {
         AcceptUni {
@@ -343,6 +432,32 @@ impl Connection {
         }
     }
 
+    /// Incoming uni-directional streams, as an asynchronous stream
+    ///
+    /// Yields the same streams as repeated calls to [`accept_uni()`](Self::accept_uni). See
+    /// [`IncomingUniStreams`] for how it ends.
+    pub fn incoming_uni_streams(&self) -> IncomingUniStreams {
+        IncomingUniStreams(IncomingStreams::new(self.clone(), |conn| {
+            Box::pin(async move {
+                let result = conn.accept_uni().await;
+                (conn, result)
+            })
+        }))
+    }
+
+    /// Incoming bidirectional streams, as an asynchronous stream
+    ///
+    /// Yields the same streams as repeated calls to [`accept_bi()`](Self::accept_bi). See
+    /// [`IncomingBiStreams`] for how it ends.
+    pub fn incoming_bi_streams(&self) -> IncomingBiStreams {
+        IncomingBiStreams(IncomingStreams::new(self.clone(), |conn| {
+            Box::pin(async move {
+                let result = conn.accept_bi().await;
+                (conn, result)
+            })
+        }))
+    }
+
     /// Receive an application datagram
     pub fn read_datagram(&self) -> ReadDatagram<'_> {
         ReadDatagram {
@@ -351,6 +466,19 @@ impl Connection {
         }
     }
 
+    /// Receive all application datagrams which have already arrived, without waiting
+    ///
+    /// Appends the buffered datagrams to `out` in the order they were received, and returns how
+    /// many were appended. Useful for processing bursts in a single pass rather than awaiting
+    /// [`read_datagram()`](Self::read_datagram) for each one.
+    pub fn read_datagrams(&self, out: &mut Vec<Bytes>) -> usize {
+        let mut state = self.0.state.lock("read_datagrams");
+        let mut datagrams = state.inner.datagrams();
+        let len = out.len();
+        out.extend(iter::from_fn(|| datagrams.recv()));
+        out.len() - len
+    }
+
     /// Wait for the connection to be closed for any reason
     ///
     /// Despite the return type's name, closed connections are often not an error condition at the
@@ -377,6 +505,20 @@ impl Connection {
             .clone()
     }
 
+    /// Wait for the connection to be lost due to a stateless reset from the peer
+    ///
+    /// Resolves to `Ok(())` if the connection was terminated by a stateless reset, i.e. with
+    /// [`ConnectionError::Reset`], or to the error that otherwise closed the connection. A peer
+    /// that has lost the connection's state, or a different host now reached on the same path,
+    /// may respond with a stateless reset, so establishing a new connection may succeed where
+    /// retrying after e.g. a timeout would not.
+    pub async fn on_stateless_reset(&self) -> Result<(), ConnectionError> {
+        match self.closed().await {
+            ConnectionError::Reset => Ok(()),
+            error => Err(error),
+        }
+    }
+
     /// Whether the connection is closed, and why.
     ///
     /// The close_reason is always set to `Some(ConnectionError)` when a socket is
@@ -425,6 +567,55 @@ impl Connection {
         conn.close(error_code, Bytes::copy_from_slice(reason), &self.0.shared);
     }
 
+    /// Close the connection once all data written to streams has been delivered
+    ///
+    /// New streams can no longer be opened once this is called; attempts fail with
+    /// [`ConnectionError::LocallyClosed`]. Existing streams may still be written to and finished.
+    /// When the peer has acknowledged all data written to send streams, including the end of
+    /// each finished stream, or when `timeout` elapses first, the connection is closed as if by
+    /// [`close()`](Self::close), which abandons any data still unacknowledged at that point.
+    ///
+    /// As with [`close()`](Self::close), acknowledged data has only reached the peer's QUIC stack:
+    /// the peer may discard data its application has yet to read upon receiving the
+    /// CONNECTION_CLOSE frame.
+    ///
+    /// [`ConnectionError::LocallyClosed`]: crate::ConnectionError::LocallyClosed
+    pub async fn close_gracefully(&self, error_code: VarInt, reason: &[u8], timeout: Duration) {
+        let mut timer = {
+            let mut conn = self.0.state.lock("close_gracefully");
+            conn.closing_gracefully = true;
+            // Fail any pending `open_uni`/`open_bi` calls
+            self.0.shared.stream_budget_available[Dir::Uni as usize].notify_waiters();
+            self.0.shared.stream_budget_available[Dir::Bi as usize].notify_waiters();
+            conn.runtime.new_timer(conn.runtime.now() + timeout)
+        };
+        loop {
+            let notified = {
+                let conn = self.0.state.lock("close_gracefully");
+                if conn.error.is_some() {
+                    return;
+                }
+                if conn.inner.is_send_data_acked() {
+                    break;
+                }
+                // Construct the future while the lock is held, as in `closed()`
+                self.0.shared.send_data_acked.notified()
+            };
+            let mut notified = pin!(notified);
+            let timed_out = poll_fn(|cx| {
+                if notified.as_mut().poll(cx).is_ready() {
+                    return Poll::Ready(false);
+                }
+                timer.as_mut().poll(cx).map(|()| true)
+            })
+            .await;
+            if timed_out {
+                break;
+            }
+        }
+        self.close(error_code, reason);
+    }
+
     /// Wait for the handshake to be confirmed.
     ///
     /// As a server, who must be authenticated by clients,
@@ -485,6 +676,39 @@ impl Connection {
         }
     }
 
+    /// Transmit `data` as an unreliable, unordered application datagram, tracking whether it is
+    /// discarded before transmission
+    ///
+    /// Behaves like [`send_datagram()`](Self::send_datagram), but the returned
+    /// [`DatagramSentHandle`] can be used to learn whether the datagram was evicted from the
+    /// outgoing queue, e.g. to make space for newer datagrams, rather than sent.
+    pub fn send_datagram_with_handle(
+        &self,
+        data: Bytes,
+    ) -> Result<DatagramSentHandle, SendDatagramError> {
+        let conn = &mut *self.0.state.lock("send_datagram_with_handle");
+        if let Some(ref x) = conn.error {
+            return Err(SendDatagramError::ConnectionLost(x.clone()));
+        }
+        use proto::SendDatagramError::*;
+        match conn.inner.datagrams().send_tracked(data, true) {
+            Ok(id) => {
+                conn.tracked_datagrams.insert(id, false);
+                conn.wake();
+                Ok(DatagramSentHandle {
+                    conn: self.0.clone(),
+                    id,
+                })
+            }
+            Err(e) => Err(match e {
+                Blocked(..) => unreachable!(),
+                UnsupportedByPeer => SendDatagramError::UnsupportedByPeer,
+                Disabled => SendDatagramError::Disabled,
+                TooLarge => SendDatagramError::TooLarge,
+            }),
+        }
+    }
+
     /// Transmit `data` as an unreliable, unordered application datagram
     ///
     /// Unlike [`send_datagram()`], this method will wait for buffer space during congestion
@@ -521,6 +745,63 @@ impl Connection {
             .max_size()
     }
 
+    /// Largest UDP payload the peer is willing to receive
+    ///
+    /// Underlies the peer's contribution to [`max_datagram_size()`].
+    /// See [`proto::Connection::peer_max_udp_payload_size()`] for details.
+    ///
+    /// [`max_datagram_size()`]: Connection::max_datagram_size
+    pub fn peer_max_udp_payload_size(&self) -> u16 {
+        self.0
+            .state
+            .lock("peer_max_udp_payload_size")
+            .inner
+            .peer_max_udp_payload_size()
+    }
+
+    /// Wait for the value returned by [`max_datagram_size()`] to change
+    ///
+    /// Resolves with the new value as soon as it differs from the value observed by the previous
+    /// call, or from the value at connection establishment if this is the first call. Resolves
+    /// immediately if the value has already changed since then. Useful for applications which
+    /// size their datagrams to fill the path MTU as it is discovered.
+    ///
+    /// [`max_datagram_size()`]: Connection::max_datagram_size
+    pub async fn max_datagram_size_changed(&self) -> Result<Option<usize>, ConnectionError> {
+        loop {
+            {
+                let mut conn = self.0.state.lock("max_datagram_size_changed");
+                if let Some(error) = conn.error.as_ref() {
+                    return Err(error.clone());
+                }
+                let max_datagram_size = conn.inner.datagrams().max_size();
+                if max_datagram_size != conn.reported_max_datagram_size {
+                    conn.reported_max_datagram_size = max_datagram_size;
+                    return Ok(max_datagram_size);
+                }
+                // Construct the future while the lock is held to ensure we can't miss a wakeup if
+                // the `Notify` is signaled immediately after we release the lock. `await` it after
+                // the lock guard is out of scope.
+                self.0.shared.max_datagram_size_changed.notified()
+            }
+            .await;
+        }
+    }
+
+    /// Bytes available in the incoming datagram buffer
+    ///
+    /// Once received datagrams exceed this space, the oldest datagrams not yet read with
+    /// [`read_datagram()`](Self::read_datagram) are dropped and counted in
+    /// [`ConnectionStats::datagrams_dropped_incoming`].
+    pub fn datagram_receive_buffer_space(&self) -> usize {
+        self.0
+            .state
+            .lock("datagram_receive_buffer_space")
+            .inner
+            .datagrams()
+            .recv_buffer_space()
+    }
+
     /// Bytes available in the outgoing datagram buffer
     ///
     /// When greater than zero, calling [`send_datagram()`](Self::send_datagram) with a datagram of
@@ -539,6 +820,22 @@ impl Connection {
         self.0.state.lock("side").inner.side()
     }
 
+    /// Connection ID the peer currently uses to address this connection's packets
+    ///
+    /// See [`proto::Connection::current_local_cid()`] for details.
+    pub fn current_local_cid(&self) -> ConnectionId {
+        self.0
+            .state
+            .lock("current_local_cid")
+            .inner
+            .current_local_cid()
+    }
+
+    /// Connection IDs issued to the peer that it hasn't yet retired, in order of issuance
+    pub fn local_cids(&self) -> Vec<ConnectionId> {
+        self.0.state.lock("local_cids").inner.local_cids()
+    }
+
     /// The peer's UDP address
     ///
     /// If `ServerConfig::migration` is `true`, clients may change addresses at will, e.g. when
@@ -547,29 +844,229 @@ impl Connection {
         self.0.state.lock("remote_address").inner.remote_address()
     }
 
-    /// The local IP address which was used when the peer established
-    /// the connection
+    /// Whether the peer has been confirmed reachable at [`remote_address()`](Self::remote_address)
+    ///
+    /// See [`proto::Connection::path_validation_state()`] for details.
+    pub fn path_validation_state(&self) -> PathValidationState {
+        self.0
+            .state
+            .lock("path_validation_state")
+            .inner
+            .path_validation_state()
+    }
+
+    /// Wait for the peer to migrate to a new address
+    ///
+    /// Resolves with the peer's new address once it has been validated and become the
+    /// connection's active path, or with the reason the connection was closed. Only servers
+    /// observe migrations, and only if [`ServerConfig::migration`](crate::ServerConfig::migration)
+    /// is enabled.
+    pub async fn migrated(&self) -> Result<SocketAddr, ConnectionError> {
+        {
+            let conn = self.0.state.lock("migrated");
+            if let Some(error) = conn.error.as_ref() {
+                return Err(error.clone());
+            }
+            // Construct the future while the lock is held, as in `closed()`
+            self.0.shared.migrated.notified()
+        }
+        .await;
+        let conn = self.0.state.lock("migrated");
+        match conn.error.as_ref() {
+            Some(error) => Err(error.clone()),
+            None => Ok(conn.inner.remote_address()),
+        }
+    }
+
+    /// Changes of the peer's validated address, as an asynchronous stream
+    ///
+    /// A change is reported each time the peer is validated at a different address than before,
+    /// e.g. so that applications can repeat checks based on the peer's address.
+    /// [`AddressChange::is_port_only()`] tells a likely NAT rebinding apart from a move to another
+    /// network. As with [`migrated()`](Self::migrated), only servers observe migrations. See
+    /// [`AddressChanges`] for how it ends.
+    pub fn observe_address_changes(&self) -> AddressChanges {
+        let (send, recv) = mpsc::unbounded_channel();
+        let mut conn = self.0.state.lock("observe_address_changes");
+        // Dropping the sender of a closed connection ends the stream immediately
+        if conn.error.is_none() {
+            conn.address_observers.push(send);
+        }
+        AddressChanges(recv)
+    }
+
+    /// The local IP address to which the peer most recently sent an authenticated packet
     ///
     /// This can be different from the address the endpoint is bound to, in case
-    /// the endpoint is bound to a wildcard address like `0.0.0.0` or `::`.
+    /// the endpoint is bound to a wildcard address like `0.0.0.0` or `::`, and may change over
+    /// the connection's lifetime if the peer addresses another of this host's addresses.
     ///
-    /// This will return `None` for clients, or when the platform does not expose this
-    /// information. See [`quinn_udp::RecvMeta::dst_ip`](udp::RecvMeta::dst_ip) for a list of
-    /// supported platforms when using [`quinn_udp`](udp) for I/O, which is the default.
+    /// This will return `None` until a packet has been received, or when the platform does not
+    /// expose this information. See [`quinn_udp::RecvMeta::dst_ip`](udp::RecvMeta::dst_ip) for a
+    /// list of supported platforms when using [`quinn_udp`](udp) for I/O, which is the default.
     pub fn local_ip(&self) -> Option<IpAddr> {
         self.0.state.lock("local_ip").inner.local_ip()
     }
 
+    /// Send a PING to the peer and measure the time until it's acknowledged
+    ///
+    /// Any delay the peer reports having deliberately introduced before acknowledging is
+    /// subtracted, so the result approximates the network round trip. Unlike [`rtt()`](Self::rtt),
+    /// this is a single fresh measurement. Concurrent calls are timed independently.
+    pub async fn ping(&self) -> Result<Duration, ConnectionError> {
+        let id = {
+            let mut conn = self.0.state.lock("ping");
+            if let Some(error) = conn.error.as_ref() {
+                return Err(error.clone());
+            }
+            let id = conn.inner.ping_tracked();
+            conn.tracked_pings.insert(id, None);
+            conn.wake();
+            id
+        };
+        let _guard = TrackedPing { conn: &self.0, id };
+        loop {
+            {
+                let conn = self.0.state.lock("ping");
+                if let Some(&Some(rtt)) = conn.tracked_pings.get(&id) {
+                    return Ok(rtt);
+                }
+                if let Some(error) = conn.error.as_ref() {
+                    return Err(error.clone());
+                }
+                // Construct the future while the lock is held to ensure we can't miss a wakeup
+                self.0.shared.ping_acknowledged.notified()
+            }
+            .await;
+        }
+    }
+
+    /// Check whether the peer is reachable at `remote` without migrating to it
+    ///
+    /// Sends a PATH_CHALLENGE from this connection's endpoint to `remote` and resolves with the
+    /// time until the matching PATH_RESPONSE arrives. Useful to validate a candidate address
+    /// before switching to it. Waits for the handshake to complete before sending; fails with
+    /// [`PathError::TimedOut`] if no response arrives within three probe timeouts. See
+    /// [`proto::Connection::probe_path()`].
+    pub async fn probe_path(&self, remote: SocketAddr) -> Result<Duration, PathError> {
+        let id = {
+            let mut conn = self.0.state.lock("probe_path");
+            if let Some(error) = conn.error.as_ref() {
+                return Err(error.clone().into());
+            }
+            let id = conn.inner.probe_path(remote);
+            conn.tracked_path_probes.insert(id, None);
+            conn.wake();
+            id
+        };
+        let _guard = TrackedPathProbe { conn: &self.0, id };
+        loop {
+            {
+                let conn = self.0.state.lock("probe_path");
+                if let Some(&Some(rtt)) = conn.tracked_path_probes.get(&id) {
+                    return rtt.ok_or(PathError::TimedOut);
+                }
+                if let Some(error) = conn.error.as_ref() {
+                    return Err(error.clone().into());
+                }
+                // Construct the future while the lock is held to ensure we can't miss a wakeup
+                self.0.shared.path_probed.notified()
+            }
+            .await;
+        }
+    }
+
+    /// Describe every open stream on this connection
+    ///
+    /// The snapshot is taken atomically and doesn't change afterwards. See
+    /// [`proto::Streams::snapshot()`].
+    pub fn stream_snapshot(&self) -> Vec<proto::StreamInfo> {
+        self.0
+            .state
+            .lock("stream_snapshot")
+            .inner
+            .streams()
+            .snapshot()
+    }
+
     /// Current best estimate of this connection's latency (round-trip-time)
     pub fn rtt(&self) -> Duration {
         self.0.state.lock("rtt").inner.rtt()
     }
 
+    /// Recent raw round-trip-time measurements, oldest first
+    ///
+    /// Unlike [`rtt()`](Self::rtt), these aren't smoothed, making them suitable for estimating
+    /// e.g. jitter. Only as many as [`proto::TransportConfig::rtt_sample_history()`] are retained,
+    /// so none by default.
+    pub fn rtt_samples(&self) -> Vec<Duration> {
+        self.0
+            .state
+            .lock("rtt_samples")
+            .inner
+            .rtt_samples()
+            .collect()
+    }
+
+    /// Congestion control and network path events, as an asynchronous stream
+    ///
+    /// Events that occur while no subscriber exists are discarded, as are events a subscriber
+    /// falls too far behind on. See [`PathEvents`] for how it ends.
+    pub fn path_events(&self) -> PathEvents {
+        let (send, recv) = mpsc::channel(PATH_EVENTS_CAPACITY);
+        let mut conn = self.0.state.lock("path_events");
+        // Dropping the sender of a closed connection ends the stream immediately
+        if conn.error.is_none() {
+            conn.path_observers.push(send);
+        }
+        PathEvents(recv)
+    }
+
     /// Returns connection statistics
     pub fn stats(&self) -> ConnectionStats {
         self.0.state.lock("stats").inner.stats()
     }
 
+    /// When the connection will time out if no further packets are received
+    ///
+    /// See [`proto::Connection::idle_timeout_deadline()`] for details.
+    pub fn idle_timeout_deadline(&self) -> Option<Instant> {
+        self.0
+            .state
+            .lock("idle_timeout_deadline")
+            .inner
+            .idle_timeout_deadline()
+    }
+
+    /// Bytes sent and received over each network path the connection has recently used
+    ///
+    /// See [`proto::Connection::path_stats()`] for details.
+    pub fn path_stats(&self) -> Vec<PathStatsEntry> {
+        self.0.state.lock("path_stats").inner.path_stats()
+    }
+
+    /// Transport parameters advertised by the peer
+    ///
+    /// See [`proto::Connection::peer_transport_params()`] for details.
+    pub fn peer_transport_params(&self) -> Option<TransportParameters> {
+        self.0
+            .state
+            .lock("peer_transport_params")
+            .inner
+            .peer_transport_params()
+    }
+
+    /// Returns the state of connection-level flow control and stream count limits
+    ///
+    /// See [`proto::Connection::flow_control_stats()`] for details.
+    pub fn flow_control_stats(&self) -> FlowControlStats {
+        self.0
+            .state
+            .lock("flow_control_stats")
+            .inner
+            .flow_control_stats()
+    }
+
     /// Current state of the congestion control algorithm, for debugging purposes
     pub fn congestion_state(&self) -> Box<dyn Controller> {
         self.0
@@ -580,6 +1077,40 @@ impl Connection {
             .clone_box()
     }
 
+    /// Name of the congestion control algorithm in use
+    ///
+    /// See [`proto::Connection::congestion_controller_name()`] for details.
+    pub fn congestion_controller_name(&self) -> &'static str {
+        self.0
+            .state
+            .lock("congestion_controller_name")
+            .inner
+            .congestion_controller_name()
+    }
+
+    /// Replace the congestion controller of this connection
+    ///
+    /// See [`proto::Connection::set_congestion_controller()`] for how state carries over to the
+    /// new controller.
+    pub fn set_congestion_controller(&self, factory: Arc<dyn ControllerFactory + Send + Sync>) {
+        let mut conn = self.0.state.lock("set_congestion_controller");
+        let now = conn.runtime.now();
+        conn.inner.set_congestion_controller(now, factory);
+        conn.wake();
+    }
+
+    /// Start or stop writing qlog events for this connection
+    ///
+    /// See [`proto::Connection::set_qlog_stream()`].
+    #[cfg(feature = "qlog")]
+    pub fn set_qlog_stream(&self, stream: Option<proto::QlogStream>) {
+        self.0
+            .state
+            .lock("set_qlog_stream")
+            .inner
+            .set_qlog_stream(stream);
+    }
+
     /// Succeeds when an incoming connection is proven not to be a replay attack.
     ///
     /// Only interesting for `Connection`s obtained from [`Connecting::into_0rtt`]. On 1-RTT
@@ -607,6 +1138,33 @@ impl Connection {
         conn.error.clone().map_or(Ok(()), Err)
     }
 
+    /// Resolves once the handshake completes, indicating whether the server accepted 0-RTT data
+    ///
+    /// Only interesting for outgoing `Connection`s obtained from [`Connecting::into_0rtt`]. If this
+    /// yields `false`, data sent before the handshake completed was discarded by the server, and
+    /// [`zero_rtt_rejected_streams()`](Self::zero_rtt_rejected_streams) lists the affected
+    /// streams. Always yields `false` for incoming connections and connections which did not
+    /// attempt 0-RTT.
+    pub async fn zero_rtt_accepted(&self) -> Result<bool, ConnectionError> {
+        self.authenticated().await?;
+        let conn = self.0.state.lock("zero_rtt_accepted");
+        Ok(conn.inner.side().is_client() && conn.inner.accepted_0rtt())
+    }
+
+    /// Streams whose data was sent in 0-RTT but discarded because the server rejected 0-RTT
+    ///
+    /// Includes outgoing streams which had data written, or were finished or reset, before the
+    /// handshake completed. Applications which need to replay such requests should do so on newly
+    /// opened streams. Empty until [`zero_rtt_accepted()`](Self::zero_rtt_accepted) resolves.
+    pub fn zero_rtt_rejected_streams(&self) -> Vec<StreamId> {
+        self.0
+            .state
+            .lock("zero_rtt_rejected_streams")
+            .inner
+            .zero_rtt_rejected_streams()
+            .to_vec()
+    }
+
     /// Parameters negotiated during the handshake
     ///
     /// Guaranteed to return `Some` on fully established connections or after
@@ -623,6 +1181,18 @@ impl Connection {
             .handshake_data()
     }
 
+    /// The application protocol negotiated through ALPN
+    ///
+    /// Returns `None` if the handshake hasn't completed yet, or if no protocol was negotiated.
+    pub fn negotiated_alpn(&self) -> Option<Vec<u8>> {
+        self.0
+            .state
+            .lock("negotiated_alpn")
+            .inner
+            .negotiated_alpn()
+            .map(|x| x.to_vec())
+    }
+
     /// Cryptographic identity of the peer
     ///
     /// The dynamic type returned is determined by the configured
@@ -645,6 +1215,22 @@ impl Connection {
         self.0.stable_id()
     }
 
+    /// `tracing` span that all of this connection's internal events are recorded in
+    ///
+    /// The span is tagged with the connection's [initial destination
+    /// CID](proto::Connection::initial_dst_cid) and the peer's address at the time the connection
+    /// was created. Entering it, or instrumenting futures with it, nests application events under
+    /// the connection too. It's a child of whatever span was current when the connection was
+    /// created or accepted.
+    pub fn span(&self) -> Span {
+        self.0.span.clone()
+    }
+
+    /// Current time according to this connection's runtime
+    pub(crate) fn now(&self) -> Instant {
+        self.0.state.lock("now").runtime.now()
+    }
+
     /// Update traffic keys spontaneously
     ///
     /// This primarily exists for testing purposes.
@@ -663,6 +1249,9 @@ impl Connection {
     /// same sequence of bytes in `output`. These bytes are cryptographically
     /// strong and pseudorandom, and are suitable for use as keying material.
     ///
+    /// Fails if called before the handshake has completed, since the secrets
+    /// are not yet available.
+    ///
     /// See [RFC5705](https://tools.ietf.org/html/rfc5705) for more information.
     pub fn export_keying_material(
         &self,
@@ -703,6 +1292,30 @@ impl Connection {
         conn.wake();
     }
 
+    /// Update the local maximum idle timeout for the remainder of the connection
+    ///
+    /// The effective timeout is the minimum of both peers' values, and the peer keeps enforcing
+    /// the [`proto::TransportConfig::max_idle_timeout()`] it was sent during the handshake, so this
+    /// can only shorten the timeout; raising it above either advertised value has no effect.
+    /// `None` reverts to the configured value.
+    ///
+    /// Fails with [`ConfigError::OutOfBounds`] if `timeout` is zero.
+    pub fn set_max_idle_timeout(&self, timeout: Option<IdleTimeout>) -> Result<(), ConfigError> {
+        let mut conn = self.0.state.lock("set_max_idle_timeout");
+        let now = conn.runtime.now();
+        conn.inner.set_max_idle_timeout(now, timeout)?;
+        conn.wake();
+        Ok(())
+    }
+
+    /// See [`proto::Connection::set_max_send_rate()`]
+    pub fn set_max_send_rate(&self, bytes_per_second: Option<u64>) {
+        let mut conn = self.0.state.lock("set_max_send_rate");
+        let now = conn.runtime.now();
+        conn.inner.set_max_send_rate(now, bytes_per_second);
+        conn.wake();
+    }
+
     /// Modify the number of remotely initiated bidirectional streams that may be concurrently open
     ///
     /// No streams may be opened by the peer unless fewer than `count` are already open. Large
@@ -721,6 +1334,7 @@ pin_project! {
         conn: &'a ConnectionRef,
         #[pin]
         notify: Notified<'a>,
+        priority: Option<i32>,
     }
 }
 
@@ -728,17 +1342,24 @@ impl Future for OpenUni<'_> {
     type Output = Result<SendStream, ConnectionError>;
     fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.project();
-        let (conn, id, is_0rtt) = ready!(poll_open(ctx, this.conn, this.notify, Dir::Uni))?;
+        let (conn, id, is_0rtt) = ready!(poll_open(
+            ctx,
+            this.conn,
+            this.notify,
+            Dir::Uni,
+            *this.priority
+        ))?;
         Poll::Ready(Ok(SendStream::new(conn, id, is_0rtt)))
     }
 }
 
 pin_project! {
-    /// Future produced by [`Connection::open_bi`]
+    /// Future produced by [`Connection::open_bi`] or [`Connecting::open_bi`]
     pub struct OpenBi<'a> {
         conn: &'a ConnectionRef,
         #[pin]
         notify: Notified<'a>,
+        priority: Option<i32>,
     }
 }
 
@@ -746,7 +1367,13 @@ impl Future for OpenBi<'_> {
     type Output = Result<(SendStream, RecvStream), ConnectionError>;
     fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.project();
-        let (conn, id, is_0rtt) = ready!(poll_open(ctx, this.conn, this.notify, Dir::Bi))?;
+        let (conn, id, is_0rtt) = ready!(poll_open(
+            ctx,
+            this.conn,
+            this.notify,
+            Dir::Bi,
+            *this.priority
+        ))?;
 
         Poll::Ready(Ok((
             SendStream::new(conn.clone(), id, is_0rtt),
@@ -755,16 +1382,44 @@ impl Future for OpenBi<'_> {
     }
 }
 
+async fn open_with_timeout<T>(
+    conn: &ConnectionRef,
+    timeout: Duration,
+    open: impl Future<Output = Result<T, ConnectionError>>,
+) -> Result<T, OpenStreamError> {
+    let mut timer = {
+        let conn = conn.state.lock("open_with_timeout");
+        conn.runtime.new_timer(conn.runtime.now() + timeout)
+    };
+    let mut open = pin!(open);
+    poll_fn(|cx| {
+        if let Poll::Ready(result) = open.as_mut().poll(cx) {
+            return Poll::Ready(result.map_err(OpenStreamError::from));
+        }
+        timer
+            .as_mut()
+            .poll(cx)
+            .map(|()| Err(OpenStreamError::TimedOut))
+    })
+    .await
+}
+
 fn poll_open<'a>(
     ctx: &mut Context<'_>,
     conn: &'a ConnectionRef,
     mut notify: Pin<&mut Notified<'a>>,
     dir: Dir,
+    priority: Option<i32>,
 ) -> Poll<Result<(ConnectionRef, StreamId, bool), ConnectionError>> {
     let mut state = conn.state.lock("poll_open");
     if let Some(ref e) = state.error {
         return Poll::Ready(Err(e.clone()));
-    } else if let Some(id) = state.inner.streams().open(dir) {
+    } else if state.closing_gracefully {
+        return Poll::Ready(Err(ConnectionError::LocallyClosed));
+    } else if let Some(id) = match priority {
+        Some(priority) => state.inner.streams().open_with_priority(dir, priority),
+        None => state.inner.streams().open(dir),
+    } {
         let is_0rtt = state.inner.side().is_client() && state.inner.is_handshaking();
         drop(state); // Release the lock so clone can take it
         return Poll::Ready(Ok((conn.clone(), id, is_0rtt)));
@@ -932,6 +1587,233 @@ impl Future for SendDatagram<'_> {
     }
 }
 
+/// Handle to a datagram sent with [`Connection::send_datagram_with_handle`]
+///
+/// Dropping the handle stops tracking the datagram but does not affect its transmission.
+#[derive(Debug)]
+pub struct DatagramSentHandle {
+    conn: ConnectionRef,
+    id: DatagramId,
+}
+
+impl DatagramSentHandle {
+    /// Wait for the datagram to be discarded from the outgoing queue without being sent
+    ///
+    /// Never resolves if the datagram is transmitted, so callers will typically race this against
+    /// a timeout. Fails if the connection is lost first, in which case the datagram may or may not
+    /// have been sent.
+    pub async fn dropped(&self) -> Result<(), ConnectionError> {
+        loop {
+            {
+                let conn = self.conn.state.lock("DatagramSentHandle::dropped");
+                if conn.tracked_datagrams.get(&self.id) == Some(&true) {
+                    return Ok(());
+                }
+                if let Some(error) = conn.error.as_ref() {
+                    return Err(error.clone());
+                }
+                // Construct the future while the lock is held to ensure we can't miss a wakeup if
+                // the `Notify` is signaled immediately after we release the lock. `await` it after
+                // the lock guard is out of scope.
+                self.conn.shared.datagram_dropped.notified()
+            }
+            .await;
+        }
+    }
+}
+
+impl Drop for DatagramSentHandle {
+    fn drop(&mut self) {
+        self.conn
+            .state
+            .lock("DatagramSentHandle::drop")
+            .tracked_datagrams
+            .remove(&self.id);
+    }
+}
+
+/// Incoming uni-directional streams, created by [`Connection::incoming_uni_streams`]
+///
+/// Implements `futures_core::Stream` when the `futures-core` feature is enabled. Ends once the
+/// connection is closed and every stream received before that has been yielded. If the
+/// connection was lost for any reason other than either application closing it, the
+/// [`ConnectionError`] is yielded just before the end.
+pub struct IncomingUniStreams(IncomingStreams<RecvStream>);
+
+impl IncomingUniStreams {
+    /// Attempt to accept the next stream
+    pub fn poll_next(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<RecvStream, ConnectionError>>> {
+        self.0.poll_next(cx)
+    }
+}
+
+#[cfg(feature = "futures-core")]
+impl futures_core::Stream for IncomingUniStreams {
+    type Item = Result<RecvStream, ConnectionError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().0.poll_next(cx)
+    }
+}
+
+impl fmt::Debug for IncomingUniStreams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IncomingUniStreams").finish_non_exhaustive()
+    }
+}
+
+/// Incoming bidirectional streams, created by [`Connection::incoming_bi_streams`]
+///
+/// Behaves like [`IncomingUniStreams`].
+pub struct IncomingBiStreams(IncomingStreams<(SendStream, RecvStream)>);
+
+impl IncomingBiStreams {
+    /// Attempt to accept the next stream
+    pub fn poll_next(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<(SendStream, RecvStream), ConnectionError>>> {
+        self.0.poll_next(cx)
+    }
+}
+
+#[cfg(feature = "futures-core")]
+impl futures_core::Stream for IncomingBiStreams {
+    type Item = Result<(SendStream, RecvStream), ConnectionError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().0.poll_next(cx)
+    }
+}
+
+impl fmt::Debug for IncomingBiStreams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IncomingBiStreams").finish_non_exhaustive()
+    }
+}
+
+/// Pending accept of the next stream, owning the connection so it can outlive any borrow
+type AcceptNext<T> =
+    Pin<Box<dyn Future<Output = (Connection, Result<T, ConnectionError>)> + Send + 'static>>;
+
+/// Shared implementation of [`IncomingUniStreams`] and [`IncomingBiStreams`]
+struct IncomingStreams<T> {
+    /// `None` once the connection has closed
+    next: Option<AcceptNext<T>>,
+    accept: fn(Connection) -> AcceptNext<T>,
+}
+
+impl<T> IncomingStreams<T> {
+    fn new(conn: Connection, accept: fn(Connection) -> AcceptNext<T>) -> Self {
+        Self {
+            next: Some(accept(conn)),
+            accept,
+        }
+    }
+
+    fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<T, ConnectionError>>> {
+        let Some(next) = &mut self.next else {
+            return Poll::Ready(None);
+        };
+        let (conn, result) = ready!(next.as_mut().poll(cx));
+        Poll::Ready(match result {
+            Ok(stream) => {
+                self.next = Some((self.accept)(conn));
+                Some(Ok(stream))
+            }
+            Err(ConnectionError::ApplicationClosed(_) | ConnectionError::LocallyClosed) => {
+                self.next = None;
+                None
+            }
+            Err(e) => {
+                self.next = None;
+                Some(Err(e))
+            }
+        })
+    }
+}
+
+/// Changes of a peer's address, created by [`Connection::observe_address_changes`]
+///
+/// Implements `futures_core::Stream` when the `futures-core` feature is enabled. Ends once the
+/// connection is closed and every change observed before that has been yielded.
+#[derive(Debug)]
+pub struct AddressChanges(mpsc::UnboundedReceiver<AddressChange>);
+
+impl AddressChanges {
+    /// Attempt to receive the next change
+    pub fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<AddressChange>> {
+        self.0.poll_recv(cx)
+    }
+}
+
+#[cfg(feature = "futures-core")]
+impl futures_core::Stream for AddressChanges {
+    type Item = AddressChange;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().0.poll_recv(cx)
+    }
+}
+
+/// A connection's [`PathEvent`]s, created by [`Connection::path_events`]
+///
+/// Implements `futures_core::Stream` when the `futures-core` feature is enabled. Ends once the
+/// connection is closed and every event buffered before that has been yielded.
+#[derive(Debug)]
+pub struct PathEvents(mpsc::Receiver<PathEvent>);
+
+impl PathEvents {
+    /// Attempt to receive the next event
+    pub fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<PathEvent>> {
+        self.0.poll_recv(cx)
+    }
+}
+
+#[cfg(feature = "futures-core")]
+impl futures_core::Stream for PathEvents {
+    type Item = PathEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().0.poll_recv(cx)
+    }
+}
+
+/// Stops tracking a [`Connection::ping`] once it completes or is cancelled
+struct TrackedPing<'a> {
+    conn: &'a ConnectionRef,
+    id: PingId,
+}
+
+impl Drop for TrackedPing<'_> {
+    fn drop(&mut self) {
+        self.conn
+            .state
+            .lock("TrackedPing::drop")
+            .tracked_pings
+            .remove(&self.id);
+    }
+}
+
+/// Stops tracking a [`Connection::probe_path`] once it completes or is cancelled
+struct TrackedPathProbe<'a> {
+    conn: &'a ConnectionRef,
+    id: PathProbeId,
+}
+
+impl Drop for TrackedPathProbe<'_> {
+    fn drop(&mut self) {
+        self.conn
+            .state
+            .lock("TrackedPathProbe::drop")
+            .tracked_path_probes
+            .remove(&self.id);
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct ConnectionRef(Arc<ConnectionInner>);
 
@@ -977,6 +1859,8 @@ impl std::ops::Deref for ConnectionRef {
 pub(crate) struct ConnectionInner {
     pub(crate) state: Mutex<State>,
     pub(crate) shared: Shared,
+    /// Span the driver runs in, tagged with the connection ID and remote address
+    span: Span,
 }
 
 #[derive(Debug, Default)]
@@ -989,6 +1873,19 @@ pub(crate) struct Shared {
     stream_incoming: [Notify; 2],
     datagram_received: Notify,
     datagrams_unblocked: Notify,
+    /// Notified when a datagram sent with `send_datagram_with_handle()` is discarded
+    datagram_dropped: Notify,
+    /// Notified when the value of `max_datagram_size()` changes
+    max_datagram_size_changed: Notify,
+    /// Notified when a PING sent by `ping()` is acknowledged
+    ping_acknowledged: Notify,
+    /// Notified when a PATH_CHALLENGE sent by `probe_path()` is answered or times out
+    path_probed: Notify,
+    /// Notified when a new peer address has been validated, or the connection is closed
+    migrated: Notify,
+    /// Notified when all send stream data is acknowledged after `close_gracefully()` was called,
+    /// or the connection is closed
+    send_data_acked: Notify,
     closed: Notify,
     connected: Arc<Notify>,
     /// Number of live handles that can used to initiate or handle I/O; excludes the driver
@@ -1002,6 +1899,10 @@ pub(crate) struct State {
     on_handshake_data: Option<oneshot::Sender<()>>,
     connected: bool,
     handshake_confirmed: bool,
+    /// Maximum datagram size as of the last time the driver checked for changes
+    max_datagram_size: Option<usize>,
+    /// Maximum datagram size as of the last `max_datagram_size_changed()` call
+    reported_max_datagram_size: Option<usize>,
     timer: Option<Pin<Box<dyn AsyncTimer>>>,
     timer_deadline: Option<Instant>,
     conn_events: mpsc::UnboundedReceiver<ConnectionEvent>,
@@ -1009,13 +1910,32 @@ pub(crate) struct State {
     pub(crate) blocked_writers: FxHashMap<StreamId, Waker>,
     pub(crate) blocked_readers: FxHashMap<StreamId, Waker>,
     pub(crate) stopped: FxHashMap<StreamId, Arc<Notify>>,
+    /// Datagrams with live `DatagramSentHandle`s, and whether each has been discarded unsent
+    tracked_datagrams: FxHashMap<DatagramId, bool>,
+    /// Pings sent by live `ping()` futures, and their round-trip time once acknowledged
+    tracked_pings: FxHashMap<PingId, Option<Duration>>,
+    /// Probes sent by live `probe_path()` futures, and their outcome once known
+    tracked_path_probes: FxHashMap<PathProbeId, Option<Option<Duration>>>,
+    /// Subscribers created by `path_events()`
+    path_observers: Vec<mpsc::Sender<PathEvent>>,
+    /// Subscribers created by `observe_address_changes()`
+    address_observers: Vec<mpsc::UnboundedSender<AddressChange>>,
+    /// Set by `close_gracefully()` to stop new streams from being opened
+    closing_gracefully: bool,
     /// Always set to Some before the connection becomes drained
     pub(crate) error: Option<ConnectionError>,
     sender: Pin<Box<dyn UdpSender>>,
-    runtime: Arc<dyn Runtime>,
+    pub(crate) runtime: Arc<dyn Runtime>,
     send_buffer: Vec<u8>,
     /// We buffer a transmit when the underlying I/O would block
     buffered_transmit: Option<proto::Transmit>,
+    /// The endpoint's aggregate send rate limit, if any
+    send_rate: Option<Arc<SendRateLimiter>>,
+    /// Wakes the driver once the endpoint's send budget recovers
+    send_rate_timer: Option<Pin<Box<dyn AsyncTimer>>>,
+    /// Counters of the endpoint this connection belongs to
+    #[cfg(feature = "metrics")]
+    metrics: Arc<MetricsCounters>,
 }
 
 impl State {
@@ -1028,6 +1948,8 @@ impl State {
         on_handshake_data: oneshot::Sender<()>,
         sender: Pin<Box<dyn UdpSender>>,
         runtime: Arc<dyn Runtime>,
+        send_rate: Option<Arc<SendRateLimiter>>,
+        #[cfg(feature = "metrics")] metrics: Arc<MetricsCounters>,
     ) -> Self {
         Self {
             inner,
@@ -1036,6 +1958,8 @@ impl State {
             on_handshake_data: Some(on_handshake_data),
             connected: false,
             handshake_confirmed: false,
+            max_datagram_size: None,
+            reported_max_datagram_size: None,
             timer: None,
             timer_deadline: None,
             conn_events,
@@ -1043,11 +1967,21 @@ impl State {
             blocked_writers: FxHashMap::default(),
             blocked_readers: FxHashMap::default(),
             stopped: FxHashMap::default(),
+            tracked_datagrams: FxHashMap::default(),
+            tracked_pings: FxHashMap::default(),
+            tracked_path_probes: FxHashMap::default(),
+            path_observers: Vec::new(),
+            address_observers: Vec::new(),
+            closing_gracefully: false,
             error: None,
             sender,
             runtime,
             send_buffer: Vec::new(),
             buffered_transmit: None,
+            send_rate,
+            send_rate_timer: None,
+            #[cfg(feature = "metrics")]
+            metrics,
         }
     }
 
@@ -1065,6 +1999,21 @@ impl State {
             let t = match self.buffered_transmit.take() {
                 Some(t) => t,
                 None => {
+                    if let Some(Err(deadline)) =
+                        self.send_rate.as_ref().map(|limit| limit.poll_budget(now))
+                    {
+                        // Leave the data in the connection until the endpoint may send again
+                        match &mut self.send_rate_timer {
+                            Some(timer) => timer.as_mut().reset(deadline),
+                            None => self.send_rate_timer = Some(self.runtime.new_timer(deadline)),
+                        }
+                        let timer = self
+                            .send_rate_timer
+                            .as_mut()
+                            .expect("timer must exist in this state");
+                        // If the deadline already passed, check the budget again next time
+                        return Ok(timer.as_mut().poll(cx).is_ready());
+                    }
                     self.send_buffer.clear();
                     self.send_buffer.reserve(self.inner.current_mtu() as usize);
                     match self
@@ -1096,6 +2045,11 @@ impl State {
                 Poll::Ready(Err(e)) => return Err(e),
                 Poll::Ready(Ok(())) => {}
             }
+            #[cfg(feature = "metrics")]
+            self.metrics.on_sent(len);
+            if let Some(limit) = &self.send_rate {
+                limit.on_sent(len);
+            }
 
             if transmits >= MAX_TRANSMIT_DATAGRAMS {
                 // TODO: What isn't ideal here yet is that if we don't poll all
@@ -1158,8 +2112,14 @@ impl State {
                 }
                 Connected => {
                     self.connected = true;
+                    self.max_datagram_size = self.inner.datagrams().max_size();
+                    self.reported_max_datagram_size = self.max_datagram_size;
                     shared.connected.notify_waiters();
-                    if self.inner.side().is_client() && !self.inner.accepted_0rtt() {
+                    // The peer's transport parameters may have just made streams available
+                    for notify in &shared.stream_budget_available {
+                        notify.notify_waiters();
+                    }
+                    if self.inner.side().is_client() && self.check_0rtt().is_err() {
                         // Wake up rejected 0-RTT streams so they can fail immediately with
                         // `ZeroRttRejected` errors.
                         wake_all(&mut self.blocked_writers);
@@ -1187,6 +2147,24 @@ impl State {
                 DatagramsUnblocked => {
                     shared.datagrams_unblocked.notify_waiters();
                 }
+                DatagramDropped { id } => {
+                    if let Some(dropped) = self.tracked_datagrams.get_mut(&id) {
+                        *dropped = true;
+                        shared.datagram_dropped.notify_waiters();
+                    }
+                }
+                PingAcknowledged { id, rtt } => {
+                    if let Some(result) = self.tracked_pings.get_mut(&id) {
+                        *result = Some(rtt);
+                        shared.ping_acknowledged.notify_waiters();
+                    }
+                }
+                PathProbed { id, rtt } => {
+                    if let Some(result) = self.tracked_path_probes.get_mut(&id) {
+                        *result = Some(rtt);
+                        shared.path_probed.notify_waiters();
+                    }
+                }
                 Stream(StreamEvent::Readable { id }) => wake_stream(id, &mut self.blocked_readers),
                 Stream(StreamEvent::Available { dir }) => {
                     // Might mean any number of streams are ready, so we wake up everyone
@@ -1199,6 +2177,39 @@ impl State {
                 }
             }
         }
+
+        while let Some(event) = self.inner.poll_path_event() {
+            match event {
+                PathEvent::MigrationCompleted => shared.migrated.notify_waiters(),
+                PathEvent::RemoteAddressChanged(change) => {
+                    // Forget subscribers that have been dropped
+                    self.address_observers
+                        .retain(|observer| observer.send(change).is_ok());
+                }
+                _ => {}
+            }
+            // Forget subscribers that have been dropped, skipping those that fell behind
+            self.path_observers.retain(|observer| {
+                !matches!(
+                    observer.try_send(event),
+                    Err(mpsc::error::TrySendError::Closed(_))
+                )
+            });
+        }
+
+        // The datagram size limit tracks the path MTU, which PMTUD and black hole detection may
+        // revise while handling ACKs and loss timers
+        if self.connected {
+            let max_datagram_size = self.inner.datagrams().max_size();
+            if max_datagram_size != self.max_datagram_size {
+                self.max_datagram_size = max_datagram_size;
+                shared.max_datagram_size_changed.notify_waiters();
+            }
+        }
+
+        if self.closing_gracefully && self.inner.is_send_data_acked() {
+            shared.send_data_acked.notify_waiters();
+        }
     }
 
     fn drive_timer(&mut self, cx: &mut Context<'_>) -> bool {
@@ -1253,6 +2264,10 @@ impl State {
 
     /// Used to wake up all blocked futures when the connection becomes closed for any reason
     fn terminate(&mut self, reason: ConnectionError, shared: &Shared) {
+        #[cfg(feature = "metrics")]
+        if self.error.is_none() && !self.connected {
+            self.metrics.on_handshake_failed();
+        }
         self.error = Some(reason.clone());
         if let Some(x) = self.on_handshake_data.take() {
             let _ = x.send(());
@@ -1265,8 +2280,16 @@ impl State {
         shared.stream_incoming[Dir::Bi as usize].notify_waiters();
         shared.datagram_received.notify_waiters();
         shared.datagrams_unblocked.notify_waiters();
+        shared.datagram_dropped.notify_waiters();
+        shared.max_datagram_size_changed.notify_waiters();
+        shared.ping_acknowledged.notify_waiters();
+        shared.path_probed.notify_waiters();
         shared.handshake_confirmed.notify_waiters();
+        shared.migrated.notify_waiters();
+        shared.send_data_acked.notify_waiters();
         wake_all_notify(&mut self.stopped);
+        self.path_observers.clear();
+        self.address_observers.clear();
         shared.closed.notify_waiters();
         shared.connected.notify_waiters();
     }
@@ -1285,6 +2308,7 @@ impl State {
     pub(crate) fn check_0rtt(&self) -> Result<(), ()> {
         if self.inner.is_handshaking()
             || self.inner.accepted_0rtt()
+            || self.inner.retransmitted_0rtt()
             || self.inner.side().is_server()
         {
             Ok(())
@@ -1353,6 +2377,28 @@ pub enum SendDatagramError {
     ConnectionLost(#[from] ConnectionError),
 }
 
+/// Errors that can arise when opening a stream with a timeout
+#[derive(Debug, Error, Clone, Eq, PartialEq)]
+pub enum OpenStreamError {
+    /// The peer didn't allow another stream to be opened in time
+    #[error("timed out waiting for stream credit")]
+    TimedOut,
+    /// The connection was lost
+    #[error("connection lost")]
+    ConnectionLost(#[from] ConnectionError),
+}
+
+/// Errors that can arise when probing a network path
+#[derive(Debug, Error, Clone, Eq, PartialEq)]
+pub enum PathError {
+    /// No response arrived from the probed address in time
+    #[error("path probe timed out")]
+    TimedOut,
+    /// The connection was lost
+    #[error("connection lost")]
+    ConnectionLost(#[from] ConnectionError),
+}
+
 /// The maximum amount of datagrams which will be produced in a single `drive_transmit` call
 ///
 /// This limits the amount of CPU resources consumed by datagram generation,
@@ -1365,3 +2411,6 @@ const MAX_TRANSMIT_DATAGRAMS: usize = 20;
 /// memory allocations when calling `poll_transmit()`. Benchmarks have shown
 /// that numbers around 10 are a good compromise.
 const MAX_TRANSMIT_SEGMENTS: usize = 10;
+
+/// Number of [`PathEvent`]s buffered for each [`PathEvents`] subscriber
+const PATH_EVENTS_CAPACITY: usize = 64;
@@ -1,11 +1,11 @@
 use std::{
     collections::VecDeque,
     fmt,
-    future::Future,
+    future::{Future, poll_fn},
     io::{self, IoSliceMut},
     mem,
     net::{SocketAddr, SocketAddrV6},
-    pin::Pin,
+    pin::{Pin, pin},
     str,
     sync::{
         Arc, Mutex,
@@ -21,7 +21,7 @@ use std::{
 ))]
 use crate::runtime::default_runtime;
 use crate::{
-    Instant,
+    Duration, Instant,
     runtime::{AsyncUdpSocket, Runtime, UdpSender},
     udp_transmit,
 };
@@ -44,8 +44,11 @@ use udp::{BATCH_SIZE, RecvMeta};
 
 use crate::{
     ConnectionEvent, EndpointConfig, IO_LOOP_BOUND, RECV_TIME_BOUND, VarInt,
-    connection::Connecting, incoming::Incoming, work_limiter::WorkLimiter,
+    connection::Connecting, incoming::Incoming, send_rate_limiter::SendRateLimiter,
+    work_limiter::WorkLimiter,
 };
+#[cfg(feature = "metrics")]
+use crate::{EndpointMetrics, metrics::MetricsCounters};
 
 /// A QUIC endpoint.
 ///
@@ -105,6 +108,26 @@ impl Endpoint {
         self.inner.state.lock().unwrap().stats
     }
 
+    /// Returns statistics on the I/O performed by this endpoint's driver
+    ///
+    /// Useful to detect when the driver, which handles all of the endpoint's incoming traffic on
+    /// a single task, is falling behind.
+    pub fn io_stats(&self) -> EndpointIoStats {
+        let state = self.inner.state.lock().unwrap();
+        EndpointIoStats {
+            incoming_queue_depth: state.recv_state.incoming.len(),
+            ..state.recv_state.io_stats
+        }
+    }
+
+    /// Current values of the counters aggregated over this endpoint and all of its connections
+    ///
+    /// Unlike [`stats()`](Self::stats), reading these doesn't contend with I/O.
+    #[cfg(feature = "metrics")]
+    pub fn metrics_snapshot(&self) -> EndpointMetrics {
+        self.inner.shared.metrics.snapshot()
+    }
+
     /// Helper to construct an endpoint for use with both incoming and outgoing connections
     ///
     /// Note that `addr` is the *local* address to bind to, which should usually be a wildcard
@@ -154,6 +177,81 @@ impl Endpoint {
         Self::new_with_abstract_socket(config, server_config, socket, runtime)
     }
 
+    /// Construct a group of `shards` server endpoints sharing `addr`, each with its own driver
+    ///
+    /// Spreads the work of a busy server across several endpoint drivers, and hence across the
+    /// threads of a multi-threaded runtime. Every shard binds its own socket to `addr` with
+    /// `SO_REUSEPORT`, and the kernel distributes incoming datagrams among them by hashing the
+    /// 4-tuple. A connection is owned by whichever shard receives its first packet, so
+    /// [`accept()`](Self::accept) must be called on every returned endpoint.
+    ///
+    /// A peer that migrates, or whose NAT binding changes, usually hashes to a different shard. To
+    /// keep each connection on its shard regardless, `config`'s CID generator is replaced by a
+    /// [`ShardedConnectionIdGenerator`](proto::ShardedConnectionIdGenerator) recording the
+    /// shard's index, and shards hand packets for connections they don't own to the shard named
+    /// by their destination CID. [`EndpointIoStats::datagrams_forwarded`] counts how often that
+    /// happens, and [`EndpointIoStats::forwarded_datagrams_dropped`] how often the receiving
+    /// shard's queue was full.
+    ///
+    /// If `addr` has port 0, all shards share the port assigned to the first. At most 256 shards
+    /// are supported.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn new_sharded(
+        config: EndpointConfig,
+        server_config: ServerConfig,
+        addr: SocketAddr,
+        shards: usize,
+        runtime: Arc<dyn Runtime>,
+    ) -> io::Result<Vec<Self>> {
+        use socket2::{Domain, Protocol, Socket, Type};
+
+        if shards == 0 || shards > usize::from(u8::MAX) + 1 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "shard count must be between 1 and 256",
+            ));
+        }
+        let router = Arc::new(ShardRouter {
+            inboxes: (0..shards).map(|_| Mutex::default()).collect(),
+        });
+        let mut addr = addr;
+        let mut endpoints = Vec::with_capacity(shards);
+        for index in 0..shards {
+            let index = index as u8;
+            let socket = Socket::new(Domain::for_address(addr), Type::DGRAM, Some(Protocol::UDP))?;
+            if addr.is_ipv6() {
+                if let Err(e) = socket.set_only_v6(false) {
+                    tracing::debug!(%e, "unable to make socket dual-stack");
+                }
+            }
+            socket.set_reuse_port(true)?;
+            socket.bind(&addr.into())?;
+            let socket = std::net::UdpSocket::from(socket);
+            addr = socket.local_addr()?;
+
+            let mut config = config.clone();
+            config.cid_generator(Arc::new(move || {
+                Box::new(proto::ShardedConnectionIdGenerator::new(
+                    index,
+                    SHARDED_CID_LEN,
+                ))
+            }));
+            let endpoint = Self::new(config, Some(server_config.clone()), socket, runtime.clone())?;
+            let mut state = endpoint.inner.state.lock().unwrap();
+            state.recv_state.shard = Some(ShardLink {
+                router: router.clone(),
+                index,
+            });
+            // The driver must run again to start taking datagrams forwarded by other shards
+            if let Some(driver) = &state.driver {
+                driver.wake_by_ref();
+            }
+            drop(state);
+            endpoints.push(endpoint);
+        }
+        Ok(endpoints)
+    }
+
     /// Construct an endpoint with arbitrary configuration and pre-constructed abstract socket
     ///
     /// Useful when `socket` has additional state (e.g. sidechannels) attached for which shared
@@ -165,6 +263,7 @@ impl Endpoint {
         runtime: Arc<dyn Runtime>,
     ) -> io::Result<Self> {
         let addr = socket.local_addr()?;
+        configure_socket(&config, &*socket)?;
         let allow_mtud = !socket.may_fragment();
         let rc = EndpointRef::new(
             socket,
@@ -190,6 +289,11 @@ impl Endpoint {
     /// can be `await`ed to obtain the final [`Connection`](crate::Connection), or used to e.g.
     /// filter connection attempts or force address validation, or converted into an intermediate
     /// `Connecting` future which can be used to e.g. send 0.5-RTT data.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancellation safe. A connection attempt is only dequeued when the returned
+    /// future resolves, so dropping it early never loses one.
     pub fn accept(&self) -> Accept<'_> {
         Accept {
             endpoint: self,
@@ -197,6 +301,26 @@ impl Endpoint {
         }
     }
 
+    /// Get the next incoming connection attempt, giving up after `timeout`
+    ///
+    /// Like [`accept()`](Self::accept), but yields `None` if no connection attempt arrives within
+    /// `timeout`, e.g. to perform housekeeping between attempts. A connection attempt arriving
+    /// after the timeout remains queued for the next call.
+    ///
+    /// Once the endpoint is [`close`](Self::close)d, this yields `None` immediately, just like
+    /// `accept()`, so callers looping on this method should track closure themselves.
+    pub async fn accept_timeout(&self, timeout: Duration) -> Option<Incoming> {
+        let mut timer = self.runtime.new_timer(self.runtime.now() + timeout);
+        let mut accept = pin!(self.accept());
+        poll_fn(|cx| {
+            if let Poll::Ready(incoming) = accept.as_mut().poll(cx) {
+                return Poll::Ready(incoming);
+            }
+            timer.as_mut().poll(cx).map(|()| None)
+        })
+        .await
+    }
+
     /// Set the client configuration used by `connect`
     pub fn set_default_client_config(&self, config: ClientConfig) {
         self.inner.0.state.lock().unwrap().default_client_config = Some(config);
@@ -275,10 +399,16 @@ impl Endpoint {
     /// Allows the endpoint's address to be updated live, affecting all active connections. Incoming
     /// connections and connections to servers unreachable from the new address will be lost.
     ///
+    /// `socket` may be any [`AsyncUdpSocket`] implementation, not necessarily one produced by the
+    /// endpoint's runtime, so platform integrations can hand over a transport of their own.
+    /// Existing connections move to the new socket immediately; peers see a new address and
+    /// validate the new path before fully trusting it.
+    ///
     /// On error, the old UDP socket is retained.
     pub fn rebind_abstract(&self, socket: Box<dyn AsyncUdpSocket>) -> io::Result<()> {
         let addr = socket.local_addr()?;
         let mut inner = self.inner.state.lock().unwrap();
+        configure_socket(inner.inner.config(), &*socket)?;
         inner.prev_socket = Some(mem::replace(&mut inner.socket, socket));
         inner.ipv6 = addr.is_ipv6();
 
@@ -295,6 +425,35 @@ impl Endpoint {
         Ok(())
     }
 
+    /// Receive on an additional UDP socket
+    ///
+    /// See [`add_abstract_socket()`](Self::add_abstract_socket).
+    pub fn add_socket(&self, socket: std::net::UdpSocket) -> io::Result<()> {
+        self.add_abstract_socket(self.runtime.wrap_udp_socket(socket)?)
+    }
+
+    /// Receive on an additional UDP socket
+    ///
+    /// Datagrams arriving on `socket` are routed by connection ID like those arriving on the
+    /// endpoint's own socket, so one endpoint can serve several local addresses, e.g. one per
+    /// network interface. Connections accepted from `socket` send through it for their lifetime;
+    /// connections initiated with [`connect()`](Self::connect) always use the endpoint's own
+    /// socket, as reported by [`local_addr()`](Self::local_addr).
+    ///
+    /// Additional sockets can't be removed, and [`rebind()`](Self::rebind) moves every connection,
+    /// including those accepted from additional sockets, to the new socket.
+    pub fn add_abstract_socket(&self, socket: Box<dyn AsyncUdpSocket>) -> io::Result<()> {
+        let mut inner = self.inner.state.lock().unwrap();
+        configure_socket(inner.inner.config(), &*socket)?;
+        let sender = socket.create_sender();
+        inner.extra_sockets.push((socket, sender));
+        if let Some(driver) = inner.driver.take() {
+            // Ensure the driver can register for wake-ups from the new socket
+            driver.wake();
+        }
+        Ok(())
+    }
+
     /// Replace the server configuration, affecting new incoming connections only
     ///
     /// Useful for e.g. refreshing TLS certificates without disrupting existing connections.
@@ -307,16 +466,69 @@ impl Endpoint {
             .set_server_config(server_config.map(Arc::new))
     }
 
+    /// Perform the cryptographic provider's one-time initialization ahead of the first connection
+    ///
+    /// Crypto libraries lazily initialize global state, such as detected CPU features, which adds
+    /// latency to the first handshake an endpoint performs. The amount depends on the crypto
+    /// provider and platform. Calling this after configuring the endpoint moves that cost out of
+    /// the first connection, using the default client configuration and the server configuration,
+    /// whichever are set. See [`ClientConfig::prewarm()`] and [`ServerConfig::prewarm()`].
+    pub fn prewarm(&self) {
+        let (client_config, server_config) = {
+            let state = self.inner.state.lock().unwrap();
+            (
+                state.default_client_config.clone(),
+                state.inner.server_config().cloned(),
+            )
+        };
+        if let Some(config) = client_config {
+            config.prewarm();
+        }
+        if let Some(config) = server_config {
+            config.prewarm();
+        }
+    }
+
     /// Get the local `SocketAddr` the underlying socket is bound to
     pub fn local_addr(&self) -> io::Result<SocketAddr> {
         self.inner.state.lock().unwrap().socket.local_addr()
     }
 
+    /// Maximum number of datagrams the underlying socket currently sends in a single system call
+    ///
+    /// Greater than 1 when the socket uses Generic Segmentation Offload (GSO) or a similar
+    /// batching mechanism, and 1 otherwise. This may decrease over the endpoint's lifetime if
+    /// batched sends turn out to be unsupported by the network interface.
+    pub fn max_transmit_segments(&self) -> usize {
+        self.inner
+            .state
+            .lock()
+            .unwrap()
+            .sender
+            .max_transmit_segments()
+    }
+
     /// Get the number of connections that are currently open
     pub fn open_connections(&self) -> usize {
         self.inner.state.lock().unwrap().inner.open_connections()
     }
 
+    /// Refuse new incoming connections while leaving established connections untouched
+    ///
+    /// Subsequent connection attempts are answered with a `CONNECTION_REFUSED` error. Once any
+    /// connection attempts that were already queued have been returned, [`accept()`] yields
+    /// `None`. Useful for graceful shutdown in combination with [`open_connections()`] or
+    /// [`wait_idle()`].
+    ///
+    /// [`accept()`]: Endpoint::accept
+    /// [`open_connections()`]: Endpoint::open_connections
+    /// [`wait_idle()`]: Endpoint::wait_idle
+    pub fn reject_new_connections(&self) {
+        let mut endpoint = self.inner.state.lock().unwrap();
+        endpoint.recv_state.connections.reject_new = true;
+        self.inner.shared.incoming.notify_waiters();
+    }
+
     /// Close all of this endpoint's connections immediately and cease accepting new connections.
     ///
     /// See [`Connection::close()`] for details.
@@ -375,6 +587,31 @@ pub struct EndpointStats {
     pub ignored_handshakes: u64,
 }
 
+/// Statistics on the I/O performed by an [Endpoint]'s driver
+#[non_exhaustive]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct EndpointIoStats {
+    /// Number of connection attempts received but not yet taken by [`Endpoint::accept()`]
+    pub incoming_queue_depth: usize,
+    /// Cumulative number of times the driver stopped receiving to yield to other tasks while more
+    /// datagrams may have been waiting
+    ///
+    /// Steady growth indicates that the endpoint is receiving more traffic than a single task
+    /// can keep up with.
+    pub recv_budget_exhausted: u64,
+    /// Cumulative number of stateless responses, e.g. version negotiation, stateless resets, or
+    /// refusals, dropped because the socket's send buffer was full
+    pub responses_dropped: u64,
+    /// Cumulative number of datagrams handed to another shard of the group created by
+    /// [`Endpoint::new_sharded()`], because their connection is owned by that shard
+    pub datagrams_forwarded: u64,
+    /// Cumulative number of datagrams owned by another shard, but dropped because that shard
+    /// already had too many forwarded datagrams waiting
+    ///
+    /// Growth indicates that the owning shard's driver isn't keeping up.
+    pub forwarded_datagrams_dropped: u64,
+}
+
 /// A future that drives IO on an endpoint
 ///
 /// This task functions as the switch point between the UDP socket object and the
@@ -445,6 +682,7 @@ impl EndpointInner {
     pub(crate) fn accept(
         &self,
         incoming: proto::Incoming,
+        socket: SocketIndex,
         server_config: Option<Arc<ServerConfig>>,
     ) -> Result<Connecting, ConnectionError> {
         let mut state = self.state.lock().unwrap();
@@ -456,7 +694,9 @@ impl EndpointInner {
         {
             Ok((handle, conn)) => {
                 state.stats.accepted_handshakes += 1;
-                let sender = state.socket.create_sender();
+                #[cfg(feature = "metrics")]
+                self.shared.metrics.on_accepted();
+                let sender = state.create_sender(socket);
                 let runtime = state.runtime.clone();
                 Ok(state
                     .recv_state
@@ -465,26 +705,38 @@ impl EndpointInner {
             }
             Err(error) => {
                 if let Some(transmit) = error.response {
-                    respond(transmit, &response_buffer, &mut state.sender);
+                    if !respond(transmit, &response_buffer, state.sender(socket)) {
+                        state.recv_state.io_stats.responses_dropped += 1;
+                    }
                 }
                 Err(error.cause)
             }
         }
     }
 
-    pub(crate) fn refuse(&self, incoming: proto::Incoming) {
+    pub(crate) fn refuse(&self, incoming: proto::Incoming, socket: SocketIndex) {
         let mut state = self.state.lock().unwrap();
         state.stats.refused_handshakes += 1;
         let mut response_buffer = Vec::new();
         let transmit = state.inner.refuse(incoming, &mut response_buffer);
-        respond(transmit, &response_buffer, &mut state.sender);
+        if !respond(transmit, &response_buffer, state.sender(socket)) {
+            state.recv_state.io_stats.responses_dropped += 1;
+        }
     }
 
-    pub(crate) fn retry(&self, incoming: proto::Incoming) -> Result<(), proto::RetryError> {
+    pub(crate) fn retry(
+        &self,
+        incoming: proto::Incoming,
+        socket: SocketIndex,
+    ) -> Result<(), proto::RetryError> {
         let mut state = self.state.lock().unwrap();
         let mut response_buffer = Vec::new();
         let transmit = state.inner.retry(incoming, &mut response_buffer)?;
-        respond(transmit, &response_buffer, &mut state.sender);
+        if !respond(transmit, &response_buffer, state.sender(socket)) {
+            state.recv_state.io_stats.responses_dropped += 1;
+        }
+        #[cfg(feature = "metrics")]
+        self.shared.metrics.on_retry();
         Ok(())
     }
 
@@ -495,6 +747,9 @@ impl EndpointInner {
     }
 }
 
+/// A socket registered by `add_socket()` with the sender for responses sent through it
+type ExtraSocket = (Box<dyn AsyncUdpSocket>, Pin<Box<dyn UdpSender>>);
+
 #[derive(Debug)]
 pub(crate) struct State {
     socket: Box<dyn AsyncUdpSocket>,
@@ -502,6 +757,8 @@ pub(crate) struct State {
     /// During an active migration, abandoned_socket receives traffic
     /// until the first packet arrives on the new socket.
     prev_socket: Option<Box<dyn AsyncUdpSocket>>,
+    /// Sockets registered by `add_socket()`
+    extra_sockets: Vec<ExtraSocket>,
     inner: proto::Endpoint,
     recv_state: RecvState,
     driver: Option<Waker>,
@@ -519,10 +776,27 @@ pub(crate) struct Shared {
     idle: Notify,
     /// Number of live handles that can be used to initiate or handle I/O; excludes the driver
     ref_count: AtomicUsize,
+    #[cfg(feature = "metrics")]
+    metrics: Arc<MetricsCounters>,
 }
 
 impl State {
     fn drive_recv(&mut self, cx: &mut Context<'_>, now: Instant) -> Result<bool, io::Error> {
+        let forwarded = match &self.recv_state.shard {
+            Some(shard) => shard.take_forwarded(cx),
+            None => VecDeque::new(),
+        };
+        for (meta, datagram) in forwarded {
+            self.recv_state.handle_datagram(
+                &mut self.inner,
+                &mut self.sender,
+                now,
+                &meta,
+                datagram,
+                PRIMARY_SOCKET,
+            );
+        }
+
         let get_time = || self.runtime.now();
         self.recv_state.recv_limiter.start_cycle(get_time);
         if let Some(socket) = &mut self.prev_socket {
@@ -534,19 +808,40 @@ impl State {
                 &mut self.sender,
                 &*self.runtime,
                 now,
+                PRIMARY_SOCKET,
             );
             if poll_res.is_err() {
                 self.prev_socket = None;
             }
         };
-        let poll_res = self.recv_state.poll_socket(
-            cx,
-            &mut self.inner,
-            &mut *self.socket,
-            &mut self.sender,
-            &*self.runtime,
-            now,
-        );
+        let poll_res = self
+            .recv_state
+            .poll_socket(
+                cx,
+                &mut self.inner,
+                &mut *self.socket,
+                &mut self.sender,
+                &*self.runtime,
+                now,
+                PRIMARY_SOCKET,
+            )
+            .and_then(|mut progress| {
+                for (i, (socket, sender)) in self.extra_sockets.iter_mut().enumerate() {
+                    progress.keep_going |= self
+                        .recv_state
+                        .poll_socket(
+                            cx,
+                            &mut self.inner,
+                            &mut **socket,
+                            sender,
+                            &*self.runtime,
+                            now,
+                            i + 1,
+                        )?
+                        .keep_going;
+                }
+                Ok(progress)
+            });
         self.recv_state.recv_limiter.finish_cycle(get_time);
         let poll_res = poll_res?;
         if poll_res.received_connection_packet {
@@ -557,6 +852,22 @@ impl State {
         Ok(poll_res.keep_going)
     }
 
+    /// Sender for traffic leaving through `socket`
+    fn sender(&mut self, socket: SocketIndex) -> &mut Pin<Box<dyn UdpSender>> {
+        match socket {
+            PRIMARY_SOCKET => &mut self.sender,
+            i => &mut self.extra_sockets[i - 1].1,
+        }
+    }
+
+    /// Create a sender for a new connection using `socket`
+    fn create_sender(&self, socket: SocketIndex) -> Pin<Box<dyn UdpSender>> {
+        match socket {
+            PRIMARY_SOCKET => self.socket.create_sender(),
+            i => self.extra_sockets[i - 1].0.create_sender(),
+        }
+    }
+
     fn handle_events(&mut self, cx: &mut Context<'_>, shared: &Shared) -> bool {
         for _ in 0..IO_LOOP_BOUND {
             let (ch, event) = match self.events.poll_recv(cx) {
@@ -592,17 +903,29 @@ impl State {
 
 impl Drop for State {
     fn drop(&mut self) {
-        for incoming in self.recv_state.incoming.drain(..) {
+        for (incoming, _) in self.recv_state.incoming.drain(..) {
             self.inner.ignore(incoming);
         }
     }
 }
 
+/// Apply the socket options from `config` to a socket the endpoint is about to use
+fn configure_socket(config: &EndpointConfig, socket: &dyn AsyncUdpSocket) -> io::Result<()> {
+    if let Some(max) = config.get_max_receive_segments() {
+        socket.set_max_receive_segments(max)?;
+    }
+    if config.get_disable_gso() {
+        socket.disable_gso();
+    }
+    Ok(())
+}
+
+/// Returns `false` if the response was dropped because the socket wasn't ready to send
 fn respond(
     transmit: proto::Transmit,
     response_buffer: &[u8],
     sender: &mut Pin<Box<dyn UdpSender>>,
-) {
+) -> bool {
     // Send if there's kernel buffer space; otherwise, drop it
     //
     // As an endpoint-generated packet, we know this is an
@@ -642,10 +965,13 @@ fn respond(
     // it doesn't access the data pointer at all.
     let waker = unsafe { Waker::from_raw(NOOP) };
     let mut cx = Context::from_waker(&waker);
-    _ = sender.as_mut().poll_send(
-        &udp_transmit(&transmit, &response_buffer[..transmit.size]),
-        &mut cx,
-    );
+    sender
+        .as_mut()
+        .poll_send(
+            &udp_transmit(&transmit, &response_buffer[..transmit.size]),
+            &mut cx,
+        )
+        .is_ready()
 }
 
 #[inline]
@@ -665,6 +991,13 @@ struct ConnectionSet {
     sender: mpsc::UnboundedSender<(ConnectionHandle, EndpointEvent)>,
     /// Set if the endpoint has been manually closed
     close: Option<(VarInt, Bytes)>,
+    /// Whether new incoming connections should be refused
+    reject_new: bool,
+    /// Handed to new connections so they can account for their traffic
+    #[cfg(feature = "metrics")]
+    metrics: Arc<MetricsCounters>,
+    /// Shared by all connections to enforce [`EndpointConfig::max_send_rate`]
+    send_rate: Option<Arc<SendRateLimiter>>,
 }
 
 impl ConnectionSet {
@@ -684,12 +1017,27 @@ impl ConnectionSet {
             .unwrap();
         }
         self.senders.insert(handle, send);
-        Connecting::new(handle, conn, self.sender.clone(), recv, sender, runtime)
+        Connecting::new(
+            handle,
+            conn,
+            self.sender.clone(),
+            recv,
+            sender,
+            runtime,
+            self.send_rate.clone(),
+            #[cfg(feature = "metrics")]
+            self.metrics.clone(),
+        )
     }
 
     fn is_empty(&self) -> bool {
         self.senders.is_empty()
     }
+
+    /// Whether new incoming connections may be accepted
+    fn accepting(&self) -> bool {
+        self.close.is_none() && !self.reject_new
+    }
 }
 
 fn ensure_ipv6(x: SocketAddr) -> SocketAddrV6 {
@@ -716,13 +1064,13 @@ impl Future for Accept<'_> {
         if endpoint.driver_lost {
             return Poll::Ready(None);
         }
-        if let Some(incoming) = endpoint.recv_state.incoming.pop_front() {
+        if let Some((incoming, socket)) = endpoint.recv_state.incoming.pop_front() {
             // Release the mutex lock on endpoint so cloning it doesn't deadlock
             drop(endpoint);
-            let incoming = Incoming::new(incoming, this.endpoint.inner.clone());
+            let incoming = Incoming::new(incoming, socket, this.endpoint.inner.clone());
             return Poll::Ready(Some(incoming));
         }
-        if endpoint.recv_state.connections.close.is_some() {
+        if !endpoint.recv_state.connections.accepting() {
             return Poll::Ready(None);
         }
         loop {
@@ -749,18 +1097,29 @@ impl EndpointRef {
         runtime: Arc<dyn Runtime>,
     ) -> Self {
         let (sender, events) = mpsc::unbounded_channel();
-        let recv_state = RecvState::new(sender, socket.max_receive_segments(), &inner);
+        #[cfg(feature = "metrics")]
+        let metrics = Arc::new(MetricsCounters::default());
+        let recv_state = RecvState::new(
+            sender,
+            socket.max_receive_segments(),
+            &inner,
+            #[cfg(feature = "metrics")]
+            metrics.clone(),
+        );
         let sender = socket.create_sender();
         Self(Arc::new(EndpointInner {
             shared: Shared {
                 incoming: Notify::new(),
                 idle: Notify::new(),
                 ref_count: AtomicUsize::new(0),
+                #[cfg(feature = "metrics")]
+                metrics,
             },
             state: Mutex::new(State {
                 socket,
                 sender,
                 prev_socket: None,
+                extra_sockets: Vec::new(),
                 inner,
                 ipv6,
                 events,
@@ -806,10 +1165,14 @@ impl std::ops::Deref for EndpointRef {
 
 /// State directly involved in handling incoming packets
 struct RecvState {
-    incoming: VecDeque<proto::Incoming>,
+    /// Connection attempts yet to be accepted, and the socket each arrived on
+    incoming: VecDeque<(proto::Incoming, SocketIndex)>,
     connections: ConnectionSet,
     recv_buf: Box<[u8]>,
     recv_limiter: WorkLimiter,
+    io_stats: EndpointIoStats,
+    /// Membership in a group created by `Endpoint::new_sharded()`
+    shard: Option<ShardLink>,
 }
 
 impl RecvState {
@@ -817,6 +1180,7 @@ impl RecvState {
         sender: mpsc::UnboundedSender<(ConnectionHandle, EndpointEvent)>,
         max_receive_segments: usize,
         endpoint: &proto::Endpoint,
+        #[cfg(feature = "metrics")] metrics: Arc<MetricsCounters>,
     ) -> Self {
         let recv_buf = vec![
             0;
@@ -829,13 +1193,23 @@ impl RecvState {
                 senders: FxHashMap::default(),
                 sender,
                 close: None,
+                reject_new: false,
+                #[cfg(feature = "metrics")]
+                metrics,
+                send_rate: endpoint
+                    .config()
+                    .get_max_send_rate()
+                    .map(|rate| Arc::new(SendRateLimiter::new(rate))),
             },
             incoming: VecDeque::new(),
             recv_buf: recv_buf.into(),
             recv_limiter: WorkLimiter::new(RECV_TIME_BOUND),
+            io_stats: EndpointIoStats::default(),
+            shard: None,
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn poll_socket(
         &mut self,
         cx: &mut Context<'_>,
@@ -844,16 +1218,18 @@ impl RecvState {
         sender: &mut Pin<Box<dyn UdpSender>>,
         runtime: &dyn Runtime,
         now: Instant,
+        socket_index: SocketIndex,
     ) -> Result<PollProgress, io::Error> {
         let mut received_connection_packet = false;
         let mut metas = [RecvMeta::default(); BATCH_SIZE];
+        // Moved out for the duration so that datagrams can be handled through `&mut self`
+        let mut recv_buf = mem::take(&mut self.recv_buf);
         let mut iovs: [IoSliceMut<'_>; BATCH_SIZE] = {
-            let mut bufs = self
-                .recv_buf
-                .chunks_mut(self.recv_buf.len() / BATCH_SIZE)
+            let mut bufs = recv_buf
+                .chunks_mut(recv_buf.len() / BATCH_SIZE)
                 .map(IoSliceMut::new);
 
-            // expect() safe as self.recv_buf is chunked into BATCH_SIZE items
+            // expect() safe as recv_buf is chunked into BATCH_SIZE items
             // and iovs will be of size BATCH_SIZE, thus from_fn is called
             // exactly BATCH_SIZE times.
             std::array::from_fn(|_| bufs.next().expect("BATCH_SIZE elements"))
@@ -866,43 +1242,33 @@ impl RecvState {
                         let mut data: BytesMut = buf[0..meta.len].into();
                         while !data.is_empty() {
                             let buf = data.split_to(meta.stride.min(data.len()));
-                            let mut response_buffer = Vec::new();
-                            match endpoint.handle(
+                            let buf = match &self.shard {
+                                Some(shard) => match shard.steer(meta, buf) {
+                                    Steered::Local(buf) => buf,
+                                    Steered::Forwarded => {
+                                        self.io_stats.datagrams_forwarded += 1;
+                                        continue;
+                                    }
+                                    Steered::Dropped => {
+                                        self.io_stats.forwarded_datagrams_dropped += 1;
+                                        continue;
+                                    }
+                                },
+                                None => buf,
+                            };
+                            received_connection_packet |= self.handle_datagram(
+                                endpoint,
+                                sender,
                                 now,
-                                meta.addr,
-                                meta.dst_ip,
-                                meta.ecn.map(proto_ecn),
+                                meta,
                                 buf,
-                                &mut response_buffer,
-                            ) {
-                                Some(DatagramEvent::NewConnection(incoming)) => {
-                                    if self.connections.close.is_none() {
-                                        self.incoming.push_back(incoming);
-                                    } else {
-                                        let transmit =
-                                            endpoint.refuse(incoming, &mut response_buffer);
-                                        respond(transmit, &response_buffer, sender);
-                                    }
-                                }
-                                Some(DatagramEvent::ConnectionEvent(handle, event)) => {
-                                    // Ignoring errors from dropped connections that haven't yet been cleaned up
-                                    received_connection_packet = true;
-                                    let _ = self
-                                        .connections
-                                        .senders
-                                        .get_mut(&handle)
-                                        .unwrap()
-                                        .send(ConnectionEvent::Proto(event));
-                                }
-                                Some(DatagramEvent::Response(transmit)) => {
-                                    respond(transmit, &response_buffer, sender);
-                                }
-                                None => {}
-                            }
+                                socket_index,
+                            );
                         }
                     }
                 }
                 Poll::Pending => {
+                    self.recv_buf = recv_buf;
                     return Ok(PollProgress {
                         received_connection_packet,
                         keep_going: false,
@@ -914,10 +1280,13 @@ impl RecvState {
                     continue;
                 }
                 Poll::Ready(Err(e)) => {
+                    self.recv_buf = recv_buf;
                     return Err(e);
                 }
             }
             if !self.recv_limiter.allow_work(|| runtime.now()) {
+                self.io_stats.recv_budget_exhausted += 1;
+                self.recv_buf = recv_buf;
                 return Ok(PollProgress {
                     received_connection_packet,
                     keep_going: true,
@@ -925,6 +1294,64 @@ impl RecvState {
             }
         }
     }
+
+    /// Passes a single datagram to `endpoint` and acts on the result
+    ///
+    /// Returns whether the datagram was routed to an existing connection.
+    fn handle_datagram(
+        &mut self,
+        endpoint: &mut proto::Endpoint,
+        sender: &mut Pin<Box<dyn UdpSender>>,
+        now: Instant,
+        meta: &RecvMeta,
+        buf: BytesMut,
+        socket_index: SocketIndex,
+    ) -> bool {
+        #[cfg(feature = "metrics")]
+        let len = buf.len();
+        let mut response_buffer = Vec::new();
+        match endpoint.handle(
+            now,
+            meta.addr,
+            meta.dst_ip,
+            meta.ecn.map(proto_ecn),
+            buf,
+            &mut response_buffer,
+        ) {
+            Some(DatagramEvent::NewConnection(incoming)) => {
+                #[cfg(feature = "metrics")]
+                self.connections.metrics.on_received(len);
+                if self.connections.accepting() {
+                    self.incoming.push_back((incoming, socket_index));
+                } else {
+                    let transmit = endpoint.refuse(incoming, &mut response_buffer);
+                    if !respond(transmit, &response_buffer, sender) {
+                        self.io_stats.responses_dropped += 1;
+                    }
+                }
+            }
+            Some(DatagramEvent::ConnectionEvent(handle, event)) => {
+                #[cfg(feature = "metrics")]
+                self.connections.metrics.on_received(len);
+                // Ignoring errors from dropped connections that haven't yet been cleaned up
+                let _ = self
+                    .connections
+                    .senders
+                    .get_mut(&handle)
+                    .unwrap()
+                    .send(ConnectionEvent::Proto(event));
+                return true;
+            }
+            Some(DatagramEvent::Response(transmit)) => {
+                let sent = respond(transmit, &response_buffer, sender);
+                if !sent {
+                    self.io_stats.responses_dropped += 1;
+                }
+            }
+            None => {}
+        }
+        false
+    }
 }
 
 impl fmt::Debug for RecvState {
@@ -934,10 +1361,103 @@ impl fmt::Debug for RecvState {
             .field("connections", &self.connections)
             // recv_buf too large
             .field("recv_limiter", &self.recv_limiter)
+            .field("io_stats", &self.io_stats)
+            .field("shard", &self.shard)
             .finish_non_exhaustive()
     }
 }
 
+/// Identifies one of an endpoint's sockets: the primary socket, or one added by `add_socket()`
+pub(crate) type SocketIndex = usize;
+
+/// The socket the endpoint was created with, or most recently rebound to
+pub(crate) const PRIMARY_SOCKET: SocketIndex = 0;
+
+/// Length of the CIDs issued by the shards created by `Endpoint::new_sharded()`
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const SHARDED_CID_LEN: usize = 8;
+
+/// Most datagrams a shard holds for another before dropping them, in case the other's driver stalled
+const MAX_FORWARDED_DATAGRAMS: usize = 1024;
+
+/// Mailboxes through which the shards created by `Endpoint::new_sharded()` exchange datagrams
+#[cfg_attr(not(any(target_os = "linux", target_os = "android")), allow(dead_code))]
+#[derive(Debug)]
+struct ShardRouter {
+    /// Indexed by shard
+    inboxes: Box<[Mutex<ShardInbox>]>,
+}
+
+#[derive(Debug, Default)]
+struct ShardInbox {
+    datagrams: VecDeque<(RecvMeta, BytesMut)>,
+    /// Woken when datagrams are added
+    driver: Option<Waker>,
+}
+
+/// A shard's handle on the [`ShardRouter`] of its group
+#[cfg_attr(not(any(target_os = "linux", target_os = "android")), allow(dead_code))]
+#[derive(Debug)]
+struct ShardLink {
+    router: Arc<ShardRouter>,
+    index: u8,
+}
+
+impl ShardLink {
+    /// Hands `datagram` to the shard owning its connection, unless that is this shard
+    fn steer(&self, meta: &RecvMeta, datagram: BytesMut) -> Steered {
+        // Only short header packets are steered. Long header packets are only exchanged during
+        // the handshake, which clients must complete before migrating, and the destination CIDs of
+        // the first ones are chosen by the client rather than a shard.
+        let target = match datagram.first() {
+            Some(&first) if first & LONG_HEADER_FORM == 0 => datagram
+                .get(1..)
+                .and_then(proto::ShardedConnectionIdGenerator::shard_of),
+            _ => None,
+        };
+        let target = match target {
+            Some(target) if target != self.index => target,
+            _ => return Steered::Local(datagram),
+        };
+        let Some(inbox) = self.router.inboxes.get(usize::from(target)) else {
+            return Steered::Local(datagram);
+        };
+        let mut inbox = inbox.lock().unwrap();
+        let steered = match inbox.datagrams.len() < MAX_FORWARDED_DATAGRAMS {
+            true => {
+                inbox.datagrams.push_back((*meta, datagram));
+                Steered::Forwarded
+            }
+            false => Steered::Dropped,
+        };
+        if let Some(driver) = inbox.driver.take() {
+            driver.wake();
+        }
+        steered
+    }
+
+    /// Takes the datagrams other shards handed to this one, arranging for `cx` to be woken by
+    /// the next
+    fn take_forwarded(&self, cx: &mut Context<'_>) -> VecDeque<(RecvMeta, BytesMut)> {
+        let mut inbox = self.router.inboxes[usize::from(self.index)].lock().unwrap();
+        inbox.driver = Some(cx.waker().clone());
+        mem::take(&mut inbox.datagrams)
+    }
+}
+
+/// Outcome of [`ShardLink::steer()`]
+enum Steered {
+    /// The datagram belongs to this shard
+    Local(BytesMut),
+    /// The datagram was queued for the shard owning its connection
+    Forwarded,
+    /// The datagram belongs to another shard, whose queue is full
+    Dropped,
+}
+
+/// Bit distinguishing long header packets from short header ones in their first byte
+const LONG_HEADER_FORM: u8 = 0x80;
+
 #[derive(Default)]
 struct PollProgress {
     /// Whether a datagram was routed to an existing connection
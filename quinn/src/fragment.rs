@@ -0,0 +1,197 @@
+use std::collections::hash_map;
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use rustc_hash::FxHashMap;
+use thiserror::Error;
+use tracing::trace;
+
+use crate::{
+    ConnectionError, Duration, Instant,
+    connection::{Connection, SendDatagramError},
+};
+
+/// Sends application datagrams larger than a single QUIC packet by splitting them into fragments
+///
+/// Each fragment fits in [`Connection::max_datagram_size()`], and a [`DatagramReassembler`] on the
+/// peer puts them back together. Fragments are sent as ordinary unreliable datagrams, so a payload
+/// is delivered only if every one of its fragments arrives.
+///
+/// # Wire format
+///
+/// Each fragment is a single datagram consisting of an 8-byte header followed by a slice of the
+/// payload. All header fields are big-endian:
+///
+/// ```text
+/// +----------------+-----------------+-----------------+----------------------+
+/// | id (32 bits)   | index (16 bits) | count (16 bits) | fragment payload ... |
+/// +----------------+-----------------+-----------------+----------------------+
+/// ```
+///
+/// - `id` identifies the payload, and is shared by all of its fragments. Senders assign ids
+///   sequentially, wrapping on overflow.
+/// - `index` is the fragment's zero-based position within the payload.
+/// - `count` is the total number of fragments in the payload, and is at least 1.
+///
+/// The payload is the concatenation of the fragments' payloads in `index` order. Every payload is
+/// framed this way, even those which would fit in a single datagram.
+#[derive(Debug)]
+pub struct DatagramSender {
+    conn: Connection,
+    next_id: u32,
+}
+
+impl DatagramSender {
+    /// Send datagrams on `conn`
+    pub fn new(conn: Connection) -> Self {
+        Self { conn, next_id: 0 }
+    }
+
+    /// Transmit `data`, fragmenting it as needed
+    ///
+    /// Fragments are sized to the current [`Connection::max_datagram_size()`] and queued with
+    /// [`Connection::send_datagram_wait()`], so that fragments of the same payload don't evict
+    /// each other from the outgoing queue. Fails with [`SendDatagramError::TooLarge`] if `data`
+    /// would need more than 65535 fragments.
+    pub async fn send(&mut self, data: Bytes) -> Result<(), SendDatagramError> {
+        let max_size = self
+            .conn
+            .max_datagram_size()
+            .ok_or(SendDatagramError::Disabled)?;
+        let chunk_size = max_size
+            .checked_sub(HEADER_LEN)
+            .filter(|&x| x > 0)
+            .ok_or(SendDatagramError::TooLarge)?;
+        let count = u16::try_from(data.len().div_ceil(chunk_size).max(1))
+            .map_err(|_| SendDatagramError::TooLarge)?;
+
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        for index in 0..count {
+            let start = usize::from(index) * chunk_size;
+            let end = data.len().min(start + chunk_size);
+            let mut fragment = BytesMut::with_capacity(HEADER_LEN + end - start);
+            fragment.put_u32(id);
+            fragment.put_u16(index);
+            fragment.put_u16(count);
+            fragment.put_slice(&data[start..end]);
+            self.conn.send_datagram_wait(fragment.freeze()).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Reassembles payloads sent by a [`DatagramSender`]
+///
+/// Payloads whose fragments don't all arrive within the configured timeout are dropped, as are
+/// the oldest incomplete payloads once more than 64 are pending.
+#[derive(Debug)]
+pub struct DatagramReassembler {
+    conn: Connection,
+    timeout: Duration,
+    pending: FxHashMap<u32, Partial>,
+}
+
+impl DatagramReassembler {
+    /// Receive datagrams on `conn`, waiting at most `timeout` for a payload's fragments to arrive
+    pub fn new(conn: Connection, timeout: Duration) -> Self {
+        Self {
+            conn,
+            timeout,
+            pending: FxHashMap::default(),
+        }
+    }
+
+    /// Receive the next complete payload
+    ///
+    /// Datagrams that aren't well-formed fragments are ignored.
+    pub async fn recv(&mut self) -> Result<Bytes, ConnectionError> {
+        loop {
+            let datagram = self.conn.read_datagram().await?;
+            let now = self.conn.now();
+            match self.insert(datagram, now) {
+                Ok(Some(payload)) => return Ok(payload),
+                Ok(None) => {}
+                Err(e) => trace!("ignoring datagram: {e}"),
+            }
+        }
+    }
+
+    fn insert(
+        &mut self,
+        mut datagram: Bytes,
+        now: Instant,
+    ) -> Result<Option<Bytes>, MalformedFragment> {
+        self.pending
+            .retain(|_, x| now.saturating_duration_since(x.started) < self.timeout);
+
+        if datagram.len() < HEADER_LEN {
+            return Err(MalformedFragment("truncated header"));
+        }
+        let id = datagram.get_u32();
+        let index = usize::from(datagram.get_u16());
+        let count = usize::from(datagram.get_u16());
+        if index >= count {
+            return Err(MalformedFragment("index out of range"));
+        }
+        if count == 1 {
+            return Ok(Some(datagram));
+        }
+
+        if !self.pending.contains_key(&id) && self.pending.len() >= MAX_PENDING {
+            let oldest = self
+                .pending
+                .iter()
+                .min_by_key(|(_, x)| x.started)
+                .map(|(&id, _)| id);
+            if let Some(oldest) = oldest {
+                self.pending.remove(&oldest);
+            }
+        }
+        let mut entry = match self.pending.entry(id) {
+            hash_map::Entry::Occupied(e) => e,
+            hash_map::Entry::Vacant(e) => e.insert_entry(Partial {
+                started: now,
+                fragments: vec![None; count],
+                received: 0,
+            }),
+        };
+        let partial = entry.get_mut();
+        if partial.fragments.len() != count {
+            return Err(MalformedFragment("inconsistent fragment count"));
+        }
+        let slot = &mut partial.fragments[index];
+        if slot.is_none() {
+            *slot = Some(datagram);
+            partial.received += 1;
+        }
+        if partial.received < count {
+            return Ok(None);
+        }
+
+        let fragments = entry.remove().fragments;
+        let mut payload = BytesMut::with_capacity(fragments.iter().flatten().map(Bytes::len).sum());
+        for fragment in fragments.into_iter().flatten() {
+            payload.put(fragment);
+        }
+        Ok(Some(payload.freeze()))
+    }
+}
+
+/// Fragments received so far for a single payload
+#[derive(Debug)]
+struct Partial {
+    started: Instant,
+    fragments: Vec<Option<Bytes>>,
+    received: usize,
+}
+
+/// A received datagram was not a valid fragment
+#[derive(Debug, Error)]
+#[error("malformed fragment: {0}")]
+struct MalformedFragment(&'static str);
+
+/// Size of the header prefixed to every fragment
+const HEADER_LEN: usize = 8;
+
+/// Maximum number of incomplete payloads retained by a [`DatagramReassembler`]
+const MAX_PENDING: usize = 64;
@@ -11,7 +11,7 @@ use thiserror::Error;
 
 use crate::{
     connection::{Connecting, Connection},
-    endpoint::EndpointRef,
+    endpoint::{EndpointRef, SocketIndex},
 };
 
 /// An incoming connection for which the server has not yet begun its part of the handshake
@@ -19,31 +19,40 @@ use crate::{
 pub struct Incoming(Option<State>);
 
 impl Incoming {
-    pub(crate) fn new(inner: proto::Incoming, endpoint: EndpointRef) -> Self {
-        Self(Some(State { inner, endpoint }))
+    pub(crate) fn new(inner: proto::Incoming, socket: SocketIndex, endpoint: EndpointRef) -> Self {
+        Self(Some(State {
+            inner,
+            socket,
+            endpoint,
+        }))
     }
 
     /// Attempt to accept this incoming connection (an error may still occur)
     pub fn accept(mut self) -> Result<Connecting, ConnectionError> {
         let state = self.0.take().unwrap();
-        state.endpoint.accept(state.inner, None)
+        state.endpoint.accept(state.inner, state.socket, None)
     }
 
     /// Accept this incoming connection using a custom configuration
     ///
-    /// See [`accept()`][Incoming::accept] for more details.
+    /// Overrides the endpoint's server configuration for this connection only, allowing e.g. a
+    /// different [`TransportConfig`](crate::TransportConfig) to be selected based on
+    /// [`remote_address()`][Incoming::remote_address]. See [`accept()`][Incoming::accept] for more
+    /// details.
     pub fn accept_with(
         mut self,
         server_config: Arc<ServerConfig>,
     ) -> Result<Connecting, ConnectionError> {
         let state = self.0.take().unwrap();
-        state.endpoint.accept(state.inner, Some(server_config))
+        state
+            .endpoint
+            .accept(state.inner, state.socket, Some(server_config))
     }
 
     /// Reject this incoming connection attempt
     pub fn refuse(mut self) {
         let state = self.0.take().unwrap();
-        state.endpoint.refuse(state.inner);
+        state.endpoint.refuse(state.inner, state.socket);
     }
 
     /// Respond with a retry packet, requiring the client to retry with address validation
@@ -51,12 +60,16 @@ impl Incoming {
     /// Errors if `may_retry()` is false.
     pub fn retry(mut self) -> Result<(), RetryError> {
         let state = self.0.take().unwrap();
-        state.endpoint.retry(state.inner).map_err(|e| {
-            RetryError(Box::new(Self(Some(State {
-                inner: e.into_incoming(),
-                endpoint: state.endpoint,
-            }))))
-        })
+        state
+            .endpoint
+            .retry(state.inner, state.socket)
+            .map_err(|e| {
+                RetryError(Box::new(Self(Some(State {
+                    inner: e.into_incoming(),
+                    socket: state.socket,
+                    endpoint: state.endpoint,
+                }))))
+            })
     }
 
     /// Ignore this incoming connection attempt, not sending any packet in response
@@ -104,7 +117,7 @@ impl Drop for Incoming {
     fn drop(&mut self) {
         // Implicit reject, similar to Connection's implicit close
         if let Some(state) = self.0.take() {
-            state.endpoint.refuse(state.inner);
+            state.endpoint.refuse(state.inner, state.socket);
         }
     }
 }
@@ -112,6 +125,8 @@ impl Drop for Incoming {
 #[derive(Debug)]
 struct State {
     inner: proto::Incoming,
+    /// The endpoint socket the connection attempt arrived on
+    socket: SocketIndex,
     endpoint: EndpointRef,
 }
 
@@ -43,10 +43,14 @@ use std::pin::Pin;
 
 mod connection;
 mod endpoint;
+mod fragment;
 mod incoming;
+#[cfg(feature = "metrics")]
+mod metrics;
 mod mutex;
 mod recv_stream;
 mod runtime;
+mod send_rate_limiter;
 mod send_stream;
 mod work_limiter;
 
@@ -58,13 +62,17 @@ pub(crate) use web_time::{Duration, Instant};
 #[cfg(feature = "bloom")]
 pub use proto::BloomTokenLog;
 pub use proto::{
-    AckFrequencyConfig, ApplicationClose, Chunk, ClientConfig, ClosedStream, ConfigError,
-    ConnectError, ConnectionClose, ConnectionError, ConnectionId, ConnectionIdGenerator,
-    ConnectionStats, Dir, EcnCodepoint, EndpointConfig, FrameStats, FrameType, IdleTimeout,
-    InvalidCid, MtuDiscoveryConfig, NoneTokenLog, NoneTokenStore, PathStats, ServerConfig, Side,
-    StdSystemTime, StreamId, TimeSource, TokenLog, TokenMemoryCache, TokenReuseError, TokenStore,
-    Transmit, TransportConfig, TransportErrorCode, UdpStats, ValidationTokenConfig, VarInt,
-    VarIntBoundsExceeded, Written, congestion, crypto,
+    AckFrequencyConfig, AddressChange, ApplicationClose, Chunk, ClientConfig, ClosedStream,
+    ConfigError, ConnectError, ConnectionClose, ConnectionError, ConnectionId,
+    ConnectionIdGenerator, ConnectionRateLimiter, ConnectionStats, Dir, EcnCodepoint, EcnPolicy,
+    EndpointConfig, FlowControlStats, FrameStats, FrameType, IdleTimeout, InvalidCid,
+    KeepAlivePolicy, MtuDiscoveryConfig, NoneTokenLog, NoneTokenStore, PathEvent, PathStats,
+    PathStatsEntry, PathValidationState, RecvStreamState, RecvStreamStats, ResetAtError, RttCache,
+    RttMemoryCache, SendStreamState, SendStreamStats, ServerConfig, Side, StdSystemTime, StreamId,
+    StreamInfo, TimeSource, TokenGenerator, TokenLog, TokenMemoryCache, TokenReuseError,
+    TokenStore, TokenValidation, TokenValidator, Transmit, TransportConfig, TransportErrorCode,
+    UdpStats, ValidationTokenConfig, VarInt, VarIntBoundsExceeded, Written, congestion, crypto,
+    pacing, transport_parameters,
 };
 #[cfg(feature = "qlog")]
 pub use proto::{QlogConfig, QlogStream};
@@ -73,11 +81,15 @@ pub use rustls;
 pub use udp;
 
 pub use crate::connection::{
-    AcceptBi, AcceptUni, Connecting, Connection, OpenBi, OpenUni, ReadDatagram, SendDatagram,
-    SendDatagramError,
+    AcceptBi, AcceptUni, AddressChanges, Connecting, Connection, DatagramSentHandle,
+    IncomingBiStreams, IncomingUniStreams, OpenBi, OpenStreamError, OpenUni, PathError, PathEvents,
+    ReadDatagram, SendDatagram, SendDatagramError,
 };
-pub use crate::endpoint::{Accept, Endpoint, EndpointStats};
+pub use crate::endpoint::{Accept, Endpoint, EndpointIoStats, EndpointStats};
+pub use crate::fragment::{DatagramReassembler, DatagramSender};
 pub use crate::incoming::{Incoming, IncomingFuture, RetryError};
+#[cfg(feature = "metrics")]
+pub use crate::metrics::EndpointMetrics;
 pub use crate::recv_stream::{ReadError, ReadExactError, ReadToEndError, RecvStream, ResetError};
 #[cfg(feature = "runtime-smol")]
 pub use crate::runtime::SmolRuntime;
@@ -86,7 +98,7 @@ pub use crate::runtime::TokioRuntime;
 #[cfg(any(feature = "runtime-tokio", feature = "runtime-smol"))]
 pub use crate::runtime::default_runtime;
 pub use crate::runtime::{AsyncTimer, AsyncUdpSocket, Runtime, UdpSender};
-pub use crate::send_stream::{SendStream, StoppedError, WriteError};
+pub use crate::send_stream::{SendStream, StoppedError, WriteError, WriteTimeoutError};
 
 #[cfg(test)]
 mod tests;
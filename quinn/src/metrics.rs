@@ -0,0 +1,105 @@
+use std::{
+    fmt::Write,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Cumulative counters for an [`Endpoint`](crate::Endpoint) and all of its connections
+///
+/// Obtained from [`Endpoint::metrics_snapshot()`](crate::Endpoint::metrics_snapshot).
+#[non_exhaustive]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct EndpointMetrics {
+    /// Incoming connections accepted
+    pub connections_accepted: u64,
+    /// Incoming or outgoing connections that were lost or closed before the handshake completed
+    pub handshakes_failed: u64,
+    /// UDP payload bytes sent by connections
+    pub bytes_sent: u64,
+    /// UDP payload bytes received for connections, including those which opened new ones
+    pub bytes_received: u64,
+    /// Retry packets sent in response to incoming connection attempts
+    pub retries_issued: u64,
+}
+
+impl EndpointMetrics {
+    /// Append the metrics to `buf` in the Prometheus text exposition format
+    ///
+    /// Every metric is an unlabeled counter prefixed with `quinn_endpoint_`.
+    pub fn encode_prometheus(&self, buf: &mut String) {
+        for (name, help, value) in [
+            (
+                "connections_accepted",
+                "Incoming connections accepted",
+                self.connections_accepted,
+            ),
+            (
+                "handshakes_failed",
+                "Connections lost or closed before the handshake completed",
+                self.handshakes_failed,
+            ),
+            (
+                "sent_bytes",
+                "UDP payload bytes sent by connections",
+                self.bytes_sent,
+            ),
+            (
+                "received_bytes",
+                "UDP payload bytes received for connections",
+                self.bytes_received,
+            ),
+            (
+                "retries_issued",
+                "Retry packets sent to incoming connection attempts",
+                self.retries_issued,
+            ),
+        ] {
+            // Writing to a `String` can't fail
+            let _ = writeln!(buf, "# HELP quinn_endpoint_{name}_total {help}");
+            let _ = writeln!(buf, "# TYPE quinn_endpoint_{name}_total counter");
+            let _ = writeln!(buf, "quinn_endpoint_{name}_total {value}");
+        }
+    }
+}
+
+/// Live counters backing [`EndpointMetrics`], shared between an endpoint and its connections
+#[derive(Debug, Default)]
+pub(crate) struct MetricsCounters {
+    connections_accepted: AtomicU64,
+    handshakes_failed: AtomicU64,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    retries_issued: AtomicU64,
+}
+
+impl MetricsCounters {
+    pub(crate) fn on_accepted(&self) {
+        self.connections_accepted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn on_handshake_failed(&self) {
+        self.handshakes_failed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn on_sent(&self, bytes: usize) {
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn on_received(&self, bytes: usize) {
+        self.bytes_received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn on_retry(&self) {
+        self.retries_issued.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> EndpointMetrics {
+        EndpointMetrics {
+            connections_accepted: self.connections_accepted.load(Ordering::Relaxed),
+            handshakes_failed: self.handshakes_failed.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            retries_issued: self.retries_issued.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -6,7 +6,9 @@ use std::{
 };
 
 use bytes::Bytes;
-use proto::{Chunk, Chunks, ClosedStream, ConnectionError, ReadableError, StreamId};
+use proto::{
+    Chunk, Chunks, ClosedStream, ConnectionError, ReadableError, RecvStreamStats, StreamId,
+};
 use thiserror::Error;
 use tokio::io::ReadBuf;
 
@@ -115,6 +117,38 @@ impl RecvStream {
         Poll::Ready(Ok(buf.filled().len()))
     }
 
+    /// Poll for the stream becoming readable, without consuming any data
+    ///
+    /// Returns `Poll::Ready(Ok(()))` once the next read will complete immediately: data is
+    /// available at the current read offset, or the stream has been [`finish`]ed or reset, which
+    /// the read then reports. Otherwise, arranges for the current task (via `cx.waker()`) to be
+    /// notified when that changes, like [`poll_read()`](Self::poll_read) does.
+    ///
+    /// [`finish`]: crate::SendStream::finish
+    pub fn poll_readable(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), ReadError>> {
+        if self.all_data_read || self.reset.is_some() {
+            return Poll::Ready(Ok(()));
+        }
+
+        let mut conn = self.conn.state.lock("RecvStream::poll_readable");
+        if self.is_0rtt {
+            conn.check_0rtt().map_err(|()| ReadError::ZeroRttRejected)?;
+        }
+        let readable = conn
+            .inner
+            .recv_stream(self.stream)
+            .is_readable()
+            .map_err(|_| ReadError::ClosedStream)?;
+        if readable {
+            return Poll::Ready(Ok(()));
+        }
+        if let Some(ref x) = conn.error {
+            return Poll::Ready(Err(ReadError::ConnectionLost(x.clone())));
+        }
+        conn.blocked_readers.insert(self.stream, cx.waker().clone());
+        Poll::Pending
+    }
+
     /// Attempts to read from the stream into the provided buffer, which may be uninitialized
     ///
     /// On success, returns `Poll::Ready(Ok(()))` and places data into the unfilled portion of
@@ -211,6 +245,10 @@ impl RecvStream {
     /// Slightly more efficient than `read` due to not copying. Chunk boundaries
     /// do not correspond to peer writes, and hence cannot be used as framing.
     ///
+    /// The filled segments can be forwarded to another stream without copying by passing them to
+    /// [`SendStream::write_all_chunks`](crate::SendStream::write_all_chunks), which queues as
+    /// many of them as flow control permits under a single lock acquisition.
+    ///
     /// This operation is cancel-safe.
     pub async fn read_chunks(&mut self, bufs: &mut [Bytes]) -> Result<Option<usize>, ReadError> {
         ReadChunks { stream: self, bufs }.await
@@ -286,6 +324,45 @@ impl RecvStream {
         Ok(())
     }
 
+    /// Grow the flow control window of this stream beyond
+    /// [`TransportConfig::stream_receive_window()`](crate::TransportConfig::stream_receive_window)
+    ///
+    /// Useful for e.g. a single bulk transfer sharing a connection with many small streams. The
+    /// window can only grow, since credit already granted to the peer can't be withdrawn; smaller
+    /// values are ignored. The connection-level
+    /// [`receive_window`](crate::TransportConfig::receive_window) still applies.
+    pub fn set_receive_window(&mut self, window: VarInt) -> Result<(), ClosedStream> {
+        let mut conn = self.conn.state.lock("RecvStream::set_receive_window");
+        if self.is_0rtt && conn.check_0rtt().is_err() {
+            return Ok(());
+        }
+        conn.inner
+            .recv_stream(self.stream)
+            .set_receive_window(window)?;
+        conn.wake();
+        Ok(())
+    }
+
+    /// Limit the flow control window of this stream
+    ///
+    /// Caps how much unread data Quinn buffers for this stream, so that the peer is throttled to
+    /// the rate at which the application reads. Overrides both
+    /// [`TransportConfig::stream_receive_window()`](crate::TransportConfig::stream_receive_window)
+    /// and [`set_receive_window()`](Self::set_receive_window). Credit already granted to the peer
+    /// can't be withdrawn, so a lower limit takes effect only once the application has read past
+    /// it.
+    pub fn set_max_receive_window(&mut self, window: VarInt) -> Result<(), ClosedStream> {
+        let mut conn = self.conn.state.lock("RecvStream::set_max_receive_window");
+        if self.is_0rtt && conn.check_0rtt().is_err() {
+            return Ok(());
+        }
+        conn.inner
+            .recv_stream(self.stream)
+            .set_max_receive_window(window)?;
+        conn.wake();
+        Ok(())
+    }
+
     /// Check if this stream predates completion of the handshake on an incoming connection.
     ///
     /// True only if the stream was accepted before the handshake completed, which is only possible
@@ -305,6 +382,28 @@ impl RecvStream {
         self.stream
     }
 
+    /// Statistics about the data received on this stream
+    pub fn stats(&self) -> Result<RecvStreamStats, ClosedStream> {
+        let mut conn = self.conn.state.lock("RecvStream::stats");
+        conn.inner.recv_stream(self.stream).stats()
+    }
+
+    /// Stream offset below which data may have been received in 0-RTT packets
+    ///
+    /// See [`proto::RecvStream::early_data_end()`] for details.
+    pub fn early_data_end(&self) -> Result<u64, ClosedStream> {
+        let mut conn = self.conn.state.lock("RecvStream::early_data_end");
+        conn.inner.recv_stream(self.stream).early_data_end()
+    }
+
+    /// Number of bytes received on this stream but not yet read
+    ///
+    /// See [`proto::RecvStream::bytes_buffered()`] for details.
+    pub fn bytes_buffered(&self) -> u64 {
+        let mut conn = self.conn.state.lock("RecvStream::bytes_buffered");
+        conn.inner.recv_stream(self.stream).bytes_buffered()
+    }
+
     /// Completes when the stream has been reset by the peer or otherwise closed
     ///
     /// Yields `Some` with the reset error code when the stream is reset by the peer. Yields `None`
@@ -25,7 +25,11 @@ pub trait Runtime: Send + Sync + Debug + 'static {
     fn wrap_udp_socket(&self, t: std::net::UdpSocket) -> io::Result<Box<dyn AsyncUdpSocket>>;
     /// Look up the current time
     ///
-    /// Allows simulating the flow of time for testing.
+    /// Allows simulating the flow of time for testing. All of Quinn's timers, including idle
+    /// timeouts, loss detection and keep-alives, are scheduled relative to this clock, so a
+    /// runtime whose timers also follow it can drive a connection through timeouts without real
+    /// delays. `TokioRuntime` does this by following Tokio's clock, which tests can pause and
+    /// advance with `tokio::time::pause()` and `tokio::time::advance()`.
     fn now(&self) -> Instant {
         Instant::now()
     }
@@ -69,6 +73,22 @@ pub trait AsyncUdpSocket: Send + Sync + Debug + 'static {
         1
     }
 
+    /// Limit the number of datagrams that might be described by a single [`RecvMeta`]
+    ///
+    /// Called before the socket is used by an endpoint configured with
+    /// [`EndpointConfig::max_receive_segments()`](crate::EndpointConfig::max_receive_segments).
+    /// Implementations without receive offloading may ignore this.
+    fn set_max_receive_segments(&self, _max: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Stop batching outgoing datagrams with segmentation offload
+    ///
+    /// Called before the socket is used by an endpoint configured with
+    /// [`EndpointConfig::disable_gso()`](crate::EndpointConfig::disable_gso). Afterwards, senders
+    /// created from this socket should report a [`UdpSender::max_transmit_segments`] of 1.
+    fn disable_gso(&self) {}
+
     /// Whether datagrams might get fragmented into multiple parts
     ///
     /// Sockets should prevent this for best performance. See e.g. the `IPV6_DONTFRAG` socket
@@ -101,4 +101,12 @@ impl AsyncUdpSocket for UdpSocket {
     fn max_receive_segments(&self) -> usize {
         self.inner.gro_segments()
     }
+
+    fn set_max_receive_segments(&self, max: usize) -> io::Result<()> {
+        self.inner.set_gro_segments((&self.io).into(), max)
+    }
+
+    fn disable_gso(&self) {
+        self.inner.disable_gso()
+    }
 }
@@ -0,0 +1,110 @@
+use std::sync::Mutex;
+
+use crate::{Duration, Instant};
+
+/// Token bucket capping the aggregate send rate of all connections of an endpoint
+///
+/// Each connection checks for budget before polling a new transmit, and charges the bytes it
+/// actually sent. A transmit may be sent as long as any budget remains, even if it's larger than
+/// the remaining budget; the resulting deficit delays subsequent transmits accordingly.
+#[derive(Debug)]
+pub(crate) struct SendRateLimiter {
+    /// Sustained rate in bytes per second
+    rate: u64,
+    /// Maximum number of bytes that may accumulate while idle
+    capacity: u64,
+    state: Mutex<Bucket>,
+}
+
+impl SendRateLimiter {
+    pub(crate) fn new(rate: u64) -> Self {
+        let rate = rate.max(1);
+        let capacity = (rate / BURSTS_PER_SECOND).max(1);
+        Self {
+            rate,
+            capacity,
+            state: Mutex::new(Bucket {
+                tokens: capacity as i64,
+                last_refill: None,
+            }),
+        }
+    }
+
+    /// Check whether a transmit may be sent at `now`
+    ///
+    /// Otherwise, returns the time at which budget will next be available.
+    pub(crate) fn poll_budget(&self, now: Instant) -> Result<(), Instant> {
+        let mut state = self.state.lock().unwrap();
+        self.refill(&mut state, now);
+        if state.tokens > 0 {
+            return Ok(());
+        }
+        let deficit = (1 - state.tokens) as u128;
+        let nanos = (deficit * 1_000_000_000).div_ceil(self.rate as u128);
+        Err(now + Duration::from_nanos(nanos.min(u64::MAX as u128) as u64))
+    }
+
+    /// Charge `bytes` sent against the budget
+    pub(crate) fn on_sent(&self, bytes: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.tokens = state.tokens.saturating_sub(bytes as i64);
+    }
+
+    fn refill(&self, state: &mut Bucket, now: Instant) {
+        let Some(last_refill) = state.last_refill else {
+            state.last_refill = Some(now);
+            return;
+        };
+        let elapsed = now.saturating_duration_since(last_refill).as_nanos();
+        let added = elapsed * self.rate as u128 / 1_000_000_000;
+        if added == 0 {
+            // Leave `last_refill` alone so that frequent checks don't discard partial progress
+            return;
+        }
+        state.tokens = (state.tokens as i128 + added as i128).min(self.capacity as i128) as i64;
+        state.last_refill = Some(now);
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// Bytes that may be sent immediately; negative after sending more than was available
+    tokens: i64,
+    last_refill: Option<Instant>,
+}
+
+/// Bursts are limited to this fraction of a second's worth of data
+const BURSTS_PER_SECOND: u64 = 50;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_rate() {
+        const RATE: u64 = 100_000;
+        let limiter = SendRateLimiter::new(RATE);
+        let start = Instant::now();
+
+        // The initial burst allowance is available immediately
+        assert_eq!(limiter.poll_budget(start), Ok(()));
+        limiter.on_sent((RATE / BURSTS_PER_SECOND) as usize);
+        let next = limiter.poll_budget(start).unwrap_err();
+        assert_eq!(next - start, Duration::from_micros(10));
+
+        // Overdrafts must be repaid before sending again
+        assert_eq!(limiter.poll_budget(next), Ok(()));
+        limiter.on_sent(1000);
+        let next = limiter.poll_budget(next).unwrap_err();
+        assert_eq!(
+            next - start,
+            Duration::from_millis(10) + Duration::from_micros(10)
+        );
+
+        // Budget doesn't accumulate beyond the burst allowance
+        let later = start + Duration::from_secs(10);
+        assert_eq!(limiter.poll_budget(later), Ok(()));
+        limiter.on_sent((RATE / BURSTS_PER_SECOND) as usize);
+        assert!(limiter.poll_budget(later).is_err());
+    }
+}
@@ -6,11 +6,13 @@ use std::{
 };
 
 use bytes::Bytes;
-use proto::{ClosedStream, ConnectionError, FinishError, StreamId, Written};
+use proto::{
+    ClosedStream, ConnectionError, FinishError, ResetAtError, SendStreamStats, StreamId, Written,
+};
 use thiserror::Error;
 
 use crate::{
-    VarInt,
+    Duration, Instant, VarInt,
     connection::{ConnectionRef, State},
 };
 
@@ -77,6 +79,73 @@ impl SendStream {
         Ok(())
     }
 
+    /// Write a buffer into this stream in its entirety, reporting progress as it goes
+    ///
+    /// Like [`write_all`](Self::write_all), but calls `on_progress` with the total number of bytes
+    /// of `buf` written so far whenever flow control admits more of it. Written bytes are queued
+    /// for transmission, not necessarily acknowledged by the peer; see [`stats()`](Self::stats)
+    /// for the latter.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is *not* cancellation safe. Even if this does not resolve, some prefix of `buf`
+    /// may have been written when previously polled.
+    pub async fn write_all_with_progress(
+        &mut self,
+        buf: &[u8],
+        mut on_progress: impl FnMut(usize),
+    ) -> Result<(), WriteError> {
+        let mut total = 0;
+        while total < buf.len() {
+            total += self.write(&buf[total..]).await?;
+            on_progress(total);
+        }
+        Ok(())
+    }
+
+    /// Write a buffer into this stream in its entirety, giving up at `deadline`
+    ///
+    /// Like [`write_all`](Self::write_all), but if flow or congestion control keep the whole of
+    /// `buf` from being written before `deadline`, fails with [`WriteTimeoutError::TimedOut`]
+    /// reporting how much of `buf` was written so far. The remainder can then be retried or
+    /// abandoned, e.g. by [resetting](Self::reset) the stream. On success, returns the number of
+    /// bytes written, which is always `buf.len()`.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is *not* cancellation safe. Even if this does not resolve, some prefix of `buf`
+    /// may have been written when previously polled.
+    pub async fn write_timeout(
+        &mut self,
+        mut buf: &[u8],
+        deadline: Instant,
+    ) -> Result<usize, WriteTimeoutError> {
+        let mut timer = self
+            .conn
+            .state
+            .lock("SendStream::write_timeout")
+            .runtime
+            .new_timer(deadline);
+        let mut written = 0;
+        while !buf.is_empty() {
+            let mut write = pin!(self.write(buf));
+            let result = poll_fn(|cx| {
+                if let Poll::Ready(result) = write.as_mut().poll(cx) {
+                    return Poll::Ready(Some(result));
+                }
+                timer.as_mut().poll(cx).map(|()| None)
+            })
+            .await;
+            let Some(result) = result else {
+                return Err(WriteTimeoutError::TimedOut { written });
+            };
+            let n = result?;
+            written += n;
+            buf = &buf[n..];
+        }
+        Ok(written)
+    }
+
     /// Write a slice of [`Bytes`] into this stream, returning how much was written
     ///
     /// Bytes to try to write are provided to this method as an array of cheaply cloneable chunks.
@@ -214,6 +283,30 @@ impl SendStream {
         Ok(())
     }
 
+    /// Close the send stream, discarding data written at or beyond `final_size`
+    ///
+    /// Data below `final_size` is still delivered, and the stream is only reset once all of it has
+    /// been acknowledged, with `final_size` as the stream's final size. [`reset()`](Self::reset)
+    /// is equivalent to passing the amount of data written so far. Acknowledgement doesn't imply
+    /// the peer application has read the data; it may be discarded when the reset arrives.
+    ///
+    /// Fails if `final_size` is less than the amount of data already transmitted or more than has
+    /// been written, or if [`finish()`](Self::finish) or [`reset()`](Self::reset) was previously
+    /// called.
+    ///
+    /// See [`proto::SendStream::reset_at()`] for details.
+    pub fn reset_at(&mut self, error_code: VarInt, final_size: u64) -> Result<(), ResetAtError> {
+        let mut conn = self.conn.state.lock("SendStream::reset_at");
+        if self.is_0rtt && conn.check_0rtt().is_err() {
+            return Ok(());
+        }
+        conn.inner
+            .send_stream(self.stream)
+            .reset_at(error_code, final_size)?;
+        conn.wake();
+        Ok(())
+    }
+
     /// Set the priority of the send stream
     ///
     /// Every send stream has an initial priority of 0. Locally buffered data from streams with
@@ -227,12 +320,41 @@ impl SendStream {
         Ok(())
     }
 
+    /// Reset the stream with `error_code` rather than retransmit lost data older than `expiry`
+    ///
+    /// See [`proto::SendStream::set_reset_on_expiry()`] for details, including why whole streams
+    /// are the unit of partial reliability.
+    pub fn set_reset_on_expiry(
+        &self,
+        expiry: Option<Duration>,
+        error_code: VarInt,
+    ) -> Result<(), ClosedStream> {
+        let mut conn = self.conn.state.lock("SendStream::set_reset_on_expiry");
+        conn.inner
+            .send_stream(self.stream)
+            .set_reset_on_expiry(expiry, error_code)
+    }
+
     /// Get the priority of the send stream
     pub fn priority(&self) -> Result<i32, ClosedStream> {
         let mut conn = self.conn.state.lock("SendStream::priority");
         conn.inner.send_stream(self.stream).priority()
     }
 
+    /// Statistics about the data sent on this stream
+    pub fn stats(&self) -> Result<SendStreamStats, ClosedStream> {
+        let mut conn = self.conn.state.lock("SendStream::stats");
+        conn.inner.send_stream(self.stream).stats()
+    }
+
+    /// Number of bytes written to the stream that have not yet been transmitted
+    ///
+    /// See [`proto::SendStream::bytes_in_send_buffer()`] for details.
+    pub fn bytes_in_send_buffer(&self) -> Result<u64, ClosedStream> {
+        let mut conn = self.conn.state.lock("SendStream::bytes_in_send_buffer");
+        conn.inner.send_stream(self.stream).bytes_in_send_buffer()
+    }
+
     /// Completes when the peer stops the stream or reads the stream to completion
     ///
     /// Yields `Some` with the stop error code if the peer stops the stream. Yields `None` if the
@@ -426,6 +548,29 @@ impl From<WriteError> for io::Error {
     }
 }
 
+/// Errors that arise from [`SendStream::write_timeout`]
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum WriteTimeoutError {
+    /// The deadline passed before the whole buffer could be written
+    #[error("timed out after writing {written} bytes")]
+    TimedOut {
+        /// Length of the prefix of the buffer that was written
+        written: usize,
+    },
+    /// The write failed
+    #[error(transparent)]
+    Write(#[from] WriteError),
+}
+
+impl From<WriteTimeoutError> for io::Error {
+    fn from(x: WriteTimeoutError) -> Self {
+        match x {
+            WriteTimeoutError::TimedOut { .. } => Self::new(io::ErrorKind::TimedOut, x),
+            WriteTimeoutError::Write(e) => e.into(),
+        }
+    }
+}
+
 /// Errors that arise while monitoring for a send stream stop from the peer
 #[derive(Debug, Error, Clone, PartialEq, Eq)]
 pub enum StoppedError {
@@ -7,19 +7,19 @@ use rustls::crypto::ring::default_provider;
 
 use std::{
     convert::TryInto,
-    future::Future,
+    future::{Future, poll_fn},
     io,
     net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket},
-    pin::pin,
+    pin::{Pin, pin},
     str,
     sync::{
         Arc,
-        atomic::{AtomicUsize, Ordering},
+        atomic::{AtomicBool, AtomicUsize, Ordering},
     },
     task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
 };
 
-use crate::runtime::TokioRuntime;
+use crate::runtime::{AsyncUdpSocket, Runtime as _, TokioRuntime, UdpSender};
 use crate::{Duration, Instant};
 use bytes::Bytes;
 use proto::{RandomConnectionIdGenerator, crypto::rustls::QuicClientConfig};
@@ -37,7 +37,11 @@ use tracing::{error_span, info};
 use tracing_futures::Instrument as _;
 use tracing_subscriber::EnvFilter;
 
-use super::{ClientConfig, Endpoint, EndpointConfig, RecvStream, SendStream, TransportConfig};
+use super::{
+    ClientConfig, DatagramReassembler, DatagramSender, Endpoint, EndpointConfig,
+    MtuDiscoveryConfig, OpenStreamError, PathError, PathEvent, PathValidationState, ReadError,
+    ReadExactError, RecvStream, SendStream, TransportConfig, WriteTimeoutError,
+};
 
 #[test]
 fn handshake_timeout() {
@@ -81,6 +85,75 @@ fn handshake_timeout() {
     assert!(dt > IDLE_TIMEOUT && dt < 2 * IDLE_TIMEOUT);
 }
 
+#[tokio::test(start_paused = true)]
+async fn handshake_timeout_paused_clock() {
+    let _guard = subscribe();
+    let client = Endpoint::client(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).unwrap();
+
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+    let mut roots = RootCertStore::empty();
+    roots.add(cert.cert.into()).unwrap();
+
+    let mut client_config = ClientConfig::with_root_certificates(Arc::new(roots)).unwrap();
+    const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+    let mut transport_config = TransportConfig::default();
+    transport_config.max_idle_timeout(Some(IDLE_TIMEOUT.try_into().unwrap()));
+    client_config.transport_config(Arc::new(transport_config));
+
+    // The runtime's clock skips ahead whenever every task is waiting on a timer, so the timeout
+    // fires after the configured duration of simulated time without any real delay
+    let start = tokio::time::Instant::now();
+    let result = client
+        .connect_with(
+            client_config,
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 1),
+            "localhost",
+        )
+        .unwrap()
+        .await;
+    assert!(matches!(result, Err(crate::ConnectionError::TimedOut)));
+    let dt = start.elapsed();
+    assert!(dt >= IDLE_TIMEOUT && dt < IDLE_TIMEOUT + Duration::from_millis(10));
+}
+
+#[tokio::test(start_paused = true)]
+async fn handshake_timeout_before_idle() {
+    let _guard = subscribe();
+    let client = Endpoint::client(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).unwrap();
+
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+    let mut roots = RootCertStore::empty();
+    roots.add(cert.cert.into()).unwrap();
+
+    let mut client_config = ClientConfig::with_root_certificates(Arc::new(roots)).unwrap();
+    const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(2);
+    let mut transport_config = TransportConfig::default();
+    transport_config
+        .max_idle_timeout(Some(Duration::from_secs(30).try_into().unwrap()))
+        .handshake_timeout(Some(HANDSHAKE_TIMEOUT));
+    client_config.transport_config(Arc::new(transport_config));
+
+    let start = tokio::time::Instant::now();
+    let result = client
+        .connect_with(
+            client_config,
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 1),
+            "localhost",
+        )
+        .unwrap()
+        .await;
+    assert!(matches!(
+        result,
+        Err(crate::ConnectionError::HandshakeTimeout)
+    ));
+    let dt = start.elapsed();
+    assert!(dt >= HANDSHAKE_TIMEOUT && dt < HANDSHAKE_TIMEOUT + Duration::from_millis(10));
+
+    // The abandoned connection doesn't linger in the endpoint
+    client.wait_idle().await;
+    assert_eq!(client.open_connections(), 0);
+}
+
 #[tokio::test]
 async fn close_endpoint() {
     let _guard = subscribe();
@@ -121,6 +194,119 @@ async fn close_endpoint() {
     }
 }
 
+#[tokio::test]
+async fn accept_timeout() {
+    let _guard = subscribe();
+    let endpoint = endpoint();
+
+    let start = Instant::now();
+    assert!(
+        endpoint
+            .accept_timeout(Duration::from_millis(50))
+            .await
+            .is_none()
+    );
+    assert!(start.elapsed() >= Duration::from_millis(50));
+
+    let (client, server) = join!(
+        endpoint
+            .connect(endpoint.local_addr().unwrap(), "localhost")
+            .unwrap(),
+        async {
+            endpoint
+                .accept_timeout(Duration::from_secs(5))
+                .await
+                .unwrap()
+                .await
+        },
+    );
+    client.unwrap();
+    server.unwrap();
+
+    endpoint.close(0u32.into(), &[]);
+    assert!(
+        endpoint
+            .accept_timeout(Duration::from_secs(5))
+            .await
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn open_stream_timeout() {
+    let _guard = subscribe();
+    let mut transport = TransportConfig::default();
+    transport.max_concurrent_bidi_streams(0u32.into());
+    let endpoint = endpoint_with_config(transport);
+
+    let (client, server) = join!(
+        endpoint
+            .connect(endpoint.local_addr().unwrap(), "localhost")
+            .unwrap(),
+        async { endpoint.accept().await.unwrap().await },
+    );
+    let client = client.unwrap();
+    let _server = server.unwrap();
+
+    // The server never grants bidirectional stream credit
+    let start = Instant::now();
+    assert!(matches!(
+        client.open_bi_timeout(Duration::from_millis(50)).await,
+        Err(OpenStreamError::TimedOut)
+    ));
+    assert!(start.elapsed() >= Duration::from_millis(50));
+
+    client
+        .open_uni_timeout(Duration::from_secs(5))
+        .await
+        .unwrap();
+
+    client.close(0u32.into(), &[]);
+    assert!(matches!(
+        client.open_uni_timeout(Duration::from_secs(5)).await,
+        Err(OpenStreamError::ConnectionLost(_))
+    ));
+}
+
+#[tokio::test]
+async fn write_all_with_progress() {
+    let _guard = subscribe();
+    let mut transport = TransportConfig::default();
+    transport.stream_receive_window(10_000u32.into());
+    let endpoint = endpoint_with_config(transport);
+
+    let (client, server) = join!(
+        endpoint
+            .connect(endpoint.local_addr().unwrap(), "localhost")
+            .unwrap(),
+        async { endpoint.accept().await.unwrap().await },
+    );
+    let client = client.unwrap();
+    let server = server.unwrap();
+
+    const LEN: usize = 100_000;
+    let mut progress = Vec::new();
+    let (_, received) = join!(
+        async {
+            let mut send = client.open_uni().await.unwrap();
+            send.write_all_with_progress(&[0xAB; LEN], |n| progress.push(n))
+                .await
+                .unwrap();
+            send.finish().unwrap();
+        },
+        async {
+            let mut recv = server.accept_uni().await.unwrap();
+            recv.read_to_end(usize::MAX).await.unwrap()
+        }
+    );
+    assert_eq!(received.len(), LEN);
+
+    // The receive window forces the write to be admitted in several steps
+    assert!(progress.len() > 1);
+    assert!(progress.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(progress.last(), Some(&LEN));
+}
+
 #[test]
 fn local_addr() {
     let socket = UdpSocket::bind((Ipv6Addr::LOCALHOST, 0)).unwrap();
@@ -263,6 +449,291 @@ async fn ip_blocking() {
     server_task.abort();
 }
 
+#[cfg(feature = "metrics")]
+#[tokio::test]
+async fn endpoint_metrics() {
+    let _guard = subscribe();
+    let endpoint_factory = EndpointFactory::new();
+    let client = endpoint_factory.endpoint();
+    let server = endpoint_factory.endpoint();
+    let server_addr = server.local_addr().unwrap();
+    let server_task = tokio::spawn({
+        let server = server.clone();
+        async move {
+            let incoming = server.accept().await.unwrap();
+            incoming.retry().unwrap();
+            let conn = server.accept().await.unwrap().await.expect("connection");
+            let mut stream = conn.accept_uni().await.expect("incoming stream");
+            stream.read_to_end(usize::MAX).await.expect("read_to_end");
+        }
+    });
+    let conn = client
+        .connect(server_addr, "localhost")
+        .unwrap()
+        .await
+        .expect("connect");
+    let mut stream = conn.open_uni().await.unwrap();
+    stream.write_all(&[0xAB; 10_000]).await.unwrap();
+    stream.finish().unwrap();
+    timeout(Duration::from_secs(5), server_task)
+        .await
+        .expect("timeout")
+        .unwrap();
+
+    let metrics = server.metrics_snapshot();
+    assert_eq!(metrics.connections_accepted, 1);
+    assert_eq!(metrics.retries_issued, 1);
+    assert_eq!(metrics.handshakes_failed, 0);
+    assert!(metrics.bytes_received >= 10_000);
+    assert!(metrics.bytes_sent > 0);
+
+    let metrics = client.metrics_snapshot();
+    assert_eq!(metrics.connections_accepted, 0);
+    assert!(metrics.bytes_sent >= 10_000);
+
+    let mut text = String::new();
+    server.metrics_snapshot().encode_prometheus(&mut text);
+    assert!(text.contains("# TYPE quinn_endpoint_retries_issued_total counter\n"));
+    assert!(text.contains("\nquinn_endpoint_connections_accepted_total 1\n"));
+}
+
+#[tokio::test]
+async fn reject_new_connections() {
+    let _guard = subscribe();
+    let endpoint_factory = EndpointFactory::new();
+    let client = endpoint_factory.endpoint();
+    let server = endpoint_factory.endpoint();
+    let server_addr = server.local_addr().unwrap();
+
+    let mut conns = Vec::new();
+    for _ in 0..2 {
+        let (client_conn, server_conn) =
+            tokio::try_join!(client.connect(server_addr, "localhost").unwrap(), async {
+                server.accept().await.unwrap().await
+            })
+            .unwrap();
+        conns.push((client_conn, server_conn));
+    }
+    assert_eq!(server.open_connections(), 2);
+
+    server.reject_new_connections();
+    assert!(server.accept().await.is_none());
+    let e = client
+        .connect(server_addr, "localhost")
+        .unwrap()
+        .await
+        .expect_err("server should have refused this");
+    assert!(
+        matches!(e, crate::ConnectionError::ConnectionClosed(_)),
+        "wrong error"
+    );
+
+    for (client_conn, server_conn) in conns {
+        let mut send = client_conn.open_uni().await.unwrap();
+        send.write_all(b"still here").await.unwrap();
+        send.finish().unwrap();
+        let mut recv = server_conn.accept_uni().await.unwrap();
+        assert_eq!(recv.read_to_end(usize::MAX).await.unwrap(), b"still here");
+    }
+    assert_eq!(server.open_connections(), 2);
+}
+
+#[tokio::test]
+async fn endpoint_io_stats() {
+    let _guard = subscribe();
+    let endpoint_factory = EndpointFactory::new();
+    let client = endpoint_factory.endpoint();
+    let server = endpoint_factory.endpoint();
+    let server_addr = server.local_addr().unwrap();
+    assert_eq!(server.io_stats().incoming_queue_depth, 0);
+
+    // Connection attempts pile up while the server isn't accepting them
+    const ATTEMPTS: usize = 8;
+    let _connecting = (0..ATTEMPTS)
+        .map(|_| client.connect(server_addr, "localhost").unwrap())
+        .collect::<Vec<_>>();
+    timeout(Duration::from_secs(5), async {
+        while server.io_stats().incoming_queue_depth < ATTEMPTS {
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("connection attempts weren't queued");
+
+    let incoming = server.accept().await.unwrap();
+    assert_eq!(server.io_stats().incoming_queue_depth, ATTEMPTS - 1);
+    incoming.refuse();
+}
+
+#[tokio::test]
+async fn additional_socket() {
+    let _guard = subscribe();
+    let endpoint_factory = EndpointFactory::new();
+    let client = endpoint_factory.endpoint();
+    let server = endpoint_factory.endpoint();
+    let extra = UdpSocket::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).unwrap();
+    let extra_addr = extra.local_addr().unwrap();
+    server.add_socket(extra).unwrap();
+
+    for server_addr in [server.local_addr().unwrap(), extra_addr] {
+        let (client_conn, server_conn) =
+            tokio::try_join!(client.connect(server_addr, "localhost").unwrap(), async {
+                server.accept().await.unwrap().await
+            })
+            .unwrap();
+        assert_eq!(client_conn.remote_address(), server_addr);
+
+        let mut send = server_conn.open_uni().await.unwrap();
+        send.write_all(b"hello").await.unwrap();
+        send.finish().unwrap();
+        let mut recv = client_conn.accept_uni().await.unwrap();
+        assert_eq!(recv.read_to_end(usize::MAX).await.unwrap(), b"hello");
+    }
+    assert_eq!(server.open_connections(), 2);
+}
+
+#[tokio::test]
+async fn accept_with_transport_config() {
+    let _guard = subscribe();
+    let endpoint_factory = EndpointFactory::new();
+    let client = endpoint_factory.endpoint();
+    let server = endpoint_factory.endpoint();
+    let server_addr = server.local_addr().unwrap();
+
+    let key = PrivateKeyDer::Pkcs8(endpoint_factory.cert.signing_key.serialize_der().into());
+    let mut server_config =
+        crate::ServerConfig::with_single_cert(vec![endpoint_factory.cert.cert.der().clone()], key)
+            .unwrap();
+    let mut transport_config = TransportConfig::default();
+    transport_config.datagram_receive_buffer_size(None);
+    server_config.transport_config(Arc::new(transport_config));
+    let server_config = Arc::new(server_config);
+
+    let (default_conn, _) =
+        tokio::try_join!(client.connect(server_addr, "localhost").unwrap(), async {
+            server.accept().await.unwrap().accept().unwrap().await
+        })
+        .unwrap();
+    let (custom_conn, _) =
+        tokio::try_join!(client.connect(server_addr, "localhost").unwrap(), async {
+            let incoming = server.accept().await.unwrap();
+            incoming.accept_with(server_config.clone()).unwrap().await
+        })
+        .unwrap();
+
+    // Only the connection accepted with the default configuration accepts datagrams
+    assert!(default_conn.max_datagram_size().is_some());
+    assert_eq!(custom_conn.max_datagram_size(), None);
+}
+
+#[tokio::test]
+async fn incoming_streams_adapter() {
+    let _guard = subscribe();
+    let endpoint = endpoint();
+    let (client, server) = join!(
+        async {
+            endpoint
+                .connect(endpoint.local_addr().unwrap(), "localhost")
+                .unwrap()
+                .await
+                .unwrap()
+        },
+        async { endpoint.accept().await.unwrap().await.unwrap() }
+    );
+
+    const STREAMS: usize = 3;
+    for i in 0..STREAMS {
+        let mut send = client.open_uni().await.unwrap();
+        send.write_all(&[i as u8]).await.unwrap();
+        send.finish().unwrap();
+    }
+
+    let mut incoming = server.incoming_uni_streams();
+    for i in 0..STREAMS {
+        let mut recv = poll_fn(|cx| incoming.poll_next(cx))
+            .await
+            .expect("stream")
+            .unwrap();
+        assert_eq!(recv.read_to_end(1).await.unwrap(), [i as u8]);
+    }
+
+    client.close(0u32.into(), b"done");
+    assert!(poll_fn(|cx| incoming.poll_next(cx)).await.is_none());
+    assert!(poll_fn(|cx| incoming.poll_next(cx)).await.is_none());
+}
+
+#[tokio::test]
+async fn connection_span() {
+    let logs = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let sub = tracing_subscriber::FmtSubscriber::builder()
+        .with_max_level(tracing::Level::TRACE)
+        .with_ansi(false)
+        .with_writer({
+            let logs = logs.clone();
+            move || CaptureWriter(logs.clone())
+        })
+        .finish();
+    let _guard = tracing::subscriber::set_default(sub);
+
+    let endpoint = endpoint();
+    let server_addr = endpoint.local_addr().unwrap();
+    let (client, _server) = join!(
+        async {
+            endpoint
+                .connect(server_addr, "localhost")
+                .unwrap()
+                .await
+                .unwrap()
+        },
+        async { endpoint.accept().await.unwrap().await.unwrap() }
+    );
+    client.span().in_scope(|| info!("application event"));
+    client.close(0u32.into(), b"done");
+    endpoint.wait_idle().await;
+
+    let logs = String::from_utf8(logs.lock().unwrap().clone()).unwrap();
+    let suffix = format!(" remote={server_addr}}}");
+    let header = logs
+        .lines()
+        .filter_map(|line| {
+            let start = line.find("connection{id=")?;
+            let end = start + line[start..].find(&suffix)? + suffix.len();
+            Some(&line[start..end])
+        })
+        .next()
+        .expect("no events recorded in a connection span");
+    // Protocol events from the driver are tagged with the connection
+    assert!(
+        logs.lines()
+            .any(|line| line.contains(header) && line.contains("quinn_proto"))
+    );
+    // As are application events recorded in `Connection::span()`
+    assert!(
+        logs.lines()
+            .any(|line| line.contains(header) && line.contains("application event"))
+    );
+}
+
+#[tokio::test]
+async fn prewarm() {
+    let _guard = subscribe();
+    let endpoint = endpoint();
+    endpoint.prewarm();
+
+    let (client, server) = tokio::try_join!(
+        endpoint
+            .connect(endpoint.local_addr().unwrap(), "localhost")
+            .unwrap(),
+        async { endpoint.accept().await.unwrap().await }
+    )
+    .unwrap();
+    client.close(0u32.into(), b"done");
+    assert!(matches!(
+        server.closed().await,
+        crate::ConnectionError::ApplicationClosed(_)
+    ));
+}
+
 /// Construct an endpoint suitable for connecting to itself
 fn endpoint() -> Endpoint {
     EndpointFactory::new().endpoint()
@@ -393,6 +864,8 @@ async fn zero_rtt() {
     connection.authenticated().await.expect("connected");
 
     // Ensure 0-RTT was accepted
+    assert!(connection.zero_rtt_accepted().await.expect("connected"));
+    assert!(connection.zero_rtt_rejected_streams().is_empty());
     stream_0rtt.stopped().await.expect("0-RTT stopped");
 
     // Allow the connection to close
@@ -401,6 +874,33 @@ async fn zero_rtt() {
     endpoint.wait_idle().await;
 }
 
+#[tokio::test]
+async fn connecting_open_bi() {
+    let _guard = subscribe();
+    let endpoint = endpoint();
+    let server_addr = endpoint.local_addr().unwrap();
+
+    let server = async {
+        let conn = endpoint.accept().await.unwrap().await.unwrap();
+        let (_send, mut recv) = conn.accept_bi().await.unwrap();
+        assert_eq!(recv.read_to_end(usize::MAX).await.unwrap(), b"hello");
+        conn.close(0u32.into(), b"done");
+    };
+
+    let client = async {
+        let connecting = endpoint.connect(server_addr, "localhost").unwrap();
+        let (mut send, _recv) = connecting.open_bi().await.unwrap();
+        send.write_all(b"hello").await.unwrap();
+        send.finish().unwrap();
+        let conn = connecting.await.unwrap();
+        conn.closed().await;
+    };
+
+    timeout(Duration::from_secs(10), async { join!(server, client) })
+        .await
+        .unwrap();
+}
+
 #[test]
 #[cfg_attr(
     any(target_os = "solaris", target_os = "illumos"),
@@ -665,6 +1165,19 @@ fn subscribe() -> tracing::subscriber::DefaultGuard {
     tracing::subscriber::set_default(sub)
 }
 
+/// Appends everything written to a shared buffer, for inspecting logs
+struct CaptureWriter(Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl io::Write for CaptureWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 struct TestWriter;
 
 impl io::Write for TestWriter {
@@ -688,8 +1201,9 @@ fn rt_threaded() -> Runtime {
     Builder::new_multi_thread().enable_all().build().unwrap()
 }
 
+#[cfg(any(target_os = "linux", target_os = "android"))]
 #[tokio::test]
-async fn rebind_recv() {
+async fn sharded_connection_pinned_across_migration() {
     let _guard = subscribe();
 
     let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
@@ -698,20 +1212,90 @@ async fn rebind_recv() {
 
     let mut roots = RootCertStore::empty();
     roots.add(cert.clone()).unwrap();
-
     let client = Endpoint::client(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).unwrap();
-    let mut client_config = ClientConfig::with_root_certificates(Arc::new(roots)).unwrap();
-    client_config.transport_config(Arc::new({
-        let mut cfg = TransportConfig::default();
-        cfg.max_concurrent_uni_streams(1u32.into());
-        cfg
-    }));
-    client.set_default_client_config(client_config);
+    client
+        .set_default_client_config(ClientConfig::with_root_certificates(Arc::new(roots)).unwrap());
 
-    let server_config =
-        crate::ServerConfig::with_single_cert(vec![cert.clone()], key.into()).unwrap();
-    let server = {
-        let _guard = tracing::error_span!("server").entered();
+    let server_config = crate::ServerConfig::with_single_cert(vec![cert], key.into()).unwrap();
+    let shards = Endpoint::new_sharded(
+        EndpointConfig::default(),
+        server_config,
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0),
+        2,
+        Arc::new(TokioRuntime),
+    )
+    .unwrap();
+    let server_addr = shards[0].local_addr().unwrap();
+    assert_eq!(shards[1].local_addr().unwrap(), server_addr);
+    for shard in &shards {
+        let shard = shard.clone();
+        tokio::spawn(async move {
+            while let Some(incoming) = shard.accept().await {
+                let connection = incoming.await.unwrap();
+                while let Ok((mut send, mut recv)) = connection.accept_bi().await {
+                    let msg = recv.read_to_end(usize::MAX).await.unwrap();
+                    send.write_all(&msg).await.unwrap();
+                    send.finish().unwrap();
+                }
+            }
+        });
+    }
+
+    let connection = client
+        .connect(server_addr, "localhost")
+        .unwrap()
+        .await
+        .unwrap();
+    let owner = shards
+        .iter()
+        .position(|shard| shard.open_connections() == 1)
+        .unwrap();
+    let other = &shards[1 - owner];
+
+    // Each new port is hashed to a shard anew, so the other shard soon receives packets for the
+    // connection, which only survives if they reach the owner
+    for _ in 0..16 {
+        client
+            .rebind(UdpSocket::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).unwrap())
+            .unwrap();
+        let (mut send, mut recv) = connection.open_bi().await.unwrap();
+        send.write_all(b"ping").await.unwrap();
+        send.finish().unwrap();
+        assert_eq!(recv.read_to_end(4).await.unwrap(), b"ping");
+        if other.io_stats().datagrams_forwarded > 0 {
+            break;
+        }
+    }
+    assert!(other.io_stats().datagrams_forwarded > 0);
+    assert_eq!(shards[owner].io_stats().datagrams_forwarded, 0);
+    assert_eq!(shards[owner].open_connections(), 1);
+    assert_eq!(other.open_connections(), 0);
+}
+
+#[tokio::test]
+async fn rebind_recv() {
+    let _guard = subscribe();
+
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+    let key = PrivatePkcs8KeyDer::from(cert.signing_key.serialize_der());
+    let cert = CertificateDer::from(cert.cert);
+
+    let mut roots = RootCertStore::empty();
+    roots.add(cert.clone()).unwrap();
+
+    let client = Endpoint::client(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).unwrap();
+    let mut client_config = ClientConfig::with_root_certificates(Arc::new(roots)).unwrap();
+    client_config.transport_config(Arc::new({
+        let mut cfg = TransportConfig::default();
+        cfg.max_concurrent_uni_streams(1u32.into());
+        cfg
+    }));
+    client.set_default_client_config(client_config);
+
+    let server_config =
+        crate::ServerConfig::with_single_cert(vec![cert.clone()], key.into()).unwrap();
+    let server = {
+        let _guard = tracing::error_span!("server").entered();
         Endpoint::server(
             server_config,
             SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0),
@@ -758,6 +1342,401 @@ async fn rebind_recv() {
     server.await.unwrap();
 }
 
+#[tokio::test]
+async fn migrated() {
+    let _guard = subscribe();
+    let factory = EndpointFactory::new();
+    let server = factory.endpoint();
+    let client = factory.endpoint();
+    let server_addr = server.local_addr().unwrap();
+
+    let (client_conn, server_conn) = join!(
+        async {
+            client
+                .connect(server_addr, "localhost")
+                .unwrap()
+                .await
+                .unwrap()
+        },
+        async { server.accept().await.unwrap().await.unwrap() },
+    );
+    assert_eq!(
+        server_conn.path_validation_state(),
+        PathValidationState::Validated
+    );
+
+    let (migrated, ()) = join!(server_conn.migrated(), async {
+        client
+            .rebind(UdpSocket::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).unwrap())
+            .unwrap();
+        client_conn.ping().await.unwrap();
+    });
+    assert_eq!(migrated.unwrap(), client.local_addr().unwrap());
+    assert_eq!(server_conn.remote_address(), client.local_addr().unwrap());
+    assert_eq!(
+        server_conn.path_validation_state(),
+        PathValidationState::Validated
+    );
+}
+
+#[tokio::test]
+async fn observe_address_changes() {
+    let _guard = subscribe();
+    let factory = EndpointFactory::new();
+    let server = factory.endpoint();
+    let client = factory.endpoint();
+    let server_addr = server.local_addr().unwrap();
+
+    let (client_conn, server_conn) = join!(
+        async {
+            client
+                .connect(server_addr, "localhost")
+                .unwrap()
+                .await
+                .unwrap()
+        },
+        async { server.accept().await.unwrap().await.unwrap() },
+    );
+    let mut changes = server_conn.observe_address_changes();
+    let old_addr = client.local_addr().unwrap();
+
+    // Rebinding to a new port on the same host looks like a NAT rebinding to the server
+    client
+        .rebind(UdpSocket::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).unwrap())
+        .unwrap();
+    client_conn.ping().await.unwrap();
+    let change = poll_fn(|cx| changes.poll_next(cx)).await.unwrap();
+    assert_eq!(change.old, old_addr);
+    assert_eq!(change.new, client.local_addr().unwrap());
+    assert!(change.is_port_only());
+
+    server_conn.close(0u32.into(), b"done");
+    assert!(poll_fn(|cx| changes.poll_next(cx)).await.is_none());
+}
+
+#[tokio::test]
+async fn close_gracefully() {
+    let _guard = subscribe();
+    let endpoint = endpoint();
+    let server_addr = endpoint.local_addr().unwrap();
+    const LEN: usize = 1024 * 1024;
+
+    let (client_conn, server_conn) = join!(
+        async {
+            endpoint
+                .connect(server_addr, "localhost")
+                .unwrap()
+                .await
+                .unwrap()
+        },
+        async { endpoint.accept().await.unwrap().await.unwrap() },
+    );
+
+    let mut send = client_conn.open_uni().await.unwrap();
+    send.write_all(&vec![0xab; LEN]).await.unwrap();
+    send.finish().unwrap();
+    let ((), (), received) = join!(
+        client_conn.close_gracefully(42u32.into(), b"done", Duration::from_secs(10)),
+        async {
+            assert!(matches!(
+                client_conn.open_uni().await,
+                Err(crate::ConnectionError::LocallyClosed)
+            ));
+        },
+        async {
+            let mut recv = server_conn.accept_uni().await.unwrap();
+            recv.read_to_end(usize::MAX).await.unwrap()
+        },
+    );
+    assert_eq!(received.len(), LEN);
+    match server_conn.closed().await {
+        crate::ConnectionError::ApplicationClosed(close) => {
+            assert_eq!(close.error_code, crate::VarInt::from_u32(42));
+        }
+        e => panic!("unexpected close: {e}"),
+    }
+}
+
+/// Delegating socket standing in for a platform-provided [`AsyncUdpSocket`]
+///
+/// Counts the datagrams it receives, and discards one batch of them whenever `drop_next` is set.
+#[derive(Debug)]
+struct TestSocket {
+    inner: Box<dyn AsyncUdpSocket>,
+    received: Arc<AtomicUsize>,
+    drop_next: Arc<AtomicBool>,
+}
+
+impl TestSocket {
+    fn new(inner: Box<dyn AsyncUdpSocket>) -> Self {
+        Self {
+            inner,
+            received: Arc::default(),
+            drop_next: Arc::default(),
+        }
+    }
+}
+
+impl AsyncUdpSocket for TestSocket {
+    fn create_sender(&self) -> Pin<Box<dyn UdpSender>> {
+        self.inner.create_sender()
+    }
+
+    fn poll_recv(
+        &mut self,
+        cx: &mut Context<'_>,
+        bufs: &mut [io::IoSliceMut<'_>],
+        meta: &mut [udp::RecvMeta],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            let result = self.inner.poll_recv(cx, bufs, meta);
+            if let Poll::Ready(Ok(n)) = result {
+                if self.drop_next.swap(false, Ordering::Relaxed) {
+                    continue;
+                }
+                self.received.fetch_add(n, Ordering::Relaxed);
+            }
+            return result;
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    fn max_receive_segments(&self) -> usize {
+        self.inner.max_receive_segments()
+    }
+
+    fn may_fragment(&self) -> bool {
+        self.inner.may_fragment()
+    }
+}
+
+#[tokio::test]
+async fn rebind_abstract() {
+    let _guard = subscribe();
+    let factory = EndpointFactory::new();
+    let server = {
+        let _guard = error_span!("server").entered();
+        factory.endpoint()
+    };
+    let server_addr = server.local_addr().unwrap();
+    let client = {
+        let _guard = error_span!("client").entered();
+        factory.endpoint()
+    };
+
+    const MSG: &[u8] = b"hello";
+    let server = async move {
+        let conn = server.accept().await.unwrap().await.unwrap();
+        let mut stream = conn.accept_uni().await.unwrap();
+        assert_eq!(stream.read_to_end(MSG.len()).await.unwrap(), MSG);
+        let mut stream = conn.open_uni().await.unwrap();
+        stream.write_all(MSG).await.unwrap();
+        stream.finish().unwrap();
+        _ = stream.stopped().await;
+    }
+    .instrument(error_span!("server"));
+
+    let received = Arc::new(AtomicUsize::new(0));
+    let client = async {
+        let conn = client
+            .connect(server_addr, "localhost")
+            .unwrap()
+            .await
+            .unwrap();
+        let socket = TokioRuntime
+            .wrap_udp_socket(
+                UdpSocket::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).unwrap(),
+            )
+            .unwrap();
+        let new_addr = socket.local_addr().unwrap();
+        client
+            .rebind_abstract(Box::new(TestSocket {
+                received: received.clone(),
+                ..TestSocket::new(socket)
+            }))
+            .unwrap();
+        assert_eq!(client.local_addr().unwrap(), new_addr);
+
+        let mut stream = conn.open_uni().await.unwrap();
+        stream.write_all(MSG).await.unwrap();
+        stream.finish().unwrap();
+        let mut stream = conn.accept_uni().await.unwrap();
+        assert_eq!(stream.read_to_end(MSG.len()).await.unwrap(), MSG);
+    }
+    .instrument(error_span!("client"));
+
+    timeout(Duration::from_secs(5), async { join!(client, server) })
+        .await
+        .expect("timeout");
+    assert!(received.load(Ordering::Relaxed) > 0);
+}
+
+#[tokio::test]
+async fn path_events_loss() {
+    let _guard = subscribe();
+    let factory = EndpointFactory::new();
+    let server = {
+        let _guard = error_span!("server").entered();
+        factory.endpoint()
+    };
+    let server_addr = server.local_addr().unwrap();
+    let client = {
+        let _guard = error_span!("client").entered();
+        factory.endpoint()
+    };
+    let socket = TestSocket::new(
+        TokioRuntime
+            .wrap_udp_socket(
+                UdpSocket::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).unwrap(),
+            )
+            .unwrap(),
+    );
+    let drop_next = socket.drop_next.clone();
+    client.rebind_abstract(Box::new(socket)).unwrap();
+
+    const SIZE: usize = 64 * 1024;
+    let server = async move {
+        let conn = server.accept().await.unwrap().await.unwrap();
+        let mut events = conn.path_events();
+        let mut stream = conn.open_uni().await.unwrap();
+        stream.write_all(&[0xAB; SIZE]).await.unwrap();
+        stream.finish().unwrap();
+        loop {
+            match poll_fn(|cx| events.poll_next(cx)).await.unwrap() {
+                PathEvent::LossDetected { packets } => {
+                    assert!(packets > 0);
+                    break;
+                }
+                event => info!(?event, "path event"),
+            }
+        }
+        // Let the client finish reading before closing
+        _ = stream.stopped().await;
+        conn.close(0u32.into(), b"done");
+        // The subscription ends with the connection
+        while poll_fn(|cx| events.poll_next(cx)).await.is_some() {}
+    }
+    .instrument(error_span!("server"));
+
+    let client = async {
+        let conn = client
+            .connect(server_addr, "localhost")
+            .unwrap()
+            .await
+            .unwrap();
+        drop_next.store(true, Ordering::Relaxed);
+        let mut stream = conn.accept_uni().await.unwrap();
+        assert_eq!(stream.read_to_end(SIZE).await.unwrap().len(), SIZE);
+        conn.closed().await;
+    }
+    .instrument(error_span!("client"));
+
+    timeout(Duration::from_secs(5), async { join!(client, server) })
+        .await
+        .expect("timeout");
+    assert!(!drop_next.load(Ordering::Relaxed));
+}
+
+#[tokio::test]
+async fn write_timeout() {
+    let _guard = subscribe();
+    let mut cfg = TransportConfig::default();
+    cfg.stream_receive_window(1000u32.into());
+    let endpoint = endpoint_with_config(cfg);
+
+    let (client, server) = tokio::join!(
+        endpoint
+            .connect(endpoint.local_addr().unwrap(), "localhost")
+            .unwrap(),
+        async { endpoint.accept().await.unwrap().await }
+    );
+    let client = client.unwrap();
+    let _server = server.unwrap();
+
+    // The peer never reads, so only the stream's receive window can be written
+    let mut stream = client.open_uni().await.unwrap();
+    let deadline = Instant::now() + Duration::from_millis(100);
+    assert_eq!(stream.write_timeout(&[0xAB; 600], deadline).await, Ok(600));
+    assert_eq!(
+        stream.write_timeout(&[0xAB; 600], deadline).await,
+        Err(WriteTimeoutError::TimedOut { written: 400 })
+    );
+    assert!(Instant::now() >= deadline);
+}
+
+#[tokio::test]
+async fn ping() {
+    let _guard = subscribe();
+    let endpoint = endpoint();
+
+    let (client, server) = tokio::join!(
+        endpoint
+            .connect(endpoint.local_addr().unwrap(), "localhost")
+            .unwrap(),
+        async { endpoint.accept().await.unwrap().await }
+    );
+    let client = client.unwrap();
+    let server = server.unwrap();
+
+    // Loopback round trips typically take well under a millisecond, but leave plenty of margin
+    // for slow CI machines
+    let (a, b, c) = timeout(Duration::from_secs(5), async {
+        join!(client.ping(), client.ping(), server.ping())
+    })
+    .await
+    .expect("timeout");
+    for rtt in [a, b, c] {
+        let rtt = rtt.unwrap();
+        info!(?rtt, "ping");
+        assert!(rtt < Duration::from_millis(100));
+    }
+
+    client.close(0u32.into(), b"done");
+    assert!(client.ping().await.is_err());
+}
+
+#[tokio::test]
+async fn probe_path() {
+    let _guard = subscribe();
+    let endpoint = endpoint();
+    // A second socket that never answers, standing in for an unreachable alternate path
+    let silent = UdpSocket::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).unwrap();
+    silent
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    let (client, server) = tokio::join!(
+        endpoint
+            .connect(endpoint.local_addr().unwrap(), "localhost")
+            .unwrap(),
+        async { endpoint.accept().await.unwrap().await }
+    );
+    let client = client.unwrap();
+    let _server = server.unwrap();
+
+    let (reachable, unreachable) = timeout(Duration::from_secs(5), async {
+        join!(
+            client.probe_path(endpoint.local_addr().unwrap()),
+            client.probe_path(silent.local_addr().unwrap())
+        )
+    })
+    .await
+    .expect("timeout");
+    let rtt = reachable.unwrap();
+    info!(?rtt, "probe");
+    assert!(rtt < Duration::from_millis(100));
+    assert_eq!(unreachable, Err(PathError::TimedOut));
+
+    // The challenge reached the silent socket, padded like any other path validation datagram
+    let mut buf = [0; 1500];
+    assert!(silent.recv(&mut buf).unwrap() >= 1200);
+    // Probing never migrates the connection
+    assert_eq!(client.remote_address(), endpoint.local_addr().unwrap());
+}
+
 #[tokio::test]
 async fn stream_id_flow_control() {
     let _guard = subscribe();
@@ -792,6 +1771,168 @@ async fn stream_id_flow_control() {
     );
 }
 
+#[tokio::test]
+async fn poll_readable() {
+    let _guard = subscribe();
+    let endpoint = endpoint();
+
+    let (client, server) = tokio::join!(
+        endpoint
+            .connect(endpoint.local_addr().unwrap(), "localhost")
+            .unwrap(),
+        async { endpoint.accept().await.unwrap().await }
+    );
+    let client = client.unwrap();
+    let server = server.unwrap();
+
+    let mut send = client.open_uni().await.unwrap();
+    send.write_all(b"hello").await.unwrap();
+    let mut recv = server.accept_uni().await.unwrap();
+
+    // Readiness doesn't consume anything
+    poll_fn(|cx| recv.poll_readable(cx)).await.unwrap();
+    poll_fn(|cx| recv.poll_readable(cx)).await.unwrap();
+    let chunk = recv.read_chunk(usize::MAX, true).await.unwrap().unwrap();
+    assert_eq!(chunk.bytes, &b"hello"[..]);
+
+    assert!(
+        timeout(
+            Duration::from_millis(50),
+            poll_fn(|cx| recv.poll_readable(cx))
+        )
+        .await
+        .is_err()
+    );
+
+    // The end of the stream is reported as readable too
+    send.finish().unwrap();
+    timeout(Duration::from_secs(5), poll_fn(|cx| recv.poll_readable(cx)))
+        .await
+        .expect("timeout")
+        .unwrap();
+    assert_eq!(recv.read_chunk(usize::MAX, true).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn forward_chunks() {
+    let _guard = subscribe();
+    let endpoint = endpoint();
+
+    let (client, server) = tokio::join!(
+        endpoint
+            .connect(endpoint.local_addr().unwrap(), "localhost")
+            .unwrap(),
+        async { endpoint.accept().await.unwrap().await }
+    );
+    let client = client.unwrap();
+    let server = server.unwrap();
+
+    const SIZE: usize = 8 * 1024 * 1024;
+    let data = gen_data(SIZE, 42);
+    let send_task = async {
+        let mut send = client.open_uni().await.unwrap();
+        send.write_all(&data).await.unwrap();
+        send.finish().unwrap();
+    };
+    let recv_task = async {
+        let mut recv = client.accept_uni().await.unwrap();
+        recv.read_to_end(SIZE).await.unwrap()
+    };
+    // Relay everything the client sends back to it on a new stream, without copying
+    let forward_task = async {
+        let mut recv = server.accept_uni().await.unwrap();
+        let mut send = server.open_uni().await.unwrap();
+        let mut bufs = vec![Bytes::new(); 32];
+        while let Some(n) = recv.read_chunks(&mut bufs).await.unwrap() {
+            send.write_all_chunks(&mut bufs[..n]).await.unwrap();
+        }
+        send.finish().unwrap();
+        send.stopped().await.unwrap();
+    };
+
+    let ((), received, ()) = timeout(Duration::from_secs(30), async {
+        tokio::join!(send_task, recv_task, forward_task)
+    })
+    .await
+    .expect("timeout");
+    assert!(received == data, "data mismatch");
+}
+
+#[tokio::test]
+async fn fragmented_datagrams() {
+    let _guard = subscribe();
+    let endpoint = endpoint();
+
+    let (client, server) = tokio::join!(
+        endpoint
+            .connect(endpoint.local_addr().unwrap(), "localhost")
+            .unwrap(),
+        async { endpoint.accept().await.unwrap().await }
+    );
+    let client = client.unwrap();
+    let server = server.unwrap();
+
+    let large = (0..10_000u32).map(|i| i as u8).collect::<Bytes>();
+    assert!(large.len() > client.max_datagram_size().unwrap());
+    let mut sender = DatagramSender::new(client);
+    let mut reassembler = DatagramReassembler::new(server, Duration::from_secs(5));
+    sender.send(Bytes::from_static(b"small")).await.unwrap();
+    sender.send(large.clone()).await.unwrap();
+    sender.send(Bytes::new()).await.unwrap();
+
+    let recv = async {
+        for expected in [Bytes::from_static(b"small"), large, Bytes::new()] {
+            assert_eq!(reassembler.recv().await.unwrap(), expected);
+        }
+    };
+    timeout(Duration::from_secs(5), recv)
+        .await
+        .expect("timeout");
+}
+
+#[tokio::test]
+async fn fragmented_datagram_timeout() {
+    let _guard = subscribe();
+    let endpoint = endpoint();
+
+    let (client, server) = tokio::join!(
+        endpoint
+            .connect(endpoint.local_addr().unwrap(), "localhost")
+            .unwrap(),
+        async { endpoint.accept().await.unwrap().await }
+    );
+    let client = client.unwrap();
+    let server = server.unwrap();
+
+    // Hand-built fragments, per the documented wire format
+    let fragment = |id: u32, index: u16, count: u16, payload: &[u8]| {
+        Bytes::from(
+            [
+                &id.to_be_bytes()[..],
+                &index.to_be_bytes(),
+                &count.to_be_bytes(),
+                payload,
+            ]
+            .concat(),
+        )
+    };
+    let mut reassembler = DatagramReassembler::new(server, Duration::from_millis(50));
+
+    client.send_datagram(fragment(7, 0, 2, b"stale ")).unwrap();
+    timeout(Duration::from_millis(200), reassembler.recv())
+        .await
+        .expect_err("incomplete payload delivered");
+
+    // The first fragment has timed out, so completing the payload late yields nothing
+    client.send_datagram(fragment(7, 1, 2, b"data")).unwrap();
+    client.send_datagram(fragment(8, 0, 1, b"fresh")).unwrap();
+    let payload = timeout(Duration::from_secs(5), reassembler.recv())
+        .await
+        .expect("timeout")
+        .unwrap();
+    assert_eq!(payload, Bytes::from_static(b"fresh"));
+}
+
 #[tokio::test]
 async fn two_datagram_readers() {
     let _guard = subscribe();
@@ -828,6 +1969,224 @@ async fn two_datagram_readers() {
     assert!(*a == *b"two" || *b == *b"two");
 }
 
+#[tokio::test]
+async fn read_datagrams_batch() {
+    let _guard = subscribe();
+    let endpoint = endpoint();
+
+    let (client, server) = tokio::join!(
+        endpoint
+            .connect(endpoint.local_addr().unwrap(), "localhost")
+            .unwrap(),
+        async { endpoint.accept().await.unwrap().await }
+    );
+    let client = client.unwrap();
+    let server = server.unwrap();
+
+    let mut out = Vec::new();
+    assert_eq!(client.read_datagrams(&mut out), 0);
+
+    for msg in [&b"one"[..], b"two", b"three"] {
+        server.send_datagram(msg.into()).unwrap();
+    }
+    // Stream data sent after the datagrams arrives after them over loopback
+    let mut send = server.open_uni().await.unwrap();
+    send.finish().unwrap();
+    client.accept_uni().await.unwrap();
+
+    assert_eq!(client.read_datagrams(&mut out), 3);
+    assert_eq!(out, [&b"one"[..], b"two", b"three"]);
+    assert_eq!(client.read_datagrams(&mut out), 0);
+}
+
+#[tokio::test]
+async fn max_datagram_size_changed() {
+    let _guard = subscribe();
+    let mut mtu_discovery_config = MtuDiscoveryConfig::default();
+    mtu_discovery_config.upper_bound(1452);
+    let mut transport_config = TransportConfig::default();
+    transport_config
+        .initial_mtu(1200)
+        .mtu_discovery_config(Some(mtu_discovery_config));
+    let endpoint = endpoint_with_config(transport_config);
+
+    let (client, _server) = tokio::try_join!(
+        endpoint
+            .connect(endpoint.local_addr().unwrap(), "localhost")
+            .unwrap(),
+        async { endpoint.accept().await.unwrap().await }
+    )
+    .unwrap();
+
+    // PMTUD raises the MTU shortly after the handshake completes
+    let changed = timeout(Duration::from_secs(5), client.max_datagram_size_changed())
+        .await
+        .expect("max datagram size did not change")
+        .unwrap();
+    assert!(changed.is_some());
+    assert!(client.stats().path.current_mtu > 1200);
+}
+
+#[tokio::test]
+async fn endpoint_send_rate_limit() {
+    let _guard = subscribe();
+    const RATE: u64 = 1_000_000;
+    const CONNECTIONS: usize = 3;
+    const SIZE: usize = 200_000;
+
+    let mut factory = EndpointFactory::new();
+    let server = {
+        let _guard = error_span!("server").entered();
+        factory.endpoint()
+    };
+    let server_addr = server.local_addr().unwrap();
+    factory.endpoint_config.max_send_rate(Some(RATE));
+    let client = {
+        let _guard = error_span!("client").entered();
+        factory.endpoint()
+    };
+
+    let start = Instant::now();
+    let mut senders = Vec::new();
+    for _ in 0..CONNECTIONS {
+        let connecting = client.connect(server_addr, "localhost").unwrap();
+        senders.push(tokio::spawn(
+            async move {
+                let conn = connecting.await.unwrap();
+                let mut send = conn.open_uni().await.unwrap();
+                send.write_all(&[0xAB; SIZE]).await.unwrap();
+                send.finish().unwrap();
+                conn.closed().await;
+            }
+            .instrument(error_span!("client")),
+        ));
+    }
+    for _ in 0..CONNECTIONS {
+        let conn = server.accept().await.unwrap().await.unwrap();
+        let mut recv = conn.accept_uni().await.unwrap();
+        assert_eq!(recv.read_to_end(SIZE).await.unwrap().len(), SIZE);
+        conn.close(0u32.into(), b"done");
+    }
+    let elapsed = start.elapsed();
+    for sender in senders {
+        sender.await.unwrap();
+    }
+
+    // Aside from the initial burst allowance, the connections' combined data can't be sent any
+    // faster than the endpoint-wide limit
+    let min_duration = Duration::from_secs_f64((CONNECTIONS * SIZE) as f64 / RATE as f64) * 4 / 5;
+    assert!(
+        elapsed >= min_duration,
+        "sent {} bytes in {elapsed:?}",
+        CONNECTIONS * SIZE
+    );
+}
+
+#[tokio::test]
+async fn on_stateless_reset() {
+    let _guard = subscribe();
+    // Endpoints from the same factory share a reset key, so `stale` can reset connections `server`
+    // accepted, provided it doesn't discard their CIDs as invalid
+    let mut factory = EndpointFactory::new();
+    factory
+        .endpoint_config
+        .cid_generator(Arc::new(|| Box::new(RandomConnectionIdGenerator::new(8))));
+    let server = factory.endpoint();
+    let stale = factory.endpoint();
+    let client = factory.endpoint();
+
+    // Relay the client's traffic to `server`, until it's redirected to `stale`
+    let front = tokio::net::UdpSocket::bind((Ipv4Addr::LOCALHOST, 0))
+        .await
+        .unwrap();
+    let back = tokio::net::UdpSocket::bind((Ipv4Addr::LOCALHOST, 0))
+        .await
+        .unwrap();
+    let relay_addr = front.local_addr().unwrap();
+    let target = Arc::new(std::sync::Mutex::new(server.local_addr().unwrap()));
+    let relay = tokio::spawn({
+        let target = target.clone();
+        async move {
+            let mut client_addr = None;
+            let (mut up, mut down) = ([0; 65536], [0; 65536]);
+            loop {
+                tokio::select! {
+                    Ok((n, addr)) = front.recv_from(&mut up) => {
+                        client_addr = Some(addr);
+                        let to = *target.lock().unwrap();
+                        let _ = back.send_to(&up[..n], to).await;
+                    }
+                    Ok((n, _)) = back.recv_from(&mut down) => {
+                        if let Some(addr) = client_addr {
+                            let _ = front.send_to(&down[..n], addr).await;
+                        }
+                    }
+                }
+            }
+        }
+    });
+
+    let (conn, server_conn) = tokio::join!(
+        async {
+            client
+                .connect(relay_addr, "localhost")
+                .unwrap()
+                .await
+                .unwrap()
+        },
+        async { server.accept().await.unwrap().await.unwrap() }
+    );
+
+    *target.lock().unwrap() = stale.local_addr().unwrap();
+    let mut send = conn.open_uni().await.unwrap();
+    send.write_all(&[0; 100]).await.unwrap();
+    timeout(Duration::from_secs(5), conn.on_stateless_reset())
+        .await
+        .expect("timeout")
+        .expect("not reset");
+
+    drop(server_conn);
+    relay.abort();
+}
+
+#[tokio::test]
+async fn seeded_cids() {
+    let _guard = subscribe();
+    let mut factory = EndpointFactory::new();
+    let server = factory.endpoint();
+    let server_addr = server.local_addr().unwrap();
+
+    let mut cids = Vec::new();
+    for seed in [[1; 32], [1; 32], [2; 32]] {
+        factory
+            .endpoint_config
+            .rng_seed(Some(seed))
+            .cid_generator(Arc::new(move || {
+                let mut generator = RandomConnectionIdGenerator::new(8);
+                generator.set_rng_seed(seed);
+                Box::new(generator)
+            }));
+        let client = factory.endpoint();
+        let (conn, _) = tokio::join!(
+            async {
+                client
+                    .connect(server_addr, "localhost")
+                    .unwrap()
+                    .await
+                    .unwrap()
+            },
+            async { server.accept().await.unwrap().await.unwrap() }
+        );
+        cids.push(conn.local_cids()[0]);
+        conn.close(0u32.into(), b"done");
+        client.wait_idle().await;
+    }
+
+    // Endpoints with the same seed pick the same CIDs
+    assert_eq!(cids[0], cids[1]);
+    assert_ne!(cids[0], cids[2]);
+}
+
 #[tokio::test]
 async fn multiple_conns_with_zero_length_cids() {
     let _guard = subscribe();
@@ -962,6 +2321,96 @@ async fn stream_stopped_2() {
     assert_eq!(res, Ok(Ok(None)));
 }
 
+#[tokio::test]
+async fn read_exact() {
+    let _guard = subscribe();
+    let factory = EndpointFactory::new();
+    let server = {
+        let _guard = error_span!("server").entered();
+        factory.endpoint()
+    };
+    let server_addr = server.local_addr().unwrap();
+
+    let client = {
+        let _guard = error_span!("client1").entered();
+        factory.endpoint()
+    };
+
+    let client = async move {
+        let conn = client
+            .connect(server_addr, "localhost")
+            .unwrap()
+            .await
+            .unwrap();
+        // Exact fill across several writes
+        let mut stream = conn.open_uni().await.unwrap();
+        stream.write_all(b"hello ").await.unwrap();
+        stream.write_all(b"world").await.unwrap();
+        stream.finish().unwrap();
+        // Finished before the buffer is filled
+        let mut stream = conn.open_uni().await.unwrap();
+        stream.write_all(b"abc").await.unwrap();
+        stream.finish().unwrap();
+        // Reset before the buffer is filled
+        let mut stream = conn.open_uni().await.unwrap();
+        stream.write_all(b"abc").await.unwrap();
+        stream.reset(7u32.into()).unwrap();
+        conn.closed().await;
+    }
+    .instrument(error_span!("client"));
+    let server = async move {
+        let conn = server.accept().await.unwrap().await.unwrap();
+
+        let mut stream = conn.accept_uni().await.unwrap();
+        let mut buf = [0u8; 11];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello world");
+        assert_eq!(
+            stream.read_exact(&mut [0u8; 1]).await,
+            Err(ReadExactError::FinishedEarly(0))
+        );
+
+        let mut stream = conn.accept_uni().await.unwrap();
+        assert_eq!(
+            stream.read_exact(&mut [0u8; 5]).await,
+            Err(ReadExactError::FinishedEarly(3))
+        );
+
+        let mut stream = conn.accept_uni().await.unwrap();
+        assert_eq!(
+            stream.read_exact(&mut [0u8; 5]).await,
+            Err(ReadExactError::ReadError(ReadError::Reset(7u32.into())))
+        );
+        conn.close(0u32.into(), b"done");
+    }
+    .instrument(error_span!("server"));
+    timeout(Duration::from_secs(5), async {
+        tokio::join!(client, server)
+    })
+    .await
+    .expect("timeout");
+}
+
+#[tokio::test]
+async fn stream_priority_readback() {
+    let _guard = subscribe();
+    let endpoint = endpoint();
+
+    let (conn, _server_conn) = tokio::try_join!(
+        endpoint
+            .connect(endpoint.local_addr().unwrap(), "localhost")
+            .unwrap(),
+        async { endpoint.accept().await.unwrap().await }
+    )
+    .unwrap();
+    let send_stream = conn.open_uni().await.unwrap();
+    assert_eq!(send_stream.priority(), Ok(0));
+    send_stream.set_priority(7).unwrap();
+    assert_eq!(send_stream.priority(), Ok(7));
+    send_stream.set_priority(-3).unwrap();
+    assert_eq!(send_stream.priority(), Ok(-3));
+}
+
 #[tokio::test]
 async fn stream_drop_removes_blocked_reader() {
     let _guard = subscribe();